@@ -0,0 +1,56 @@
+//! Concurrent signing example.
+//!
+//! Clones of a `yubihsm::Client` share its session, so several worker
+//! threads can each be handed their own clone and sign concurrently (with
+//! their commands serialized through the shared session). This example runs
+//! against the `MockHsm`, so it doesn't need a YubiHSM 2.
+
+use std::thread;
+use yubihsm::{asymmetric, Capability, Client, Connector, Credentials, Domain};
+
+/// Number of worker threads to sign with
+const WORKERS: usize = 4;
+
+/// Number of messages each worker signs
+const SIGNATURES_PER_WORKER: usize = 8;
+
+/// ID of the Ed25519 key to sign with
+const KEY_ID: yubihsm::object::Id = 100;
+
+fn main() {
+    let client = Client::open(Connector::mockhsm(), Credentials::default(), true).unwrap();
+
+    client
+        .generate_asymmetric_key(
+            KEY_ID,
+            "concurrent signing example".into(),
+            Domain::DOM1,
+            Capability::SIGN_EDDSA,
+            asymmetric::Algorithm::Ed25519,
+        )
+        .unwrap();
+
+    let workers: Vec<_> = (0..WORKERS)
+        .map(|worker| {
+            let client = client.clone();
+
+            thread::spawn(move || {
+                for n in 0..SIGNATURES_PER_WORKER {
+                    let message = format!("message {n} from worker {worker}");
+                    client.sign_ed25519(KEY_ID, message.as_bytes()).unwrap();
+                }
+            })
+        })
+        .collect();
+
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    println!(
+        "signed {} messages from {} threads using session {}",
+        WORKERS * SIGNATURES_PER_WORKER,
+        WORKERS,
+        client.session().unwrap().id().to_u8()
+    );
+}
@@ -5,6 +5,10 @@ mod error;
 pub use self::error::{Error, ErrorKind};
 
 use crate::{asymmetric, authentication, ecdh, ecdsa, hmac, opaque, otp, rsa, template, wrap};
+use std::ops::RangeInclusive;
+
+/// IDs of all algorithms known to the `YubiHSM 2`
+const ALGORITHM_IDS: RangeInclusive<u8> = 0x01..=0x2f;
 
 /// Cryptographic algorithm types supported by the `YubiHSM 2`
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -45,6 +49,53 @@ pub enum Algorithm {
 }
 
 impl Algorithm {
+    /// Iterate over every known algorithm, in order of algorithm ID
+    pub fn all() -> impl Iterator<Item = Algorithm> {
+        ALGORITHM_IDS.map(|id| Algorithm::from_u8(id).unwrap())
+    }
+
+    /// Iterate over the algorithms of keys which can be stored in the HSM
+    /// (i.e. asymmetric, authentication, HMAC, wrap, and OTP AEAD keys)
+    pub fn key_algorithms() -> impl Iterator<Item = Algorithm> {
+        Self::all().filter(|alg| alg.is_key_algorithm())
+    }
+
+    /// Iterate over the signature algorithms (i.e. ECDSA, RSASSA-PKCS#1v1.5,
+    /// and RSASSA-PSS) keys can be used with.
+    ///
+    /// Ed25519 signatures and HMACs don't have algorithms of their own: they
+    /// are identified by the algorithm of the key they're computed with.
+    pub fn signing_algorithms() -> impl Iterator<Item = Algorithm> {
+        Self::all().filter(|alg| alg.is_signing_algorithm())
+    }
+
+    /// Iterate over the algorithms of wrap keys
+    pub fn wrap_algorithms() -> impl Iterator<Item = Algorithm> {
+        Self::all().filter(|alg| alg.wrap().is_some())
+    }
+
+    /// Is this the algorithm of a key which can be stored in the HSM?
+    pub fn is_key_algorithm(self) -> bool {
+        matches!(
+            self,
+            Algorithm::Asymmetric(_)
+                | Algorithm::Authentication(_)
+                | Algorithm::Hmac(_)
+                | Algorithm::Wrap(_)
+                | Algorithm::YubicoOtp(_)
+        )
+    }
+
+    /// Is this a signature algorithm?
+    pub fn is_signing_algorithm(self) -> bool {
+        matches!(
+            self,
+            Algorithm::Ecdsa(_)
+                | Algorithm::Rsa(rsa::Algorithm::Pkcs1(_))
+                | Algorithm::Rsa(rsa::Algorithm::Pss(_))
+        )
+    }
+
     /// Convert an unsigned byte into an Algorithm (if valid)
     pub fn from_u8(byte: u8) -> Result<Self, Error> {
         Ok(match byte {
@@ -86,6 +137,25 @@ impl Algorithm {
         }
     }
 
+    /// Length in bytes of the key material the `YubiHSM 2` expects when
+    /// importing a key with this algorithm, or `None` if the length is
+    /// variable (e.g. HMAC keys) or this algorithm isn't used for keys
+    pub fn expected_key_length(self) -> Option<usize> {
+        match self {
+            Algorithm::Asymmetric(alg) => Some(alg.key_len()),
+            Algorithm::Authentication(alg) => Some(alg.key_len()),
+            Algorithm::Wrap(alg) => Some(alg.key_len()),
+            Algorithm::YubicoOtp(alg) => Some(alg.key_len()),
+            Algorithm::Ecdh(_)
+            | Algorithm::Ecdsa(_)
+            | Algorithm::Hmac(_)
+            | Algorithm::Mgf(_)
+            | Algorithm::Opaque(_)
+            | Algorithm::Rsa(_)
+            | Algorithm::Template(_) => None,
+        }
+    }
+
     /// Get `asymmetric::Algorithm`
     pub fn asymmetric(self) -> Option<asymmetric::Algorithm> {
         match self {
@@ -185,7 +255,7 @@ impl From<asymmetric::Algorithm> for Algorithm {
 
 impl From<authentication::Algorithm> for Algorithm {
     fn from(alg: authentication::Algorithm) -> Algorithm {
-        crate::Algorithm::Authentication(alg)
+        Algorithm::Authentication(alg)
     }
 }
 
@@ -243,6 +313,66 @@ impl From<wrap::Algorithm> for Algorithm {
     }
 }
 
+impl TryFrom<Algorithm> for asymmetric::Algorithm {
+    type Error = Error;
+
+    fn try_from(alg: Algorithm) -> Result<Self, Error> {
+        alg.asymmetric().ok_or_else(|| {
+            format_err!(
+                ErrorKind::CategoryInvalid,
+                "not an asymmetric algorithm: {:?}",
+                alg
+            )
+            .into()
+        })
+    }
+}
+
+impl TryFrom<Algorithm> for authentication::Algorithm {
+    type Error = Error;
+
+    fn try_from(alg: Algorithm) -> Result<Self, Error> {
+        alg.authentication().ok_or_else(|| {
+            format_err!(
+                ErrorKind::CategoryInvalid,
+                "not an authentication algorithm: {:?}",
+                alg
+            )
+            .into()
+        })
+    }
+}
+
+impl TryFrom<Algorithm> for hmac::Algorithm {
+    type Error = Error;
+
+    fn try_from(alg: Algorithm) -> Result<Self, Error> {
+        alg.hmac().ok_or_else(|| {
+            format_err!(
+                ErrorKind::CategoryInvalid,
+                "not an HMAC algorithm: {:?}",
+                alg
+            )
+            .into()
+        })
+    }
+}
+
+impl TryFrom<Algorithm> for wrap::Algorithm {
+    type Error = Error;
+
+    fn try_from(alg: Algorithm) -> Result<Self, Error> {
+        alg.wrap().ok_or_else(|| {
+            format_err!(
+                ErrorKind::CategoryInvalid,
+                "not a wrap algorithm: {:?}",
+                alg
+            )
+            .into()
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -349,4 +479,105 @@ mod tests {
             assert_eq!(*tag, alg.to_u8());
         }
     }
+
+    #[test]
+    fn test_all() {
+        let all: Vec<_> = Algorithm::all().collect();
+        let expected: Vec<_> = ALGORITHM_MAPPING.iter().map(|(_, alg)| *alg).collect();
+        assert_eq!(all, expected);
+    }
+
+    #[test]
+    fn test_subsets() {
+        assert_eq!(Algorithm::all().count(), 47);
+        assert_eq!(Algorithm::key_algorithms().count(), 23);
+        assert_eq!(Algorithm::signing_algorithms().count(), 12);
+        assert_eq!(Algorithm::wrap_algorithms().count(), 3);
+
+        // Keys aren't signature algorithms or vice versa
+        assert!(Algorithm::key_algorithms().all(|alg| !alg.is_signing_algorithm()));
+        assert!(Algorithm::wrap_algorithms().all(Algorithm::is_key_algorithm));
+
+        // Algorithms which are neither are all used by other operations
+        for alg in Algorithm::all() {
+            if !alg.is_key_algorithm() && !alg.is_signing_algorithm() {
+                assert!(
+                    matches!(
+                        alg,
+                        Algorithm::Ecdh(_)
+                            | Algorithm::Mgf(_)
+                            | Algorithm::Opaque(_)
+                            | Algorithm::Rsa(rsa::Algorithm::Oaep(_))
+                            | Algorithm::Template(_)
+                    ),
+                    "{alg:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_expected_key_length() {
+        const EXPECTED_KEY_LENGTHS: &[(Algorithm, usize)] = &[
+            (Algorithm::Asymmetric(asymmetric::Algorithm::Rsa2048), 256),
+            (Algorithm::Asymmetric(asymmetric::Algorithm::Rsa3072), 384),
+            (Algorithm::Asymmetric(asymmetric::Algorithm::Rsa4096), 512),
+            (Algorithm::Asymmetric(asymmetric::Algorithm::EcP224), 28),
+            (Algorithm::Asymmetric(asymmetric::Algorithm::EcP256), 32),
+            (Algorithm::Asymmetric(asymmetric::Algorithm::EcP384), 48),
+            (Algorithm::Asymmetric(asymmetric::Algorithm::EcP521), 66),
+            (Algorithm::Asymmetric(asymmetric::Algorithm::EcK256), 32),
+            (Algorithm::Asymmetric(asymmetric::Algorithm::EcBp256), 32),
+            (Algorithm::Asymmetric(asymmetric::Algorithm::EcBp384), 48),
+            (Algorithm::Asymmetric(asymmetric::Algorithm::EcBp512), 64),
+            (Algorithm::Asymmetric(asymmetric::Algorithm::Ed25519), 32),
+            (
+                Algorithm::Authentication(authentication::Algorithm::YubicoAes),
+                32,
+            ),
+            (Algorithm::Wrap(wrap::Algorithm::Aes128Ccm), 16),
+            (Algorithm::Wrap(wrap::Algorithm::Aes192Ccm), 24),
+            (Algorithm::Wrap(wrap::Algorithm::Aes256Ccm), 32),
+            (Algorithm::YubicoOtp(otp::Algorithm::Aes128), 16),
+            (Algorithm::YubicoOtp(otp::Algorithm::Aes192), 24),
+            (Algorithm::YubicoOtp(otp::Algorithm::Aes256), 32),
+        ];
+
+        for (_, alg) in ALGORITHM_MAPPING {
+            let expected = EXPECTED_KEY_LENGTHS
+                .iter()
+                .find(|(a, _)| a == alg)
+                .map(|(_, len)| *len);
+
+            assert_eq!(alg.expected_key_length(), expected, "{alg:?}");
+        }
+    }
+
+    #[test]
+    fn test_try_from_category() {
+        let auth_alg = Algorithm::Authentication(authentication::Algorithm::YubicoAes);
+
+        let err = wrap::Algorithm::try_from(auth_alg).unwrap_err();
+        assert_eq!(*err.kind(), ErrorKind::CategoryInvalid);
+        assert!(hmac::Algorithm::try_from(auth_alg).is_err());
+        assert!(asymmetric::Algorithm::try_from(auth_alg).is_err());
+
+        assert_eq!(
+            authentication::Algorithm::try_from(auth_alg).unwrap(),
+            authentication::Algorithm::YubicoAes
+        );
+        assert_eq!(
+            wrap::Algorithm::try_from(Algorithm::Wrap(wrap::Algorithm::Aes256Ccm)).unwrap(),
+            wrap::Algorithm::Aes256Ccm
+        );
+        assert_eq!(
+            asymmetric::Algorithm::try_from(Algorithm::from(asymmetric::Algorithm::Ed25519))
+                .unwrap(),
+            asymmetric::Algorithm::Ed25519
+        );
+        assert_eq!(
+            hmac::Algorithm::try_from(Algorithm::from(hmac::Algorithm::Sha256)).unwrap(),
+            hmac::Algorithm::Sha256
+        );
+    }
 }
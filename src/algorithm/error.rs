@@ -9,6 +9,11 @@ pub type Error = crate::Error<ErrorKind>;
 /// Kinds of `Algorithm`-related errors
 #[derive(Copy, Clone, Eq, Error, PartialEq, Debug)]
 pub enum ErrorKind {
+    /// Algorithm is not in the expected category (e.g. an authentication
+    /// algorithm was given where a wrap algorithm was expected)
+    #[error("invalid algorithm category")]
+    CategoryInvalid,
+
     /// Invalid algorithm tag
     #[error("invalid tag")]
     TagInvalid,
@@ -15,6 +15,13 @@ pub(crate) struct GenAsymmetricKeyCommand(pub(crate) generate::Params);
 
 impl Command for GenAsymmetricKeyCommand {
     type ResponseType = GenAsymmetricKeyResponse;
+
+    fn object(&self) -> Option<object::Handle> {
+        Some(object::Handle::new(
+            self.0.key_id,
+            object::Type::AsymmetricKey,
+        ))
+    }
 }
 
 /// Response from `command::generate_asymmetric_key`
@@ -19,6 +19,13 @@ pub(crate) struct GetPublicKeyCommand {
 
 impl Command for GetPublicKeyCommand {
     type ResponseType = GetPublicKeyResponse;
+
+    fn object(&self) -> Option<object::Handle> {
+        Some(object::Handle::new(
+            self.key_id,
+            object::Type::AsymmetricKey,
+        ))
+    }
 }
 
 /// Response from `command::get_public_key`
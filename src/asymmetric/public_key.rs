@@ -1,12 +1,19 @@
 //! Public keys for use with asymmetric cryptography / signatures
 
-use crate::{asymmetric, ecdsa::algorithm::CurveAlgorithm, ed25519};
+use crate::{asymmetric, attestation::x509, ecdsa::algorithm::CurveAlgorithm, ed25519};
 use ::ecdsa::elliptic_curve::{
     bigint::Integer, generic_array::GenericArray, point::PointCompression, sec1, FieldBytesSize,
     PrimeCurve,
 };
+use der::{
+    asn1::{AnyRef, BitStringRef, Null, ObjectIdentifier, UintRef},
+    Encode, Tag,
+};
 use serde::{Deserialize, Serialize};
 
+/// RSA public exponent used by the YubiHSM (F4), as a big endian integer
+const RSA_PUBLIC_EXPONENT: [u8; 3] = [0x01, 0x00, 0x01];
+
 /// Response from `command::get_public_key`
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct PublicKey {
@@ -66,6 +73,40 @@ impl PublicKey {
         }
     }
 
+    /// Return the ECDSA public key of the given curve type as a compressed
+    /// point if applicable
+    pub fn ecdsa_compressed<C>(&self) -> Option<sec1::EncodedPoint<C>>
+    where
+        C: PrimeCurve + CurveAlgorithm + PointCompression,
+        FieldBytesSize<C>: sec1::ModulusSize,
+    {
+        self.ecdsa::<C>().map(|point| point.compress())
+    }
+
+    /// Return the SEC1 encoding of an ECC (non-Ed25519) public key:
+    /// compressed (`0x02`/`0x03` followed by the X coordinate) or
+    /// uncompressed (`0x04` followed by the X and Y coordinates).
+    ///
+    /// Unlike [`PublicKey::ecdsa`] this works with any curve (e.g.
+    /// Brainpool), as compressing a point only needs the parity of Y.
+    /// Returns `None` for other keys, or if the key's length doesn't match
+    /// its curve.
+    pub fn ecdsa_bytes(&self, compress: bool) -> Option<Vec<u8>> {
+        curve_oid(self.algorithm)?;
+
+        if self.bytes.len() != self.algorithm.key_len() * 2 {
+            return None;
+        }
+
+        let (x, y) = self.bytes.split_at(self.algorithm.key_len());
+
+        Some(if compress {
+            [&[0x02 | (y[y.len() - 1] & 1)], x].concat()
+        } else {
+            [&[0x04], self.bytes.as_slice()].concat()
+        })
+    }
+
     /// Return the Ed25519 public key if applicable
     pub fn ed25519(&self) -> Option<ed25519::PublicKey> {
         if self.algorithm == asymmetric::Algorithm::Ed25519 {
@@ -74,6 +115,83 @@ impl PublicKey {
             None
         }
     }
+
+    /// Encode this public key as an ASN.1 DER `SubjectPublicKeyInfo` (i.e.
+    /// the `PUBLIC KEY` PEM format), using the OIDs from RFC 5480 (ECC),
+    /// RFC 5639 (Brainpool), RFC 8410 (Ed25519) and RFC 8017 (RSA).
+    ///
+    /// Returns `None` if the length of the key doesn't match its algorithm.
+    pub fn to_spki_der(&self) -> Option<Vec<u8>> {
+        let (algorithm, parameters, public_key) = match self.algorithm {
+            asymmetric::Algorithm::Rsa2048
+            | asymmetric::Algorithm::Rsa3072
+            | asymmetric::Algorithm::Rsa4096 => {
+                if self.bytes.len() != self.algorithm.key_len() {
+                    return None;
+                }
+
+                // RSAPublicKey ::= SEQUENCE { modulus INTEGER, publicExponent INTEGER }
+                let rsa_public_key = sequence(&[
+                    UintRef::new(&self.bytes).ok()?.to_der().ok()?,
+                    UintRef::new(&RSA_PUBLIC_EXPONENT).ok()?.to_der().ok()?,
+                ])?;
+
+                (
+                    x509::RSA_ENCRYPTION,
+                    Some(Null.to_der().ok()?),
+                    rsa_public_key,
+                )
+            }
+            asymmetric::Algorithm::Ed25519 => {
+                if self.bytes.len() != self.algorithm.key_len() {
+                    return None;
+                }
+
+                (x509::ED25519, None, self.bytes.clone())
+            }
+            curve => {
+                if self.bytes.len() != curve.key_len() * 2 {
+                    return None;
+                }
+
+                (
+                    x509::EC_PUBLIC_KEY,
+                    Some(curve_oid(curve)?.to_der().ok()?),
+                    [&[0x04], self.bytes.as_slice()].concat(),
+                )
+            }
+        };
+
+        let mut algorithm_identifier = vec![algorithm.to_der().ok()?];
+        algorithm_identifier.extend(parameters);
+
+        sequence(&[
+            sequence(&algorithm_identifier)?,
+            BitStringRef::from_bytes(&public_key).ok()?.to_der().ok()?,
+        ])
+    }
+}
+
+/// Get the OID of the named curve of an ECC algorithm
+fn curve_oid(algorithm: asymmetric::Algorithm) -> Option<ObjectIdentifier> {
+    Some(match algorithm {
+        asymmetric::Algorithm::EcP224 => x509::SECP224R1,
+        asymmetric::Algorithm::EcP256 => x509::SECP256R1,
+        asymmetric::Algorithm::EcP384 => x509::SECP384R1,
+        asymmetric::Algorithm::EcP521 => x509::SECP521R1,
+        asymmetric::Algorithm::EcK256 => x509::SECP256K1,
+        asymmetric::Algorithm::EcBp256 => x509::BRAINPOOL_P256R1,
+        asymmetric::Algorithm::EcBp384 => x509::BRAINPOOL_P384R1,
+        asymmetric::Algorithm::EcBp512 => x509::BRAINPOOL_P512R1,
+        _ => return None,
+    })
+}
+
+/// Encode a `SEQUENCE` of already DER-encoded values
+fn sequence(values: &[Vec<u8>]) -> Option<Vec<u8>> {
+    AnyRef::new(Tag::Sequence, &values.concat())
+        .and_then(|any| any.to_der())
+        .ok()
 }
 
 impl AsRef<[u8]> for PublicKey {
@@ -87,3 +205,89 @@ impl Into<Vec<u8>> for PublicKey {
         self.bytes
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decode a hex string
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// Public key of `algorithm` from the BIT STRING at the end of an SPKI
+    /// fixture (which for ECC keys ends with the untagged point)
+    fn public_key(algorithm: asymmetric::Algorithm, spki: &[u8], len: usize) -> PublicKey {
+        PublicKey {
+            algorithm,
+            bytes: spki[spki.len() - len..].into(),
+        }
+    }
+
+    // SPKI fixtures generated with `openssl ec -pubout -outform DER` from
+    // keys created with `openssl ecparam -genkey -name brainpoolP{256,512}r1`
+    const BRAINPOOL_P256R1_SPKI: &str = "305a301406072a8648ce3d020106092b2403030208010107034200\
+        045772dc6d55cb81fc66b55bd979e27f8cfa68799207c666a62d911c0cb18487f2a8b5d27ed47eb69550a5\
+        984e2039e7fe0639e9e29e4ee82c95041a35be0c924d";
+
+    const BRAINPOOL_P512R1_SPKI: &str = "30819b301406072a8648ce3d020106092b240303020801010d03\
+        818200047fd29b3418cc02bd20ad23432b230ec2bb84ed00d97987dc5483a3989c13f4e790f1c21ac3807e\
+        a621c46256a64e0f144e4375a1c67ccb889a452cbda6b414b7431a46c0ef8aee04b3f63482a151d346bac1\
+        4ac4be5f5349d9780ed46d1f6bc1e74898c31aaa8c43c9c31fa750ab0a38cbcd32038941cca96df11b9729\
+        0c582c";
+
+    // Generated with `openssl pkey -pubout -outform DER`
+    const ED25519_SPKI: &str =
+        "302a300506032b6570032100f158ab009461a5c7199926d5574f12de8bb19238bfa306442e5049e2c0d3380b";
+
+    #[test]
+    fn brainpool_spki_fixtures() {
+        for (algorithm, fixture) in [
+            (asymmetric::Algorithm::EcBp256, BRAINPOOL_P256R1_SPKI),
+            (asymmetric::Algorithm::EcBp512, BRAINPOOL_P512R1_SPKI),
+        ] {
+            let spki = hex(fixture);
+            let key = public_key(algorithm, &spki, algorithm.key_len() * 2);
+            assert_eq!(key.to_spki_der().unwrap(), spki, "{algorithm:?}");
+        }
+    }
+
+    #[test]
+    fn ed25519_spki_fixture() {
+        let spki = hex(ED25519_SPKI);
+        let key = public_key(asymmetric::Algorithm::Ed25519, &spki, 32);
+        assert_eq!(key.to_spki_der().unwrap(), spki);
+    }
+
+    #[test]
+    fn rsa_spki() {
+        use rsa::{pkcs8::DecodePublicKey, traits::PublicKeyParts};
+
+        let key = PublicKey {
+            algorithm: asymmetric::Algorithm::Rsa2048,
+            bytes: vec![0xc5; 256],
+        };
+
+        let rsa_key = rsa::RsaPublicKey::from_public_key_der(&key.to_spki_der().unwrap()).unwrap();
+        assert_eq!(rsa_key.n().to_bytes_be(), key.bytes);
+        assert_eq!(rsa_key.e().to_bytes_be(), RSA_PUBLIC_EXPONENT);
+    }
+
+    #[test]
+    fn reject_wrong_lengths() {
+        for algorithm in [
+            asymmetric::Algorithm::Rsa2048,
+            asymmetric::Algorithm::Ed25519,
+            asymmetric::Algorithm::EcBp384,
+        ] {
+            let key = PublicKey {
+                algorithm,
+                bytes: vec![0x42; algorithm.key_len() + 1],
+            };
+            assert!(key.to_spki_der().is_none(), "{algorithm:?}");
+        }
+    }
+}
@@ -3,5 +3,20 @@
 
 mod certificate;
 pub(crate) mod commands;
+mod error;
+mod info;
+mod signed;
+pub(crate) mod x509;
 
-pub use self::certificate::Certificate;
+pub use self::{
+    certificate::Certificate,
+    error::{Error, ErrorKind},
+    info::Info,
+    signed::SignedAttestation,
+};
+
+use crate::object;
+
+/// Object ID of the device attestation key, which is also the ID of the
+/// opaque object containing the device attestation certificate
+pub const DEVICE_ATTESTATION_KEY_ID: object::Id = 0;
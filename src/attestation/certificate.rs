@@ -1,4 +1,6 @@
+use super::{x509, Error, Info};
 use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
 
 /// Attestation certificates (DER encoded X.509)
 #[derive(Serialize, Deserialize, Debug)]
@@ -20,6 +22,54 @@ impl Certificate {
     pub fn as_slice(&self) -> &[u8] {
         self.as_ref()
     }
+
+    /// Get the subject public key this certificate was issued for, i.e. the
+    /// public key of the attested key (as a SEC1 encoded point for elliptic
+    /// curve keys, or a DER encoded `RSAPublicKey` for RSA keys)
+    pub fn public_key(&self) -> Result<Vec<u8>, Error> {
+        Ok(x509::Certificate::parse(self.as_slice())?.public_key.into())
+    }
+
+    /// Verify this certificate's signature chain and extract the attested
+    /// properties of the key it was issued for.
+    ///
+    /// The certificate must be signed by the first of the `intermediates`,
+    /// each intermediate by the next, and the last by `root` (or the
+    /// certificate by `root` directly if there are no intermediates).
+    /// All certificates are DER encoded.
+    ///
+    /// Each intermediate must be a CA (`basicConstraints` with `cA` set, and
+    /// `keyCertSign` in its `keyUsage` if present), which e.g. stops another
+    /// attested key from posing as one. All certificates must be within
+    /// their validity period and may not have critical extensions other
+    /// than `basicConstraints` and `keyUsage`. The root is trusted as given,
+    /// so it needn't be a CA.
+    pub fn verify(&self, intermediates: &[&[u8]], root: &[u8]) -> Result<Info, Error> {
+        let now = SystemTime::now();
+        let leaf = x509::Certificate::parse(self.as_slice())?;
+        leaf.ensure_acceptable(now)?;
+
+        let mut chain = vec![];
+
+        for der in intermediates.iter().copied().chain(Some(root)) {
+            let cert = x509::Certificate::parse(der)?;
+            cert.ensure_acceptable(now)?;
+            chain.push(cert);
+        }
+
+        let mut subject = &leaf;
+
+        for (i, issuer) in chain.iter().enumerate() {
+            if i < intermediates.len() {
+                issuer.ensure_ca()?;
+            }
+
+            subject.verify_issued_by(issuer)?;
+            subject = issuer;
+        }
+
+        Info::from_certificate(&leaf)
+    }
 }
 
 impl AsRef<[u8]> for Certificate {
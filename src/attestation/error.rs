@@ -0,0 +1,36 @@
+//! Attestation errors
+
+use crate::error::{BoxError, Context};
+use thiserror::Error;
+
+/// Attestation-related errors
+pub type Error = crate::Error<ErrorKind>;
+
+/// Kinds of attestation-related errors
+#[derive(Copy, Clone, Debug, Eq, Error, PartialEq)]
+pub enum ErrorKind {
+    /// Malformed certificate
+    #[error("invalid certificate")]
+    CertificateInvalid,
+
+    /// Unsupported key or signature algorithm
+    #[error("unsupported algorithm")]
+    UnsupportedAlgorithm,
+
+    /// Certificate chain failed to verify
+    #[error("verification failed")]
+    VerifyFailed,
+}
+
+impl ErrorKind {
+    /// Create an error context from this error
+    pub fn context(self, source: impl Into<BoxError>) -> Context<ErrorKind> {
+        Context::new(self, Some(source.into()))
+    }
+}
+
+impl From<der::Error> for Error {
+    fn from(err: der::Error) -> Self {
+        ErrorKind::CertificateInvalid.context(err).into()
+    }
+}
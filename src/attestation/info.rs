@@ -0,0 +1,99 @@
+//! Information about an attested key, as embedded in its attestation certificate
+
+use super::{x509, Error, ErrorKind};
+use crate::{asymmetric, object, Capability, Domain};
+use der::{
+    asn1::{BitStringRef, Utf8StringRef},
+    Decode,
+};
+
+/// Properties of a key attested to by the HSM, extracted from the YubiHSM
+/// extensions of its attestation certificate.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Info {
+    /// Algorithm of the attested key
+    pub algorithm: asymmetric::Algorithm,
+
+    /// Object ID of the attested key
+    pub object_id: object::Id,
+
+    /// Capabilities of the attested key
+    pub capabilities: Capability,
+
+    /// Domains the attested key is accessible from
+    pub domains: Domain,
+
+    /// How the attested key was created
+    pub origin: object::Origin,
+
+    /// Label of the attested key (empty if the certificate has no label
+    /// extension, e.g. from firmware which predates it)
+    pub label: object::Label,
+}
+
+impl Info {
+    /// Extract attested key information from a parsed certificate
+    pub(super) fn from_certificate(cert: &x509::Certificate<'_>) -> Result<Self, Error> {
+        let object_id = u16::from_der(extension(cert, x509::YUBICO_OBJECT_ID)?)?;
+
+        let capabilities = Capability::from_bits_retain(u64::from_be_bytes(bit_string(
+            extension(cert, x509::YUBICO_CAPABILITIES)?,
+        )?));
+
+        let domains = Domain::from_bits_retain(u16::from_be_bytes(bit_string(extension(
+            cert,
+            x509::YUBICO_DOMAINS,
+        )?)?));
+
+        let [origin] = bit_string(extension(cert, x509::YUBICO_ORIGIN)?)?;
+        let origin = object::Origin::from_u8(origin)
+            .map_err(|e| ErrorKind::CertificateInvalid.context(e))?;
+
+        let label = match cert.extension(x509::YUBICO_LABEL) {
+            Some(value) => {
+                object::Label::from_bytes(Utf8StringRef::from_der(value)?.as_str().as_bytes())
+                    .map_err(|e| ErrorKind::CertificateInvalid.context(e))?
+            }
+            None => object::Label::default(),
+        };
+
+        Ok(Self {
+            algorithm: cert.public_key_algorithm()?,
+            object_id,
+            capabilities,
+            domains,
+            origin,
+            label,
+        })
+    }
+}
+
+/// Get the value of a required attestation extension
+fn extension<'a>(
+    cert: &x509::Certificate<'a>,
+    id: der::asn1::ObjectIdentifier,
+) -> Result<&'a [u8], Error> {
+    cert.extension(id).ok_or_else(|| {
+        format_err!(
+            ErrorKind::CertificateInvalid,
+            "missing attestation extension: {}",
+            id
+        )
+        .into()
+    })
+}
+
+/// Decode a fixed-size `BIT STRING` extension value
+fn bit_string<const N: usize>(value: &[u8]) -> Result<[u8; N], Error> {
+    BitStringRef::from_der(value)?
+        .as_bytes()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| {
+            format_err!(
+                ErrorKind::CertificateInvalid,
+                "expected {}-byte BIT STRING in attestation extension",
+                N
+            )
+            .into()
+        })
+}
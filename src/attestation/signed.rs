@@ -0,0 +1,29 @@
+//! Signatures bundled with an attestation certificate for the signing key
+
+use crate::object::{self, label};
+use serde::{Deserialize, Serialize};
+
+/// An ECDSA signature over a message, together with an attestation
+/// certificate for the key which produced it, as returned by
+/// [`Client::sign_and_attest`](crate::Client::sign_and_attest).
+///
+/// This allows a verifier to check both that the message was signed by the
+/// key, and (by verifying the certificate) that the key was generated by a
+/// genuine YubiHSM.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SignedAttestation {
+    /// Digest of the message which was signed, computed with the signing
+    /// key's curve's default hash (e.g. SHA-256 for NIST P-256)
+    pub message_hash: Vec<u8>,
+
+    /// ASN.1 DER encoded ECDSA signature of `message_hash`
+    pub signature: Vec<u8>,
+
+    /// DER encoded X.509 attestation certificate for the signing key
+    pub certificate_der: Vec<u8>,
+
+    /// Label of the signing key when the attestation was made (serialized
+    /// as a string)
+    #[serde(default, with = "label::text")]
+    pub label: object::Label,
+}
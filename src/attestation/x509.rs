@@ -0,0 +1,450 @@
+//! Minimal X.509 certificate parser: just enough to verify attestation
+//! certificate chains and extract the YubiHSM-specific extensions.
+
+use super::{Error, ErrorKind};
+use crate::asymmetric;
+use der::{
+    asn1::{AnyRef, BitStringRef, GeneralizedTime, ObjectIdentifier, UintRef, UtcTime},
+    Decode, Reader, Tag, TagNumber, Tagged,
+};
+use signature::Verifier;
+use std::time::SystemTime;
+
+/// `id-ecPublicKey` (RFC 5480)
+pub(crate) const EC_PUBLIC_KEY: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+
+/// `rsaEncryption` (RFC 8017)
+pub(crate) const RSA_ENCRYPTION: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+
+/// `sha256WithRSAEncryption` (RFC 8017)
+#[cfg(all(feature = "rsa", feature = "sha2"))]
+pub(crate) const SHA256_WITH_RSA_ENCRYPTION: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.11");
+
+/// `id-Ed25519` (RFC 8410)
+pub(crate) const ED25519: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+
+/// `ecdsa-with-SHA256` (RFC 5758)
+pub(crate) const ECDSA_WITH_SHA256: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.2");
+
+/// `ecdsa-with-SHA384` (RFC 5758)
+pub(crate) const ECDSA_WITH_SHA384: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.3");
+
+/// NIST P-224 (`secp224r1`)
+pub(crate) const SECP224R1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.132.0.33");
+
+/// NIST P-256 (`secp256r1`, `prime256v1`)
+pub(crate) const SECP256R1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.3.1.7");
+
+/// NIST P-384 (`secp384r1`)
+pub(crate) const SECP384R1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.132.0.34");
+
+/// NIST P-521 (`secp521r1`)
+pub(crate) const SECP521R1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.132.0.35");
+
+/// `secp256k1`
+pub(crate) const SECP256K1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.132.0.10");
+
+/// `brainpoolP256r1`
+pub(crate) const BRAINPOOL_P256R1: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.36.3.3.2.8.1.1.7");
+
+/// `brainpoolP384r1`
+pub(crate) const BRAINPOOL_P384R1: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.36.3.3.2.8.1.1.11");
+
+/// `brainpoolP512r1`
+pub(crate) const BRAINPOOL_P512R1: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.36.3.3.2.8.1.1.13");
+
+/// `basicConstraints` extension (RFC 5280 Section 4.2.1.9)
+pub(crate) const BASIC_CONSTRAINTS: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.29.19");
+
+/// `keyUsage` extension (RFC 5280 Section 4.2.1.3)
+pub(crate) const KEY_USAGE: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.29.15");
+
+/// `keyCertSign` bit of the `keyUsage` extension (bit 5, i.e. in the first byte)
+const KEY_CERT_SIGN: u8 = 0x04;
+
+/// Attestation extension: firmware version of the device (`OCTET STRING`)
+#[cfg(feature = "mockhsm")]
+pub(crate) const YUBICO_FIRMWARE_VERSION: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.41482.4.1");
+
+/// Attestation extension: serial number of the device (`INTEGER`)
+#[cfg(feature = "mockhsm")]
+pub(crate) const YUBICO_SERIAL_NUMBER: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.41482.4.2");
+
+/// Attestation extension: origin of the key (`BIT STRING`)
+pub(crate) const YUBICO_ORIGIN: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.41482.4.3");
+
+/// Attestation extension: domains of the key (`BIT STRING`)
+pub(crate) const YUBICO_DOMAINS: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.41482.4.4");
+
+/// Attestation extension: capabilities of the key (`BIT STRING`)
+pub(crate) const YUBICO_CAPABILITIES: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.41482.4.5");
+
+/// Attestation extension: object ID of the key (`INTEGER`)
+pub(crate) const YUBICO_OBJECT_ID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.41482.4.6");
+
+/// Attestation extension: label of the key (`UTF8String`)
+pub(crate) const YUBICO_LABEL: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.41482.4.9");
+
+/// Parsed (borrowed) X.509 certificate
+pub(crate) struct Certificate<'a> {
+    /// DER encoding of the `TBSCertificate`, i.e. the signed portion of the certificate
+    pub tbs_certificate: &'a [u8],
+
+    /// Algorithm used to sign the certificate
+    pub signature_algorithm: ObjectIdentifier,
+
+    /// Signature over `tbs_certificate`
+    pub signature: &'a [u8],
+
+    /// DER encoding of the issuer `Name`
+    pub issuer: &'a [u8],
+
+    /// Start of the certificate's validity period
+    pub not_before: SystemTime,
+
+    /// End of the certificate's validity period
+    pub not_after: SystemTime,
+
+    /// DER encoding of the subject `Name`
+    pub subject: &'a [u8],
+
+    /// Algorithm of the subject public key
+    pub public_key_algorithm: ObjectIdentifier,
+
+    /// Named curve of the subject public key (for elliptic curve keys)
+    pub public_key_curve: Option<ObjectIdentifier>,
+
+    /// Subject public key
+    pub public_key: &'a [u8],
+
+    /// Certificate extensions
+    pub extensions: Vec<Extension<'a>>,
+}
+
+/// Parsed (borrowed) certificate extension
+pub(crate) struct Extension<'a> {
+    /// Extension ID (`extnID`)
+    pub id: ObjectIdentifier,
+
+    /// Must the extension be understood to accept the certificate?
+    pub critical: bool,
+
+    /// DER encoding of the extension value (`extnValue`)
+    pub value: &'a [u8],
+}
+
+impl<'a> Certificate<'a> {
+    /// Parse a DER-encoded certificate
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, Error> {
+        let (tbs_certificate, signature_algorithm, signature) =
+            AnyRef::from_der(bytes)?.sequence(|reader| {
+                let tbs_certificate = reader.tlv_bytes()?;
+                let (signature_algorithm, _) = algorithm_identifier(reader.decode()?)?;
+                let signature = reader.decode::<BitStringRef<'a>>()?.raw_bytes();
+                Ok((tbs_certificate, signature_algorithm, signature))
+            })?;
+
+        AnyRef::from_der(tbs_certificate)?
+            .sequence(|reader| {
+                // version [0] EXPLICIT INTEGER DEFAULT v1
+                if reader.peek_tag()? == context_specific(TagNumber::N0) {
+                    reader.decode::<AnyRef<'a>>()?;
+                }
+
+                let _serial_number = reader.decode::<AnyRef<'a>>()?;
+                let _signature = reader.decode::<AnyRef<'a>>()?;
+                let issuer = reader.tlv_bytes()?;
+                let (not_before, not_after) =
+                    reader.decode::<AnyRef<'a>>()?.sequence(|validity| {
+                        Ok((time(validity.decode()?)?, time(validity.decode()?)?))
+                    })?;
+                let subject = reader.tlv_bytes()?;
+
+                let (public_key_algorithm, public_key_curve, public_key) =
+                    reader.decode::<AnyRef<'a>>()?.sequence(|spki| {
+                        let (algorithm, parameters) = algorithm_identifier(spki.decode()?)?;
+                        let public_key = spki.decode::<BitStringRef<'a>>()?.raw_bytes();
+                        let curve = match parameters {
+                            Some(params) if params.tag() == Tag::ObjectIdentifier => {
+                                Some(params.decode_as()?)
+                            }
+                            _ => None,
+                        };
+                        Ok((algorithm, curve, public_key))
+                    })?;
+
+                let mut extensions = vec![];
+
+                // issuerUniqueID [1], subjectUniqueID [2], extensions [3]
+                while !reader.is_finished() {
+                    let field = reader.decode::<AnyRef<'a>>()?;
+
+                    if field.tag() == context_specific(TagNumber::N3) {
+                        AnyRef::from_der(field.value())?.sequence(|exts| {
+                            while !exts.is_finished() {
+                                extensions.push(exts.decode::<AnyRef<'a>>()?.sequence(|ext| {
+                                    let id = ext.decode::<ObjectIdentifier>()?;
+
+                                    // critical BOOLEAN DEFAULT FALSE
+                                    let critical =
+                                        ext.peek_tag()? == Tag::Boolean && ext.decode::<bool>()?;
+
+                                    let value = ext.decode::<AnyRef<'a>>()?;
+                                    value.tag().assert_eq(Tag::OctetString)?;
+
+                                    Ok(Extension {
+                                        id,
+                                        critical,
+                                        value: value.value(),
+                                    })
+                                })?);
+                            }
+
+                            Ok(())
+                        })?;
+                    }
+                }
+
+                Ok(Self {
+                    tbs_certificate,
+                    signature_algorithm,
+                    signature,
+                    issuer,
+                    not_before,
+                    not_after,
+                    subject,
+                    public_key_algorithm,
+                    public_key_curve,
+                    public_key,
+                    extensions,
+                })
+            })
+            .map_err(Into::into)
+    }
+
+    /// Get the value of the extension with the given ID (if present)
+    pub fn extension(&self, id: ObjectIdentifier) -> Option<&'a [u8]> {
+        self.extensions
+            .iter()
+            .find(|ext| ext.id == id)
+            .map(|ext| ext.value)
+    }
+
+    /// Ensure this certificate is within its validity period at `now`, and
+    /// has no critical extensions we don't understand (RFC 5280 Section 4.2)
+    pub fn ensure_acceptable(&self, now: SystemTime) -> Result<(), Error> {
+        ensure!(
+            self.not_before <= now && now <= self.not_after,
+            ErrorKind::VerifyFailed,
+            "certificate is not valid at the current time"
+        );
+
+        if let Some(ext) = self
+            .extensions
+            .iter()
+            .find(|ext| ext.critical && ![BASIC_CONSTRAINTS, KEY_USAGE].contains(&ext.id))
+        {
+            fail!(
+                ErrorKind::VerifyFailed,
+                "unsupported critical extension: {}",
+                ext.id
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Ensure this certificate is a CA which may issue other certificates,
+    /// i.e. its `basicConstraints` has `cA` set and its `keyUsage` (if
+    /// present) includes `keyCertSign`
+    pub fn ensure_ca(&self) -> Result<(), Error> {
+        // BasicConstraints ::= SEQUENCE { cA BOOLEAN DEFAULT FALSE, ... }
+        let is_ca = match self.extension(BASIC_CONSTRAINTS) {
+            Some(value) => AnyRef::from_der(value)?.sequence(|reader| {
+                let ca = !reader.is_finished()
+                    && reader.peek_tag()? == Tag::Boolean
+                    && reader.decode::<bool>()?;
+
+                // pathLenConstraint INTEGER OPTIONAL
+                reader.decode::<Option<UintRef<'_>>>()?;
+                Ok(ca)
+            })?,
+            None => false,
+        };
+
+        ensure!(
+            is_ca,
+            ErrorKind::VerifyFailed,
+            "issuing certificate is not a CA"
+        );
+
+        if let Some(value) = self.extension(KEY_USAGE) {
+            let key_usage = BitStringRef::from_der(value)?;
+
+            ensure!(
+                key_usage.raw_bytes().first().copied().unwrap_or_default() & KEY_CERT_SIGN != 0,
+                ErrorKind::VerifyFailed,
+                "issuing certificate's key usage does not allow signing certificates"
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Determine the YubiHSM asymmetric algorithm of the subject public key
+    pub fn public_key_algorithm(&self) -> Result<asymmetric::Algorithm, Error> {
+        Ok(match (self.public_key_algorithm, self.public_key_curve) {
+            (EC_PUBLIC_KEY, Some(SECP224R1)) => asymmetric::Algorithm::EcP224,
+            (EC_PUBLIC_KEY, Some(SECP256R1)) => asymmetric::Algorithm::EcP256,
+            (EC_PUBLIC_KEY, Some(SECP384R1)) => asymmetric::Algorithm::EcP384,
+            (EC_PUBLIC_KEY, Some(SECP521R1)) => asymmetric::Algorithm::EcP521,
+            (EC_PUBLIC_KEY, Some(SECP256K1)) => asymmetric::Algorithm::EcK256,
+            (EC_PUBLIC_KEY, Some(BRAINPOOL_P256R1)) => asymmetric::Algorithm::EcBp256,
+            (EC_PUBLIC_KEY, Some(BRAINPOOL_P384R1)) => asymmetric::Algorithm::EcBp384,
+            (EC_PUBLIC_KEY, Some(BRAINPOOL_P512R1)) => asymmetric::Algorithm::EcBp512,
+            (ED25519, _) => asymmetric::Algorithm::Ed25519,
+            (RSA_ENCRYPTION, _) => {
+                // RSAPublicKey ::= SEQUENCE { modulus INTEGER, publicExponent INTEGER }
+                let modulus = AnyRef::from_der(self.public_key)?
+                    .sequence(|reader| {
+                        let modulus = reader.decode::<UintRef<'a>>()?;
+                        reader.decode::<UintRef<'a>>()?;
+                        Ok(modulus)
+                    })?
+                    .as_bytes();
+
+                match modulus.len() {
+                    256 => asymmetric::Algorithm::Rsa2048,
+                    384 => asymmetric::Algorithm::Rsa3072,
+                    512 => asymmetric::Algorithm::Rsa4096,
+                    len => fail!(
+                        ErrorKind::UnsupportedAlgorithm,
+                        "unsupported RSA modulus size: {}-bits",
+                        len * 8
+                    ),
+                }
+            }
+            (algorithm, curve) => fail!(
+                ErrorKind::UnsupportedAlgorithm,
+                "unsupported public key algorithm: {} (curve: {:?})",
+                algorithm,
+                curve
+            ),
+        })
+    }
+
+    /// Verify this certificate was signed by the subject of `issuer`
+    pub fn verify_issued_by(&self, issuer: &Certificate<'_>) -> Result<(), Error> {
+        ensure!(
+            self.issuer == issuer.subject,
+            ErrorKind::VerifyFailed,
+            "certificate issuer does not match the subject of the issuing certificate"
+        );
+
+        match (
+            issuer.public_key_algorithm,
+            issuer.public_key_curve,
+            self.signature_algorithm,
+        ) {
+            (EC_PUBLIC_KEY, Some(SECP256R1), ECDSA_WITH_SHA256) => {
+                let verifying_key = p256::ecdsa::VerifyingKey::from_sec1_bytes(issuer.public_key)
+                    .map_err(|e| ErrorKind::CertificateInvalid.context(e))?;
+                let signature = p256::ecdsa::Signature::from_der(self.signature)
+                    .map_err(|e| ErrorKind::CertificateInvalid.context(e))?;
+
+                verifying_key
+                    .verify(self.tbs_certificate, &signature)
+                    .map_err(|e| ErrorKind::VerifyFailed.context(e).into())
+            }
+            (EC_PUBLIC_KEY, Some(SECP384R1), ECDSA_WITH_SHA384) => {
+                let verifying_key = p384::ecdsa::VerifyingKey::from_sec1_bytes(issuer.public_key)
+                    .map_err(|e| ErrorKind::CertificateInvalid.context(e))?;
+                let signature = p384::ecdsa::Signature::from_der(self.signature)
+                    .map_err(|e| ErrorKind::CertificateInvalid.context(e))?;
+
+                verifying_key
+                    .verify(self.tbs_certificate, &signature)
+                    .map_err(|e| ErrorKind::VerifyFailed.context(e).into())
+            }
+            #[cfg(all(feature = "rsa", feature = "sha2"))]
+            (RSA_ENCRYPTION, _, SHA256_WITH_RSA_ENCRYPTION) => {
+                use rsa::{pkcs1::DecodeRsaPublicKey, Pkcs1v15Sign, RsaPublicKey};
+                use sha2::{Digest, Sha256};
+
+                /// `DigestInfo` prefix for SHA-256 (RFC 8017 Section 9.2)
+                const SHA256_DIGEST_INFO_PREFIX: &[u8] = &[
+                    0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04,
+                    0x02, 0x01, 0x05, 0x00, 0x04, 0x20,
+                ];
+
+                let verifying_key = RsaPublicKey::from_pkcs1_der(issuer.public_key)
+                    .map_err(|e| ErrorKind::CertificateInvalid.context(e))?;
+
+                let scheme = Pkcs1v15Sign {
+                    hash_len: Some(32),
+                    prefix: SHA256_DIGEST_INFO_PREFIX.into(),
+                };
+
+                verifying_key
+                    .verify(
+                        scheme,
+                        &Sha256::digest(self.tbs_certificate),
+                        self.signature,
+                    )
+                    .map_err(|e| ErrorKind::VerifyFailed.context(e).into())
+            }
+            (key_algorithm, curve, signature_algorithm) => fail!(
+                ErrorKind::UnsupportedAlgorithm,
+                "unsupported signature algorithm: {} (issuer key: {}, curve: {:?})",
+                signature_algorithm,
+                key_algorithm,
+                curve
+            ),
+        }
+    }
+}
+
+/// Decode an `AlgorithmIdentifier` into its OID and (optional) parameters
+fn algorithm_identifier(any: AnyRef<'_>) -> der::Result<(ObjectIdentifier, Option<AnyRef<'_>>)> {
+    any.sequence(|reader| {
+        let algorithm = reader.decode()?;
+        let parameters = if reader.is_finished() {
+            None
+        } else {
+            Some(reader.decode()?)
+        };
+        Ok((algorithm, parameters))
+    })
+}
+
+/// Decode a `Time` (either a `UTCTime` or a `GeneralizedTime`)
+fn time(any: AnyRef<'_>) -> der::Result<SystemTime> {
+    if any.tag() == Tag::UtcTime {
+        Ok(any.decode_as::<UtcTime>()?.to_system_time())
+    } else {
+        Ok(any.decode_as::<GeneralizedTime>()?.to_system_time())
+    }
+}
+
+/// Constructed context-specific tag with the given number
+pub(crate) fn context_specific(number: TagNumber) -> Tag {
+    Tag::ContextSpecific {
+        constructed: true,
+        number,
+    }
+}
@@ -1,9 +1,15 @@
 //! Auditing options (for use with the `get_option` and `put_option` command)
+//! and analysis of the audit log
 
 pub(crate) mod commands;
 mod error;
+mod usage;
 
-pub use self::error::{Error, ErrorKind};
+pub use self::{
+    commands::{LogDigest, LogEntries, LogEntry, LOG_DIGEST_SIZE},
+    error::{Error, ErrorKind},
+    usage::{usage_report, CommandUsage, KeyUsage, UsageReport},
+};
 
 use crate::command;
 use serde::{de, ser, Deserialize, Serialize};
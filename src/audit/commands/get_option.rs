@@ -15,7 +15,6 @@
 //! [get_force_audit_option()]: https://docs.rs/yubihsm/latest/yubihsm/client/struct.Client.html#method.get_force_audit_option
 
 use crate::{
-    audit::AuditTag,
     command::{self, Command},
     response::Response,
 };
@@ -24,8 +23,8 @@ use serde::{Deserialize, Serialize};
 /// Request parameters for `command::get_option`
 #[derive(Serialize, Deserialize, Debug)]
 pub(crate) struct GetOptionCommand {
-    /// Tag byte for `Force` vs `Command` options
-    pub tag: AuditTag,
+    /// Option ID (e.g. an `AuditTag` for the `Force` and `Command` options)
+    pub tag: u8,
 }
 
 impl Command for GetOptionCommand {
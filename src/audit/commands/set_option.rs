@@ -4,7 +4,6 @@
 //! <https://developers.yubico.com/YubiHSM2/Commands/Set_Option.html>
 
 use crate::{
-    audit::*,
     command::{self, Command},
     response::Response,
 };
@@ -13,8 +12,8 @@ use serde::{Deserialize, Serialize};
 /// Request parameters for `command::put_option`
 #[derive(Serialize, Deserialize, Debug)]
 pub(crate) struct SetOptionCommand {
-    /// Tag byte for `Force` vs `Command` options
-    pub tag: AuditTag,
+    /// Option ID (e.g. an `AuditTag` for the `Force` and `Command` options)
+    pub tag: u8,
 
     /// Length of the option-specific data
     pub length: u16,
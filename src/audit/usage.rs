@@ -0,0 +1,234 @@
+//! Key usage reports aggregated from the audit log
+
+use super::LogEntry;
+use crate::{command, object, object::label};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Key ID the HSM logs for entries which don't target a key
+const NO_KEY: object::Id = 0xffff;
+
+/// Aggregate audit log entries into a [`UsageReport`].
+///
+/// See [`UsageReport::extend`] for how item numbers are interpreted.
+pub fn usage_report(entries: impl Iterator<Item = LogEntry>) -> UsageReport {
+    let mut report = UsageReport::default();
+    report.extend(entries);
+    report
+}
+
+/// Per-key usage counts derived from the audit log, broken down by command
+/// type and result
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct UsageReport {
+    /// Item number of the first entry included in this report
+    pub first_item: Option<u16>,
+
+    /// Item number of the last entry included in this report
+    pub last_item: Option<u16>,
+
+    /// Number of log entries included in this report
+    pub entries: u64,
+
+    /// Number of entries missing from the log (i.e. gaps in item numbers,
+    /// which indicate the log was consumed or overwritten between reads)
+    pub missing_entries: u64,
+
+    /// Usage of each key targeted by a logged command
+    pub keys: BTreeMap<object::Id, KeyUsage>,
+
+    /// Item number of the last entry included when key labels were resolved
+    /// with [`UsageReport::resolve_labels`] (if they have been). Labels are
+    /// possibly stale for keys used after this point.
+    pub labels_as_of: Option<u16>,
+}
+
+impl UsageReport {
+    /// Add entries to this report.
+    ///
+    /// Entries are expected in log order. Item numbers are 16-bit and wrap
+    /// around, so each entry is placed relative to the previous one: entries
+    /// which were already included (e.g. when successive reads of the log
+    /// overlap) are skipped, and gaps are counted as missing entries.
+    ///
+    /// Entries are attributed to the key they target regardless of whether
+    /// the session which performed them was opened within this report, so a
+    /// report may be extended with the next read of the log to cover sessions
+    /// spanning report boundaries.
+    pub fn extend(&mut self, entries: impl Iterator<Item = LogEntry>) {
+        for entry in entries {
+            if let Some(last_item) = self.last_item {
+                // Entries more than half the counter range "behind" the last
+                // one are treated as having wrapped around
+                match entry.item.wrapping_sub(last_item) {
+                    0 => continue,
+                    delta if delta > u16::MAX / 2 => continue,
+                    delta => self.missing_entries += u64::from(delta - 1),
+                }
+            } else {
+                self.first_item = Some(entry.item);
+            }
+
+            self.last_item = Some(entry.item);
+            self.entries += 1;
+
+            if entry.target_key != NO_KEY {
+                self.keys
+                    .entry(entry.target_key)
+                    .or_default()
+                    .record(&entry);
+            }
+        }
+    }
+
+    /// Get the usage of a particular key
+    pub fn key(&self, key_id: object::Id) -> Option<&KeyUsage> {
+        self.keys.get(&key_id)
+    }
+
+    /// Label each key in this report using information about the objects
+    /// in the HSM, e.g. as listed by
+    /// [`Client::list_objects_detailed`](crate::Client::list_objects_detailed).
+    ///
+    /// These are the objects' current labels, not necessarily the ones they
+    /// had when the logged commands were performed, so they're possibly
+    /// stale: a key may have since been relabeled, or deleted and replaced
+    /// by another object with the same ID. Keys which no longer exist (or
+    /// whose ID is shared by objects with different labels) are unlabeled.
+    pub fn resolve_labels(&mut self, objects: &[object::Info]) {
+        for (&key_id, usage) in &mut self.keys {
+            let mut labels = objects
+                .iter()
+                .filter(|info| info.object_id == key_id)
+                .map(|info| &info.label);
+
+            usage.label = labels
+                .next()
+                .filter(|first| labels.all(|label| label == *first))
+                .cloned();
+        }
+
+        self.labels_as_of = self.last_item;
+    }
+}
+
+/// Usage of a particular key
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct KeyUsage {
+    /// Counts for each command type performed with this key
+    pub commands: BTreeMap<command::Code, CommandUsage>,
+
+    /// Label of this key, if resolved with [`UsageReport::resolve_labels`]
+    /// (serialized as a string)
+    #[serde(default, with = "label::text::option")]
+    pub label: Option<object::Label>,
+}
+
+impl KeyUsage {
+    /// Get the counts for a particular command type
+    pub fn command(&self, command_type: command::Code) -> CommandUsage {
+        self.commands
+            .get(&command_type)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Record a log entry
+    fn record(&mut self, entry: &LogEntry) {
+        let usage = self.commands.entry(entry.cmd).or_default();
+
+        if entry.result.is_success() {
+            usage.succeeded += 1;
+        } else {
+            usage.failed += 1;
+        }
+    }
+}
+
+/// Number of times a command was performed with a key, by result
+#[derive(Copy, Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CommandUsage {
+    /// Successful invocations
+    pub succeeded: u64,
+
+    /// Failed invocations
+    pub failed: u64,
+}
+
+impl CommandUsage {
+    /// Total invocations regardless of result
+    pub fn total(&self) -> u64 {
+        self.succeeded + self.failed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{audit::LogDigest, response};
+
+    fn entry(item: u16, cmd: command::Code, target_key: object::Id, success: bool) -> LogEntry {
+        LogEntry {
+            item,
+            cmd,
+            length: 0,
+            session_key: 1,
+            target_key,
+            second_key: NO_KEY,
+            result: if success {
+                response::Code::Success(cmd)
+            } else {
+                response::Code::DeviceInsufficientPermissions
+            },
+            tick: 0,
+            digest: LogDigest([0u8; 16]),
+        }
+    }
+
+    #[test]
+    fn counter_wraparound_test() {
+        let entries = vec![
+            entry(0xfffe, command::Code::SignEcdsa, 201, true),
+            entry(0xffff, command::Code::SignEcdsa, 201, true),
+            entry(0x0000, command::Code::SignEcdsa, 201, false),
+            entry(0x0002, command::Code::SignHmac, 300, true),
+        ];
+
+        let report = usage_report(entries.into_iter());
+        assert_eq!(report.first_item, Some(0xfffe));
+        assert_eq!(report.last_item, Some(0x0002));
+        assert_eq!(report.entries, 4);
+        assert_eq!(report.missing_entries, 1);
+
+        let usage = report.key(201).unwrap().command(command::Code::SignEcdsa);
+        assert_eq!((usage.succeeded, usage.failed), (2, 1));
+        assert_eq!(
+            report
+                .key(300)
+                .unwrap()
+                .command(command::Code::SignHmac)
+                .total(),
+            1
+        );
+    }
+
+    #[test]
+    fn overlapping_reads_test() {
+        let mut report =
+            usage_report((1..=3).map(|item| entry(item, command::Code::SignEcdsa, 201, true)));
+
+        // The next read of the log repeats entries already included
+        report.extend((2..=5).map(|item| entry(item, command::Code::SignEcdsa, 201, true)));
+
+        assert_eq!(report.entries, 5);
+        assert_eq!(report.missing_entries, 0);
+        assert_eq!(
+            report
+                .key(201)
+                .unwrap()
+                .command(command::Code::SignEcdsa)
+                .succeeded,
+            5
+        );
+    }
+}
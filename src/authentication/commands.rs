@@ -1,6 +1,8 @@
-//! Put an existing auth key into the `YubiHSM 2`
+//! Put an existing auth key into the `YubiHSM 2`, or change the key material
+//! of the current session's auth key
 //!
 //! <https://developers.yubico.com/YubiHSM2/Commands/Put_Authentication_Key.html>
+//! <https://developers.yubico.com/YubiHSM2/Commands/Change_Authentication_Key.html>
 
 use crate::{
     authentication,
@@ -38,3 +40,31 @@ pub(crate) struct PutAuthenticationKeyResponse {
 impl Response for PutAuthenticationKeyResponse {
     const COMMAND_CODE: command::Code = command::Code::PutAuthenticationKey;
 }
+
+/// Request parameters for `command::change_authentication_key`
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct ChangeAuthenticationKeyCommand {
+    /// ID of the key to change (which must be the current session's)
+    pub key_id: object::Id,
+
+    /// Authentication key algorithm
+    pub algorithm: authentication::Algorithm,
+
+    /// New authentication key
+    pub authentication_key: authentication::Key,
+}
+
+impl Command for ChangeAuthenticationKeyCommand {
+    type ResponseType = ChangeAuthenticationKeyResponse;
+}
+
+/// Response from `command::change_authentication_key`
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct ChangeAuthenticationKeyResponse {
+    /// ID of the key
+    pub key_id: object::Id,
+}
+
+impl Response for ChangeAuthenticationKeyResponse {
+    const COMMAND_CODE: command::Code = command::Code::ChangeAuthenticationKey;
+}
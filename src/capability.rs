@@ -1,5 +1,6 @@
 //! Object attributes specifying which operations are allowed to be performed
 
+use crate::{command, object};
 use bitflags::bitflags;
 use serde::{
     de::{self, Deserialize, Deserializer, Visitor},
@@ -14,6 +15,19 @@ bitflags! {
     /// Object attributes specifying which operations are allowed to be performed
     ///
     /// <https://developers.yubico.com/YubiHSM2/Concepts/Capability.html>
+    ///
+    /// Sets of capabilities can be combined with `|`, or collected from an
+    /// iterator of capabilities (an empty iterator collects to the empty set):
+    ///
+    /// ```
+    /// use yubihsm::Capability;
+    ///
+    /// let capabilities: Capability = [Capability::SIGN_ECDSA, Capability::EXPORTABLE_UNDER_WRAP]
+    ///     .into_iter()
+    ///     .collect();
+    ///
+    /// assert_eq!(capabilities, Capability::SIGN_ECDSA | Capability::EXPORTABLE_UNDER_WRAP);
+    /// ```
     #[derive(Clone, Copy, Debug, Eq, PartialEq)]
     pub struct Capability: u64 {
         /// `derive-ecdh`: perform ECDH operation
@@ -211,15 +225,27 @@ bitflags! {
     }
 }
 
-impl Default for Capability {
-    fn default() -> Self {
-        Capability::empty()
+impl Capability {
+    /// Names of the capabilities in this set (as used by `yubihsm-shell`),
+    /// sorted alphabetically.
+    ///
+    /// Capabilities without a known name are listed as `unknown-<bit>`.
+    pub fn names(self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .iter()
+            .map(|capability| match capability.name() {
+                Some(name) => name.to_owned(),
+                None => format!("unknown-{}", capability.bits().trailing_zeros()),
+            })
+            .collect();
+
+        names.sort();
+        names
     }
-}
 
-impl Display for Capability {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let s = match *self {
+    /// Name of a single capability, if it's known
+    fn name(self) -> Option<&'static str> {
+        Some(match self {
             Capability::DERIVE_ECDH => "derive-ecdh",
             Capability::DECRYPT_OAEP => "decrypt-oaep",
             Capability::DECRYPT_PKCS => "decrypt-pkcs",
@@ -267,9 +293,151 @@ impl Display for Capability {
             Capability::UNWRAP_DATA => "unwrap-data",
             Capability::WRAP_DATA => "wrap-data",
             Capability::CHANGE_AUTHENTICATION_KEY => "change-authentication-key",
-            _ => return Err(fmt::Error), // we don't support displaying this capability yet
-        };
+            _ => return None,
+        })
+    }
+
+    /// Compute the `delegated_capabilities` an authentication key with these
+    /// capabilities needs in order to create objects with (up to) the given
+    /// `child_capabilities`.
+    ///
+    /// Objects generated, imported, or unwrapped in a session can only be
+    /// given capabilities delegated by its authentication key. If the key
+    /// can create other authentication keys (i.e. it has
+    /// `PUT_AUTHENTICATION_KEY`) its own capabilities are delegated as well,
+    /// so the authentication keys it creates may be given the same rights.
+    pub fn delegated_for(self, child_capabilities: Capability) -> Capability {
+        if self.contains(Capability::PUT_AUTHENTICATION_KEY) {
+            child_capabilities | self
+        } else {
+            child_capabilities
+        }
+    }
+
+    /// Capability an authentication key needs to execute the given command
+    /// (per the YubiHSM 2 [command reference]), or the empty set if none is
+    /// needed (e.g. `Echo` or `ListObjects`).
+    ///
+    /// Commands which operate on an object (e.g. `SignEcdsa`) also require
+    /// the object to have the same capability.
+    ///
+    /// A few commands need a capability which depends on their arguments,
+    /// and the union of all of them is returned:
+    ///
+    /// - `DeleteObject` needs the `delete-*` capability for the object's
+    ///   type (see [`Capability::required_for_delete`])
+    /// - `RewrapOtpAead` needs `rewrap-from-otp-aead-key` on the key it
+    ///   rewraps from, and `rewrap-to-otp-aead-key` on the one it rewraps to
+    ///
+    /// [command reference]: https://developers.yubico.com/YubiHSM2/Commands/
+    pub fn required_for_command(code: command::Code) -> Capability {
+        use command::Code;
+
+        match code {
+            Code::ResetDevice => Capability::RESET_DEVICE,
+            Code::PutOpaqueObject => Capability::PUT_OPAQUE,
+            Code::GetOpaqueObject => Capability::GET_OPAQUE,
+            Code::PutAuthenticationKey => Capability::PUT_AUTHENTICATION_KEY,
+            Code::PutAsymmetricKey => Capability::PUT_ASYMMETRIC_KEY,
+            Code::GenerateAsymmetricKey => Capability::GENERATE_ASYMMETRIC_KEY,
+            Code::SignPkcs1 => Capability::SIGN_PKCS,
+            Code::DecryptPkcs1 => Capability::DECRYPT_PKCS,
+            Code::ExportWrapped => Capability::EXPORT_WRAPPED,
+            Code::ImportWrapped => Capability::IMPORT_WRAPPED,
+            Code::PutWrapKey => Capability::PUT_WRAP_KEY,
+            Code::GetLogEntries | Code::SetLogIndex => Capability::GET_LOG_ENTRIES,
+            Code::SetOption => Capability::PUT_OPTION,
+            Code::GetOption => Capability::GET_OPTION,
+            Code::GetPseudoRandom => Capability::GET_PSEUDO_RANDOM,
+            Code::PutHmacKey => Capability::PUT_HMAC_KEY,
+            Code::SignHmac => Capability::SIGN_HMAC,
+            Code::SignPss => Capability::SIGN_PSS,
+            Code::SignEcdsa => Capability::SIGN_ECDSA,
+            Code::DeriveEcdh => Capability::DERIVE_ECDH,
+            Code::DeleteObject => [
+                object::Type::Opaque,
+                object::Type::AuthenticationKey,
+                object::Type::AsymmetricKey,
+                object::Type::WrapKey,
+                object::Type::HmacKey,
+                object::Type::Template,
+                object::Type::OtpAeadKey,
+            ]
+            .into_iter()
+            .map(Capability::required_for_delete)
+            .collect(),
+            Code::DecryptOaep => Capability::DECRYPT_OAEP,
+            Code::GenerateHmacKey => Capability::GENERATE_HMAC_KEY,
+            Code::GenerateWrapKey => Capability::GENERATE_WRAP_KEY,
+            Code::VerifyHmac => Capability::VERIFY_HMAC,
+            Code::SignSshCertificate => Capability::SIGN_SSH_CERTIFICATE,
+            Code::PutTemplate => Capability::PUT_TEMPLATE,
+            Code::GetTemplate => Capability::GET_TEMPLATE,
+            Code::DecryptOtp => Capability::DECRYPT_OTP,
+            Code::CreateOtpAead => Capability::CREATE_OTP_AEAD,
+            Code::RandomizeOtpAead => Capability::RANDOMIZE_OTP_AEAD,
+            Code::RewrapOtpAead => {
+                Capability::REWRAP_FROM_OTP_AEAD_KEY | Capability::REWRAP_TO_OTP_AEAD_KEY
+            }
+            Code::SignAttestationCertificate => Capability::SIGN_ATTESTATION_CERTIFICATE,
+            Code::PutOtpAead => Capability::PUT_OTP_AEAD_KEY,
+            Code::GenerateOtpAead => Capability::GENERATE_OTP_AEAD_KEY,
+            Code::WrapData => Capability::WRAP_DATA,
+            Code::UnwrapData => Capability::UNWRAP_DATA,
+            Code::SignEddsa => Capability::SIGN_EDDSA,
+            Code::ChangeAuthenticationKey => Capability::CHANGE_AUTHENTICATION_KEY,
+            Code::Echo
+            | Code::CreateSession
+            | Code::AuthenticateSession
+            | Code::SessionMessage
+            | Code::DeviceInfo
+            | Code::Bsl
+            | Code::Command9
+            | Code::CloseSession
+            | Code::GetStorageInfo
+            | Code::ListObjects
+            | Code::GetObjectInfo
+            | Code::GetPublicKey
+            | Code::BlinkDevice
+            | Code::Error
+            | Code::HsmInitialization
+            | Code::Unknown(_) => Capability::empty(),
+        }
+    }
+
+    /// Capabilities an authentication key needs to execute all of the given
+    /// commands (see [`Capability::required_for_command`])
+    pub fn required_for_commands(codes: &[command::Code]) -> Capability {
+        codes
+            .iter()
+            .map(|&code| Capability::required_for_command(code))
+            .collect()
+    }
 
+    /// Capability needed to delete objects of the given type
+    pub fn required_for_delete(object_type: object::Type) -> Capability {
+        match object_type {
+            object::Type::Opaque => Capability::DELETE_OPAQUE,
+            object::Type::AuthenticationKey => Capability::DELETE_AUTHENTICATION_KEY,
+            object::Type::AsymmetricKey => Capability::DELETE_ASYMMETRIC_KEY,
+            object::Type::WrapKey => Capability::DELETE_WRAP_KEY,
+            object::Type::HmacKey => Capability::DELETE_HMAC_KEY,
+            object::Type::Template => Capability::DELETE_TEMPLATE,
+            object::Type::OtpAeadKey => Capability::DELETE_OTP_AEAD_KEY,
+        }
+    }
+}
+
+impl Default for Capability {
+    fn default() -> Self {
+        Capability::empty()
+    }
+}
+
+impl Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // we don't support displaying this capability yet
+        let s = self.name().ok_or(fmt::Error)?;
         write!(f, "{s}")
     }
 }
@@ -365,3 +533,124 @@ impl<'de> Deserialize<'de> for Capability {
         deserializer.deserialize_u64(CapabilityVisitor)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collect_capabilities() {
+        let capabilities: Capability = [
+            Capability::SIGN_ECDSA,
+            Capability::EXPORTABLE_UNDER_WRAP,
+            Capability::GET_LOG_ENTRIES,
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            capabilities,
+            Capability::SIGN_ECDSA
+                | Capability::EXPORTABLE_UNDER_WRAP
+                | Capability::GET_LOG_ENTRIES
+        );
+    }
+
+    #[test]
+    fn capability_names() {
+        let capabilities =
+            Capability::SIGN_ECDSA | Capability::DELETE_OPAQUE | Capability::UNKNOWN_CAPABILITY_63;
+
+        assert_eq!(
+            capabilities.names(),
+            ["delete-opaque", "sign-ecdsa", "unknown-63"]
+        );
+    }
+
+    #[test]
+    fn required_for_command() {
+        use crate::command::Code;
+
+        for (code, capability) in [
+            (Code::ResetDevice, Capability::RESET_DEVICE),
+            (Code::PutOpaqueObject, Capability::PUT_OPAQUE),
+            (Code::GetOpaqueObject, Capability::GET_OPAQUE),
+            (
+                Code::PutAuthenticationKey,
+                Capability::PUT_AUTHENTICATION_KEY,
+            ),
+            (Code::PutAsymmetricKey, Capability::PUT_ASYMMETRIC_KEY),
+            (
+                Code::GenerateAsymmetricKey,
+                Capability::GENERATE_ASYMMETRIC_KEY,
+            ),
+            (Code::SignPkcs1, Capability::SIGN_PKCS),
+            (Code::DecryptPkcs1, Capability::DECRYPT_PKCS),
+            (Code::ExportWrapped, Capability::EXPORT_WRAPPED),
+            (Code::ImportWrapped, Capability::IMPORT_WRAPPED),
+            (Code::PutWrapKey, Capability::PUT_WRAP_KEY),
+            (Code::GetLogEntries, Capability::GET_LOG_ENTRIES),
+            (Code::SetOption, Capability::PUT_OPTION),
+            (Code::GetOption, Capability::GET_OPTION),
+            (Code::GetPseudoRandom, Capability::GET_PSEUDO_RANDOM),
+            (Code::PutHmacKey, Capability::PUT_HMAC_KEY),
+            (Code::SignHmac, Capability::SIGN_HMAC),
+            (Code::SignPss, Capability::SIGN_PSS),
+            (Code::SignEcdsa, Capability::SIGN_ECDSA),
+            (Code::DeriveEcdh, Capability::DERIVE_ECDH),
+            (Code::DecryptOaep, Capability::DECRYPT_OAEP),
+            (Code::GenerateHmacKey, Capability::GENERATE_HMAC_KEY),
+            (Code::GenerateWrapKey, Capability::GENERATE_WRAP_KEY),
+            (Code::VerifyHmac, Capability::VERIFY_HMAC),
+            (Code::SignSshCertificate, Capability::SIGN_SSH_CERTIFICATE),
+            (Code::PutTemplate, Capability::PUT_TEMPLATE),
+            (Code::GetTemplate, Capability::GET_TEMPLATE),
+            (Code::DecryptOtp, Capability::DECRYPT_OTP),
+            (Code::CreateOtpAead, Capability::CREATE_OTP_AEAD),
+            (Code::RandomizeOtpAead, Capability::RANDOMIZE_OTP_AEAD),
+            (
+                Code::SignAttestationCertificate,
+                Capability::SIGN_ATTESTATION_CERTIFICATE,
+            ),
+            (Code::PutOtpAead, Capability::PUT_OTP_AEAD_KEY),
+            (Code::GenerateOtpAead, Capability::GENERATE_OTP_AEAD_KEY),
+            (Code::SetLogIndex, Capability::GET_LOG_ENTRIES),
+            (Code::WrapData, Capability::WRAP_DATA),
+            (Code::UnwrapData, Capability::UNWRAP_DATA),
+            (Code::SignEddsa, Capability::SIGN_EDDSA),
+            (
+                Code::ChangeAuthenticationKey,
+                Capability::CHANGE_AUTHENTICATION_KEY,
+            ),
+        ] {
+            assert_eq!(Capability::required_for_command(code), capability, "{code}");
+        }
+
+        for code in [
+            Code::Echo,
+            Code::ListObjects,
+            Code::GetPublicKey,
+            Code::Unknown(0x7e),
+        ] {
+            assert!(Capability::required_for_command(code).is_empty(), "{code}");
+        }
+
+        assert_eq!(
+            Capability::required_for_commands(&[
+                Code::SignEcdsa,
+                Code::GetPseudoRandom,
+                Code::Echo
+            ]),
+            Capability::SIGN_ECDSA | Capability::GET_PSEUDO_RANDOM
+        );
+
+        assert!(Capability::required_for_command(Code::DeleteObject)
+            .contains(Capability::required_for_delete(object::Type::WrapKey)));
+    }
+
+    #[test]
+    fn collect_no_capabilities() {
+        let capabilities: Capability = std::iter::empty().collect();
+        assert_eq!(capabilities, Capability::empty());
+    }
+}
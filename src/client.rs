@@ -9,10 +9,20 @@
 
 #[macro_use]
 mod error;
-
-pub use self::error::{Error, ErrorKind};
+mod builder;
+mod hint;
+mod retry;
+mod stats;
+
+pub use self::{
+    builder::Builder,
+    error::{Error, ErrorKind},
+    retry::RetryPolicy,
+    stats::ConnectionStats,
+};
 
 use crate::{
+    algorithm::Algorithm,
     asymmetric::{self, commands::*, PublicKey},
     attestation::{self, commands::*},
     audit::{commands::*, *},
@@ -21,8 +31,8 @@ use crate::{
     command::{self, Command},
     connector::Connector,
     device::{self, commands::*, StorageInfo},
-    domain::Domain,
-    ecdsa::commands::*,
+    domain::{Domain, DOMAINS},
+    ecdsa::{self, commands::*},
     ed25519::{self, commands::*},
     hmac::{self, commands::*},
     object::{self, commands::*, generate},
@@ -35,30 +45,50 @@ use crate::{
     uuid,
     wrap::{self, commands::*},
 };
+use rand_core::{OsRng, RngCore};
+use signature::{digest::Digest, PrehashSignature};
 use std::{
+    collections::HashMap,
+    ops::Range,
     sync::{Arc, Mutex},
+    thread,
     time::{Duration, Instant},
 };
+use zeroize::Zeroizing;
 
 #[cfg(feature = "passwords")]
-use std::{thread, time::SystemTime};
+use std::time::SystemTime;
 
 #[cfg(feature = "untested")]
 use {
     crate::{
-        algorithm::Algorithm,
         ecdh::{self, commands::*},
         rsa::{pkcs1::commands::*, pss::commands::*},
         ssh::{self, commands::*},
     },
-    sha2::{Digest, Sha256},
+    sha2::{Sha256, Sha384, Sha512},
 };
 
-#[cfg(docsrs)]
-use crate::ecdsa;
+/// Objects which couldn't be deleted by [`Client::delete_all_objects_in_domain`],
+/// along with the error encountered deleting each of them
+pub type DeletionErrors = Vec<(object::Entry, Error)>;
+
+/// Hook called before each command is dispatched (see [`Builder::dispatch_hook`])
+#[cfg(feature = "mockhsm")]
+pub(crate) type DispatchHook = Arc<dyn Fn(command::Code) + Send + Sync>;
+
+/// Number of random bytes sampled by [`Client::rng_healthcheck`]
+const RNG_HEALTHCHECK_SAMPLE_SIZE: usize = 2048;
 
 /// YubiHSM client: main API in this crate for accessing functions of the
 /// HSM hardware device.
+///
+/// Clients are cheap to clone, and clones are handles to the same client:
+/// they share its connection, session, and credentials (so e.g. reconnecting
+/// after [`Client::reset_device_and_reconnect`] applies to all of them).
+/// Commands sent from clones on different threads are serialized through
+/// the shared session, and the session is closed when the last clone is
+/// dropped.
 #[derive(Clone)]
 pub struct Client {
     /// Connector for communicating with the HSM
@@ -68,7 +98,37 @@ pub struct Client {
     session: Arc<Mutex<Option<Session>>>,
 
     /// Cached `Credentials` for reconnecting closed sessions
-    credentials: Option<Credentials>,
+    credentials: Arc<Mutex<Option<Credentials>>>,
+
+    /// Maximum time to wait for the HSM to respond to each command (if any)
+    command_timeout: Option<Duration>,
+
+    /// Range of randomized delays added before each signing command (if any)
+    timing_jitter: Option<Range<Duration>>,
+
+    /// Close the session after protocol errors, so the next command opens
+    /// a new one
+    reset_session_on_error: bool,
+
+    /// How to retry operations which fail with transient errors
+    retry_policy: RetryPolicy,
+
+    /// Refuse to delete the last admin authentication key
+    guard_last_admin_key: bool,
+
+    /// Verify sessions idle for at least this long before reusing them (if any)
+    verify_idle_sessions: Option<Duration>,
+
+    /// Device info cached by [`Client::authenticate`] (and refreshed by
+    /// [`Client::device_info`]), used to check firmware versions
+    device_info: Arc<Mutex<Option<device::Info>>>,
+
+    /// Statistics about the commands sent by this client (and its clones)
+    stats: Arc<Mutex<ConnectionStats>>,
+
+    /// Called with each command's code before it's dispatched (for tests)
+    #[cfg(feature = "mockhsm")]
+    dispatch_hook: Option<DispatchHook>,
 }
 
 impl Client {
@@ -84,12 +144,12 @@ impl Client {
         credentials: Credentials,
         reconnect: bool,
     ) -> Result<Self, Error> {
-        let mut client = Self::create(connector, credentials)?;
+        let client = Self::create(connector, credentials)?;
         client.connect()?;
 
         // Clear credentials if reconnecting has been disabled
         if !reconnect {
-            client.credentials = None;
+            *client.credentials.lock().unwrap() = None;
         }
 
         Ok(client)
@@ -100,12 +160,28 @@ impl Client {
         let client = Self {
             connector,
             session: Arc::new(Mutex::new(None)),
-            credentials: Some(credentials),
+            credentials: Arc::new(Mutex::new(Some(credentials))),
+            command_timeout: None,
+            timing_jitter: None,
+            reset_session_on_error: true,
+            retry_policy: RetryPolicy::default(),
+            guard_last_admin_key: false,
+            verify_idle_sessions: None,
+            device_info: Arc::new(Mutex::new(None)),
+            stats: Arc::new(Mutex::new(ConnectionStats::default())),
+            #[cfg(feature = "mockhsm")]
+            dispatch_hook: None,
         };
 
         Ok(client)
     }
 
+    /// Build a `yubihsm::Client` with non-default settings (e.g. a
+    /// [`Builder::command_timeout`])
+    pub fn builder(connector: Connector) -> Builder {
+        Builder::new(connector)
+    }
+
     /// Borrow this client's YubiHSM connector (which is `Clone`able)
     pub fn connector(&self) -> &Connector {
         &self.connector
@@ -118,34 +194,117 @@ impl Client {
         Ok(())
     }
 
+    /// Connect and authenticate to the HSM (as with [`Client::connect`]),
+    /// then fetch and cache its [`Client::device_info`].
+    ///
+    /// The cached firmware version is used by methods which need newer
+    /// firmware to fail with `ErrorKind::UnsupportedByFirmware` (see
+    /// [`Client::require_firmware`]) rather than with an opaque device
+    /// error. Methods which check it call this as needed, so calling it
+    /// explicitly is only necessary to detect the version up front.
+    pub fn authenticate(&self) -> Result<device::Info, Error> {
+        self.connect()?;
+        self.device_info()
+    }
+
+    /// Get the HSM's firmware version, fetching its [`Client::device_info`]
+    /// unless it's already been cached.
+    pub fn firmware_version(&self) -> Result<device::FirmwareVersion, Error> {
+        if let Some(info) = self.device_info.lock().unwrap().as_ref() {
+            return Ok(info.firmware_version());
+        }
+
+        Ok(self.authenticate()?.firmware_version())
+    }
+
+    /// Fail with `ErrorKind::UnsupportedByFirmware` unless the HSM's
+    /// firmware is at least the `required` version (e.g. before using a
+    /// feature introduced in that release), returning its actual version.
+    pub fn require_firmware(
+        &self,
+        required: device::FirmwareVersion,
+    ) -> Result<device::FirmwareVersion, Error> {
+        let actual = self.firmware_version()?;
+
+        if actual < required {
+            fail!(
+                ErrorKind::UnsupportedByFirmware { required, actual },
+                "HSM firmware {} is older than the required {}",
+                actual,
+                required
+            );
+        }
+
+        Ok(actual)
+    }
+
     /// Get current `Session` (either opening a new one or returning an already
     /// open one).
     pub fn session(&self) -> Result<session::Guard<'_>, Error> {
         // TODO(tarcieri): handle PoisonError better?
         let mut session_mutex_guard = self.session.lock().unwrap();
 
-        if let Some(session) = session_mutex_guard.as_ref() {
-            if session.is_open() {
+        if let Some(session) = session_mutex_guard.as_mut() {
+            // Sessions which have sat idle for a while may have been closed
+            // by the HSM (e.g. by a reset) so optionally check they're still usable
+            let usable = if !session.is_open() {
+                false
+            } else if self
+                .verify_idle_sessions
+                .map_or(true, |threshold| session.idle_time() < threshold)
+            {
+                true
+            } else {
+                match session.verify() {
+                    session::Health::Healthy => true,
+                    session::Health::SessionStale { .. } => false,
+                    session::Health::ConnectorDown { source } => return Err(source.into()),
+                }
+            };
+
+            if usable {
                 return Ok(session::Guard::new(session_mutex_guard));
             }
         }
 
         // If we don't have an open session, create a new one
-        let session = Session::open(
+        let credentials = self.credentials.lock().unwrap().clone().ok_or_else(|| {
+            format_err!(
+                ErrorKind::AuthenticationError,
+                "session reconnection disabled"
+            )
+        })?;
+
+        let mut session = Session::open(
             self.connector.clone(),
-            self.credentials.as_ref().ok_or_else(|| {
-                format_err!(
-                    ErrorKind::AuthenticationError,
-                    "session reconnection disabled"
-                )
-            })?,
+            &credentials,
             session::Timeout::default(),
+            Some(self.stats.clone()),
         )?;
 
+        session.set_command_timeout(self.command_timeout);
         *session_mutex_guard = Some(session);
         Ok(session::Guard::new(session_mutex_guard))
     }
 
+    /// Verify the current session is still usable by having the HSM echo
+    /// random data, checking both the echoed payload and that the session's
+    /// MAC chain advanced as expected.
+    ///
+    /// Stale sessions are aborted, so the next command opens a new session
+    /// (if reconnecting is enabled).
+    pub fn verify_session(&self) -> session::Health {
+        // TODO(tarcieri): handle PoisonError better?
+        let mut session_mutex_guard = self.session.lock().unwrap();
+
+        match session_mutex_guard.as_mut() {
+            Some(session) => session.verify(),
+            None => session::Health::SessionStale {
+                reason: format_err!(session::ErrorKind::ClosedError, "no session open").into(),
+            },
+        }
+    }
+
     /// Ping the HSM, ensuring we have a live connection and returning the
     /// end-to-end latency.
     pub fn ping(&self) -> Result<Duration, Error> {
@@ -165,25 +324,93 @@ impl Client {
     }
 
     /// Encrypt a command, send it to the HSM, then read and decrypt the response.
+    ///
+    /// Errors are annotated with details about the failed command (included
+    /// in their alternate formatting).
     fn send_command<T: Command>(&self, command: T) -> Result<T::ResponseType, Error> {
+        if let Some(jitter) = &self.timing_jitter {
+            if is_signing_command(T::COMMAND_CODE) {
+                thread::sleep(random_delay(jitter));
+            }
+        }
+
+        #[cfg(feature = "mockhsm")]
+        if let Some(hook) = &self.dispatch_hook {
+            hook(T::COMMAND_CODE);
+        }
+
+        self.dispatch_command(command)
+    }
+
+    /// Send a command to the HSM over the current session (opening one if
+    /// necessary), retrying it in a new session if the current one is spent
+    fn dispatch_command<T: Command>(&self, command: T) -> Result<T::ResponseType, Error> {
         let mut session = self.session()?;
+        let authentication_key_id = session.authentication_key_id();
+
+        let mut result = session.send_command(&command);
+
+        if matches!(&result, Err(err) if *err.kind() == session::ErrorKind::CommandLimitExceeded) {
+            // If we encounter this, we've exceeded the maximum number of
+            // messages allowed under the data volume limits and need to
+            // rekey the connection by creating a new session.
 
-        match session.send_command(&command) {
-            Ok(response) => Ok(response),
-            Err(err) if *err.kind() == session::ErrorKind::CommandLimitExceeded => {
-                // If we encounter this, we've exceeded the maximum number of
-                // messages allowed under the data volume limits and need to
-                // rekey the connection by creating a new session.
+            // Drop the stale `session::Guard` to release the session mutex.
+            drop(session);
 
-                // Drop the stale `session::Guard` to release the session mutex.
-                drop(session);
+            // Attempt to initiate a new session and retry the command.
+            // (the original command was never sent in this case)
+            session = self.session()?;
+            result = session.send_command(&command);
+        }
 
-                // Attempt to initiate a new session and retry the command.
-                // (the original command was never sent in this case)
-                Ok(self.session()?.send_command(&command)?)
+        if let Err(err) = &result {
+            if self.reset_session_on_error && is_protocol_error(*err.kind()) {
+                session.reset();
             }
-            Err(err) => Err(err.into()),
         }
+
+        result.map_err(|err| {
+            let err = Error::from(err);
+            let detail = hint::describe(
+                T::COMMAND_CODE,
+                command.object().as_ref(),
+                authentication_key_id,
+                err.device_error(),
+            );
+
+            err.with_detail(detail)
+        })
+    }
+
+    /// Get statistics about the commands sent by this client (and its
+    /// clones), including the ones which open and close sessions, e.g. to
+    /// debug performance issues
+    pub fn stats(&self) -> ConnectionStats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// Reset this client's [`Client::stats`]
+    pub fn reset_stats(&self) {
+        *self.stats.lock().unwrap() = ConnectionStats::default();
+    }
+
+    /// Get the policy for retrying operations which fail with transient
+    /// errors (see [`Builder::retry_policy`])
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry_policy
+    }
+
+    /// Maximum size of a message which can be sent to or received from the
+    /// HSM by this client (see [`Connector::max_message_size`])
+    pub fn max_message_size(&self) -> usize {
+        self.connector.max_message_size()
+    }
+
+    /// Maximum length of the data of a command or response which fits in a
+    /// single (encrypted) message over this client's connection
+    fn max_data_len(&self) -> usize {
+        session::securechannel::max_data_len(self.max_message_size())
     }
 
     //
@@ -191,6 +418,35 @@ impl Client {
     // <https://developers.yubico.com/YubiHSM2/Commands/>
     //
 
+    /// Export every object marked [`Capability::EXPORTABLE_UNDER_WRAP`] (and
+    /// visible to this session) encrypted under the given wrap key, along
+    /// with a [`wrap::BackupManifest`] describing each of them.
+    ///
+    /// Objects can be restored with [`Client::import_wrapped`], e.g. only
+    /// those whose manifest entries were selected.
+    pub fn backup_all(
+        &self,
+        wrap_key_id: object::Id,
+    ) -> Result<(wrap::BackupManifest, Vec<wrap::Message>), Error> {
+        let filters = [object::Filter::Capabilities(
+            Capability::EXPORTABLE_UNDER_WRAP,
+        )];
+
+        let mut manifest = wrap::BackupManifest::new(wrap_key_id);
+        let mut messages = vec![];
+
+        for entry in self.list_objects(&filters)? {
+            let info = self.get_object_info(entry.object_id, entry.object_type)?;
+            let message = self.export_wrapped(wrap_key_id, entry.object_type, entry.object_id)?;
+            manifest
+                .entries
+                .push(wrap::ManifestEntry::new(&info, &message));
+            messages.push(message);
+        }
+
+        Ok((manifest, messages))
+    }
+
     /// Blink the HSM's LEDs (to identify it) for the given number of seconds.
     ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Blink_Device.html>
@@ -224,11 +480,49 @@ impl Client {
 
     /// Delete an object of the given ID and type.
     ///
+    /// Deleting the current session's authentication key would lock this
+    /// client out of the HSM (and if it's the only authentication key, lock
+    /// everyone out until the device is physically reset), so this fails
+    /// with `ErrorKind::RefusingSelfDelete` instead. Use
+    /// [`Client::delete_object_force`] to delete it anyway.
+    ///
+    /// If [`Builder::guard_last_admin_key`] is enabled, this also fails with
+    /// `ErrorKind::RefusingLastAdminKeyDelete` rather than delete the last
+    /// admin authentication key.
+    ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Delete_Object.html>
     pub fn delete_object(
         &self,
         object_id: object::Id,
         object_type: object::Type,
+    ) -> Result<(), Error> {
+        if object_type == object::Type::AuthenticationKey {
+            let authentication_key_id = self.session()?.authentication_key_id();
+
+            ensure!(
+                object_id != authentication_key_id,
+                ErrorKind::RefusingSelfDelete,
+                "refusing to delete authentication key 0x{:04x} of the current session \
+                 (use `delete_object_force` to delete it anyway)",
+                object_id
+            );
+
+            if self.guard_last_admin_key {
+                self.ensure_not_last_admin_key(object_id)?;
+            }
+        }
+
+        self.delete_object_force(object_id, object_type)
+    }
+
+    /// Delete an object of the given ID and type, even if it's the current
+    /// session's authentication key (see [`Client::delete_object`]).
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Delete_Object.html>
+    pub fn delete_object_force(
+        &self,
+        object_id: object::Id,
+        object_type: object::Type,
     ) -> Result<(), Error> {
         self.send_command(DeleteObjectCommand {
             object_id,
@@ -237,6 +531,106 @@ impl Client {
         Ok(())
     }
 
+    /// Fail with `ErrorKind::RefusingLastAdminKeyDelete` if the given
+    /// authentication key is the last admin key visible to this session
+    /// (see [`Builder::guard_last_admin_key`])
+    fn ensure_not_last_admin_key(&self, key_id: object::Id) -> Result<(), Error> {
+        let admin_keys = self.list_objects(&[
+            object::Filter::Type(object::Type::AuthenticationKey),
+            object::Filter::Capabilities(Capability::PUT_AUTHENTICATION_KEY),
+        ])?;
+
+        let is_admin_key = admin_keys.iter().any(|entry| entry.object_id == key_id);
+        let others_remain = admin_keys.iter().any(|entry| entry.object_id != key_id);
+
+        ensure!(
+            !is_admin_key || others_remain,
+            ErrorKind::RefusingLastAdminKeyDelete,
+            "refusing to delete authentication key 0x{:04x}: it's the last one with \
+             the put-authentication-key capability",
+            key_id
+        );
+
+        Ok(())
+    }
+
+    /// Delete a payload stored with [`Client::put_opaque_chunked`].
+    ///
+    /// Chunks which are already missing are skipped, so this also cleans up
+    /// payloads which are incomplete (as long as their first chunk remains).
+    pub fn delete_opaque_chunked(&self, base_id: object::Id) -> Result<(), Error> {
+        let (header, _) = self.get_first_opaque_chunk(base_id)?;
+
+        // Delete the first chunk last, so a failure can be retried
+        for index in (0..header.total).rev() {
+            match self.delete_object(base_id + index, object::Type::Opaque) {
+                Err(err)
+                    if index != 0
+                        && err.device_error() == Some(device::ErrorKind::ObjectNotFound) => {}
+                result => result?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Delete every object in the given domain (optionally only those of the
+    /// given type), returning the number of objects deleted along with any
+    /// objects which couldn't be deleted (e.g. due to lack of permission).
+    ///
+    /// Only objects whose domains are exactly `domain` are deleted: objects
+    /// shared with other domains are left alone. The authentication key used
+    /// by the current session is never deleted, nor (if
+    /// [`Builder::guard_last_admin_key`] is enabled) is the last admin
+    /// authentication key.
+    pub fn delete_all_objects_in_domain(
+        &self,
+        domain: Domain,
+        object_type: Option<object::Type>,
+    ) -> Result<(u32, DeletionErrors), Error> {
+        let mut filters = vec![object::Filter::Domains(domain)];
+
+        if let Some(object_type) = object_type {
+            filters.push(object::Filter::Type(object_type));
+        }
+
+        let authentication_key_id = self.session()?.authentication_key_id();
+        let mut deleted = 0;
+        let mut errors = vec![];
+
+        for entry in self.list_objects(&filters)? {
+            let is_authentication_key = entry.object_type == object::Type::AuthenticationKey;
+
+            if is_authentication_key && entry.object_id == authentication_key_id {
+                continue;
+            }
+
+            // `Filter::Domains` also matches objects shared with other domains
+            match self.get_object_info(entry.object_id, entry.object_type) {
+                Ok(info) if info.domains != domain => continue,
+                Ok(_) => (),
+                Err(err) => {
+                    errors.push((entry, err));
+                    continue;
+                }
+            }
+
+            if is_authentication_key && self.guard_last_admin_key {
+                if let Err(err) = self.ensure_not_last_admin_key(entry.object_id) {
+                    errors.push((entry, err));
+                    continue;
+                }
+            }
+
+            match self.delete_object_force(entry.object_id, entry.object_type) {
+                Ok(()) => deleted += 1,
+                Err(err) => errors.push((entry, err)),
+            }
+        }
+
+        Ok((deleted, errors))
+    }
+
     /// Elliptic Curve Diffie-Hellman: derive a shared secret via key exchange.
     ///
     /// **WARNING**: This functionality has not been tested and has not yet been
@@ -260,21 +654,68 @@ impl Client {
     ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Device_Info.html>
     pub fn device_info(&self) -> Result<device::Info, Error> {
-        Ok(self.send_command(DeviceInfoCommand {})?.into())
+        let info: device::Info = self.send_command(DeviceInfoCommand {})?.into();
+        *self.device_info.lock().unwrap() = Some(info.clone());
+        Ok(info)
+    }
+
+    /// Fail with `ErrorKind::AlgorithmUnsupported` if the HSM's cached
+    /// [`Client::device_info`] (see [`Client::authenticate`]) doesn't report
+    /// support for the given algorithm. Nothing is sent to the HSM: if the
+    /// info hasn't been cached this always succeeds.
+    pub(crate) fn ensure_algorithm_supported(
+        &self,
+        algorithm: impl Into<Algorithm>,
+    ) -> Result<(), Error> {
+        let algorithm = algorithm.into();
+
+        if let Some(info) = self.device_info.lock().unwrap().as_ref() {
+            ensure!(
+                info.supports(algorithm),
+                ErrorKind::AlgorithmUnsupported,
+                "HSM doesn't support {:?}",
+                algorithm
+            );
+        }
+
+        Ok(())
     }
 
     /// Echo a message sent to the HSM.
     ///
+    /// Messages too long for a single command (see [`Client::max_message_size`])
+    /// are echoed in several chunks.
+    ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Echo.html>
     pub fn echo<M>(&self, msg: M) -> Result<Vec<u8>, Error>
     where
         M: Into<Vec<u8>>,
     {
-        Ok(self
-            .send_command(EchoCommand {
-                message: msg.into(),
-            })?
-            .0)
+        let message = msg.into();
+        let chunk_size = self.max_data_len();
+
+        if message.len() <= chunk_size {
+            return Ok(self.send_command(EchoCommand { message })?.0);
+        }
+
+        ensure!(
+            chunk_size > 0,
+            ErrorKind::ProtocolError,
+            "maximum message size is too small to echo messages"
+        );
+
+        let mut response = Vec::with_capacity(message.len());
+
+        for chunk in message.chunks(chunk_size) {
+            response.extend(
+                self.send_command(EchoCommand {
+                    message: chunk.into(),
+                })?
+                .0,
+            );
+        }
+
+        Ok(response)
     }
 
     /// Export an encrypted object from the HSM using the given key-wrapping key.
@@ -317,6 +758,53 @@ impl Client {
             .key_id)
     }
 
+    /// Check that `generate_asymmetric_key` would succeed with the given
+    /// parameters, without creating anything on the device.
+    ///
+    /// Returns a device error matching the one the HSM would respond with
+    /// for the first failed check:
+    ///
+    /// - `ObjectExists`: an asymmetric key with the given ID already exists
+    ///   (a `key_id` of 0 asks the HSM to pick a free ID, so it's never taken)
+    /// - `InvalidData`: the HSM doesn't support the given algorithm
+    /// - `InsufficientPermissions`: the current session's authentication key
+    ///   lacks the `GENERATE_ASYMMETRIC_KEY` capability, or the domains or
+    ///   capabilities exceed those it has
+    pub fn validate_generate(
+        &self,
+        key_id: object::Id,
+        domains: Domain,
+        capabilities: Capability,
+        algorithm: asymmetric::Algorithm,
+    ) -> Result<(), Error> {
+        if key_id != 0 {
+            match self.get_object_info(key_id, object::Type::AsymmetricKey) {
+                Ok(_) => return Err(session::Error::from(device::ErrorKind::ObjectExists).into()),
+                Err(e) if e.device_error() == Some(device::ErrorKind::ObjectNotFound) => (),
+                Err(e) => return Err(e),
+            }
+        }
+
+        if !self.device_info()?.supports(algorithm) {
+            return Err(session::Error::from(device::ErrorKind::InvalidData).into());
+        }
+
+        let authentication_key = self.whoami()?;
+
+        if !authentication_key
+            .capabilities
+            .contains(Capability::GENERATE_ASYMMETRIC_KEY)
+            || !authentication_key.domains.contains(domains)
+            || !authentication_key
+                .delegated_capabilities
+                .contains(capabilities)
+        {
+            return Err(session::Error::from(device::ErrorKind::InsufficientPermissions).into());
+        }
+
+        Ok(())
+    }
+
     /// Generate a new HMAC key within the HSM.
     ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Generate_Hmac_Key.html>
@@ -375,6 +863,15 @@ impl Client {
         self.send_command(GetLogEntriesCommand {})
     }
 
+    /// Get the fraction of the audit log which is in use (from `0.0` to
+    /// `1.0`), e.g. to alert before the log fills up.
+    ///
+    /// When the log is full, audited commands either fail (if forced
+    /// auditing is enabled) or overwrite the oldest entries.
+    pub fn log_utilization(&self) -> Result<f32, Error> {
+        Ok(self.device_info()?.log_utilization())
+    }
+
     /// Get information about an object.
     ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Get_Object_Info.html>
@@ -391,6 +888,35 @@ impl Client {
             .0)
     }
 
+    /// Get information about the authentication key used to open the
+    /// current session (e.g. to introspect its capabilities and domains).
+    pub fn whoami(&self) -> Result<object::Info, Error> {
+        let authentication_key_id = self.session()?.authentication_key_id();
+        self.get_object_info(authentication_key_id, object::Type::AuthenticationKey)
+    }
+
+    /// Get information about several objects at once, returning a result
+    /// for each of the given `(object_id, object_type)` handles (in order).
+    ///
+    /// The requests are sent back-to-back over the current session. The
+    /// YubiHSM processes commands one at a time (and each SCP03 command MAC
+    /// chains off the previous one), so they are not sent concurrently.
+    pub fn get_object_infos(
+        &self,
+        handles: &[(object::Id, object::Type)],
+    ) -> Vec<Result<object::Info, Error>> {
+        handles
+            .iter()
+            .map(|&(object_id, object_type)| self.get_object_info(object_id, object_type))
+            .collect()
+    }
+
+    /// Get information about every object in the HSM which is visible to
+    /// the current session.
+    pub fn full_inventory(&self) -> Result<Vec<object::Info>, Error> {
+        self.list_objects_detailed(&[])
+    }
+
     /// Get an opaque object stored in the HSM.
     ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Get_Opaque.html>
@@ -398,6 +924,127 @@ impl Client {
         Ok(self.send_command(GetOpaqueCommand { object_id })?.0)
     }
 
+    /// Get the first opaque object with the given label (in the order
+    /// they're listed by the HSM), along with its ID.
+    pub fn get_opaque_by_label(&self, label: &str) -> Result<Option<(object::Id, Vec<u8>)>, Error> {
+        match self.list_opaques_by_label(label)?.first() {
+            Some(entry) => Ok(Some((entry.object_id, self.get_opaque(entry.object_id)?))),
+            None => Ok(None),
+        }
+    }
+
+    /// Get all opaque objects with the given label, along with their IDs.
+    pub fn get_all_opaques_by_label(
+        &self,
+        label: &str,
+    ) -> Result<Vec<(object::Id, Vec<u8>)>, Error> {
+        self.list_opaques_by_label(label)?
+            .iter()
+            .map(|entry| Ok((entry.object_id, self.get_opaque(entry.object_id)?)))
+            .collect()
+    }
+
+    /// Get a payload stored with [`Client::put_opaque_chunked`], verifying
+    /// every chunk is present and the reassembled payload's checksum.
+    pub fn get_opaque_chunked(&self, base_id: object::Id) -> Result<Vec<u8>, Error> {
+        let (header, mut payload) = self.get_first_opaque_chunk(base_id)?;
+
+        for index in 1..header.total {
+            let object_id = base_id + index;
+
+            let (chunk_header, data) = match self.get_opaque_chunk(object_id, index) {
+                Err(err) if err.device_error() == Some(device::ErrorKind::ObjectNotFound) => fail!(
+                    ErrorKind::ProtocolError,
+                    "chunk {} of {} (object 0x{:04x}) is missing",
+                    index + 1,
+                    header.total,
+                    object_id
+                ),
+                result => result?,
+            };
+
+            ensure!(
+                chunk_header == opaque::chunked::Header { index, ..header },
+                ErrorKind::ProtocolError,
+                "chunk {} (object 0x{:04x}) belongs to a different payload",
+                index + 1,
+                object_id
+            );
+
+            payload.extend_from_slice(&data);
+        }
+
+        ensure!(
+            payload.len() == header.len as usize,
+            ErrorKind::ProtocolError,
+            "chunked payload length mismatch: expected {} bytes, got {}",
+            header.len,
+            payload.len()
+        );
+
+        ensure!(
+            opaque::chunked::crc32(&payload) == header.checksum,
+            ErrorKind::ProtocolError,
+            "chunked payload checksum mismatch"
+        );
+
+        Ok(payload)
+    }
+
+    /// Get the opaque object with the given ID as the chunk with the given
+    /// index, returning its header and data
+    fn get_opaque_chunk(
+        &self,
+        object_id: object::Id,
+        index: u16,
+    ) -> Result<(opaque::chunked::Header, Vec<u8>), Error> {
+        let chunk = self.get_opaque(object_id)?;
+
+        match opaque::chunked::Header::parse(&chunk) {
+            Some((header, data)) if header.index == index => Ok((header, data.into())),
+            _ => fail!(
+                ErrorKind::ProtocolError,
+                "object 0x{:04x} isn't chunk {} of a chunked payload",
+                object_id,
+                index + 1
+            ),
+        }
+    }
+
+    /// Get the first chunk of a chunked payload, checking its header
+    /// describes a payload which fits in the object ID space after `base_id`
+    fn get_first_opaque_chunk(
+        &self,
+        base_id: object::Id,
+    ) -> Result<(opaque::chunked::Header, Vec<u8>), Error> {
+        let (header, data) = self.get_opaque_chunk(base_id, 0)?;
+
+        ensure!(
+            header.len as usize <= opaque::MAX_CHUNKED_SIZE
+                && usize::from(base_id) + usize::from(header.total) - 1
+                    <= usize::from(object::Id::MAX),
+            ErrorKind::ProtocolError,
+            "object 0x{:04x} has an invalid chunked payload header: {:?}",
+            base_id,
+            header
+        );
+
+        Ok((header, data))
+    }
+
+    /// List the opaque objects with the given label
+    fn list_opaques_by_label(&self, label: &str) -> Result<Vec<object::Entry>, Error> {
+        // Labels longer than `object::LABEL_SIZE` can't match any object
+        let Ok(label) = label.parse() else {
+            return Ok(vec![]);
+        };
+
+        self.list_objects(&[
+            object::Filter::Type(object::Type::Opaque),
+            object::Filter::Label(label),
+        ])
+    }
+
     /// Get the audit policy setting for a particular command.
     ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Get_Option.html>
@@ -414,11 +1061,8 @@ impl Client {
     ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Get_Option.html>
     pub fn get_commands_audit_options(&self) -> Result<Vec<AuditCommand>, Error> {
-        let response = self.send_command(GetOptionCommand {
-            tag: AuditTag::Command,
-        })?;
-
-        Ok(deserialize(&response.0)?)
+        let value = self.get_option_raw(AuditTag::Command.to_u8())?;
+        Ok(deserialize(&value)?)
     }
 
     /// Get the forced auditing global option: when enabled, the device will
@@ -428,38 +1072,66 @@ impl Client {
     ///
     /// [log store]: https://developers.yubico.com/YubiHSM2/Concepts/Logs.html
     pub fn get_force_audit_option(&self) -> Result<AuditOption, Error> {
-        let response = self.send_command(GetOptionCommand {
-            tag: AuditTag::Force,
-        })?;
+        let value = self.get_option_raw(AuditTag::Force.to_u8())?;
 
         ensure!(
-            response.0.len() == 1,
+            value.len() == 1,
             ErrorKind::ProtocolError,
             "expected 1-byte response, got {}",
-            response.0.len()
+            value.len()
         );
 
-        AuditOption::from_u8(response.0[0])
-            .map_err(|e| format_err!(ErrorKind::ProtocolError, e).into())
+        AuditOption::from_u8(value[0]).map_err(|e| format_err!(ErrorKind::ProtocolError, e).into())
+    }
+
+    /// Get the raw value of the device option with the given ID.
+    ///
+    /// This allows reading options added by newer firmware which this crate
+    /// has no typed method for (e.g. [`Client::get_force_audit_option`] is
+    /// built on this, with option ID `0x01`).
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Get_Option.html>
+    pub fn get_option_raw(&self, option_id: u8) -> Result<Vec<u8>, Error> {
+        Ok(self.send_command(GetOptionCommand { tag: option_id })?.0)
     }
 
     /// Get some number of bytes of pseudo random data generated on the device.
     ///
+    /// Requests for more data than fits in a single response (see
+    /// [`Client::max_message_size`]) are split across several commands.
+    ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Get_Pseudo_Random.html>
     pub fn get_pseudo_random(&self, bytes: usize) -> Result<Vec<u8>, Error> {
-        ensure!(
-            bytes <= MAX_RAND_BYTES,
-            ErrorKind::ProtocolError,
-            "requested number of bytes too large: {} (max: {})",
-            bytes,
-            MAX_RAND_BYTES
-        );
+        let chunk_size = self.max_data_len().min(u16::MAX as usize);
+        let mut result = Vec::with_capacity(bytes);
 
-        Ok(self
-            .send_command(GetPseudoRandomCommand {
-                bytes: bytes as u16,
-            })?
-            .bytes)
+        while result.len() < bytes {
+            let chunk = self.send_command(GetPseudoRandomCommand {
+                bytes: (bytes - result.len()).min(chunk_size) as u16,
+            })?;
+
+            ensure!(
+                !chunk.bytes.is_empty(),
+                ErrorKind::ProtocolError,
+                "HSM returned no random data"
+            );
+
+            result.extend_from_slice(&chunk.bytes);
+        }
+
+        result.truncate(bytes);
+        Ok(result)
+    }
+
+    /// Sanity check the HSM's random number generator by running basic
+    /// statistical tests (see [`device::RngHealth`]) on a sample of its
+    /// output obtained via [`Client::get_pseudo_random`].
+    ///
+    /// This is a convenience for detecting grossly broken output, and is not
+    /// a substitute for certified testing of the RNG.
+    pub fn rng_healthcheck(&self) -> Result<device::RngHealth, Error> {
+        let sample = self.get_pseudo_random(RNG_HEALTHCHECK_SAMPLE_SIZE)?;
+        Ok(device::RngHealth::from_sample(&sample))
     }
 
     /// Get the public key for an asymmetric key stored on the device.
@@ -476,6 +1148,37 @@ impl Client {
         Ok(self.send_command(GetStorageInfoCommand {})?.into())
     }
 
+    /// Estimate the storage the objects wrapped in the given messages (e.g.
+    /// a backup made with [`Client::backup_all`]) will occupy once imported
+    /// (see [`wrap::Message::decoded_len_estimate`]), and check the HSM has
+    /// that much free storage, returning the combined footprint.
+    ///
+    /// Fails with `ErrorKind::InsufficientStorage` if the objects won't fit.
+    pub fn ensure_restore_fits(
+        &self,
+        messages: &[wrap::Message],
+    ) -> Result<device::StorageFootprint, Error> {
+        let storage = self.get_storage_info()?;
+
+        let footprint = messages
+            .iter()
+            .map(|message| storage.footprint(message.decoded_len_estimate()))
+            .sum();
+
+        if !storage.has_room_for(footprint) {
+            fail!(
+                ErrorKind::InsufficientStorage,
+                "restore needs {} records and {} pages, but only {} and {} are free",
+                footprint.records,
+                footprint.pages,
+                storage.free_records,
+                storage.free_pages
+            );
+        }
+
+        Ok(footprint)
+    }
+
     /// Get a certificate template (i.e. for SSH CA) stored in the HSM.
     ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Get_Template.html>
@@ -508,6 +1211,35 @@ impl Client {
         ))
     }
 
+    /// Import an encrypted object under a different object ID and
+    /// (optionally) label, e.g. to restore the same backup more than once.
+    ///
+    /// The YubiHSM 2 always restores wrapped objects under their original ID,
+    /// so the message is decrypted in software, has its ID and label
+    /// rewritten, and is re-encrypted under the same wrap key before being
+    /// imported. This exposes the plaintext of the wrapped object to host
+    /// memory, hence the wrap key must be explicitly supplied as a
+    /// [`wrap::SoftwareKey`].
+    #[cfg(feature = "software-wrap")]
+    pub fn import_wrapped_as(
+        &self,
+        wrap_key: &wrap::SoftwareKey,
+        wrap_message: &wrap::Message,
+        new_id: object::Id,
+        new_label: Option<&str>,
+    ) -> Result<object::Handle, Error> {
+        let mut object = wrap_key.decrypt(wrap_message)?;
+        object.object_info.object_id = new_id;
+
+        if let Some(label) = new_label {
+            object.object_info.label = label
+                .parse()
+                .map_err(|e| format_err!(ErrorKind::WrapError, "invalid label: {}", e))?;
+        }
+
+        self.import_wrapped(wrap_key.id(), wrap_key.encrypt(&object)?)
+    }
+
     /// List objects visible from the current session.
     ///
     /// Optionally apply a set of provided `filters` which select objects
@@ -524,6 +1256,67 @@ impl Client {
         Ok(self.send_command(ListObjectsCommand(filter_bytes))?.0)
     }
 
+    /// List the IDs of objects of the given type visible from the current
+    /// session, in ascending order.
+    ///
+    /// Unlike [`Client::list_objects_detailed`] this only sends a single
+    /// List Objects command, so it's the cheaper way to check which IDs of a
+    /// given type are in use.
+    pub fn list_object_ids(&self, object_type: object::Type) -> Result<Vec<object::Id>, Error> {
+        let mut ids = self
+            .list_objects(&[object::Filter::Type(object_type)])?
+            .into_iter()
+            .map(|entry| entry.object_id)
+            .collect::<Vec<_>>();
+
+        ids.sort_unstable();
+        Ok(ids)
+    }
+
+    /// List objects visible from the current session (optionally matching
+    /// the given `filters`, as with [`Client::list_objects`]) along with
+    /// their full information, sorted by object ID and then type.
+    ///
+    /// Information about each object is fetched with
+    /// [`Client::get_object_infos`]. The HSM's `List Objects` response only
+    /// includes IDs, types and sequence numbers, so this is how to list
+    /// objects along with their labels (e.g. for inventory UIs).
+    pub fn list_objects_detailed(
+        &self,
+        filters: &[object::Filter],
+    ) -> Result<Vec<object::Info>, Error> {
+        let mut entries = self.list_objects(filters)?;
+        entries.sort();
+
+        let handles = entries
+            .iter()
+            .map(|entry| (entry.object_id, entry.object_type))
+            .collect::<Vec<_>>();
+
+        self.get_object_infos(&handles).into_iter().collect()
+    }
+
+    /// Group the objects visible from the current session by domain, along
+    /// with their full information (as returned by
+    /// [`Client::list_objects_detailed`]).
+    ///
+    /// Objects in several domains appear in the group for each of them.
+    /// Domains containing no (visible) objects are omitted.
+    pub fn objects_by_domain(&self) -> Result<HashMap<Domain, Vec<object::Info>>, Error> {
+        let mut groups: HashMap<Domain, Vec<object::Info>> = HashMap::new();
+
+        for info in self.list_objects_detailed(&[])? {
+            for domain in DOMAINS
+                .iter()
+                .filter(|&&domain| info.domains.contains(domain))
+            {
+                groups.entry(*domain).or_default().push(info.clone());
+            }
+        }
+
+        Ok(groups)
+    }
+
     /// Put an existing asymmetric key into the HSM.
     ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Put_Asymmetric.html>
@@ -541,59 +1334,278 @@ impl Client {
     {
         let data = key_bytes.into();
 
-        if data.len() != algorithm.key_len() {
+        ensure_key_length(algorithm.into(), &data)?;
+
+        Ok(self
+            .send_command(PutAsymmetricKeyCommand {
+                params: object::put::Params {
+                    id: key_id,
+                    label,
+                    domains,
+                    capabilities,
+                    algorithm: algorithm.into(),
+                },
+                data,
+            })?
+            .key_id)
+    }
+
+    /// Put an existing RSA private key into the HSM.
+    ///
+    /// The key's `components` can be constructed from PKCS#1/PKCS#8 keys or
+    /// from its modulus and exponents using the functions in [`rsa::import`].
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Put_Asymmetric.html>
+    #[cfg(feature = "rsa-import")]
+    pub fn put_rsa_key(
+        &self,
+        key_id: object::Id,
+        label: object::Label,
+        domains: Domain,
+        capabilities: Capability,
+        components: &rsa::import::Components,
+    ) -> Result<object::Id, Error> {
+        self.put_asymmetric_key(
+            key_id,
+            label,
+            domains,
+            capabilities,
+            components.algorithm(),
+            components.as_secret_slice(),
+        )
+    }
+
+    /// Put an existing asymmetric key into the HSM, then confirm the HSM
+    /// derived the expected public key from it (e.g. to catch keys encoded
+    /// for the wrong curve or with the wrong byte order).
+    ///
+    /// If the public keys don't match, the imported key is deleted and an
+    /// `ErrorKind::ImportVerificationFailed` error is returned.
+    pub fn put_asymmetric_key_verified<K>(
+        &self,
+        key_id: object::Id,
+        label: object::Label,
+        domains: Domain,
+        capabilities: Capability,
+        algorithm: asymmetric::Algorithm,
+        key_bytes: K,
+        expected_public_key: &PublicKey,
+    ) -> Result<object::Id, Error>
+    where
+        K: Into<Vec<u8>>,
+    {
+        let key_id =
+            self.put_asymmetric_key(key_id, label, domains, capabilities, algorithm, key_bytes)?;
+
+        let public_key = self.get_public_key(key_id)?;
+
+        if &public_key != expected_public_key {
+            self.delete_object(key_id, object::Type::AsymmetricKey)?;
+
             fail!(
-                ErrorKind::ProtocolError,
-                "invalid key length for {:?}: {} (expected {})",
-                algorithm,
-                data.len(),
-                algorithm.key_len()
+                ErrorKind::ImportVerificationFailed,
+                "public key for imported key 0x{:04x} doesn't match expected public key",
+                key_id
+            );
+        }
+
+        Ok(key_id)
+    }
+
+    /// Put an existing `authentication::Key` into the HSM.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Put_Authentication_Key.html>
+    pub fn put_authentication_key<K>(
+        &self,
+        key_id: object::Id,
+        label: object::Label,
+        domains: Domain,
+        capabilities: Capability,
+        delegated_capabilities: Capability,
+        algorithm: authentication::Algorithm,
+        authentication_key: K,
+    ) -> Result<object::Id, Error>
+    where
+        K: Into<authentication::Key>,
+    {
+        if delegated_capabilities.is_empty() {
+            warn!(
+                "authentication key 0x{:04x} has no delegated capabilities: \
+                 it won't be able to create any objects",
+                key_id
             );
         }
 
-        Ok(self
-            .send_command(PutAsymmetricKeyCommand {
-                params: object::put::Params {
-                    id: key_id,
-                    label,
-                    domains,
-                    capabilities,
-                    algorithm: algorithm.into(),
-                },
-                data,
-            })?
-            .key_id)
+        Ok(self
+            .send_command(PutAuthenticationKeyCommand {
+                params: object::put::Params {
+                    id: key_id,
+                    label,
+                    domains,
+                    capabilities,
+                    algorithm: algorithm.into(),
+                },
+                delegated_capabilities,
+                authentication_key: authentication_key.into(),
+            })?
+            .key_id)
+    }
+
+    /// Put an existing `authentication::Key` into the HSM which can be used to
+    /// create objects with (up to) the given `child_capabilities`.
+    ///
+    /// Its `delegated_capabilities` are computed using
+    /// [`Capability::delegated_for`].
+    pub fn put_delegating_authentication_key<K>(
+        &self,
+        key_id: object::Id,
+        label: object::Label,
+        domains: Domain,
+        capabilities: Capability,
+        child_capabilities: Capability,
+        algorithm: authentication::Algorithm,
+        authentication_key: K,
+    ) -> Result<object::Id, Error>
+    where
+        K: Into<authentication::Key>,
+    {
+        self.put_authentication_key(
+            key_id,
+            label,
+            domains,
+            capabilities,
+            capabilities.delegated_for(child_capabilities),
+            algorithm,
+            authentication_key,
+        )
+    }
+
+    /// Change the key material of the current session's authentication key
+    /// (keeping its ID, label, domains, and capabilities), verifying that
+    /// it works by authenticating a fresh session with it.
+    ///
+    /// The HSM replaces the key atomically, so if the command fails, the
+    /// old key is left intact on the device. If the command succeeds but
+    /// the new key can't authenticate a session, the old key is restored
+    /// (through the current session, which remains valid) before failing
+    /// with `ErrorKind::AuthenticationError`. Either way, a failure leaves
+    /// the old key usable, unless restoring it fails too (which the error
+    /// describes).
+    ///
+    /// Restoring the old key requires the client's cached credentials, so
+    /// this fails up front if reconnecting has been disabled. On success,
+    /// the cached credentials are updated so reconnecting uses the new key.
+    /// The authentication key needs the `CHANGE_AUTHENTICATION_KEY`
+    /// capability.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Change_Authentication_Key.html>
+    pub fn change_authentication_key<K>(&self, authentication_key: K) -> Result<(), Error>
+    where
+        K: Into<authentication::Key>,
+    {
+        let old_credentials = self.credentials.lock().unwrap().clone().ok_or_else(|| {
+            format_err!(
+                ErrorKind::AuthenticationError,
+                "session reconnection disabled: can't restore the old authentication key \
+                 if the change fails"
+            )
+        })?;
+
+        let key_id = old_credentials.authentication_key_id;
+        let new_credentials = Credentials::new(key_id, authentication_key.into());
+
+        self.send_command(ChangeAuthenticationKeyCommand {
+            key_id,
+            algorithm: authentication::Algorithm::YubicoAes,
+            authentication_key: new_credentials.authentication_key.clone(),
+        })?;
+
+        let verified = Session::open(
+            self.connector.clone(),
+            &new_credentials,
+            session::Timeout::default(),
+            Some(self.stats.clone()),
+        )
+        .and_then(Session::close);
+
+        if let Err(e) = verified {
+            let restored = self.send_command(ChangeAuthenticationKeyCommand {
+                key_id,
+                algorithm: authentication::Algorithm::YubicoAes,
+                authentication_key: old_credentials.authentication_key,
+            });
+
+            match restored {
+                Ok(_) => fail!(
+                    ErrorKind::AuthenticationError,
+                    "new authentication key 0x{:04x} failed to authenticate (old key restored): {}",
+                    key_id,
+                    e
+                ),
+                Err(restore_error) => fail!(
+                    ErrorKind::AuthenticationError,
+                    "new authentication key 0x{:04x} failed to authenticate ({}), and restoring \
+                     the old key failed: {}",
+                    key_id,
+                    e,
+                    restore_error
+                ),
+            }
+        }
+
+        *self.credentials.lock().unwrap() = Some(new_credentials);
+        Ok(())
     }
 
-    /// Put an existing `authentication::Key` into the HSM.
+    /// Derive a new authentication key from the HMAC-SHA256 key `base_key_id`
+    /// and the given `context`, and put it into the HSM as `key_id`.
     ///
-    /// <https://developers.yubico.com/YubiHSM2/Commands/Put_Authentication_Key.html>
-    pub fn put_authentication_key<K>(
+    /// The YubiHSM 2 firmware has no command for deriving authentication
+    /// keys, so the derivation is built from its `Sign HMAC` command: the
+    /// key is HKDF-Expand-SHA256 (RFC 5869) with the base key as the
+    /// pseudorandom key, `context` as the info, and a 32-byte output
+    /// (i.e. `HMAC-SHA256(base_key, context || 0x01)`). Deriving with the
+    /// same base key and context always results in the same key.
+    ///
+    /// The derived key briefly passes through host memory on its way back
+    /// into the HSM (it's zeroized afterwards). The base key needs the
+    /// `SIGN_HMAC` capability.
+    pub fn derive_authentication_key(
         &self,
+        base_key_id: object::Id,
+        context: &[u8],
         key_id: object::Id,
         label: object::Label,
         domains: Domain,
         capabilities: Capability,
         delegated_capabilities: Capability,
-        algorithm: authentication::Algorithm,
-        authentication_key: K,
-    ) -> Result<object::Id, Error>
-    where
-        K: Into<authentication::Key>,
-    {
-        Ok(self
-            .send_command(PutAuthenticationKeyCommand {
-                params: object::put::Params {
-                    id: key_id,
-                    label,
-                    domains,
-                    capabilities,
-                    algorithm: algorithm.into(),
-                },
-                delegated_capabilities,
-                authentication_key: authentication_key.into(),
-            })?
-            .key_id)
+    ) -> Result<object::Id, Error> {
+        let mut info = Vec::with_capacity(context.len() + 1);
+        info.extend_from_slice(context);
+        info.push(0x01);
+
+        let okm = Zeroizing::new(self.sign_hmac(base_key_id, info)?.into_vec());
+
+        ensure!(
+            okm.len() == authentication::key::SIZE,
+            ErrorKind::ProtocolError,
+            "base key 0x{:04x} must be an HMAC-SHA256 key (got {}-byte tag)",
+            base_key_id,
+            okm.len()
+        );
+
+        let mut key_bytes = [0u8; authentication::key::SIZE];
+        key_bytes.copy_from_slice(&okm);
+
+        self.put_authentication_key(
+            key_id,
+            label,
+            domains,
+            capabilities,
+            delegated_capabilities,
+            authentication::Algorithm::YubicoAes,
+            authentication::Key::new(key_bytes),
+        )
     }
 
     /// Put an existing HMAC key into the HSM.
@@ -640,6 +1652,9 @@ impl Client {
 
     /// Put an opaque object (X.509 certificate or other bytestring) into the HSM.
     ///
+    /// Fails with `ErrorKind::ProtocolError` if the object is too large to
+    /// send in a single command (see [`Client::max_message_size`]).
+    ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Put_Opaque.html>
     pub fn put_opaque<B>(
         &self,
@@ -653,18 +1668,117 @@ impl Client {
     where
         B: Into<Vec<u8>>,
     {
-        Ok(self
-            .send_command(PutOpaqueCommand {
-                params: object::put::Params {
-                    id: object_id,
-                    label,
-                    domains,
-                    capabilities,
-                    algorithm: algorithm.into(),
-                },
-                data: opaque_data.into(),
-            })?
-            .object_id)
+        let command = PutOpaqueCommand {
+            params: object::put::Params {
+                id: object_id,
+                label,
+                domains,
+                capabilities,
+                algorithm: algorithm.into(),
+            },
+            data: opaque_data.into(),
+        };
+
+        let command_len = serialize(&command)?.len();
+        let max_data_len = self.max_data_len();
+
+        ensure!(
+            command_len <= max_data_len,
+            ErrorKind::ProtocolError,
+            "opaque object too large: {} bytes ({}-byte command, max {})",
+            command.data.len(),
+            command_len,
+            max_data_len
+        );
+
+        Ok(self.send_command(command)?.object_id)
+    }
+
+    /// Put a payload too large for a single opaque object (e.g. a CRL or a
+    /// certificate chain) into the HSM, split across opaque objects with
+    /// consecutive IDs starting from `base_id`, returning the number of
+    /// objects used.
+    ///
+    /// Each chunk begins with a header containing its index, the number of
+    /// chunks, and the length and CRC-32 checksum of the whole payload,
+    /// which [`Client::get_opaque_chunked`] uses to reassemble and verify it.
+    ///
+    /// Fails with `ErrorKind::ProtocolError` if the payload is larger than
+    /// [`opaque::MAX_CHUNKED_SIZE`], or the IDs would run past the last
+    /// object ID. If any chunk can't be stored, the chunks already stored
+    /// are deleted.
+    pub fn put_opaque_chunked(
+        &self,
+        base_id: object::Id,
+        label: object::Label,
+        domains: Domain,
+        data: &[u8],
+    ) -> Result<u16, Error> {
+        ensure!(
+            data.len() <= opaque::MAX_CHUNKED_SIZE,
+            ErrorKind::ProtocolError,
+            "chunked opaque payload too large: {} bytes (max {})",
+            data.len(),
+            opaque::MAX_CHUNKED_SIZE
+        );
+
+        // Each chunk is the largest opaque object which fits in one command
+        let empty_command = PutOpaqueCommand {
+            params: object::put::Params::new(0, opaque::Algorithm::Data.into()),
+            data: vec![],
+        };
+
+        let overhead = serialize(&empty_command)?.len() + opaque::chunked::HEADER_SIZE;
+        let chunk_len = self.max_data_len().saturating_sub(overhead);
+
+        ensure!(
+            chunk_len > 0,
+            ErrorKind::ProtocolError,
+            "maximum message size is too small for chunked opaque objects"
+        );
+
+        let chunks: Vec<&[u8]> = if data.is_empty() {
+            vec![data]
+        } else {
+            data.chunks(chunk_len).collect()
+        };
+
+        ensure!(
+            usize::from(base_id) + chunks.len() - 1 <= usize::from(object::Id::MAX),
+            ErrorKind::ProtocolError,
+            "{} chunks starting at object 0x{:04x} would exceed the maximum object ID",
+            chunks.len(),
+            base_id
+        );
+
+        let header = opaque::chunked::Header {
+            index: 0,
+            total: chunks.len() as u16,
+            len: data.len() as u32,
+            checksum: opaque::chunked::crc32(data),
+        };
+
+        for (index, chunk) in (0..).zip(&chunks) {
+            let result = self.put_opaque(
+                base_id + index,
+                label.clone(),
+                domains,
+                Capability::default(),
+                opaque::Algorithm::Data,
+                opaque::chunked::Header { index, ..header }.to_chunk(chunk),
+            );
+
+            if let Err(err) = result {
+                // Don't leave an incomplete payload behind
+                for stored in 0..index {
+                    let _ = self.delete_object(base_id + stored, object::Type::Opaque);
+                }
+
+                return Err(err);
+            }
+        }
+
+        Ok(header.total)
     }
 
     /// Put an existing OTP AEAD key into the HSM.
@@ -684,15 +1798,7 @@ impl Client {
     {
         let data = key_bytes.into();
 
-        if data.len() != algorithm.key_len() {
-            fail!(
-                ErrorKind::ProtocolError,
-                "invalid key length for {:?}: {} (expected {})",
-                algorithm,
-                data.len(),
-                algorithm.key_len()
-            );
-        }
+        ensure_key_length(algorithm.into(), &data)?;
 
         Ok(self
             .send_command(PutOtpAeadKeyCommand {
@@ -726,15 +1832,7 @@ impl Client {
     {
         let data = key_bytes.into();
 
-        if data.len() != algorithm.key_len() {
-            fail!(
-                ErrorKind::ProtocolError,
-                "invalid key length for {:?}: {} (expected {})",
-                algorithm,
-                data.len(),
-                algorithm.key_len()
-            );
-        }
+        ensure_key_length(algorithm.into(), &data)?;
 
         Ok(self
             .send_command(PutWrapKeyCommand {
@@ -751,6 +1849,86 @@ impl Client {
             .key_id)
     }
 
+    /// Put an existing wrap key into the HSM (as with
+    /// [`Client::put_wrap_key`]) after checking its key check value matches
+    /// the expected one, e.g. the KCV recorded when the key was created.
+    ///
+    /// Fails with `ErrorKind::KcvMismatch` (without importing the key) if
+    /// they differ.
+    pub fn put_wrap_key_with_kcv<K>(
+        &self,
+        key_id: object::Id,
+        label: object::Label,
+        domains: Domain,
+        capabilities: Capability,
+        delegated_capabilities: Capability,
+        algorithm: wrap::Algorithm,
+        key_bytes: K,
+        expected_kcv: wrap::WrapKeyKcv,
+    ) -> Result<object::Id, Error>
+    where
+        K: Into<Vec<u8>>,
+    {
+        let data = Zeroizing::new(key_bytes.into());
+
+        ensure_key_length(algorithm.into(), &data)?;
+        let actual = wrap::WrapKeyKcv::compute(algorithm, &data).expect("key length checked");
+
+        if actual != expected_kcv {
+            fail!(
+                ErrorKind::KcvMismatch {
+                    expected: expected_kcv,
+                    actual,
+                },
+                "wrap key 0x{:04x} has KCV {} (expected {})",
+                key_id,
+                actual,
+                expected_kcv
+            );
+        }
+
+        self.put_wrap_key(
+            key_id,
+            label,
+            domains,
+            capabilities,
+            delegated_capabilities,
+            algorithm,
+            data.as_slice(),
+        )
+    }
+
+    /// Put a wrap key derived from a passphrase into the HSM (see
+    /// [`wrap::KdfParams`]).
+    ///
+    /// The key can be derived again from the same passphrase and parameters,
+    /// e.g. to restore a backup on another HSM or decrypt it in software.
+    /// Record the parameters along with the backup (in
+    /// [`wrap::BackupManifest::kdf`]) so the key can be re-derived.
+    #[cfg(feature = "passwords")]
+    pub fn put_wrap_key_from_passphrase(
+        &self,
+        key_id: object::Id,
+        label: object::Label,
+        domains: Domain,
+        capabilities: Capability,
+        delegated_capabilities: Capability,
+        passphrase: &str,
+        params: &wrap::KdfParams,
+    ) -> Result<object::Id, Error> {
+        let key_bytes = params.derive_key(passphrase)?;
+
+        self.put_wrap_key(
+            key_id,
+            label,
+            domains,
+            capabilities,
+            delegated_capabilities,
+            params.algorithm,
+            key_bytes.as_slice(),
+        )
+    }
+
     /// Put a template object (i.e. for SSH CA) into the HSM.
     ///
     /// Use the `yubihsm::ssh::Template` type for SSH CA templates.
@@ -817,7 +1995,7 @@ impl Client {
     ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Reset_Device.html>
     #[cfg(feature = "passwords")]
-    pub fn reset_device_and_reconnect(&mut self, timeout: Duration) -> Result<(), Error> {
+    pub fn reset_device_and_reconnect(&self, timeout: Duration) -> Result<(), Error> {
         /// How long to initially wait for a device reset to complete (1s)
         const DEVICE_RESET_WAIT_MS: u64 = 1000;
 
@@ -832,7 +2010,7 @@ impl Client {
         self.reset_device()?;
 
         // Configure default credentials
-        self.credentials = Some(Credentials::default());
+        *self.credentials.lock().unwrap() = Some(Credentials::default());
 
         let deadline = SystemTime::now() + timeout;
 
@@ -873,13 +2051,10 @@ impl Client {
         command: command::Code,
         audit_option: AuditOption,
     ) -> Result<(), Error> {
-        self.send_command(SetOptionCommand {
-            tag: AuditTag::Command,
-            length: 2,
-            value: serialize(&AuditCommand(command, audit_option))?,
-        })?;
-
-        Ok(())
+        self.set_option_raw(
+            AuditTag::Command.to_u8(),
+            &serialize(&AuditCommand(command, audit_option))?,
+        )
     }
 
     /// Put the forced auditing global option: when enabled, the device will
@@ -891,10 +2066,30 @@ impl Client {
     ///
     /// [log store]: https://developers.yubico.com/YubiHSM2/Concepts/Logs.html
     pub fn set_force_audit_option(&self, option: AuditOption) -> Result<(), Error> {
+        self.set_option_raw(AuditTag::Force.to_u8(), &[option.to_u8()])
+    }
+
+    /// Set the raw value of the device option with the given ID.
+    ///
+    /// This allows configuring options added by newer firmware which this
+    /// crate has no typed method for (e.g. [`Client::set_force_audit_option`]
+    /// is built on this, with option ID `0x01`). The value's format is
+    /// specific to each option.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Set_Option.html>
+    pub fn set_option_raw(&self, option_id: u8, value: &[u8]) -> Result<(), Error> {
+        let length = u16::try_from(value.len()).map_err(|_| {
+            format_err!(
+                ErrorKind::ProtocolError,
+                "option value too long: {} bytes",
+                value.len()
+            )
+        })?;
+
         self.send_command(SetOptionCommand {
-            tag: AuditTag::Force,
-            length: 1,
-            value: vec![option.to_u8()],
+            tag: option_id,
+            length,
+            value: value.into(),
         })?;
 
         Ok(())
@@ -908,6 +2103,50 @@ impl Client {
         Ok(())
     }
 
+    /// Sign a message with the given ECDSA key, and obtain an attestation
+    /// certificate for the key from the given attestation key (or the
+    /// device's default attestation key if none is given), packaging both
+    /// so they can be verified together.
+    ///
+    /// The message is hashed with the default digest for the key's curve
+    /// (i.e. SHA-256 for NIST P-256 and secp256k1, SHA-384 for NIST P-384).
+    pub fn sign_and_attest(
+        &self,
+        key_id: object::Id,
+        attestation_key_id: Option<object::Id>,
+        message: &[u8],
+    ) -> Result<attestation::SignedAttestation, Error> {
+        let message_hash = match self.get_public_key(key_id)?.algorithm {
+            asymmetric::Algorithm::EcP256 => {
+                default_digest::<ecdsa::Signature<ecdsa::NistP256>>(message)
+            }
+            asymmetric::Algorithm::EcP384 => {
+                default_digest::<ecdsa::Signature<ecdsa::NistP384>>(message)
+            }
+            #[cfg(feature = "secp256k1")]
+            asymmetric::Algorithm::EcK256 => {
+                default_digest::<ecdsa::Signature<ecdsa::Secp256k1>>(message)
+            }
+            algorithm => fail!(
+                ErrorKind::AlgorithmUnsupported,
+                "can't sign and attest with {:?} key 0x{:04x}",
+                algorithm,
+                key_id
+            ),
+        };
+
+        let signature = self.sign_ecdsa_prehash_raw(key_id, message_hash.as_slice())?;
+        let certificate = self.sign_attestation_certificate(key_id, attestation_key_id)?;
+        let info = self.get_object_info(key_id, object::Type::AsymmetricKey)?;
+
+        Ok(attestation::SignedAttestation {
+            message_hash,
+            signature,
+            certificate_der: certificate.into_vec(),
+            label: info.label,
+        })
+    }
+
     /// Obtain an X.509 attestation certificate for a key within the HSM.
     /// This can be used to demonstrate that a given key was generated by
     /// and stored within a HSM in a non-exportable manner.
@@ -942,6 +2181,10 @@ impl Client {
     ///
     /// We recommend using the [`ecdsa::Signer`] type instead, which provides a
     /// high-level, well-typed, misuse resistant API.
+    ///
+    /// This works with keys on any curve the device supports, and is
+    /// currently the only way to sign with Brainpool keys. The signature is
+    /// returned ASN.1 DER encoded.
     pub fn sign_ecdsa_prehash_raw<T>(&self, key_id: object::Id, digest: T) -> Result<Vec<u8>, Error>
     where
         T: Into<Vec<u8>>,
@@ -1042,37 +2285,117 @@ impl Client {
             .into())
     }
 
-    /// Sign an SSH certificate using the given template.
+    /// Compute an RSASSA-PSS signature of the given message with the given
+    /// key ID, using `hash` as both the message digest and the MGF1 hash
+    /// function, along with a salt of `salt_len` bytes.
     ///
     /// **WARNING**: This functionality has not been tested and has not yet been
     /// confirmed to actually work! USE AT YOUR OWN RISK!
     ///
     /// You will need to enable the `untested` cargo feature to use it.
     ///
-    /// <https://developers.yubico.com/YubiHSM2/Commands/Sign_Ssh_Certificate.html>
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Sign_Pss.html>
     #[cfg(feature = "untested")]
-    pub fn sign_ssh_certificate<A>(
+    pub fn sign_rsa_pss(
         &self,
         key_id: object::Id,
-        template_id: object::Id,
-        algorithm: A,
-        timestamp: u32,
-        signature: [u8; 32],
-        request: Vec<u8>,
-    ) -> Result<ssh::Certificate, Error>
-    where
-        A: Into<Algorithm>,
-    {
+        salt_len: usize,
+        hash: rsa::mgf::Algorithm,
+        message: &[u8],
+    ) -> Result<rsa::pss::Signature, Error> {
+        ensure!(
+            salt_len <= u16::MAX as usize,
+            ErrorKind::ProtocolError,
+            "salt too long (max: {})",
+            u16::MAX
+        );
+
+        let digest = match hash {
+            rsa::mgf::Algorithm::Sha1 => fail!(
+                ErrorKind::ProtocolError,
+                "SHA-1 message digests are unsupported (this crate has no SHA-1 implementation)"
+            ),
+            rsa::mgf::Algorithm::Sha256 => Sha256::digest(message).to_vec(),
+            rsa::mgf::Algorithm::Sha384 => Sha384::digest(message).to_vec(),
+            rsa::mgf::Algorithm::Sha512 => Sha512::digest(message).to_vec(),
+        };
+
         Ok(self
-            .send_command(SignSshCertificateCommand {
+            .send_command(SignPssCommand {
                 key_id,
+                mgf1_hash_alg: hash,
+                salt_len: salt_len as u16,
+                digest,
+            })?
+            .into())
+    }
+
+    /// Sign an OpenSSH certificate with the given CA key, subject to the
+    /// constraints of the given SSH template, returning the certificate in
+    /// the OpenSSH wire format (i.e. the base64-decoded contents of a
+    /// `*-cert.pub` file).
+    ///
+    /// Supports Ed25519, NIST P-256/P-384, and RSA (signing with
+    /// `rsa-sha2-256`) CA keys.
+    ///
+    /// **WARNING**: This functionality has not been tested and has not yet been
+    /// confirmed to actually work! USE AT YOUR OWN RISK!
+    ///
+    /// You will need to enable the `untested` cargo feature to use it.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Sign_Ssh_Certificate.html>
+    #[cfg(feature = "untested")]
+    pub fn sign_ssh_certificate(
+        &self,
+        template_id: object::Id,
+        ca_key_id: object::Id,
+        request: ssh::CertificateRequest,
+    ) -> Result<Vec<u8>, Error> {
+        ensure!(
+            request.timestamp_signature.len() == ssh::TIMESTAMP_SIGNATURE_SIZE,
+            ErrorKind::ProtocolError,
+            "expected {}-byte timestamp signature (got {})",
+            ssh::TIMESTAMP_SIGNATURE_SIZE,
+            request.timestamp_signature.len()
+        );
+
+        let ca_public_key = self.get_public_key(ca_key_id)?;
+
+        let algorithm: Algorithm = match ca_public_key.algorithm {
+            asymmetric::Algorithm::EcP256 => ecdsa::Algorithm::Sha256.into(),
+            asymmetric::Algorithm::EcP384 => ecdsa::Algorithm::Sha384.into(),
+            asymmetric::Algorithm::Ed25519 => asymmetric::Algorithm::Ed25519.into(),
+            asymmetric::Algorithm::Rsa2048
+            | asymmetric::Algorithm::Rsa3072
+            | asymmetric::Algorithm::Rsa4096 => {
+                rsa::Algorithm::Pkcs1(rsa::pkcs1::Algorithm::Sha256).into()
+            }
+            algorithm => fail!(
+                ErrorKind::AlgorithmUnsupported,
+                "can't sign SSH certificates with {:?} key 0x{:04x}",
+                algorithm,
+                ca_key_id
+            ),
+        };
+
+        let request = request.to_bytes(&ca_public_key).ok_or_else(|| {
+            format_err!(
+                ErrorKind::AlgorithmUnsupported,
+                "OpenSSH doesn't support {:?} keys",
+                request.public_key.algorithm
+            )
+        })?;
+
+        let certificate: ssh::Certificate = self
+            .send_command(SignSshCertificateCommand {
+                key_id: ca_key_id,
                 template_id,
-                algorithm: algorithm.into(),
-                timestamp,
-                signature,
+                algorithm,
                 request,
             })?
-            .into())
+            .into();
+
+        Ok(certificate.as_slice().into())
     }
 
     /// Decrypt data which was encrypted (using AES-CCM) under a wrap key.
@@ -1093,6 +2416,32 @@ impl Client {
             .0)
     }
 
+    /// Verify an attestation certificate (i.e. one obtained via
+    /// `sign_attestation_certificate`), checking its signature chain up to
+    /// `trusted_root` and returning the attested properties of the key.
+    ///
+    /// `intermediates` are the DER-encoded certificates between the attested
+    /// key and `trusted_root`, ordered from the one which signed `cert_der`
+    /// toward the root. For keys attested by the device attestation key this
+    /// is the device attestation certificate (opaque object 0) followed by
+    /// any Yubico intermediates.
+    ///
+    /// `trusted_root` is the DER-encoded certificate the chain must end in.
+    /// It must be obtained out of band (e.g. Yubico's attestation root), as
+    /// certificates read from the device itself attest nothing about it.
+    /// The issuers may use ECDSA (P-256, P-384) or, with the `rsa` and `sha2`
+    /// features, RSA PKCS#1 v1.5 with SHA-256.
+    /// See [`attestation::Certificate::verify`] for the other checks made.
+    pub fn verify_attestation(
+        &self,
+        cert_der: &[u8],
+        intermediates: &[&[u8]],
+        trusted_root: &[u8],
+    ) -> Result<attestation::Info, Error> {
+        let certificate = attestation::Certificate(cert_der.into());
+        Ok(certificate.verify(intermediates, trusted_root)?)
+    }
+
     /// Verify an HMAC tag of the given data with the given key ID.
     ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Verify_Hmac.html>
@@ -1130,3 +2479,85 @@ impl Client {
             .0)
     }
 }
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        // Close the session once the last clone of this client is dropped.
+        // Dropping a `Session` only aborts it (so as not to block while its
+        // mutex is held), which would leave its slot on the HSM in use.
+        if Arc::strong_count(&self.session) != 1 {
+            return;
+        }
+
+        let session = match self.session.lock() {
+            Ok(mut guard) => guard.take(),
+            Err(_) => return,
+        };
+
+        if let Some(session) = session {
+            if let Err(e) = session.close() {
+                debug!("error closing session: {}", e);
+            }
+        }
+    }
+}
+
+/// Ensure key material being imported has the length expected for its algorithm
+fn ensure_key_length(algorithm: Algorithm, key_bytes: &[u8]) -> Result<(), Error> {
+    if let Some(expected_len) = algorithm.expected_key_length() {
+        ensure!(
+            key_bytes.len() == expected_len,
+            ErrorKind::ProtocolError,
+            "invalid key length for {:?}: {} (expected {})",
+            algorithm,
+            key_bytes.len(),
+            expected_len
+        );
+    }
+
+    Ok(())
+}
+
+/// Session errors after which [`Builder::reset_session_on_error`] closes the
+/// session: those which mean we can no longer trust the state of the secure
+/// channel (as opposed to e.g. device errors, which leave it intact)
+fn is_protocol_error(kind: session::ErrorKind) -> bool {
+    matches!(
+        kind,
+        session::ErrorKind::MismatchError
+            | session::ErrorKind::ProtocolError
+            | session::ErrorKind::ReplayDetected
+            | session::ErrorKind::ResponseError
+            | session::ErrorKind::VerifyFailed
+    )
+}
+
+/// Commands which [`Builder::timing_jitter`] delays
+fn is_signing_command(code: command::Code) -> bool {
+    matches!(
+        code,
+        command::Code::SignAttestationCertificate
+            | command::Code::SignEcdsa
+            | command::Code::SignEddsa
+            | command::Code::SignHmac
+            | command::Code::SignPkcs1
+            | command::Code::SignPss
+            | command::Code::SignSshCertificate
+    )
+}
+
+/// Pick a random delay within the given range (or its start, if it's empty)
+fn random_delay(range: &Range<Duration>) -> Duration {
+    let span = range.end.saturating_sub(range.start).as_nanos() as u64;
+
+    if span == 0 {
+        return range.start;
+    }
+
+    range.start + Duration::from_nanos(OsRng.next_u64() % span)
+}
+
+/// Hash a message with the default digest for signatures of type `S`
+fn default_digest<S: PrehashSignature>(message: &[u8]) -> Vec<u8> {
+    S::Digest::digest(message).to_vec()
+}
@@ -0,0 +1,241 @@
+//! Builder for `Client`s with non-default settings
+
+use super::{Client, Error, ErrorKind, RetryPolicy};
+use crate::{authentication::Credentials, connector::Connector, device};
+use std::{
+    ops::Range,
+    thread,
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "mockhsm")]
+use {super::DispatchHook, crate::command, std::sync::Arc};
+
+/// How long to wait between attempts to open a session while the HSM's
+/// sessions are all in use
+const SESSION_RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Builder for [`Client`]s with non-default settings
+pub struct Builder {
+    /// Connector for communicating with the HSM
+    connector: Connector,
+
+    /// Credentials used to authenticate sessions
+    credentials: Option<Credentials>,
+
+    /// Reconnect automatically when the session is closed
+    reconnect: bool,
+
+    /// Maximum time to wait for the HSM to respond to each command
+    command_timeout: Option<Duration>,
+
+    /// Maximum time to wait for a session to become available
+    session_wait: Option<Duration>,
+
+    /// Range of randomized delays added before each signing command
+    timing_jitter: Option<Range<Duration>>,
+
+    /// Close the session after protocol errors
+    reset_session_on_error: bool,
+
+    /// How to retry operations which fail with transient errors
+    retry_policy: RetryPolicy,
+
+    /// Refuse to delete the last admin authentication key
+    guard_last_admin_key: bool,
+
+    /// Verify sessions idle for at least this long before reusing them
+    verify_idle_sessions: Option<Duration>,
+
+    /// Called before each command is dispatched
+    #[cfg(feature = "mockhsm")]
+    dispatch_hook: Option<DispatchHook>,
+}
+
+impl Builder {
+    /// Create a new builder which uses the given connector, the default
+    /// credentials (if the `passwords` feature is enabled; otherwise they
+    /// must be set with [`Builder::credentials`]), and reconnects
+    /// automatically
+    pub fn new(connector: Connector) -> Self {
+        Self {
+            connector,
+            #[cfg(feature = "passwords")]
+            credentials: Some(Credentials::default()),
+            #[cfg(not(feature = "passwords"))]
+            credentials: None,
+            reconnect: true,
+            command_timeout: None,
+            session_wait: None,
+            timing_jitter: None,
+            reset_session_on_error: true,
+            retry_policy: RetryPolicy::default(),
+            guard_last_admin_key: false,
+            verify_idle_sessions: None,
+            #[cfg(feature = "mockhsm")]
+            dispatch_hook: None,
+        }
+    }
+
+    /// Set the credentials used to authenticate sessions
+    pub fn credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    /// Enable or disable automatically reconnecting closed sessions
+    pub fn reconnect(mut self, reconnect: bool) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
+
+    /// Bound the time spent sending each command to the HSM and receiving
+    /// its response.
+    ///
+    /// This is enforced by the connector (i.e. as socket or USB transfer
+    /// timeouts) rather than by a separate thread. Commands which time out
+    /// fail with a `connector::ErrorKind::Timeout` error, and the session is
+    /// aborted since its MAC chain may no longer be in sync with the HSM.
+    /// The next command then opens a new session (if reconnecting).
+    pub fn command_timeout(mut self, timeout: Duration) -> Self {
+        self.command_timeout = Some(timeout);
+        self
+    }
+
+    /// Keep retrying to open a session for up to the given duration if the
+    /// HSM's sessions are all in use (i.e. it responds with
+    /// `device::ErrorKind::SessionsFull`).
+    ///
+    /// The YubiHSM 2 supports 16 concurrent sessions. The only way to close
+    /// a session is from the client which opened it, so sessions left behind
+    /// by crashed clients linger until they time out after 30 seconds of
+    /// inactivity, short of resetting the device (which deletes all of its
+    /// objects). Waiting at least that long allows recovering from sessions
+    /// leaked this way.
+    pub fn wait_for_session(mut self, timeout: Duration) -> Self {
+        self.session_wait = Some(timeout);
+        self
+    }
+
+    /// Delay each signing command (e.g. `Sign ECDSA`, `Sign EdDSA`, or
+    /// `Sign HMAC`) by a random duration within the given range before
+    /// sending it to the HSM, to blur request timing observable on the
+    /// network.
+    ///
+    /// This only affects when commands are sent, not what is signed.
+    pub fn timing_jitter(mut self, jitter: Range<Duration>) -> Self {
+        self.timing_jitter = Some(jitter);
+        self
+    }
+
+    /// Enable or disable closing the session after protocol errors (enabled
+    /// by default).
+    ///
+    /// Errors such as a MAC failure, a replayed or mismatched message, or a
+    /// malformed or unexpected response mean the secure channel's state is
+    /// no longer in sync with the HSM, so every subsequent command on the
+    /// session would fail too. With this enabled the session is closed after
+    /// such an error, and the next command opens a new session (if
+    /// reconnecting). Device errors (e.g. `ObjectNotFound`) leave the session
+    /// intact either way.
+    pub fn reset_session_on_error(mut self, reset: bool) -> Self {
+        self.reset_session_on_error = reset;
+        self
+    }
+
+    /// Retry operations which fail with transient errors (e.g. the
+    /// connection to the HSM dropping briefly) according to the given
+    /// policy (default: no retries).
+    ///
+    /// Only operations which are safe to repeat honor this, e.g. signing
+    /// with an [`ecdsa::Signer`](crate::ecdsa::Signer). Retries open a new
+    /// session, so this has no effect unless reconnecting is enabled.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Enable or disable refusing to delete the last admin authentication
+    /// key (disabled by default).
+    ///
+    /// Admin keys are authentication keys with the `PUT_AUTHENTICATION_KEY`
+    /// capability, i.e. ones which can create new authentication keys to
+    /// regain access. With this enabled, [`Client::delete_object`] fails
+    /// with `ErrorKind::RefusingLastAdminKeyDelete` rather than delete an
+    /// admin key when no others remain. Only keys in the session's domains
+    /// can be seen, so keys in other domains don't count.
+    pub fn guard_last_admin_key(mut self, guard: bool) -> Self {
+        self.guard_last_admin_key = guard;
+        self
+    }
+
+    /// Verify sessions which have been idle for at least `threshold` with
+    /// [`Client::verify_session`] before reusing them (disabled by default).
+    ///
+    /// The HSM may close sessions on its own (e.g. after its idle timeout or
+    /// a reset), and with this enabled such sessions are reopened up front
+    /// rather than failing the next command. Verifying costs an extra `Echo`
+    /// round trip.
+    pub fn verify_idle_sessions(mut self, threshold: Duration) -> Self {
+        self.verify_idle_sessions = Some(threshold);
+        self
+    }
+
+    /// Call the given hook with each command's code before dispatching it
+    /// (after any [`Builder::timing_jitter`] delay, but before acquiring the
+    /// session), so tests can observe commands or control how commands sent
+    /// concurrently interleave, e.g. by waiting on a `std::sync::Barrier`.
+    ///
+    /// Hooks run on the thread sending the command. They're shared by clones
+    /// of the client, so hooks which block until another command is
+    /// dispatched should be used with separate clients (and therefore
+    /// sessions): clones send commands through the same session one at a
+    /// time. Only available with the `mockhsm` feature.
+    #[cfg(feature = "mockhsm")]
+    pub fn dispatch_hook(mut self, hook: impl Fn(command::Code) + Send + Sync + 'static) -> Self {
+        self.dispatch_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Open a connection to the HSM with these settings
+    pub fn open(self) -> Result<Client, Error> {
+        let credentials = self.credentials.ok_or_else(|| {
+            format_err!(ErrorKind::AuthenticationError, "no credentials configured")
+        })?;
+
+        let mut client = Client::create(self.connector, credentials)?;
+        client.command_timeout = self.command_timeout;
+        client.timing_jitter = self.timing_jitter;
+        client.reset_session_on_error = self.reset_session_on_error;
+        client.retry_policy = self.retry_policy;
+        client.guard_last_admin_key = self.guard_last_admin_key;
+        client.verify_idle_sessions = self.verify_idle_sessions;
+
+        #[cfg(feature = "mockhsm")]
+        {
+            client.dispatch_hook = self.dispatch_hook;
+        }
+
+        let deadline = self.session_wait.map(|wait| Instant::now() + wait);
+
+        loop {
+            match client.connect() {
+                Ok(()) => break,
+                Err(e)
+                    if e.device_error() == Some(device::ErrorKind::SessionsFull)
+                        && deadline.map_or(false, |deadline| Instant::now() < deadline) =>
+                {
+                    thread::sleep(SESSION_RETRY_INTERVAL);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        // Clear credentials if reconnecting has been disabled
+        if !self.reconnect {
+            *client.credentials.lock().unwrap() = None;
+        }
+
+        Ok(client)
+    }
+}
@@ -1,19 +1,43 @@
 //! YubiHSM client errors
 
 use crate::{
-    connector, device,
+    asymmetric, attestation, connector, device,
     error::{BoxError, Context},
-    serialization, session,
+    serialization, session, wrap,
 };
 use std::io;
 use thiserror::Error;
 
-/// Client errors
+/// Client errors.
+///
+/// Errors from HSM commands include extended details in their alternate
+/// (`{:#}`) formatting: the command attempted, the object it operated on,
+/// the session's authentication key ID, and (for common device errors) a
+/// one-line hint for resolving the failure.
 pub type Error = crate::Error<ErrorKind>;
 
 /// Client error kinds
 #[derive(Copy, Clone, Debug, Eq, Error, PartialEq)]
 pub enum ErrorKind {
+    /// Key's algorithm isn't the one an operation requires (e.g. a P-256
+    /// signer was created for a secp256k1 key)
+    #[error("algorithm mismatch (expected {expected:?}, got {actual:?})")]
+    AlgorithmMismatch {
+        /// Algorithm the operation requires
+        expected: asymmetric::Algorithm,
+
+        /// Algorithm of the key in the HSM
+        actual: asymmetric::Algorithm,
+    },
+
+    /// Algorithm isn't supported by the HSM
+    #[error("algorithm unsupported")]
+    AlgorithmUnsupported,
+
+    /// Attestation certificate couldn't be verified
+    #[error("attestation error")]
+    AttestationError,
+
     /// Couldn't authenticate session
     #[error("authentication failed")]
     AuthenticationError,
@@ -34,13 +58,59 @@ pub enum ErrorKind {
     #[error("HSM error")]
     DeviceError,
 
+    /// Imported key didn't match what was expected
+    #[error("import verification failed")]
+    ImportVerificationFailed,
+
+    /// Key check value of a wrap key doesn't match the expected one
+    #[error("key check value mismatch (expected {expected}, got {actual})")]
+    KcvMismatch {
+        /// Key check value the key was expected to have
+        expected: wrap::WrapKeyKcv,
+
+        /// Key check value of the key
+        actual: wrap::WrapKeyKcv,
+    },
+
+    /// HSM doesn't have enough free storage (e.g. to restore a backup)
+    #[error("insufficient storage")]
+    InsufficientStorage,
+
+    /// Signature returned by the HSM is malformed or doesn't verify
+    #[error("invalid signature")]
+    InvalidSignature,
+
     /// Protocol error occurred
     #[error("protocol error")]
     ProtocolError,
 
+    /// Refused to delete the last admin authentication key (see
+    /// [`Builder::guard_last_admin_key`](super::Builder::guard_last_admin_key))
+    #[error("refusing to delete the last admin authentication key")]
+    RefusingLastAdminKeyDelete,
+
+    /// Refused to delete the current session's authentication key (see
+    /// [`Client::delete_object_force`](super::Client::delete_object_force))
+    #[error("refusing to delete the current session's authentication key")]
+    RefusingSelfDelete,
+
     /// Error response from HSM we can't further specify
     #[error("HSM response error")]
     ResponseError,
+
+    /// Operation requires a newer firmware version than the HSM's
+    #[error("unsupported by firmware {actual} (requires {required})")]
+    UnsupportedByFirmware {
+        /// Firmware version the operation requires
+        required: device::FirmwareVersion,
+
+        /// Firmware version the HSM reported
+        actual: device::FirmwareVersion,
+    },
+
+    /// Errors encrypting or decrypting wrapped objects in software
+    #[error("wrap error")]
+    WrapError,
 }
 
 impl Error {
@@ -54,6 +124,33 @@ impl Error {
             None
         }
     }
+
+    /// Is this error transient, i.e. caused by the connection to the HSM
+    /// (e.g. it dropped or timed out) rather than by the command itself?
+    ///
+    /// Cryptographic and authorization failures (e.g. a MAC which doesn't
+    /// verify, or a device error such as `InsufficientPermissions`) are
+    /// never transient: retrying them would fail the same way, or worse.
+    pub fn is_transient(&self) -> bool {
+        use std::error::Error;
+        let mut source = self.source();
+
+        while let Some(err) = source {
+            if let Some(connector_err) = err.downcast_ref::<connector::Error>() {
+                return matches!(
+                    connector_err.kind(),
+                    connector::ErrorKind::ConnectionFailed
+                        | connector::ErrorKind::DeviceBusyError
+                        | connector::ErrorKind::IoError
+                        | connector::ErrorKind::Timeout
+                );
+            }
+
+            source = err.source();
+        }
+
+        false
+    }
 }
 
 impl ErrorKind {
@@ -63,6 +160,12 @@ impl ErrorKind {
     }
 }
 
+impl From<attestation::Error> for Error {
+    fn from(err: attestation::Error) -> Self {
+        ErrorKind::AttestationError.context(err).into()
+    }
+}
+
 impl From<connector::Error> for Error {
     fn from(err: connector::Error) -> Self {
         ErrorKind::ConnectorError.context(err).into()
@@ -79,6 +182,7 @@ impl From<session::Error> for Error {
             session::ErrorKind::ProtocolError
             | session::ErrorKind::CommandLimitExceeded
             | session::ErrorKind::MismatchError
+            | session::ErrorKind::ReplayDetected
             | session::ErrorKind::VerifyFailed => ErrorKind::ProtocolError,
             session::ErrorKind::ResponseError => ErrorKind::ResponseError,
         };
@@ -99,6 +203,12 @@ impl From<serialization::Error> for Error {
     }
 }
 
+impl From<wrap::Error> for Error {
+    fn from(err: wrap::Error) -> Self {
+        ErrorKind::WrapError.context(err).into()
+    }
+}
+
 impl From<Error> for signature::Error {
     fn from(client_error: Error) -> signature::Error {
         signature::Error::from_source(client_error)
@@ -0,0 +1,185 @@
+//! Extended descriptions of failed commands (included in the alternate,
+//! i.e. `{:#}`, formatting of client errors), with remediation hints for
+//! common device errors
+
+use crate::{command, device, object, Capability};
+use std::fmt::Write;
+
+/// Describe the command which failed, the object it operated on, and the
+/// session's authentication key, along with a hint for resolving the
+/// device error (if there is one we know how to resolve)
+pub(super) fn describe(
+    command: command::Code,
+    object: Option<&object::Handle>,
+    authentication_key_id: object::Id,
+    device_error: Option<device::ErrorKind>,
+) -> String {
+    let mut detail = format!("  command: {command}");
+
+    if let Some(handle) = object {
+        write!(
+            detail,
+            "\n  object: 0x{:04x} ({})",
+            handle.object_id, handle.object_type
+        )
+        .unwrap();
+    }
+
+    write!(
+        detail,
+        "\n  authentication key: 0x{authentication_key_id:04x}"
+    )
+    .unwrap();
+
+    if let Some(hint) =
+        device_error.and_then(|kind| hint(command, object, authentication_key_id, kind))
+    {
+        write!(detail, "\n  hint: {hint}").unwrap();
+    }
+
+    detail
+}
+
+/// One-line remediation hint for the given device error
+fn hint(
+    command: command::Code,
+    object: Option<&object::Handle>,
+    authentication_key_id: object::Id,
+    kind: device::ErrorKind,
+) -> Option<String> {
+    let auth_key = format!("authentication key 0x{authentication_key_id:04x}");
+
+    Some(match kind {
+        device::ErrorKind::InsufficientPermissions => {
+            let capability = required_capability(command, object)
+                .map(|capability| format!("`{}`", capability.names().join("`, `")));
+
+            match (capability, object) {
+                (Some(capability), _) if uses_wrap_key(command) => format!(
+                    "{auth_key} and the wrap key both need the {capability} capability, and \
+                     the wrap key's delegated capabilities must include all of the object's \
+                     capabilities"
+                ),
+                (Some(capability), _) if command.creates_object() => format!(
+                    "{auth_key} needs the {capability} capability, and its delegated \
+                     capabilities must include all of those requested for the new object"
+                ),
+                (Some(capability), Some(handle)) if uses_object_capability(command) => format!(
+                    "{auth_key} and object 0x{:04x} both need the {capability} capability",
+                    handle.object_id
+                ),
+                (Some(capability), _) => format!("{auth_key} needs the {capability} capability"),
+                (None, _) => format!("{auth_key} lacks a capability this command requires"),
+            }
+        }
+        device::ErrorKind::ObjectNotFound => match object {
+            Some(handle) => format!(
+                "object 0x{:04x} ({}) doesn't exist, or shares no domains with {auth_key}",
+                handle.object_id, handle.object_type
+            ),
+            None => format!("the object doesn't exist, or shares no domains with {auth_key}"),
+        },
+        device::ErrorKind::ObjectExists => {
+            "an object with this ID and type already exists: delete it or use another ID".into()
+        }
+        device::ErrorKind::StorageFailed if command.creates_object() => {
+            "the HSM's object storage may be full (see `Client::get_storage_info`)".into()
+        }
+        device::ErrorKind::InvalidSession => {
+            "the HSM closed the session (e.g. it was idle too long, or the device was reset)".into()
+        }
+        device::ErrorKind::SessionsFull => {
+            "all of the HSM's sessions are in use: close idle clients, or wait for their \
+             sessions to time out"
+                .into()
+        }
+        _ => return None,
+    })
+}
+
+/// Capability the session's authentication key needs to perform a command
+fn required_capability(
+    command: command::Code,
+    object: Option<&object::Handle>,
+) -> Option<Capability> {
+    if command == command::Code::DeleteObject {
+        return object.map(|handle| Capability::required_for_delete(handle.object_type));
+    }
+
+    Some(Capability::required_for_command(command)).filter(|capability| !capability.is_empty())
+}
+
+/// Does this command require the key it uses to have the same capability
+/// as the session's authentication key?
+fn uses_object_capability(command: command::Code) -> bool {
+    use command::Code;
+
+    matches!(
+        command,
+        Code::DecryptOaep
+            | Code::DecryptPkcs1
+            | Code::DeriveEcdh
+            | Code::SignEcdsa
+            | Code::SignEddsa
+            | Code::SignHmac
+            | Code::SignPkcs1
+            | Code::SignPss
+            | Code::VerifyHmac
+    )
+}
+
+/// Does this command wrap or unwrap an object under a wrap key (whose
+/// delegated capabilities limit the object's capabilities)?
+fn uses_wrap_key(command: command::Code) -> bool {
+    matches!(
+        command,
+        command::Code::ExportWrapped | command::Code::ImportWrapped
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use command::Code;
+
+    #[test]
+    fn signing_key_capability_hint() {
+        let key = object::Handle::new(0x64, object::Type::AsymmetricKey);
+        let kind = device::ErrorKind::InsufficientPermissions;
+
+        assert_eq!(
+            hint(Code::SignEcdsa, Some(&key), 1, kind).unwrap(),
+            "authentication key 0x0001 and object 0x0064 both need the `sign-ecdsa` capability"
+        );
+    }
+
+    #[test]
+    fn delete_capability_depends_on_type() {
+        let key = object::Handle::new(0x64, object::Type::WrapKey);
+        let kind = device::ErrorKind::InsufficientPermissions;
+
+        assert_eq!(
+            hint(Code::DeleteObject, Some(&key), 1, kind).unwrap(),
+            "authentication key 0x0001 needs the `delete-wrap-key` capability"
+        );
+    }
+
+    #[test]
+    fn wrap_key_delegation_hint() {
+        let wrap_key = object::Handle::new(0x64, object::Type::WrapKey);
+        let kind = device::ErrorKind::InsufficientPermissions;
+
+        assert_eq!(
+            hint(Code::ExportWrapped, Some(&wrap_key), 1, kind).unwrap(),
+            "authentication key 0x0001 and the wrap key both need the `export-wrapped` \
+             capability, and the wrap key's delegated capabilities must include all of the \
+             object's capabilities"
+        );
+    }
+
+    #[test]
+    fn no_hint_for_other_errors() {
+        let detail = describe(Code::Echo, None, 1, Some(device::ErrorKind::InvalidCommand));
+        assert_eq!(detail, "  command: Echo\n  authentication key: 0x0001");
+    }
+}
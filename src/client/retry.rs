@@ -0,0 +1,135 @@
+//! Retrying operations which fail with transient errors
+
+use super::Error;
+use crate::command;
+use std::{thread, time::Duration};
+
+/// How many times to retry operations which fail with transient errors (see
+/// [`Error::is_transient`]), e.g. when the connection to the HSM drops
+/// briefly, and how long to wait between attempts.
+///
+/// Only operations which are safe to repeat honor this (e.g. signing with
+/// [`ecdsa::Signer`](crate::ecdsa::Signer)): commands which change the HSM's
+/// state (see [`command::Code::mutates_state`]) are never retried, as the
+/// HSM may have applied them before the error. Sessions are aborted after
+/// connector errors, so retries open a new session, which requires
+/// reconnecting to be enabled.
+///
+/// The default is not to retry.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximum number of times to retry a failed operation (i.e. it's
+    /// attempted at most `max_retries + 1` times)
+    pub max_retries: u32,
+
+    /// Time to wait before each retry
+    pub delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Retry failed operations up to `max_retries` times, waiting `delay`
+    /// before each retry
+    pub fn new(max_retries: u32, delay: Duration) -> Self {
+        Self { max_retries, delay }
+    }
+
+    /// Perform the given operation (which sends the command with the given
+    /// code), retrying it according to this policy while it fails with
+    /// transient errors unless the command mutates the HSM's state
+    pub(crate) fn retry<T>(
+        &self,
+        code: command::Code,
+        mut op: impl FnMut() -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let max_retries = if code.mutates_state() {
+            0
+        } else {
+            self.max_retries
+        };
+        let mut retries = 0;
+
+        loop {
+            match op() {
+                Err(e) if e.is_transient() && retries < max_retries => {
+                    debug!("retrying {} after transient error: {}", code, e);
+                    retries += 1;
+                    thread::sleep(self.delay);
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{client::ErrorKind, connector};
+
+    fn connector_error(kind: connector::ErrorKind) -> Error {
+        Error::from(connector::Error::from(kind))
+    }
+
+    #[test]
+    fn retries_transient_errors() {
+        let policy = RetryPolicy::new(2, Duration::from_millis(1));
+        let mut attempts = 0;
+
+        let result = policy.retry(command::Code::SignEcdsa, || {
+            attempts += 1;
+
+            if attempts < 3 {
+                Err(connector_error(connector::ErrorKind::ConnectionFailed))
+            } else {
+                Ok(attempts)
+            }
+        });
+
+        assert_eq!(result.unwrap(), 3);
+
+        // Give up after `max_retries`
+        attempts = 0;
+        let result: Result<(), _> = policy.retry(command::Code::SignEcdsa, || {
+            attempts += 1;
+            Err(connector_error(connector::ErrorKind::Timeout))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(attempts, 3);
+    }
+
+    #[test]
+    fn does_not_retry_mutating_commands() {
+        let policy = RetryPolicy::new(2, Duration::from_millis(1));
+        let mut attempts = 0;
+
+        let result: Result<(), _> = policy.retry(command::Code::GenerateAsymmetricKey, || {
+            attempts += 1;
+            Err(connector_error(connector::ErrorKind::ConnectionFailed))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn does_not_retry_other_errors() {
+        let policy = RetryPolicy::new(2, Duration::from_millis(1));
+
+        let errors: [fn() -> Error; 2] = [
+            || connector_error(connector::ErrorKind::AccessDenied),
+            || format_err!(ErrorKind::InvalidSignature, "bad signature").into(),
+        ];
+
+        for err in errors {
+            let mut attempts = 0;
+            let result: Result<(), _> = policy.retry(command::Code::SignEcdsa, || {
+                attempts += 1;
+                Err(err())
+            });
+
+            assert!(result.is_err());
+            assert_eq!(attempts, 1);
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Statistics about the commands sent by a `Client`
+
+use crate::command;
+use std::{collections::BTreeMap, time::Duration};
+
+/// Statistics about the commands sent by a [`Client`](super::Client) and
+/// its clones, since it was created or [`Client::reset_stats`] was last
+/// called.
+///
+/// Each round trip to the HSM counts as one command, including the ones
+/// which open and close sessions, so e.g. the first command sent by a
+/// client counts as three (`CreateSession`, `AuthenticateSession`, then the
+/// command itself). Commands wrapped in session messages are counted by
+/// their own code (not as `SessionMessage`).
+///
+/// [`Client::reset_stats`]: super::Client::reset_stats
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConnectionStats {
+    /// Number of commands sent (including ones which failed)
+    pub commands_sent: u64,
+
+    /// Number of bytes sent to the HSM in session messages (i.e. not
+    /// counting `CreateSession` commands)
+    pub bytes_sent: u64,
+
+    /// Number of bytes received from the HSM in session messages
+    pub bytes_received: u64,
+
+    /// Number of commands which failed
+    pub errors: u64,
+
+    /// Average time taken by each command in milliseconds
+    pub avg_latency_ms: f64,
+
+    /// Number of times each command was sent
+    pub by_code: BTreeMap<command::Code, u64>,
+}
+
+impl ConnectionStats {
+    /// Number of times the given command was sent
+    pub fn count(&self, code: command::Code) -> u64 {
+        self.by_code.get(&code).copied().unwrap_or_default()
+    }
+
+    /// Record a command which took `latency` to complete
+    pub(crate) fn record_command(&mut self, code: command::Code, latency: Duration, success: bool) {
+        self.commands_sent += 1;
+        *self.by_code.entry(code).or_default() += 1;
+
+        if !success {
+            self.errors += 1;
+        }
+
+        // Incrementally update the mean so we don't need to keep the total
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        self.avg_latency_ms += (latency_ms - self.avg_latency_ms) / self.commands_sent as f64;
+    }
+
+    /// Record a message of `len` bytes sent to the HSM
+    pub(crate) fn record_sent(&mut self, len: usize) {
+        self.bytes_sent += len as u64;
+    }
+
+    /// Record a message of `len` bytes received from the HSM
+    pub(crate) fn record_received(&mut self, len: usize) {
+        self.bytes_received += len as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn average_latency() {
+        let mut stats = ConnectionStats::default();
+
+        stats.record_command(command::Code::Echo, Duration::from_millis(10), true);
+        stats.record_command(command::Code::Echo, Duration::from_millis(20), false);
+        stats.record_command(command::Code::Echo, Duration::from_millis(30), true);
+
+        assert_eq!(stats.commands_sent, 3);
+        assert_eq!(stats.errors, 1);
+        assert!((stats.avg_latency_ms - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn command_counts() {
+        let mut stats = ConnectionStats::default();
+
+        stats.record_command(command::Code::Echo, Duration::ZERO, true);
+        stats.record_command(command::Code::SignEcdsa, Duration::ZERO, true);
+        stats.record_command(command::Code::Echo, Duration::ZERO, true);
+
+        assert_eq!(stats.commands_sent, 3);
+        assert_eq!(stats.count(command::Code::Echo), 2);
+        assert_eq!(stats.count(command::Code::SignEcdsa), 1);
+        assert_eq!(stats.count(command::Code::SignEddsa), 0);
+    }
+}
@@ -11,10 +11,12 @@ pub use self::{
 };
 
 pub(crate) use self::message::Message;
-use crate::{response::Response, serialization::serialize};
+use crate::{object, response::Response, serialization::serialize};
 use serde::{de::DeserializeOwned, ser::Serialize};
 
-/// Maximum size of a message sent to/from the YubiHSM
+/// Maximum size of a message sent to/from the YubiHSM. This is a conservative
+/// default: connections may support larger messages (see
+/// [`Connector::max_message_size`](crate::Connector::max_message_size)).
 pub const MAX_MSG_SIZE: usize = 2048;
 
 /// Structured command (i.e. requests) which are encrypted and then sent to
@@ -28,9 +30,14 @@ pub(crate) trait Command: Serialize + DeserializeOwned + Sized {
 
     /// Command ID for this command
     const COMMAND_CODE: Code = Self::ResponseType::COMMAND_CODE;
+
+    /// Object this command operates on (if any), used to describe failures
+    fn object(&self) -> Option<object::Handle> {
+        None
+    }
 }
 
-impl<'c, C: Command> From<&'c C> for Message {
+impl<C: Command> From<&C> for Message {
     fn from(command: &C) -> Message {
         Self::create(C::COMMAND_CODE, serialize(command).unwrap()).unwrap()
     }
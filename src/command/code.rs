@@ -2,137 +2,222 @@
 
 use super::{Error, ErrorKind};
 use serde::{de, ser, Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display},
+    str::FromStr,
+};
 
-/// Command IDs for `YubiHSM 2` operations
-#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
-#[allow(missing_docs)]
-#[repr(u8)]
-pub enum Code {
-    Unknown = 0x00,
-    Echo = 0x01,
-    CreateSession = 0x03,
-    AuthenticateSession = 0x04,
-    SessionMessage = 0x05,
-    DeviceInfo = 0x06,
-    Bsl = 0x07,
-    ResetDevice = 0x08,
-    Command9 = 0x09, // TODO: What is Command 9???
-    CloseSession = 0x40,
-    GetStorageInfo = 0x41,
-    PutOpaqueObject = 0x42,
-    GetOpaqueObject = 0x43,
-    PutAuthenticationKey = 0x44,
-    PutAsymmetricKey = 0x45,
-    GenerateAsymmetricKey = 0x46,
-    SignPkcs1 = 0x47,
-    ListObjects = 0x48,
-    DecryptPkcs1 = 0x49,
-    ExportWrapped = 0x4a,
-    ImportWrapped = 0x4b,
-    PutWrapKey = 0x4c,
-    GetLogEntries = 0x4d,
-    GetObjectInfo = 0x4e,
-    SetOption = 0x4f,
-    GetOption = 0x50,
-    GetPseudoRandom = 0x51,
-    PutHmacKey = 0x52,
-    SignHmac = 0x53,
-    GetPublicKey = 0x54,
-    SignPss = 0x55,
-    SignEcdsa = 0x56,
-    DeriveEcdh = 0x57,
-    DeleteObject = 0x58,
-    DecryptOaep = 0x59,
-    GenerateHmacKey = 0x5a,
-    GenerateWrapKey = 0x5b,
-    VerifyHmac = 0x5c,
-    SignSshCertificate = 0x5d,
-    PutTemplate = 0x5e,
-    GetTemplate = 0x5f,
-    DecryptOtp = 0x60,
-    CreateOtpAead = 0x61,
-    RandomizeOtpAead = 0x62,
-    RewrapOtpAead = 0x63,
-    SignAttestationCertificate = 0x64,
-    PutOtpAead = 0x65,
-    GenerateOtpAead = 0x66,
-    SetLogIndex = 0x67,
-    WrapData = 0x68,
-    UnwrapData = 0x69,
-    SignEddsa = 0x6a,
-    BlinkDevice = 0x6b,
-    ChangeAuthenticationKey = 0x6c,
-    Error = 0x7f,
-    HsmInitialization = 0xff,
+/// Define `Code` along with its byte and name conversions
+macro_rules! command_codes {
+    ($(($variant:ident, $byte:expr, $name:expr),)+) => {
+        /// Command IDs for `YubiHSM 2` operations
+        #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+        #[allow(missing_docs)]
+        pub enum Code {
+            $($variant,)+
+
+            /// Command code not known to this crate
+            Unknown(u8),
+        }
+
+        impl Code {
+            /// All command codes known to this crate
+            pub const KNOWN: &'static [Code] = &[$(Code::$variant,)+];
+
+            /// Convert an unsigned byte into a `command::Code`, preserving
+            /// codes not known to this crate as `Code::Unknown`
+            pub fn from_u8(byte: u8) -> Self {
+                match byte {
+                    $($byte => Code::$variant,)+
+                    other => Code::Unknown(other),
+                }
+            }
+
+            /// Serialize a command as a byte
+            pub fn to_u8(self) -> u8 {
+                match self {
+                    $(Code::$variant => $byte,)+
+                    Code::Unknown(byte) => byte,
+                }
+            }
+
+            /// Name of this command as documented by Yubico, or `None` if
+            /// it's unknown
+            pub fn name(self) -> Option<&'static str> {
+                match self {
+                    $(Code::$variant => Some($name),)+
+                    Code::Unknown(_) => None,
+                }
+            }
+        }
+    };
+}
+
+// Names from <https://developers.yubico.com/YubiHSM2/Commands/>
+command_codes! {
+    (Echo, 0x01, "Echo"),
+    (CreateSession, 0x03, "Create Session"),
+    (AuthenticateSession, 0x04, "Authenticate Session"),
+    (SessionMessage, 0x05, "Session Message"),
+    (DeviceInfo, 0x06, "Device Info"),
+    (Bsl, 0x07, "BSL"),
+    (ResetDevice, 0x08, "Reset Device"),
+    (Command9, 0x09, "Command 9"),
+    (CloseSession, 0x40, "Close Session"),
+    (GetStorageInfo, 0x41, "Get Storage Info"),
+    (PutOpaqueObject, 0x42, "Put Opaque"),
+    (GetOpaqueObject, 0x43, "Get Opaque"),
+    (PutAuthenticationKey, 0x44, "Put Authentication Key"),
+    (PutAsymmetricKey, 0x45, "Put Asymmetric Key"),
+    (GenerateAsymmetricKey, 0x46, "Generate Asymmetric Key"),
+    (SignPkcs1, 0x47, "Sign Pkcs1"),
+    (ListObjects, 0x48, "List Objects"),
+    (DecryptPkcs1, 0x49, "Decrypt Pkcs1"),
+    (ExportWrapped, 0x4a, "Export Wrapped"),
+    (ImportWrapped, 0x4b, "Import Wrapped"),
+    (PutWrapKey, 0x4c, "Put Wrap Key"),
+    (GetLogEntries, 0x4d, "Get Log Entries"),
+    (GetObjectInfo, 0x4e, "Get Object Info"),
+    (SetOption, 0x4f, "Set Option"),
+    (GetOption, 0x50, "Get Option"),
+    (GetPseudoRandom, 0x51, "Get Pseudo Random"),
+    (PutHmacKey, 0x52, "Put Hmac Key"),
+    (SignHmac, 0x53, "Sign Hmac"),
+    (GetPublicKey, 0x54, "Get Public Key"),
+    (SignPss, 0x55, "Sign Pss"),
+    (SignEcdsa, 0x56, "Sign Ecdsa"),
+    (DeriveEcdh, 0x57, "Derive Ecdh"),
+    (DeleteObject, 0x58, "Delete Object"),
+    (DecryptOaep, 0x59, "Decrypt Oaep"),
+    (GenerateHmacKey, 0x5a, "Generate Hmac Key"),
+    (GenerateWrapKey, 0x5b, "Generate Wrap Key"),
+    (VerifyHmac, 0x5c, "Verify Hmac"),
+    (SignSshCertificate, 0x5d, "Sign Ssh Certificate"),
+    (PutTemplate, 0x5e, "Put Template"),
+    (GetTemplate, 0x5f, "Get Template"),
+    (DecryptOtp, 0x60, "Decrypt Otp"),
+    (CreateOtpAead, 0x61, "Create Otp Aead"),
+    (RandomizeOtpAead, 0x62, "Randomize Otp Aead"),
+    (RewrapOtpAead, 0x63, "Rewrap Otp Aead"),
+    (SignAttestationCertificate, 0x64, "Sign Attestation Certificate"),
+    (PutOtpAead, 0x65, "Put Otp Aead Key"),
+    (GenerateOtpAead, 0x66, "Generate Otp Aead Key"),
+    (SetLogIndex, 0x67, "Set Log Index"),
+    (WrapData, 0x68, "Wrap Data"),
+    (UnwrapData, 0x69, "Unwrap Data"),
+    (SignEddsa, 0x6a, "Sign Eddsa"),
+    (BlinkDevice, 0x6b, "Blink Device"),
+    (ChangeAuthenticationKey, 0x6c, "Change Authentication Key"),
+    (Error, 0x7f, "Error"),
+    (HsmInitialization, 0xff, "HSM Initialization"),
 }
 
 impl Code {
-    /// Convert an unsigned byte into a `command::Code` (if valid)
-    pub fn from_u8(byte: u8) -> Result<Self, Error> {
-        Ok(match byte {
-            0x00 => Code::Unknown,
-            0x01 => Code::Echo,
-            0x03 => Code::CreateSession,
-            0x04 => Code::AuthenticateSession,
-            0x05 => Code::SessionMessage,
-            0x06 => Code::DeviceInfo,
-            0x07 => Code::Bsl,
-            0x08 => Code::ResetDevice,
-            0x09 => Code::Command9,
-            0x40 => Code::CloseSession,
-            0x41 => Code::GetStorageInfo,
-            0x42 => Code::PutOpaqueObject,
-            0x43 => Code::GetOpaqueObject,
-            0x44 => Code::PutAuthenticationKey,
-            0x45 => Code::PutAsymmetricKey,
-            0x46 => Code::GenerateAsymmetricKey,
-            0x47 => Code::SignPkcs1,
-            0x48 => Code::ListObjects,
-            0x49 => Code::DecryptPkcs1,
-            0x4a => Code::ExportWrapped,
-            0x4b => Code::ImportWrapped,
-            0x4c => Code::PutWrapKey,
-            0x4d => Code::GetLogEntries,
-            0x4e => Code::GetObjectInfo,
-            0x4f => Code::SetOption,
-            0x50 => Code::GetOption,
-            0x51 => Code::GetPseudoRandom,
-            0x52 => Code::PutHmacKey,
-            0x53 => Code::SignHmac,
-            0x54 => Code::GetPublicKey,
-            0x55 => Code::SignPss,
-            0x56 => Code::SignEcdsa,
-            0x57 => Code::DeriveEcdh,
-            0x58 => Code::DeleteObject,
-            0x59 => Code::DecryptOaep,
-            0x5a => Code::GenerateHmacKey,
-            0x5b => Code::GenerateWrapKey,
-            0x5c => Code::VerifyHmac,
-            0x5d => Code::SignSshCertificate,
-            0x5e => Code::PutTemplate,
-            0x5f => Code::GetTemplate,
-            0x60 => Code::DecryptOtp,
-            0x61 => Code::CreateOtpAead,
-            0x62 => Code::RandomizeOtpAead,
-            0x63 => Code::RewrapOtpAead,
-            0x64 => Code::SignAttestationCertificate,
-            0x65 => Code::PutOtpAead,
-            0x66 => Code::GenerateOtpAead,
-            0x67 => Code::SetLogIndex,
-            0x68 => Code::WrapData,
-            0x69 => Code::UnwrapData,
-            0x6a => Code::SignEddsa,
-            0x6b => Code::BlinkDevice,
-            0x6c => Code::ChangeAuthenticationKey,
-            0x7f => Code::Error,
-            0xff => Code::HsmInitialization,
-            _ => fail!(ErrorKind::CodeInvalid, "invalid command type: {}", byte),
-        })
+    /// Can the HSM be configured to log this command in its audit log?
+    /// (see `Client::set_command_audit_option`)
+    pub fn is_auditable(self) -> bool {
+        !matches!(
+            self,
+            Code::Error | Code::HsmInitialization | Code::Unknown(_)
+        )
+    }
+
+    /// Does this command change the HSM's persistent state (i.e. its objects
+    /// or configuration)? Commands which don't can safely be retried.
+    ///
+    /// Unknown commands are conservatively assumed to mutate state.
+    pub fn mutates_state(self) -> bool {
+        matches!(
+            self,
+            Code::Bsl
+                | Code::ResetDevice
+                | Code::PutOpaqueObject
+                | Code::PutAuthenticationKey
+                | Code::PutAsymmetricKey
+                | Code::GenerateAsymmetricKey
+                | Code::ImportWrapped
+                | Code::PutWrapKey
+                | Code::SetOption
+                | Code::PutHmacKey
+                | Code::DeleteObject
+                | Code::GenerateHmacKey
+                | Code::GenerateWrapKey
+                | Code::PutTemplate
+                | Code::PutOtpAead
+                | Code::GenerateOtpAead
+                | Code::SetLogIndex
+                | Code::ChangeAuthenticationKey
+                | Code::HsmInitialization
+                | Code::Unknown(_)
+        )
+    }
+
+    /// Does this command store a new object on the HSM (and therefore need
+    /// free storage, and delegate the capabilities of the session's
+    /// authentication key to the object)?
+    pub fn creates_object(self) -> bool {
+        matches!(
+            self,
+            Code::GenerateAsymmetricKey
+                | Code::GenerateHmacKey
+                | Code::GenerateWrapKey
+                | Code::ImportWrapped
+                | Code::PutAsymmetricKey
+                | Code::PutAuthenticationKey
+                | Code::PutHmacKey
+                | Code::PutOpaqueObject
+                | Code::PutOtpAead
+                | Code::PutTemplate
+                | Code::PutWrapKey
+        )
+    }
+}
+
+impl Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => f.write_str(name),
+            None => write!(f, "Unknown (0x{:02x})", self.to_u8()),
+        }
+    }
+}
+
+impl FromStr for Code {
+    type Err = Error;
+
+    /// Parse a command from its documented name (case-insensitive)
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Code::KNOWN
+            .iter()
+            .find(|code| code.name().unwrap().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or_else(|| format_err!(ErrorKind::CodeInvalid, "unknown command: {}", s).into())
+    }
+}
+
+impl From<u8> for Code {
+    fn from(byte: u8) -> Code {
+        Code::from_u8(byte)
+    }
+}
+
+impl From<Code> for u8 {
+    fn from(code: Code) -> u8 {
+        code.to_u8()
+    }
+}
+
+impl PartialOrd for Code {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
+}
 
-    /// Serialize a command as a byte
-    pub fn to_u8(self) -> u8 {
-        self as u8
+impl Ord for Code {
+    /// Commands are ordered by their byte representation
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.to_u8().cmp(&other.to_u8())
     }
 }
 
@@ -150,7 +235,57 @@ impl<'de> Deserialize<'de> for Code {
     where
         D: de::Deserializer<'de>,
     {
-        use de::Error;
-        Code::from_u8(u8::deserialize(deserializer)?).map_err(D::Error::custom)
+        Ok(Code::from_u8(u8::deserialize(deserializer)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_test() {
+        for &code in Code::KNOWN {
+            assert_eq!(Code::from_u8(code.to_u8()), code);
+            assert_eq!(code.to_string().parse::<Code>().unwrap(), code);
+            assert_eq!(
+                code.to_string().to_uppercase().parse::<Code>().unwrap(),
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_code_test() {
+        let known_bytes: Vec<u8> = Code::KNOWN.iter().map(|code| code.to_u8()).collect();
+
+        for byte in (0..=u8::MAX).filter(|byte| !known_bytes.contains(byte)) {
+            let code = Code::from(byte);
+            assert_eq!(code, Code::Unknown(byte));
+            assert_eq!(u8::from(code), byte);
+            assert_eq!(code.to_string(), format!("Unknown (0x{byte:02x})"));
+            assert!(!code.is_auditable());
+            assert!(code.mutates_state());
+        }
+
+        assert!("Not A Command".parse::<Code>().is_err());
+    }
+
+    #[test]
+    fn display_test() {
+        assert_eq!(Code::SignEcdsa.to_string(), "Sign Ecdsa");
+        assert_eq!(Code::PutOtpAead.to_string(), "Put Otp Aead Key");
+        assert!(Code::SignEcdsa.is_auditable());
+        assert!(!Code::SignEcdsa.mutates_state());
+        assert!(Code::DeleteObject.mutates_state());
+        assert!(Code::PutOpaqueObject.creates_object());
+        assert!(!Code::GenerateOtpAead.creates_object());
+    }
+
+    #[test]
+    fn creates_object_test() {
+        for &code in Code::KNOWN.iter().filter(|code| code.creates_object()) {
+            assert!(code.mutates_state(), "{code} creates objects");
+        }
     }
 }
@@ -101,8 +101,7 @@ impl Message {
             );
         }
 
-        let command_type =
-            command::Code::from_u8(bytes[0]).map_err(|e| format_err!(ProtocolError, "{}", e))?;
+        let command_type = command::Code::from_u8(bytes[0]);
 
         let mut length_bytes = [0u8; 2];
         length_bytes.copy_from_slice(&bytes[1..3]);
@@ -173,7 +172,7 @@ impl Message {
     /// Serialize this message as a byte vector
     pub fn serialize(mut self) -> Vec<u8> {
         let mut result = Vec::with_capacity(3 + self.len());
-        result.push(self.command_type as u8);
+        result.push(self.command_type.to_u8());
 
         let length = self.len() as u16;
         result.extend_from_slice(&length.to_be_bytes());
@@ -4,6 +4,8 @@
 //!   process from the Yubico SDK.
 //! - [USB][usb-connector]: communicate directly with the YubiHSM over USB using
 //!   the [rusb] crate.
+//! - [Unix domain sockets][unix-connector]: communicate with a local
+//!   `yubihsm-connector` listening on a Unix socket (on Unix targets).
 //!
 //! Additionally, this crate includes an optional development-only [mockhsm]
 //! (gated under a `mockhsm` cargo feature) which can be used as a drop-in
@@ -11,6 +13,7 @@
 //!
 //! [http-connector]: https://docs.rs/yubihsm/latest/yubihsm/connector/struct.Connector.html#method.http
 //! [usb-connector]: https://docs.rs/yubihsm/latest/yubihsm/connector/struct.Connector.html#method.usb
+//! [unix-connector]: https://docs.rs/yubihsm/latest/yubihsm/connector/struct.Connector.html#method.unix
 //! [rusb]: https://github.com/a1ien/rusb
 //! [mockhsm]: https://docs.rs/yubihsm/latest/yubihsm/connector/struct.Connector.html#method.mockhsm
 
@@ -22,6 +25,8 @@ mod connection;
 #[cfg(feature = "http")]
 pub mod http;
 mod message;
+#[cfg(all(feature = "http", unix))]
+pub mod unix;
 #[cfg(feature = "usb")]
 pub mod usb;
 
@@ -29,7 +34,10 @@ pub use self::connection::Connection;
 pub use self::error::*;
 
 pub(crate) use self::{connectable::Connectable, message::Message};
-use std::sync::{Arc, Mutex};
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 use uuid::Uuid;
 
 #[cfg(feature = "http")]
@@ -38,6 +46,11 @@ pub use self::http::HttpConfig;
 #[cfg(feature = "http")]
 use self::http::HttpConnector;
 
+#[cfg(all(feature = "http", unix))]
+pub use self::unix::UnixConfig;
+#[cfg(all(feature = "http", unix))]
+use self::unix::UnixConnector;
+
 #[cfg(feature = "usb")]
 pub use self::usb::UsbConfig;
 #[cfg(feature = "usb")]
@@ -72,30 +85,83 @@ impl Connector {
         Self::from(UsbConnector::create(config))
     }
 
-    /// Create a mock HSM connector (useful for testing)
+    /// Create a new connector for `yubihsm-connector` listening on a Unix
+    /// domain socket
+    #[cfg(all(feature = "http", unix))]
+    pub fn unix(config: &UnixConfig) -> Self {
+        Self::from(UnixConnector::create(config))
+    }
+
+    /// Create a mock HSM connector (useful for testing).
+    ///
+    /// To configure the mock HSM, build one with a [`mockhsm::Builder`]
+    /// and convert it into a `Connector` instead.
+    ///
+    /// [`mockhsm::Builder`]: crate::mockhsm::Builder
     #[cfg(feature = "mockhsm")]
     pub fn mockhsm() -> Self {
-        let mockhsm: Box<dyn Connectable> = MockHsm::new().into();
-        Self::from(mockhsm)
+        Self::from(MockHsm::new())
     }
 
     /// Send a command message to the HSM, then read and return the response
     pub fn send_message(&self, uuid: Uuid, msg: Message) -> Result<Message, Error> {
+        self.send(uuid, msg, None)
+    }
+
+    /// Send a command message to the HSM, then read and return the response,
+    /// failing with `ErrorKind::Timeout` if it isn't received by the deadline
+    pub fn send_message_with_deadline(
+        &self,
+        uuid: Uuid,
+        msg: Message,
+        deadline: Instant,
+    ) -> Result<Message, Error> {
+        self.send(uuid, msg, Some(deadline))
+    }
+
+    /// Maximum size of a message which can be sent to or received from the
+    /// HSM using this connector, allowing payload sizes to be validated
+    /// before they're sent
+    pub fn max_message_size(&self) -> usize {
+        self.driver.max_message_size()
+    }
+
+    /// Send a message over the current connection (opening one if needed)
+    fn send(&self, uuid: Uuid, msg: Message, deadline: Option<Instant>) -> Result<Message, Error> {
         let mut connection = self.connection.lock().unwrap();
 
         if connection.is_none() {
             *connection = Some(self.driver.connect()?);
         }
 
-        connection
-            .as_ref()
-            .unwrap()
-            .send_message(uuid, msg)
-            .map_err(|e| {
-                // In the event of an error, mark this connection as invalid
-                *connection = None;
-                e
-            })
+        let conn = connection.as_ref().unwrap();
+
+        let result = match deadline {
+            Some(deadline) => {
+                ensure!(
+                    Instant::now() < deadline,
+                    ErrorKind::Timeout,
+                    "deadline passed before sending message"
+                );
+
+                conn.send_message_with_deadline(uuid, msg, deadline)
+                    .map_err(|e| match deadline.checked_duration_since(Instant::now()) {
+                        // I/O which failed due to the deadline (e.g. a socket
+                        // timeout) is reported as a timeout
+                        None if *e.kind() != ErrorKind::Timeout => {
+                            ErrorKind::Timeout.context(e).into()
+                        }
+                        _ => e,
+                    })
+            }
+            None => conn.send_message(uuid, msg),
+        };
+
+        result.map_err(|e| {
+            // In the event of an error, mark this connection as invalid
+            *connection = None;
+            e
+        })
     }
 }
 
@@ -108,6 +174,14 @@ impl Clone for Connector {
     }
 }
 
+#[cfg(feature = "mockhsm")]
+impl From<MockHsm> for Connector {
+    fn from(mockhsm: MockHsm) -> Connector {
+        let driver: Box<dyn Connectable> = mockhsm.into();
+        Self::from(driver)
+    }
+}
+
 impl From<Box<dyn Connectable>> for Connector {
     fn from(driver: Box<dyn Connectable>) -> Connector {
         Connector {
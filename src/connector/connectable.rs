@@ -1,6 +1,9 @@
 //! Trait for YubiHSM2 interfaces which can be connected to
 
-use crate::connector::{self, Connection};
+use crate::{
+    command,
+    connector::{self, Connection},
+};
 
 /// Connectors which create `Connection` objects to the HSM
 pub trait Connectable: Send + Sync {
@@ -9,4 +12,10 @@ pub trait Connectable: Send + Sync {
 
     /// Open a connection to the HSM using this `Connector`
     fn connect(&self) -> Result<Box<dyn Connection>, connector::Error>;
+
+    /// Maximum size of messages sent to or received from the HSM over
+    /// connections opened by this `Connector`
+    fn max_message_size(&self) -> usize {
+        command::MAX_MSG_SIZE
+    }
 }
@@ -1,6 +1,7 @@
 //! Trait shared across all methods for connecting to the YubiHSM2
 
 use crate::connector;
+use std::time::Instant;
 use uuid::Uuid;
 
 /// Connections to the HSM
@@ -11,4 +12,26 @@ pub trait Connection: Send + Sync {
         uuid: Uuid,
         msg: connector::Message,
     ) -> Result<connector::Message, connector::Error>;
+
+    /// Send a command message to the HSM, giving up if the response hasn't
+    /// been read by the given deadline.
+    ///
+    /// Connections which can't bound their I/O by an arbitrary deadline
+    /// rely on their own timeouts, and only check the deadline afterward.
+    fn send_message_with_deadline(
+        &self,
+        uuid: Uuid,
+        msg: connector::Message,
+        deadline: Instant,
+    ) -> Result<connector::Message, connector::Error> {
+        let response = self.send_message(uuid, msg)?;
+
+        ensure!(
+            Instant::now() <= deadline,
+            connector::ErrorKind::Timeout,
+            "response received after deadline"
+        );
+
+        Ok(response)
+    }
 }
@@ -41,6 +41,10 @@ pub enum ErrorKind {
     #[error("bad response from connector")]
     ResponseError,
 
+    /// HSM didn't respond in time
+    #[error("timed out")]
+    Timeout,
+
     /// USB operation failed
     #[cfg(feature = "usb")]
     #[error("USB error")]
@@ -89,6 +93,7 @@ impl From<rusb::Error> for Error {
             rusb::Error::Access => format_err!(ErrorKind::AccessDenied, "{}", err),
             rusb::Error::Io => format_err!(ErrorKind::IoError, "{}", err),
             rusb::Error::Pipe => format_err!(ErrorKind::UsbError, "lost connection to USB device"),
+            rusb::Error::Timeout => format_err!(ErrorKind::Timeout, "{}", err),
             _ => format_err!(ErrorKind::UsbError, "{}", err),
         }
         .into()
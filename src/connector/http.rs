@@ -12,7 +12,7 @@ pub use self::config::HttpConfig;
 #[cfg(feature = "http-server")]
 pub use self::server::Server;
 
-use self::connection::HttpConnection;
+pub(super) use self::connection::HttpConnection;
 use crate::connector::{self, Connectable, Connection};
 
 /// Connect to the HSM via HTTP(S) using `yubihsm-connector`.
@@ -49,6 +49,10 @@ impl Connectable for HttpConnector {
     fn connect(&self) -> Result<Box<dyn Connection>, connector::Error> {
         Ok(Box::new(HttpConnection::open(&self.0)?))
     }
+
+    fn max_message_size(&self) -> usize {
+        self.0.max_message_size
+    }
 }
 
 impl Into<Box<dyn Connectable>> for HttpConnector {
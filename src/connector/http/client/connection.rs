@@ -2,7 +2,7 @@
 
 use std::{
     fmt::Write as FmtWrite,
-    io::Write,
+    io::{self, Read, Write},
     net::{TcpStream, ToSocketAddrs},
     ops::DerefMut,
     string::String,
@@ -11,14 +11,21 @@ use std::{
     vec::Vec,
 };
 
+#[cfg(unix)]
+use std::{os::unix::net::UnixStream, path::Path};
+
 use super::{error::Error, path::PathBuf, request, response, HTTP_VERSION, USER_AGENT};
 
 /// Default timeout in milliseconds (20 seconds)
 const DEFAULT_TIMEOUT_MS: u64 = 20000;
 
+/// Shortest timeout applied to socket operations
+const MIN_TIMEOUT: Duration = Duration::from_millis(1);
+
 /// Options when building a `Connection`
 pub struct ConnectionOptions {
-    timeout: Duration,
+    /// Timeout for socket reads and writes
+    pub timeout: Duration,
 }
 
 impl Default for ConnectionOptions {
@@ -34,8 +41,11 @@ pub struct Connection {
     /// Host header to send in HTTP requests
     host: String,
 
-    /// Open TCP socket to remote host
-    socket: Mutex<TcpStream>,
+    /// Open socket to the server
+    socket: Mutex<Socket>,
+
+    /// Default timeout for socket reads and writes
+    timeout: Duration,
 }
 
 impl Connection {
@@ -52,13 +62,27 @@ impl Connection {
         })?;
 
         // TODO: better timeout handling?
-        let socket = TcpStream::connect_timeout(socketaddr, opts.timeout)?;
-        socket.set_read_timeout(Some(opts.timeout))?;
-        socket.set_write_timeout(Some(opts.timeout))?;
+        let socket = Socket::Tcp(TcpStream::connect_timeout(socketaddr, opts.timeout)?);
+        socket.set_timeout(opts.timeout)?;
 
         Ok(Self {
             host,
             socket: Mutex::new(socket),
+            timeout: opts.timeout,
+        })
+    }
+
+    /// Create a new connection to an HTTP server listening on a Unix domain
+    /// socket at the given path
+    #[cfg(unix)]
+    pub fn open_unix(path: &Path, opts: &ConnectionOptions) -> Result<Self, Error> {
+        let socket = Socket::Unix(UnixStream::connect(path)?);
+        socket.set_timeout(opts.timeout)?;
+
+        Ok(Self {
+            host: "localhost".to_owned(),
+            socket: Mutex::new(socket),
+            timeout: opts.timeout,
         })
     }
 
@@ -67,6 +91,27 @@ impl Connection {
         &self,
         into_path: P,
         body: &request::Body,
+    ) -> Result<response::Body, Error> {
+        self.request(into_path, body, None)
+    }
+
+    /// Make an HTTP POST request to the given path, with socket reads and
+    /// writes timing out after the given duration (if less than the default)
+    pub fn post_with_timeout<P: Into<PathBuf>>(
+        &self,
+        into_path: P,
+        body: &request::Body,
+        timeout: Duration,
+    ) -> Result<response::Body, Error> {
+        self.request(into_path, body, Some(timeout))
+    }
+
+    /// Make an HTTP POST request, optionally overriding the socket timeout
+    fn request<P: Into<PathBuf>>(
+        &self,
+        into_path: P,
+        body: &request::Body,
+        timeout: Option<Duration>,
     ) -> Result<response::Body, Error> {
         let path = into_path.into();
         let mut headers = String::new();
@@ -82,9 +127,82 @@ impl Connection {
         request.extend_from_slice(body.0.as_slice());
 
         let mut socket = self.socket.lock().unwrap();
-        socket.write_all(&request)?;
 
-        let response_body = response::Reader::new(socket.deref_mut())?.into_body();
-        Ok(response_body)
+        let timeout = match timeout {
+            // Zero durations are rejected by `set_read_timeout`
+            Some(t) if t < self.timeout => Some(t.max(MIN_TIMEOUT)),
+            _ => None,
+        };
+
+        if let Some(t) = timeout {
+            socket.set_timeout(t)?;
+        }
+
+        let result = socket
+            .write_all(&request)
+            .map_err(Error::from)
+            .and_then(|_| response::Reader::new(socket.deref_mut()))
+            .map(|reader| reader.into_body());
+
+        if timeout.is_some() {
+            socket.set_timeout(self.timeout)?;
+        }
+
+        result
+    }
+}
+
+/// Sockets HTTP servers can be connected to over
+enum Socket {
+    /// TCP socket
+    Tcp(TcpStream),
+
+    /// Unix domain socket
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Socket {
+    /// Set the timeout for both reads and writes
+    fn set_timeout(&self, timeout: Duration) -> io::Result<()> {
+        match self {
+            Socket::Tcp(socket) => {
+                socket.set_read_timeout(Some(timeout))?;
+                socket.set_write_timeout(Some(timeout))
+            }
+            #[cfg(unix)]
+            Socket::Unix(socket) => {
+                socket.set_read_timeout(Some(timeout))?;
+                socket.set_write_timeout(Some(timeout))
+            }
+        }
+    }
+}
+
+impl Read for Socket {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Socket::Tcp(socket) => socket.read(buf),
+            #[cfg(unix)]
+            Socket::Unix(socket) => socket.read(buf),
+        }
+    }
+}
+
+impl Write for Socket {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Socket::Tcp(socket) => socket.write(buf),
+            #[cfg(unix)]
+            Socket::Unix(socket) => socket.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Socket::Tcp(socket) => socket.flush(),
+            #[cfg(unix)]
+            Socket::Unix(socket) => socket.flush(),
+        }
     }
 }
@@ -1,5 +1,6 @@
 //! yubihsm-connector HTTP configuration
 
+use crate::command::MAX_MSG_SIZE;
 use serde::{Deserialize, Serialize};
 use std::fmt::{self, Display};
 
@@ -17,6 +18,11 @@ pub struct HttpConfig {
 
     /// Timeout for connecting, reading, and writing in milliseconds
     pub timeout_ms: u64,
+
+    /// Maximum size of messages sent to or received from the HSM in bytes
+    /// (defaults to `command::MAX_MSG_SIZE`)
+    #[serde(default = "default_max_message_size")]
+    pub max_message_size: usize,
 }
 
 impl Default for HttpConfig {
@@ -30,6 +36,8 @@ impl Default for HttpConfig {
 
             // 5 seconds
             timeout_ms: DEFAULT_TIMEOUT_MILLIS,
+
+            max_message_size: MAX_MSG_SIZE,
         }
     }
 }
@@ -40,3 +48,8 @@ impl Display for HttpConfig {
         write!(f, "http://{}:{}", self.addr, self.port)
     }
 }
+
+/// Default `max_message_size` for configs which don't specify one
+fn default_max_message_size() -> usize {
+    MAX_MSG_SIZE
+}
@@ -2,8 +2,12 @@
 
 use super::{client, config::HttpConfig};
 use crate::connector::{self, Connection};
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
+#[cfg(unix)]
+use crate::connector::unix::UnixConfig;
+
 /// Connection to YubiHSM via HTTP requests to `yubihsm-connector`.
 ///
 /// The `yubihsm-connector` service is a small HTTP(S) service which exposes a
@@ -26,6 +30,18 @@ impl HttpConnection {
         Ok(HttpConnection { connection })
     }
 
+    /// Open a connection to a `yubihsm-connector` service listening on a
+    /// Unix domain socket
+    #[cfg(unix)]
+    pub(crate) fn open_unix(config: &UnixConfig) -> Result<Self, connector::Error> {
+        let opts = client::ConnectionOptions {
+            timeout: Duration::from_millis(config.timeout_ms),
+        };
+        let connection = client::Connection::open_unix(&config.path, &opts)?;
+
+        Ok(HttpConnection { connection })
+    }
+
     /// Make an HTTP POST request to a `yubihsm-connector` service
     pub(super) fn post(
         &self,
@@ -39,6 +55,23 @@ impl HttpConnection {
             .post(path, &client::request::Body::new(body))?
             .into_vec())
     }
+
+    /// Make an HTTP POST request to a `yubihsm-connector` service, bounding
+    /// socket I/O by the given deadline
+    pub(super) fn post_with_deadline(
+        &self,
+        path: &str,
+        _uuid: Uuid,
+        body: &[u8],
+        deadline: Instant,
+    ) -> Result<Vec<u8>, connector::Error> {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+
+        Ok(self
+            .connection
+            .post_with_timeout(path, &client::request::Body::new(body), timeout)?
+            .into_vec())
+    }
 }
 
 impl Connection for HttpConnection {
@@ -51,4 +84,16 @@ impl Connection for HttpConnection {
         self.post("/connector/api", uuid, cmd.as_ref())
             .map(Into::into)
     }
+
+    /// `POST /connector/api` with a given command message, giving up on the
+    /// response once the deadline has passed
+    fn send_message_with_deadline(
+        &self,
+        uuid: Uuid,
+        cmd: connector::Message,
+        deadline: Instant,
+    ) -> Result<connector::Message, connector::Error> {
+        self.post_with_deadline("/connector/api", uuid, cmd.as_ref(), deadline)
+            .map(Into::into)
+    }
 }
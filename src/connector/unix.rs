@@ -0,0 +1,128 @@
+//! Connection for `yubihsm-connector` listening on a Unix domain socket.
+//!
+//! This speaks the same HTTP protocol as the [`http`](super::http)
+//! connector, but avoids TCP overhead (and choosing a port) for services
+//! running on the same host as `yubihsm-connector`.
+
+mod config;
+
+pub use self::config::UnixConfig;
+
+use super::http::HttpConnection;
+use crate::connector::{self, Connectable, Connection};
+
+/// Connect to the HSM via `yubihsm-connector` listening on a Unix domain
+/// socket.
+///
+/// `UnixConnector` is available on Unix targets when the `http` cargo
+/// feature is enabled.
+#[derive(Clone, Debug)]
+pub(crate) struct UnixConnector(UnixConfig);
+
+impl UnixConnector {
+    /// Create a new `UnixConnector` with the given configuration
+    pub fn create(config: &UnixConfig) -> Box<dyn Connectable> {
+        Box::new(UnixConnector(config.clone()))
+    }
+}
+
+impl Connectable for UnixConnector {
+    /// Make a clone of this connectable as boxed trait object
+    fn box_clone(&self) -> Box<dyn Connectable> {
+        Box::new(UnixConnector(self.0.clone()))
+    }
+
+    /// Open a connection to `yubihsm-connector`
+    fn connect(&self) -> Result<Box<dyn Connection>, connector::Error> {
+        Ok(Box::new(HttpConnection::open_unix(&self.0)?))
+    }
+
+    fn max_message_size(&self) -> usize {
+        self.0.max_message_size
+    }
+}
+
+impl Into<Box<dyn Connectable>> for UnixConnector {
+    fn into(self) -> Box<dyn Connectable> {
+        Box::new(self)
+    }
+}
+
+#[cfg(all(test, feature = "mockhsm"))]
+mod tests {
+    use super::*;
+    use crate::{uuid, Client, Connector, Credentials};
+    use std::{
+        fs,
+        io::{BufRead, BufReader, Read, Write},
+        os::unix::net::{UnixListener, UnixStream},
+        process, thread,
+    };
+
+    /// Stub `yubihsm-connector` which answers each HTTP request on the given
+    /// socket with the response of a `MockHsm`
+    fn serve_mockhsm(stream: UnixStream) {
+        let mockhsm = Connector::mockhsm();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut writer = stream;
+
+        loop {
+            let mut content_length = 0;
+
+            loop {
+                let mut line = String::new();
+
+                // The client closed the connection
+                if reader.read_line(&mut line).unwrap() == 0 {
+                    return;
+                }
+
+                match line.trim_end() {
+                    "" => break,
+                    header => {
+                        if let Some(len) = header.strip_prefix("Content-Length: ") {
+                            content_length = len.parse().unwrap();
+                        }
+                    }
+                }
+            }
+
+            let mut body = vec![0u8; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            let response = mockhsm.send_message(uuid::new_v4(), body.into()).unwrap();
+            let mut http_response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n",
+                response.as_ref().len()
+            )
+            .into_bytes();
+
+            // The client's response reader expects the whole response at once
+            http_response.extend_from_slice(response.as_ref());
+            writer.write_all(&http_response).unwrap();
+        }
+    }
+
+    #[test]
+    fn mockhsm_over_unix_socket() {
+        let path = std::env::temp_dir().join(format!("yubihsm-rs-{}.sock", process::id()));
+        let _ = fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let server = thread::spawn(move || serve_mockhsm(listener.accept().unwrap().0));
+
+        let client = Client::open(
+            Connector::unix(&UnixConfig::new(&path)),
+            Credentials::default(),
+            false,
+        )
+        .unwrap();
+
+        client.ping().unwrap();
+        assert_eq!(client.device_info().unwrap().serial_number, 123_456_789);
+
+        drop(client);
+        server.join().unwrap();
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,53 @@
+//! yubihsm-connector Unix domain socket configuration
+
+use crate::{command::MAX_MSG_SIZE, connector::http::HttpConfig};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{self, Display},
+    path::PathBuf,
+};
+
+/// Configuration options for connecting to `yubihsm-connector` over a Unix
+/// domain socket
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct UnixConfig {
+    /// Path of the socket `yubihsm-connector` is listening on
+    pub path: PathBuf,
+
+    /// Timeout for reading and writing in milliseconds (defaults to the
+    /// same timeout as `HttpConfig`)
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+
+    /// Maximum size of messages sent to or received from the HSM in bytes
+    /// (defaults to `command::MAX_MSG_SIZE`)
+    #[serde(default = "default_max_message_size")]
+    pub max_message_size: usize,
+}
+
+impl UnixConfig {
+    /// Create a configuration for the socket at the given path
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            timeout_ms: default_timeout_ms(),
+            max_message_size: MAX_MSG_SIZE,
+        }
+    }
+}
+
+impl Display for UnixConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unix:{}", self.path.display())
+    }
+}
+
+/// Default `timeout_ms` for configs which don't specify one
+fn default_timeout_ms() -> u64 {
+    HttpConfig::default().timeout_ms
+}
+
+/// Default `max_message_size` for configs which don't specify one
+fn default_max_message_size() -> usize {
+    MAX_MSG_SIZE
+}
@@ -11,6 +11,7 @@
 #[macro_use]
 mod macros;
 
+mod adapter;
 mod config;
 mod connection;
 mod device;
@@ -66,6 +67,10 @@ impl Connectable for UsbConnector {
     fn connect(&self) -> Result<Box<dyn Connection>, connector::Error> {
         Ok(Box::new(UsbConnection::open(&self.0)?))
     }
+
+    fn max_message_size(&self) -> usize {
+        self.0.max_message_size
+    }
 }
 
 impl Into<Box<dyn Connectable>> for UsbConnector {
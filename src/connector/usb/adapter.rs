@@ -0,0 +1,186 @@
+//! Seam between the USB connection logic and `rusb`, allowing the bulk
+//! transfer handling to be exercised against scripted transfer sequences
+
+use super::{YUBIHSM2_BULK_IN_ENDPOINT, YUBIHSM2_BULK_OUT_ENDPOINT};
+use crate::{
+    command::MAX_MSG_SIZE,
+    connector::{
+        self,
+        ErrorKind::{DeviceBusyError, UsbError},
+    },
+};
+use std::time::Duration;
+
+/// Timeout for each bulk read while draining stale data. This is near
+/// instantaneous, but non-zero, as `rusb` interprets zero as wait forever.
+pub(super) const DRAIN_TIMEOUT: Duration = Duration::from_millis(1);
+
+/// Maximum number of stale transfers to discard before giving up on draining
+const MAX_DRAIN_TRANSFERS: usize = 16;
+
+/// Bulk transfer operations on a claimed YubiHSM 2 USB interface
+pub(super) trait UsbAdapter {
+    /// Read a bulk transfer from the given endpoint
+    fn read_bulk(&self, endpoint: u8, buf: &mut [u8], timeout: Duration) -> rusb::Result<usize>;
+
+    /// Write a bulk transfer to the given endpoint
+    fn write_bulk(&self, endpoint: u8, buf: &[u8], timeout: Duration) -> rusb::Result<usize>;
+
+    /// Clear a halt/stall condition on the given endpoint
+    fn clear_halt(&mut self, endpoint: u8) -> rusb::Result<()>;
+}
+
+impl UsbAdapter for rusb::DeviceHandle<rusb::Context> {
+    fn read_bulk(&self, endpoint: u8, buf: &mut [u8], timeout: Duration) -> rusb::Result<usize> {
+        rusb::DeviceHandle::read_bulk(self, endpoint, buf, timeout)
+    }
+
+    fn write_bulk(&self, endpoint: u8, buf: &[u8], timeout: Duration) -> rusb::Result<usize> {
+        rusb::DeviceHandle::write_bulk(self, endpoint, buf, timeout)
+    }
+
+    fn clear_halt(&mut self, endpoint: u8) -> rusb::Result<()> {
+        rusb::DeviceHandle::clear_halt(self, endpoint)
+    }
+}
+
+/// Discard any pending bulk-IN data (e.g. the half-read response of a
+/// command whose process was killed) so the next command's response isn't
+/// corrupted, returning the number of stale transfers discarded.
+///
+/// If the device reports it's busy (or has stalled the endpoint), the halt
+/// condition is cleared on both bulk endpoints, aborting the transfer in
+/// progress, and draining continues. Draining is bounded by
+/// `MAX_DRAIN_TRANSFERS` reads of at most `timeout` each.
+pub(super) fn drain(
+    adapter: &mut impl UsbAdapter,
+    timeout: Duration,
+) -> Result<usize, connector::Error> {
+    let mut buffer = [0u8; MAX_MSG_SIZE];
+    let mut discarded = 0;
+    let mut aborted = false;
+
+    for _ in 0..MAX_DRAIN_TRANSFERS {
+        match adapter.read_bulk(YUBIHSM2_BULK_IN_ENDPOINT, &mut buffer, timeout) {
+            Ok(_) => discarded += 1,
+            Err(rusb::Error::Timeout) => return Ok(discarded),
+            // Sporadic I/O errors are retried (as when receiving messages)
+            Err(rusb::Error::Io) => (),
+            Err(rusb::Error::Busy) | Err(rusb::Error::Pipe) if !aborted => {
+                debug!("USB device busy while draining; clearing endpoint halts");
+                adapter.clear_halt(YUBIHSM2_BULK_IN_ENDPOINT)?;
+                adapter.clear_halt(YUBIHSM2_BULK_OUT_ENDPOINT)?;
+                aborted = true;
+            }
+            Err(rusb::Error::Busy) => fail!(
+                DeviceBusyError,
+                "USB device still busy after aborting pending transfers"
+            ),
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    fail!(
+        UsbError,
+        "couldn't drain USB device after {} transfers",
+        MAX_DRAIN_TRANSFERS
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::RefCell, collections::VecDeque};
+
+    /// Adapter which answers bulk reads from a script of transfer results
+    struct ScriptedAdapter {
+        reads: RefCell<VecDeque<rusb::Result<usize>>>,
+        halts_cleared: Vec<u8>,
+    }
+
+    impl ScriptedAdapter {
+        fn new(reads: impl IntoIterator<Item = rusb::Result<usize>>) -> Self {
+            Self {
+                reads: RefCell::new(reads.into_iter().collect()),
+                halts_cleared: vec![],
+            }
+        }
+
+        /// Number of scripted reads which were never performed
+        fn reads_remaining(&self) -> usize {
+            self.reads.borrow().len()
+        }
+    }
+
+    impl UsbAdapter for ScriptedAdapter {
+        fn read_bulk(&self, endpoint: u8, _: &mut [u8], _: Duration) -> rusb::Result<usize> {
+            assert_eq!(endpoint, YUBIHSM2_BULK_IN_ENDPOINT);
+            self.reads
+                .borrow_mut()
+                .pop_front()
+                .expect("unscripted bulk read")
+        }
+
+        fn write_bulk(&self, _: u8, _: &[u8], _: Duration) -> rusb::Result<usize> {
+            panic!("unexpected bulk write while draining")
+        }
+
+        fn clear_halt(&mut self, endpoint: u8) -> rusb::Result<()> {
+            self.halts_cleared.push(endpoint);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn drain_idle_device() {
+        let mut adapter = ScriptedAdapter::new([Err(rusb::Error::Timeout)]);
+        assert_eq!(drain(&mut adapter, DRAIN_TIMEOUT).unwrap(), 0);
+        assert!(adapter.halts_cleared.is_empty());
+    }
+
+    #[test]
+    fn drain_stale_responses() {
+        let mut adapter = ScriptedAdapter::new([
+            Ok(64),
+            Err(rusb::Error::Io),
+            Ok(12),
+            Err(rusb::Error::Timeout),
+            Ok(3),
+        ]);
+
+        assert_eq!(drain(&mut adapter, DRAIN_TIMEOUT).unwrap(), 2);
+        assert_eq!(adapter.reads_remaining(), 1);
+    }
+
+    #[test]
+    fn drain_busy_device() {
+        let mut adapter = ScriptedAdapter::new([
+            Ok(64),
+            Err(rusb::Error::Busy),
+            Ok(7),
+            Err(rusb::Error::Timeout),
+        ]);
+
+        assert_eq!(drain(&mut adapter, DRAIN_TIMEOUT).unwrap(), 2);
+        assert_eq!(
+            adapter.halts_cleared,
+            [YUBIHSM2_BULK_IN_ENDPOINT, YUBIHSM2_BULK_OUT_ENDPOINT]
+        );
+    }
+
+    #[test]
+    fn drain_persistently_busy_device() {
+        let mut adapter = ScriptedAdapter::new([Err(rusb::Error::Pipe), Err(rusb::Error::Busy)]);
+        let err = drain(&mut adapter, DRAIN_TIMEOUT).unwrap_err();
+        assert_eq!(*err.kind(), DeviceBusyError);
+        assert_eq!(adapter.halts_cleared.len(), 2);
+    }
+
+    #[test]
+    fn drain_is_bounded() {
+        let mut adapter = ScriptedAdapter::new((0..MAX_DRAIN_TRANSFERS + 1).map(|_| Ok(64)));
+        let err = drain(&mut adapter, DRAIN_TIMEOUT).unwrap_err();
+        assert_eq!(*err.kind(), UsbError);
+        assert_eq!(adapter.reads_remaining(), 1);
+    }
+}
@@ -1,6 +1,6 @@
 //! USB device configuration
 
-use crate::device::SerialNumber;
+use crate::{command::MAX_MSG_SIZE, device::SerialNumber};
 use serde::{Deserialize, Serialize};
 
 /// Configuration for connecting to the YubiHSM via USB
@@ -11,6 +11,11 @@ pub struct UsbConfig {
 
     /// Timeout for USB operations (default 1s)
     pub timeout_ms: u64,
+
+    /// Maximum size of messages sent to or received from the HSM in bytes
+    /// (defaults to `command::MAX_MSG_SIZE`)
+    #[serde(default = "default_max_message_size")]
+    pub max_message_size: usize,
 }
 
 impl UsbConfig {
@@ -23,6 +28,12 @@ impl Default for UsbConfig {
         UsbConfig {
             serial: None,
             timeout_ms: Self::DEFAULT_TIMEOUT_MILLIS,
+            max_message_size: MAX_MSG_SIZE,
         }
     }
 }
+
+/// Default `max_message_size` for configs which don't specify one
+fn default_max_message_size() -> usize {
+    MAX_MSG_SIZE
+}
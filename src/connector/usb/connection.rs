@@ -1,18 +1,25 @@
 //! Connections to the YubiHSM 2 via USB
 
 use super::{
+    adapter::{self, UsbAdapter},
     Device, Devices, UsbConfig, UsbTimeout, YUBIHSM2_BULK_IN_ENDPOINT, YUBIHSM2_BULK_OUT_ENDPOINT,
 };
 use crate::{
     command::MAX_MSG_SIZE,
     connector::{self, Connection, ErrorKind::UsbError, Message},
 };
-use std::sync::Mutex;
+use std::{
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
 use uuid::Uuid;
 
 /// Number of times to retry a bulk message receive operation before giving up
 const MAX_RECV_RETRIES: usize = 3;
 
+/// Shortest timeout used for bulk transfers
+const MIN_TIMEOUT: Duration = Duration::from_millis(1);
+
 /// Connection to HSM via USB
 pub struct UsbConnection {
     /// Handle to the underlying USB device
@@ -23,29 +30,30 @@ pub struct UsbConnection {
 
     /// Timeout for reading from / writing to the YubiHSM 2
     timeout: UsbTimeout,
+
+    /// Maximum size of messages sent to or received from the YubiHSM 2
+    max_message_size: usize,
 }
 
 impl UsbConnection {
     /// Connect to a YubiHSM 2 using the given configuration
     pub fn open(config: &UsbConfig) -> Result<Self, connector::Error> {
-        Devices::open(config.serial, UsbTimeout::from_millis(config.timeout_ms))
+        let mut connection =
+            Devices::open(config.serial, UsbTimeout::from_millis(config.timeout_ms))?;
+
+        connection.max_message_size = config.max_message_size;
+        Ok(connection)
     }
 
     /// Create a new YubiHSM device from a rusb device
     pub(super) fn create(device: Device, timeout: UsbTimeout) -> Result<Self, connector::Error> {
         let handle = device.open_handle()?;
 
-        // Clear any lingering messages
-        for _ in 0..MAX_RECV_RETRIES {
-            if recv_message(&handle, UsbTimeout::from_millis(1)).is_err() {
-                break;
-            }
-        }
-
         Ok(Self {
             device,
             timeout,
             handle: Mutex::new(handle),
+            max_message_size: MAX_MSG_SIZE,
         })
     }
 
@@ -53,14 +61,56 @@ impl UsbConnection {
     pub fn device(&self) -> &Device {
         &self.device
     }
+
+    /// Timeout for the next bulk transfer given a deadline
+    fn timeout_until(&self, deadline: Instant) -> UsbTimeout {
+        // rusb treats a zero duration as an unlimited timeout
+        let remaining = deadline
+            .saturating_duration_since(Instant::now())
+            .max(MIN_TIMEOUT);
+
+        UsbTimeout::new(remaining.min(self.timeout.duration()))
+    }
 }
 
 impl Connection for UsbConnection {
     /// Send a command to the YubiHSM and read its response
     fn send_message(&self, _uuid: Uuid, cmd: Message) -> Result<Message, connector::Error> {
         let handle = self.handle.lock().unwrap();
-        send_message(&handle, cmd.as_ref(), self.timeout)?;
-        recv_message(&handle, self.timeout)
+        send_message(&*handle, cmd.as_ref(), self.timeout)?;
+        recv_message(&*handle, self.max_message_size, self.timeout)
+    }
+
+    /// Send a command to the YubiHSM and read its response, with bulk
+    /// transfers timing out by the given deadline
+    fn send_message_with_deadline(
+        &self,
+        _uuid: Uuid,
+        cmd: Message,
+        deadline: Instant,
+    ) -> Result<Message, connector::Error> {
+        let handle = self.handle.lock().unwrap();
+        send_message(&*handle, cmd.as_ref(), self.timeout_until(deadline))?;
+        recv_message(
+            &*handle,
+            self.max_message_size,
+            self.timeout_until(deadline),
+        )
+    }
+}
+
+impl Drop for UsbConnection {
+    /// Best-effort drain of any response still in flight (e.g. to a command
+    /// whose deadline passed), so it isn't read by the device's next user
+    fn drop(&mut self) {
+        let handle = self
+            .handle
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        if let Err(e) = adapter::drain(handle, adapter::DRAIN_TIMEOUT) {
+            usb_debug!(self.device, "error draining device on close: {}", e);
+        }
     }
 }
 
@@ -72,7 +122,7 @@ impl Default for UsbConnection {
 
 /// Write a bulk message to the YubiHSM 2
 fn send_message(
-    handle: &rusb::DeviceHandle<rusb::Context>,
+    handle: &impl UsbAdapter,
     data: &[u8],
     timeout: UsbTimeout,
 ) -> Result<usize, connector::Error> {
@@ -92,11 +142,12 @@ fn send_message(
 
 /// Receive a message
 fn recv_message(
-    handle: &rusb::DeviceHandle<rusb::Context>,
+    handle: &impl UsbAdapter,
+    max_message_size: usize,
     timeout: UsbTimeout,
 ) -> Result<Message, connector::Error> {
     // Allocate a buffer which is the maximum size we expect to receive
-    let mut response = vec![0u8; MAX_MSG_SIZE];
+    let mut response = vec![0u8; max_message_size];
 
     for attempts_remaining in (0..MAX_RECV_RETRIES).rev() {
         match handle.read_bulk(YUBIHSM2_BULK_IN_ENDPOINT, &mut response, timeout.duration()) {
@@ -1,11 +1,10 @@
 //! Support for connecting to the YubiHSM 2 USB device using rusb
 
 use super::{
-    UsbConnection, UsbTimeout, YUBICO_VENDOR_ID, YUBIHSM2_BULK_IN_ENDPOINT, YUBIHSM2_INTERFACE_NUM,
+    adapter, UsbConnection, UsbTimeout, YUBICO_VENDOR_ID, YUBIHSM2_INTERFACE_NUM,
     YUBIHSM2_PRODUCT_ID,
 };
 use crate::{
-    command::MAX_MSG_SIZE,
     connector::{
         self,
         ErrorKind::{AddrInvalid, DeviceBusyError, UsbError},
@@ -15,7 +14,6 @@ use crate::{
 use std::{
     fmt::{self, Debug},
     slice::Iter,
-    time::Duration,
     vec::IntoIter,
 };
 
@@ -226,8 +224,9 @@ impl Device {
         handle.reset()?;
         handle.claim_interface(YUBIHSM2_INTERFACE_NUM)?;
 
-        // Flush any unconsumed messages still in the buffer
-        flush(&mut handle)?;
+        // Drain any unconsumed messages still in the buffer (e.g. responses
+        // to commands sent by a process which was killed mid-command)
+        adapter::drain(&mut handle, adapter::DRAIN_TIMEOUT)?;
 
         Ok(handle)
     }
@@ -244,18 +243,3 @@ impl Debug for Device {
         )
     }
 }
-
-/// Flush any unconsumed messages still in the buffer to get the connection
-/// back into a clean state
-fn flush(handle: &mut rusb::DeviceHandle<rusb::Context>) -> Result<(), connector::Error> {
-    let mut buffer = [0u8; MAX_MSG_SIZE];
-
-    // Use a near instantaneous (but non-zero) timeout to drain the buffer.
-    // Zero is interpreted as wait forever.
-    let timeout = Duration::from_millis(1);
-
-    match handle.read_bulk(YUBIHSM2_BULK_IN_ENDPOINT, &mut buffer, timeout) {
-        Ok(_) | Err(rusb::Error::Timeout) => Ok(()),
-        Err(e) => Err(e.into()),
-    }
-}
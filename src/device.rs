@@ -2,13 +2,17 @@
 
 pub(crate) mod commands;
 mod error;
+mod firmware;
 mod info;
+mod rng;
 pub(super) mod serial;
 pub(super) mod storage;
 
 pub use self::{
     error::{Error, ErrorKind},
+    firmware::FirmwareVersion,
     info::Info,
+    rng::RngHealth,
     serial::Number as SerialNumber,
-    storage::Info as StorageInfo,
+    storage::{Footprint as StorageFootprint, Info as StorageInfo},
 };
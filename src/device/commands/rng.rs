@@ -3,14 +3,11 @@
 //! <https://developers.yubico.com/YubiHSM2/Commands/Get_Pseudo_Random.html>
 
 use crate::{
-    command::{self, Command, MAX_MSG_SIZE},
+    command::{self, Command},
     response::Response,
 };
 use serde::{Deserialize, Serialize};
 
-/// Max message size - tag byte - 16-bit response length field
-pub(crate) const MAX_RAND_BYTES: usize = MAX_MSG_SIZE - 1 - 2;
-
 /// Request parameters for `command::get_pseudo_random`
 #[derive(Serialize, Deserialize, Debug)]
 pub(crate) struct GetPseudoRandomCommand {
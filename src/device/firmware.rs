@@ -0,0 +1,48 @@
+//! YubiHSM 2 firmware versions
+
+use std::fmt::{self, Display};
+
+/// Firmware version of a YubiHSM 2 (as reported by
+/// [`Client::device_info`](crate::Client::device_info)).
+///
+/// Versions are ordered, so e.g. whether a device supports a feature
+/// introduced in some release can be checked with a comparison.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub struct FirmwareVersion {
+    /// Major version
+    pub major: u8,
+
+    /// Minor version
+    pub minor: u8,
+
+    /// Build version
+    pub build: u8,
+}
+
+impl FirmwareVersion {
+    /// Create a new firmware version
+    pub fn new(major: u8, minor: u8, build: u8) -> Self {
+        Self {
+            major,
+            minor,
+            build,
+        }
+    }
+
+    /// Serialize as `[major, minor, build]`
+    pub fn to_bytes(self) -> [u8; 3] {
+        [self.major, self.minor, self.build]
+    }
+}
+
+impl Default for FirmwareVersion {
+    fn default() -> Self {
+        Self::new(2, 0, 0)
+    }
+}
+
+impl Display for FirmwareVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.build)
+    }
+}
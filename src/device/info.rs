@@ -1,8 +1,8 @@
 //! Device info
 
-use super::serial;
-use crate::Algorithm;
-use serde::{Deserialize, Serialize};
+use super::{serial, FirmwareVersion};
+use crate::{ecdsa::algorithm::CurveAlgorithm, Algorithm};
+use serde::{Deserialize, Deserializer, Serialize};
 
 /// Information about an HSM device
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -25,6 +25,99 @@ pub struct Info {
     /// Number of log lines used
     pub log_store_used: u8,
 
-    /// Supported algorithms
+    /// Supported algorithms (excluding any this crate doesn't know about,
+    /// e.g. ones added in newer firmware)
+    #[serde(deserialize_with = "deserialize_algorithms")]
     pub algorithms: Vec<Algorithm>,
 }
+
+impl Info {
+    /// Get the device's firmware version
+    pub fn firmware_version(&self) -> FirmwareVersion {
+        FirmwareVersion::new(self.major_version, self.minor_version, self.build_version)
+    }
+
+    /// Does the device support the given algorithm?
+    pub fn supports(&self, algorithm: impl Into<Algorithm>) -> bool {
+        self.algorithms.contains(&algorithm.into())
+    }
+
+    /// Does the device support keys on the elliptic curve `C`?
+    pub fn supports_curve<C: CurveAlgorithm>(&self) -> bool {
+        self.supports(C::asymmetric_algorithm())
+    }
+
+    /// Size of the audit log (in entries)
+    pub fn log_size(&self) -> u8 {
+        self.log_store_capacity
+    }
+
+    /// Number of audit log entries which haven't yet been consumed
+    pub fn log_used(&self) -> u8 {
+        self.log_store_used
+    }
+
+    /// Fraction of the audit log which is in use (from `0.0` to `1.0`)
+    pub fn log_utilization(&self) -> f32 {
+        if self.log_store_capacity == 0 {
+            return 0.0;
+        }
+
+        f32::from(self.log_store_used) / f32::from(self.log_store_capacity)
+    }
+}
+
+/// Deserialize the supported algorithms, skipping unknown ones
+fn deserialize_algorithms<'de, D>(deserializer: D) -> Result<Vec<Algorithm>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let tags = Vec::<u8>::deserialize(deserializer)?;
+
+    Ok(tags
+        .into_iter()
+        .filter_map(|tag| Algorithm::from_u8(tag).ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        asymmetric,
+        ecdsa::{NistP256, NistP384},
+        serialization::{deserialize, serialize},
+    };
+
+    #[test]
+    fn unknown_algorithms_are_skipped() {
+        let info = Info {
+            major_version: 2,
+            minor_version: 4,
+            build_version: 0,
+            serial_number: 1_234_567_890.into(),
+            log_store_capacity: 62,
+            log_store_used: 0,
+            algorithms: vec![Algorithm::Asymmetric(asymmetric::Algorithm::EcP256)],
+        };
+
+        // 0x32 is AES-128 (symmetric keys), which this crate doesn't support
+        let mut bytes = serialize(&info).unwrap();
+        bytes.push(0x32);
+        bytes.push(asymmetric::Algorithm::Ed25519.to_u8());
+
+        let info: Info = deserialize(&bytes).unwrap();
+
+        assert_eq!(
+            info.algorithms,
+            [
+                Algorithm::Asymmetric(asymmetric::Algorithm::EcP256),
+                Algorithm::Asymmetric(asymmetric::Algorithm::Ed25519)
+            ]
+        );
+
+        assert!(info.supports(asymmetric::Algorithm::Ed25519));
+        assert!(info.supports_curve::<NistP256>());
+        assert!(!info.supports_curve::<NistP384>());
+    }
+}
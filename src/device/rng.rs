@@ -0,0 +1,134 @@
+//! Basic statistical sanity checks of the HSM's random number generator
+
+/// Minimum P-value for a test to pass (i.e. a 1% significance level)
+pub const SIGNIFICANCE_LEVEL: f64 = 0.01;
+
+/// Results of basic statistical sanity checks (the frequency/monobit and
+/// runs tests from NIST SP 800-22) on a sample of the HSM's random output,
+/// as computed by [`Client::rng_healthcheck`](crate::Client::rng_healthcheck).
+///
+/// These catch grossly broken output (e.g. stuck or biased bits), but are
+/// not a substitute for certified testing of the RNG.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RngHealth {
+    /// Number of bits in the sample
+    pub bits: usize,
+
+    /// Number of bits in the sample which are set
+    pub ones: usize,
+
+    /// P-value of the frequency (monobit) test
+    pub monobit_p_value: f64,
+
+    /// Number of runs (i.e. uninterrupted sequences of identical bits)
+    pub runs: usize,
+
+    /// P-value of the runs test (zero if the sample is too biased for the
+    /// runs test to apply, i.e. the frequency test fails)
+    pub runs_p_value: f64,
+}
+
+impl RngHealth {
+    /// Compute the statistics for a sample of random bytes (whose bits are
+    /// taken most significant first)
+    pub fn from_sample(sample: &[u8]) -> Self {
+        let bits: Vec<bool> = sample
+            .iter()
+            .flat_map(|byte| (0..8).rev().map(move |i| byte & (1 << i) != 0))
+            .collect();
+
+        Self::from_bits(&bits)
+    }
+
+    /// Did the sample pass both tests?
+    pub fn passed(&self) -> bool {
+        self.monobit_p_value >= SIGNIFICANCE_LEVEL && self.runs_p_value >= SIGNIFICANCE_LEVEL
+    }
+
+    /// Compute the statistics for a sequence of bits
+    fn from_bits(bits: &[bool]) -> Self {
+        let n = bits.len() as f64;
+        let ones = bits.iter().filter(|&&bit| bit).count();
+        let runs = 1 + bits.windows(2).filter(|pair| pair[0] != pair[1]).count();
+
+        // Frequency (monobit) test: SP 800-22 section 2.1
+        let sum = 2.0 * ones as f64 - n;
+        let monobit_p_value = erfc(sum.abs() / n.sqrt() / 2f64.sqrt());
+
+        // Runs test: SP 800-22 section 2.3
+        let pi = ones as f64 / n;
+        let runs_p_value = if (pi - 0.5).abs() >= 2.0 / n.sqrt() {
+            0.0
+        } else {
+            let expected = 2.0 * n * pi * (1.0 - pi);
+            erfc((runs as f64 - expected).abs() / (2.0 * (2.0 * n).sqrt() * pi * (1.0 - pi)))
+        };
+
+        Self {
+            bits: bits.len(),
+            ones,
+            monobit_p_value,
+            runs: if bits.is_empty() { 0 } else { runs },
+            runs_p_value,
+        }
+    }
+}
+
+/// Complementary error function (with fractional error below 1.2e-7, per
+/// Numerical Recipes' `erfcc`)
+fn erfc(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 10] = [
+        -1.265_512_23,
+        1.000_023_68,
+        0.374_091_96,
+        0.096_784_18,
+        -0.186_288_06,
+        0.278_868_07,
+        -1.135_203_98,
+        1.488_515_87,
+        -0.822_152_23,
+        0.170_872_77,
+    ];
+
+    let t = 1.0 / (1.0 + 0.5 * x.abs());
+    let poly = COEFFICIENTS.iter().rev().fold(0.0, |acc, c| acc * t + c);
+    let result = t * (poly - x * x).exp();
+
+    if x >= 0.0 {
+        result
+    } else {
+        2.0 - result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse a string of `0`s and `1`s into bits
+    fn bits(s: &str) -> Vec<bool> {
+        s.chars().map(|c| c == '1').collect()
+    }
+
+    #[test]
+    fn monobit_test_vector() {
+        // SP 800-22 section 2.1.4
+        let health = RngHealth::from_bits(&bits("1011010101"));
+        assert_eq!(health.ones, 6);
+        assert!((health.monobit_p_value - 0.527089).abs() < 1e-6);
+    }
+
+    #[test]
+    fn runs_test_vector() {
+        // SP 800-22 section 2.3.4
+        let health = RngHealth::from_bits(&bits("1001101011"));
+        assert_eq!(health.runs, 7);
+        assert!((health.runs_p_value - 0.147232).abs() < 1e-6);
+    }
+
+    #[test]
+    fn broken_output_fails() {
+        assert!(!RngHealth::from_sample(&[0; 256]).passed());
+        assert!(!RngHealth::from_sample(&[0x55; 256]).passed());
+    }
+}
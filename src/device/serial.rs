@@ -1,7 +1,7 @@
 //! YubiHSM 2 device serial numbers
 
 use super::error::{Error, ErrorKind};
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize};
 use std::{
     fmt::{self, Display},
     str::FromStr,
@@ -10,10 +10,22 @@ use std::{
 /// Length of a YubiHSM 2 serial number in base 10 digits (i.e. characters)
 const NUM_DIGITS: usize = 10;
 
-/// YubiHSM serial numbers
-#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize)]
+/// YubiHSM serial numbers.
+///
+/// These are displayed zero-padded to 10 digits (as in USB descriptors), but
+/// parse from either that or the unpadded form (as in e.g. `yubihsm-connector`
+/// status output). When deserializing from human-readable formats, they may
+/// be given as either strings or integers.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize)]
 pub struct Number(u32);
 
+impl Number {
+    /// Get this serial number as an integer
+    pub fn as_u32(self) -> u32 {
+        self.0
+    }
+}
+
 impl Display for Number {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:0width$}", self.0, width = NUM_DIGITS)
@@ -24,20 +36,151 @@ impl FromStr for Number {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Number, Error> {
-        if s.len() == NUM_DIGITS {
-            let number = s.parse::<u32>().map_err(|_| {
-                format_err!(ErrorKind::InvalidData, "error parsing serial number: {}", s)
-            })?;
-
-            Ok(Number(number))
-        } else {
+        if s.is_empty() || s.len() > NUM_DIGITS {
             fail!(
                 ErrorKind::WrongLength,
-                "invalid serial number length (expected {}, got {}): '{}'",
+                "invalid serial number length (expected at most {}, got {}): '{}'",
                 NUM_DIGITS,
                 s.len(),
                 s
             );
         }
+
+        // `u32::from_str` also accepts a leading `+`
+        ensure!(
+            s.bytes().all(|b| b.is_ascii_digit()),
+            ErrorKind::InvalidData,
+            "error parsing serial number: {}",
+            s
+        );
+
+        let number = s.parse::<u32>().map_err(|_| {
+            format_err!(ErrorKind::InvalidData, "serial number out of range: {}", s)
+        })?;
+
+        Ok(Number(number))
+    }
+}
+
+impl From<u32> for Number {
+    fn from(number: u32) -> Number {
+        Number(number)
+    }
+}
+
+impl From<Number> for u32 {
+    fn from(number: Number) -> u32 {
+        number.0
+    }
+}
+
+impl TryFrom<u64> for Number {
+    type Error = Error;
+
+    fn try_from(number: u64) -> Result<Number, Error> {
+        u32::try_from(number).map(Number).map_err(|_| {
+            format_err!(
+                ErrorKind::InvalidData,
+                "serial number out of range: {}",
+                number
+            )
+            .into()
+        })
+    }
+}
+
+impl PartialEq<u32> for Number {
+    fn eq(&self, other: &u32) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<&str> for Number {
+    fn eq(&self, other: &&str) -> bool {
+        other
+            .parse::<Number>()
+            .map_or(false, |other| *self == other)
+    }
+}
+
+impl<'de> Deserialize<'de> for Number {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Number, D::Error> {
+        struct NumberVisitor;
+
+        impl<'de> de::Visitor<'de> for NumberVisitor {
+            type Value = Number;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("a YubiHSM serial number (as an integer or a string)")
+            }
+
+            fn visit_u64<E: de::Error>(self, number: u64) -> Result<Number, E> {
+                Number::try_from(number).map_err(E::custom)
+            }
+
+            fn visit_i64<E: de::Error>(self, number: i64) -> Result<Number, E> {
+                u64::try_from(number)
+                    .map_err(|_| E::custom(format!("serial number out of range: {number}")))
+                    .and_then(|number| self.visit_u64(number))
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<Number, E> {
+                s.parse().map_err(E::custom)
+            }
+        }
+
+        // The HSM's (binary) wire format only ever encodes them as integers
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(NumberVisitor)
+        } else {
+            u32::deserialize(deserializer).map(Number)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_padded_and_unpadded() {
+        let padded: Number = "0012345678".parse().unwrap();
+        let unpadded: Number = "12345678".parse().unwrap();
+
+        assert_eq!(padded, unpadded);
+        assert_eq!(padded.as_u32(), 12_345_678);
+        assert_eq!(unpadded.to_string(), "0012345678");
+        assert_eq!(padded, "12345678");
+        assert_eq!(unpadded, 12_345_678);
+    }
+
+    #[test]
+    fn reject_overlong() {
+        let err = "00012345678".parse::<Number>().unwrap_err();
+        assert_eq!(*err.kind(), ErrorKind::WrongLength);
+
+        // Ten digits, but too large for a `u32`
+        let err = "9999999999".parse::<Number>().unwrap_err();
+        assert_eq!(*err.kind(), ErrorKind::InvalidData);
+        assert!(Number::try_from(u64::from(u32::MAX) + 1).is_err());
+    }
+
+    #[test]
+    fn reject_non_numeric() {
+        for s in ["", "12345a", "+1234567", " 1234567", "-1"] {
+            assert!(s.parse::<Number>().is_err(), "{s:?}");
+        }
+
+        assert!(Number::from(1) != "one");
+    }
+
+    #[test]
+    fn deserialize_json() {
+        let from_int: Number = serde_json::from_str("12345678").unwrap();
+        let from_str: Number = serde_json::from_str(r#""0012345678""#).unwrap();
+
+        assert_eq!(from_int, from_str);
+        assert!(serde_json::from_str::<Number>("-1").is_err());
+        assert!(serde_json::from_str::<Number>("4294967296").is_err());
     }
 }
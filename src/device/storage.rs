@@ -1,6 +1,7 @@
 //! Information about device storage
 
 use serde::{Deserialize, Serialize};
+use std::{iter::Sum, ops::Add};
 
 /// Response from the [Get Storage Info] command.
 ///
@@ -22,3 +23,54 @@ pub struct Info {
     /// Page size in bytes
     pub page_size: u16,
 }
+
+impl Info {
+    /// Storage an object whose data (e.g. key material) is `data_len` bytes
+    /// long occupies: one record, and enough pages to hold its data (at
+    /// least one)
+    pub fn footprint(&self, data_len: usize) -> Footprint {
+        let page_size = usize::from(self.page_size.max(1));
+
+        Footprint {
+            records: 1,
+            pages: ((data_len + page_size - 1) / page_size).max(1),
+        }
+    }
+
+    /// Is there enough free storage for objects with the given (combined)
+    /// footprint?
+    pub fn has_room_for(&self, footprint: Footprint) -> bool {
+        footprint.records <= usize::from(self.free_records)
+            && footprint.pages <= usize::from(self.free_pages)
+    }
+}
+
+/// Storage occupied by one or more objects (see [`Info::footprint`]).
+///
+/// Footprints can be added (or summed) to get the storage needed for
+/// several objects, e.g. all of the objects in a backup.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Footprint {
+    /// Number of storage records
+    pub records: usize,
+
+    /// Number of storage pages
+    pub pages: usize,
+}
+
+impl Add for Footprint {
+    type Output = Footprint;
+
+    fn add(self, other: Footprint) -> Footprint {
+        Footprint {
+            records: self.records + other.records,
+            pages: self.pages + other.pages,
+        }
+    }
+}
+
+impl Sum for Footprint {
+    fn sum<I: Iterator<Item = Footprint>>(iter: I) -> Footprint {
+        iter.fold(Footprint::default(), Add::add)
+    }
+}
@@ -36,7 +36,10 @@ bitflags! {
     /// basis. For more information, see the Yubico documentation:
     ///
     /// <https://developers.yubico.com/YubiHSM2/Concepts/Domain.html>
-    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    ///
+    /// Sets of domains can be combined with `|`, or collected from an
+    /// iterator of domains (an empty iterator collects to the empty set).
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
     pub struct Domain: u16 {
         const DOM1 = 0x0001;
         const DOM2 = 0x0002;
@@ -70,6 +73,32 @@ impl Domain {
             ),
         }
     }
+
+    /// Format the domain numbers in this set as comma-separated ranges,
+    /// e.g. `1-3,5` for `DOM1 | DOM2 | DOM3 | DOM5` (or an empty string if
+    /// there are no domains).
+    pub fn to_range_string(self) -> String {
+        let mut ranges: Vec<(usize, usize)> = vec![];
+
+        for index in (1..=DOMAINS.len()).filter(|&i| self.contains(DOMAINS[i - 1])) {
+            match ranges.last_mut() {
+                Some((_, end)) if *end + 1 == index => *end = index,
+                _ => ranges.push((index, index)),
+            }
+        }
+
+        ranges
+            .iter()
+            .map(|&(start, end)| {
+                if start == end {
+                    start.to_string()
+                } else {
+                    format!("{start}-{end}")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
 }
 
 impl Serialize for Domain {
@@ -101,8 +130,43 @@ impl<'de> Deserialize<'de> for Domain {
             {
                 Domain::from_bits(value).ok_or_else(|| E::custom("invalid domain bitflags"))
             }
+
+            // Self-describing formats (e.g. JSON) deserialize integers as `u64`
+            fn visit_u64<E>(self, value: u64) -> Result<Domain, E>
+            where
+                E: de::Error,
+            {
+                u16::try_from(value)
+                    .map_err(|_| E::custom("invalid domain bitflags"))
+                    .and_then(|value| self.visit_u16(value))
+            }
         }
 
         deserializer.deserialize_u16(DomainVisitor)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collect_domains() {
+        let domains: Domain = (1..=3).map(|i| Domain::at(i).unwrap()).collect();
+        assert_eq!(domains, Domain::DOM1 | Domain::DOM2 | Domain::DOM3);
+    }
+
+    #[test]
+    fn range_string() {
+        let domains = Domain::DOM1 | Domain::DOM2 | Domain::DOM3 | Domain::DOM5 | Domain::DOM16;
+        assert_eq!(domains.to_range_string(), "1-3,5,16");
+        assert_eq!(Domain::all().to_range_string(), "1-16");
+        assert_eq!(Domain::empty().to_range_string(), "");
+    }
+
+    #[test]
+    fn collect_no_domains() {
+        let domains: Domain = std::iter::empty().collect();
+        assert_eq!(domains, Domain::empty());
+    }
+}
@@ -3,14 +3,24 @@
 pub mod algorithm;
 pub mod nistp256;
 pub mod nistp384;
+pub mod sig;
 
 #[cfg(feature = "secp256k1")]
 pub mod secp256k1;
 
 pub(crate) mod commands;
+mod signable;
 mod signer;
+mod verify;
 
-pub use self::{algorithm::Algorithm, nistp256::NistP256, nistp384::NistP384, signer::Signer};
+pub use self::{
+    algorithm::Algorithm,
+    nistp256::NistP256,
+    nistp384::NistP384,
+    signable::Signable,
+    signer::{Builder as SignerBuilder, Signer},
+    verify::{verify_external, verify_external_der},
+};
 pub use ::ecdsa::{der, elliptic_curve::sec1, signature, Signature};
 
 #[cfg(feature = "secp256k1")]
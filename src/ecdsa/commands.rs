@@ -21,6 +21,13 @@ pub(crate) struct SignEcdsaCommand {
 
 impl Command for SignEcdsaCommand {
     type ResponseType = SignEcdsaResponse;
+
+    fn object(&self) -> Option<object::Handle> {
+        Some(object::Handle::new(
+            self.key_id,
+            object::Type::AsymmetricKey,
+        ))
+    }
 }
 
 /// Response from ECDSA signing request
@@ -0,0 +1,256 @@
+//! Conversions between the encodings of ECDSA signatures produced by the
+//! HSM and this crate:
+//!
+//! - [`Signature`]: fixed-size `r || s` signatures (as returned by
+//!   [`Signer`](super::Signer))
+//! - [`DerSignature`]: ASN.1 DER encoded signatures (as returned by
+//!   [`Client::sign_ecdsa_prehash_raw`](crate::Client::sign_ecdsa_prehash_raw))
+//! - raw `r || s` bytes, each scalar zero-padded (on the left) to the size
+//!   of the curve's field
+//! - (with the `secp256k1` feature) 65-byte "compact recoverable"
+//!   signatures, i.e. raw `r || s` bytes followed by a recovery ID byte
+//!
+//! Parsing is strict: DER must be canonical (minimal lengths and integer
+//! encodings, no trailing data), raw signatures must be exactly twice the
+//! field size, and `r` and `s` must both be nonzero and less than the
+//! curve's order.
+//!
+//! The `ecdsa` types are re-exported here, so they needn't be imported from
+//! a matching version of the `ecdsa` crate.
+
+pub use ::ecdsa::{der::Signature as DerSignature, Signature, SignatureBytes};
+
+use ::ecdsa::{
+    der,
+    elliptic_curve::{
+        generic_array::{typenum::Unsigned, ArrayLength},
+        FieldBytes, FieldBytesSize, PrimeCurve,
+    },
+    SignatureSize,
+};
+use signature::Error;
+use std::ops::Add;
+
+#[cfg(feature = "secp256k1")]
+use super::secp256k1::{RecoveryId, Secp256k1};
+
+/// Size of a compact recoverable secp256k1 signature
+#[cfg(feature = "secp256k1")]
+pub const COMPACT_RECOVERABLE_SIZE: usize = 65;
+
+/// Parse an ASN.1 DER encoded signature (strictly, see the module docs)
+pub fn from_der<C>(der_bytes: &[u8]) -> Result<Signature<C>, Error>
+where
+    C: PrimeCurve,
+    SignatureSize<C>: ArrayLength<u8>,
+    der::MaxSize<C>: ArrayLength<u8>,
+    <FieldBytesSize<C> as Add>::Output: Add<der::MaxOverhead> + ArrayLength<u8>,
+{
+    Signature::from_der(der_bytes)
+}
+
+/// Encode a signature as ASN.1 DER.
+///
+/// DER integers are minimal and signed, so scalars are stripped of leading
+/// zero bytes, and prefixed with a single zero byte if their high bit is
+/// set (the resulting encoding varies in length).
+pub fn to_der<C>(signature: &Signature<C>) -> DerSignature<C>
+where
+    C: PrimeCurve,
+    SignatureSize<C>: ArrayLength<u8>,
+    der::MaxSize<C>: ArrayLength<u8>,
+    <FieldBytesSize<C> as Add>::Output: Add<der::MaxOverhead> + ArrayLength<u8>,
+{
+    signature.to_der()
+}
+
+/// Parse raw `r || s` bytes, which must be exactly twice the size of the
+/// curve's field (i.e. each scalar zero-padded to the field size)
+pub fn from_raw<C>(bytes: &[u8]) -> Result<Signature<C>, Error>
+where
+    C: PrimeCurve,
+    SignatureSize<C>: ArrayLength<u8>,
+{
+    Signature::from_slice(bytes)
+}
+
+/// Serialize a signature as raw `r || s` bytes (each scalar zero-padded
+/// to the size of the curve's field)
+pub fn to_raw<C>(signature: &Signature<C>) -> SignatureBytes<C>
+where
+    C: PrimeCurve,
+    SignatureSize<C>: ArrayLength<u8>,
+{
+    signature.to_bytes()
+}
+
+/// Convert an ASN.1 DER encoded signature into raw `r || s` bytes
+pub fn der_to_raw<C>(der_bytes: &[u8]) -> Result<SignatureBytes<C>, Error>
+where
+    C: PrimeCurve,
+    SignatureSize<C>: ArrayLength<u8>,
+    der::MaxSize<C>: ArrayLength<u8>,
+    <FieldBytesSize<C> as Add>::Output: Add<der::MaxOverhead> + ArrayLength<u8>,
+{
+    from_der::<C>(der_bytes).map(|signature| to_raw(&signature))
+}
+
+/// Convert raw `r || s` bytes into an ASN.1 DER encoded signature
+pub fn raw_to_der<C>(bytes: &[u8]) -> Result<DerSignature<C>, Error>
+where
+    C: PrimeCurve,
+    SignatureSize<C>: ArrayLength<u8>,
+    der::MaxSize<C>: ArrayLength<u8>,
+    <FieldBytesSize<C> as Add>::Output: Add<der::MaxOverhead> + ArrayLength<u8>,
+{
+    from_raw::<C>(bytes).map(|signature| to_der(&signature))
+}
+
+/// Create a signature from big-endian `r` and `s` scalars of any length up
+/// to the size of the curve's field, zero-padding shorter ones (e.g. from
+/// encodings which strip leading zeros).
+///
+/// A single leading zero byte beyond the field size (as in DER integers
+/// with their high bit set) is also accepted.
+pub fn from_scalars<C>(r: &[u8], s: &[u8]) -> Result<Signature<C>, Error>
+where
+    C: PrimeCurve,
+    SignatureSize<C>: ArrayLength<u8>,
+{
+    Signature::from_scalars(pad_scalar::<C>(r)?, pad_scalar::<C>(s)?)
+}
+
+/// Serialize a secp256k1 signature and its recovery ID as a 65-byte
+/// compact recoverable signature (i.e. `r || s || recovery ID`)
+#[cfg(feature = "secp256k1")]
+pub fn to_compact_recoverable(
+    signature: &Signature<Secp256k1>,
+    recovery_id: RecoveryId,
+) -> [u8; COMPACT_RECOVERABLE_SIZE] {
+    let mut bytes = [0u8; COMPACT_RECOVERABLE_SIZE];
+    bytes[..64].copy_from_slice(&signature.to_bytes());
+    bytes[64] = recovery_id.to_byte();
+    bytes
+}
+
+/// Parse a 65-byte compact recoverable secp256k1 signature (i.e.
+/// `r || s || recovery ID`). The recovery ID must be 0 to 3, i.e. not
+/// offset as in e.g. Ethereum's `v`.
+#[cfg(feature = "secp256k1")]
+pub fn from_compact_recoverable(bytes: &[u8]) -> Result<(Signature<Secp256k1>, RecoveryId), Error> {
+    if bytes.len() != COMPACT_RECOVERABLE_SIZE {
+        return Err(Error::new());
+    }
+
+    let signature = Signature::from_slice(&bytes[..64])?;
+    let recovery_id = RecoveryId::from_byte(bytes[64]).ok_or_else(Error::new)?;
+    Ok((signature, recovery_id))
+}
+
+/// Zero-pad a big-endian scalar to the size of the curve's field
+fn pad_scalar<C>(scalar: &[u8]) -> Result<FieldBytes<C>, Error>
+where
+    C: PrimeCurve,
+{
+    let field_size = FieldBytesSize::<C>::USIZE;
+
+    let scalar = match scalar {
+        [0, rest @ ..] if rest.len() == field_size => rest,
+        _ if scalar.len() <= field_size => scalar,
+        _ => return Err(Error::new()),
+    };
+
+    let mut bytes = FieldBytes::<C>::default();
+    bytes[field_size - scalar.len()..].copy_from_slice(scalar);
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecdsa::NistP256;
+
+    /// `r` with its high bit set, and `s` with a leading zero byte
+    fn signature() -> Signature<NistP256> {
+        let mut s = [0x42; 32];
+        s[0] = 0;
+        Signature::from_scalars([0x80; 32], s).unwrap()
+    }
+
+    #[test]
+    fn der_round_trip() {
+        let der = to_der(&signature());
+
+        // r gains a leading zero, and s loses its own
+        assert_eq!(der.as_bytes()[..4], [0x30, 0x44, 0x02, 0x21]);
+        assert_eq!(der.as_bytes()[4], 0x00);
+        assert_eq!(der.as_bytes()[37..39], [0x02, 0x1f]);
+
+        assert_eq!(from_der::<NistP256>(der.as_bytes()).unwrap(), signature());
+        assert_eq!(
+            raw_to_der::<NistP256>(&to_raw(&signature()))
+                .unwrap()
+                .as_bytes(),
+            der.as_bytes()
+        );
+        assert_eq!(
+            der_to_raw::<NistP256>(der.as_bytes()).unwrap(),
+            to_raw(&signature())
+        );
+    }
+
+    #[test]
+    fn strict_parsing() {
+        let der = to_der(&signature()).as_bytes().to_vec();
+
+        // Trailing data
+        assert!(from_der::<NistP256>(&[der.as_slice(), &[0]].concat()).is_err());
+
+        // Non-minimal encoding of s (an unnecessary leading zero)
+        let mut non_minimal = der[..37].to_vec();
+        non_minimal.extend_from_slice(&[0x02, 0x20, 0x00]);
+        non_minimal.extend_from_slice(&der[39..]);
+        non_minimal[1] += 1;
+        assert!(from_der::<NistP256>(&non_minimal).is_err());
+
+        // Raw signatures must be exactly 64 bytes, with nonzero scalars
+        let raw = to_raw(&signature());
+        assert!(from_raw::<NistP256>(&raw[1..]).is_err());
+        assert!(from_raw::<NistP256>(&[0; 64]).is_err());
+        assert!(from_raw::<NistP256>(&[0xff; 64]).is_err());
+    }
+
+    #[test]
+    fn padded_scalars() {
+        let raw = to_raw(&signature());
+        let (r, s) = raw.split_at(32);
+
+        // Short s (leading zero stripped) and r with a DER-style sign byte
+        assert_eq!(
+            from_scalars::<NistP256>(&[&[0], r].concat(), &s[1..]).unwrap(),
+            signature()
+        );
+
+        assert!(from_scalars::<NistP256>(&[&[1], r].concat(), s).is_err());
+        assert!(from_scalars::<NistP256>(r, &[0; 33]).is_err());
+    }
+
+    #[cfg(feature = "secp256k1")]
+    #[test]
+    fn compact_recoverable_round_trip() {
+        let signature = Signature::<Secp256k1>::from_scalars([0x42; 32], [0x24; 32]).unwrap();
+        let recovery_id = RecoveryId::from_byte(1).unwrap();
+
+        let bytes = to_compact_recoverable(&signature, recovery_id);
+        assert_eq!(bytes[64], 1);
+        assert_eq!(
+            from_compact_recoverable(&bytes).unwrap(),
+            (signature, recovery_id)
+        );
+
+        let mut invalid = bytes;
+        invalid[64] = 27;
+        assert!(from_compact_recoverable(&invalid).is_err());
+        assert!(from_compact_recoverable(&bytes[..64]).is_err());
+    }
+}
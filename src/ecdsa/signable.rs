@@ -0,0 +1,10 @@
+//! Structured messages with a canonical encoding for signing
+
+/// Messages (e.g. transactions) which are signed over a canonical byte
+/// encoding, so callers serialize them the same way everywhere.
+///
+/// See [`Signer::sign_signable`](super::Signer::sign_signable).
+pub trait Signable {
+    /// Canonical encoding of this message to be signed
+    fn signing_bytes(&self) -> Vec<u8>;
+}
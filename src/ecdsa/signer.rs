@@ -2,20 +2,37 @@
 //!
 //! To enable secp256k1 support, build with the `secp256k1` cargo feature enabled.
 
-use super::{algorithm::CurveAlgorithm, NistP256, NistP384};
-use crate::{object, Client};
+mod builder;
+
+pub use self::builder::Builder;
+
+use super::{algorithm::CurveAlgorithm, NistP256, NistP384, Signable};
+use crate::{client, command, object, Client};
 use ecdsa::{
     elliptic_curve::{
         consts::U32,
-        generic_array::ArrayLength,
+        generic_array::{typenum::Unsigned, ArrayLength},
         point::PointCompression,
         sec1::{self, FromEncodedPoint, ToEncodedPoint},
         AffinePoint, CurveArithmetic, FieldBytesSize, PrimeCurve,
     },
     Signature, SignatureSize, VerifyingKey,
 };
-use signature::{digest::Digest, hazmat::PrehashSigner, DigestSigner, Error, KeypairRef};
-use std::ops::Add;
+use signature::{
+    digest::Digest,
+    hazmat::{PrehashSigner, PrehashVerifier},
+    DigestSigner, Error, KeypairRef,
+};
+use std::{
+    fmt::{self, Debug},
+    ops::Add,
+};
+
+#[cfg(feature = "pkcs8")]
+use ecdsa::elliptic_curve::pkcs8::spki::{AlgorithmIdentifier, SignatureAlgorithmIdentifier};
+
+#[cfg(feature = "ssh")]
+use crate::{asymmetric, ssh};
 
 #[cfg(feature = "secp256k1")]
 use super::{secp256k1::RecoveryId, Secp256k1};
@@ -39,6 +56,10 @@ where
     /// Public key associated with the private key in the YubiHSM.
     // TODO(tarcieri): remove this in favor of `verifying_key` in the next breaking release
     public_key: sec1::EncodedPoint<C>,
+
+    /// Verify signatures returned by the HSM (see
+    /// [`Builder::verify_responses`])
+    verify_responses: bool,
 }
 
 impl<C> Signer<C>
@@ -48,26 +69,138 @@ where
     FieldBytesSize<C>: sec1::ModulusSize,
 {
     /// Create a new YubiHSM-backed ECDSA signer
+    ///
+    /// Signers hold a clone of the client, so any number of them can share
+    /// one client (and its session). The key's algorithm is checked here,
+    /// and signing sends only the `SignEcdsa` command.
+    ///
+    /// Fails with `client::ErrorKind::AlgorithmUnsupported` as the error's
+    /// source if the HSM's cached device info (see
+    /// [`Client::authenticate`]) says it doesn't support the curve `C`, with
+    /// `client::ErrorKind::AlgorithmMismatch` if the key isn't on the curve
+    /// `C` (which would otherwise produce signatures that never verify),
+    /// or with `client::ErrorKind::ProtocolError` if its public key isn't a
+    /// valid point on the curve.
     pub fn create(client: Client, signing_key_id: object::Id) -> Result<Self, Error> {
-        let public_key = client
-            .get_public_key(signing_key_id)?
-            .ecdsa::<C>()
-            .ok_or_else(Error::new)?;
+        client
+            .ensure_algorithm_supported(C::asymmetric_algorithm())
+            .map_err(Error::from_source)?;
 
-        let verifying_key = VerifyingKey::<C>::from_encoded_point(&public_key)?;
+        Self::from_public_key(client, signing_key_id)
+    }
+
+    /// Build a signer with non-default settings (e.g.
+    /// [`Builder::verify_responses`])
+    pub fn builder(client: Client, signing_key_id: object::Id) -> Builder<C> {
+        Builder::new(client, signing_key_id)
+    }
+
+    /// Create signers for several keys sharing the given client, checking
+    /// the HSM's cached device info supports the curve `C` once for all of
+    /// them.
+    ///
+    /// Fails like [`Signer::create`] if any of the keys can't be used.
+    pub fn create_many(
+        client: &Client,
+        signing_key_ids: &[object::Id],
+    ) -> Result<Vec<Self>, Error> {
+        client
+            .ensure_algorithm_supported(C::asymmetric_algorithm())
+            .map_err(Error::from_source)?;
+
+        signing_key_ids
+            .iter()
+            .map(|&signing_key_id| Self::from_public_key(client.clone(), signing_key_id))
+            .collect()
+    }
+
+    /// Create a signer from the key's public key (which must be on the
+    /// curve `C`)
+    fn from_public_key(client: Client, signing_key_id: object::Id) -> Result<Self, Error> {
+        let public_key = client.get_public_key(signing_key_id)?;
+        let expected = C::asymmetric_algorithm();
+
+        if public_key.algorithm != expected {
+            return Err(client::Error::from(format_err!(
+                client::ErrorKind::AlgorithmMismatch {
+                    expected,
+                    actual: public_key.algorithm
+                },
+                "key 0x{:04x} is a {:?} key, not {:?}",
+                signing_key_id,
+                public_key.algorithm,
+                expected
+            ))
+            .into());
+        }
+
+        let invalid_point = || -> Error {
+            client::Error::from(format_err!(
+                client::ErrorKind::ProtocolError,
+                "public key of key 0x{:04x} isn't a valid {:?} point",
+                signing_key_id,
+                expected
+            ))
+            .into()
+        };
+
+        let public_key = public_key.ecdsa::<C>().ok_or_else(invalid_point)?;
+        let verifying_key =
+            VerifyingKey::<C>::from_encoded_point(&public_key).map_err(|_| invalid_point())?;
 
         Ok(Self {
             client,
             signing_key_id,
             verifying_key,
             public_key,
+            verify_responses: false,
         })
     }
 
     /// Get the public key for the YubiHSM-backed private key.
+    ///
+    /// This is compressed if the curve's [`PointCompression`] says so (e.g.
+    /// for secp256k1), and uncompressed otherwise.
     pub fn public_key(&self) -> &sec1::EncodedPoint<C> {
         &self.public_key
     }
+
+    /// Get the public key as a compressed SEC1 point (e.g. the 33-byte
+    /// encoding used by Bitcoin), regardless of the curve's default
+    pub fn public_key_compressed(&self) -> sec1::EncodedPoint<C> {
+        self.verifying_key.to_encoded_point(true)
+    }
+
+    /// Get the SEC1 encoding of the public key: compressed (`0x02`/`0x03`
+    /// followed by the X coordinate) or uncompressed (`0x04` followed by
+    /// the X and Y coordinates)
+    pub fn public_key_bytes(&self, compress: bool) -> Vec<u8> {
+        self.verifying_key
+            .to_encoded_point(compress)
+            .as_bytes()
+            .into()
+    }
+
+    /// Get the public key in OpenSSH `authorized_keys` format, or `None` if
+    /// OpenSSH doesn't support this curve (e.g. secp256k1).
+    #[cfg(feature = "ssh")]
+    pub fn ssh_public_key(&self, comment: &str) -> Option<String> {
+        let public_key = asymmetric::PublicKey {
+            algorithm: C::asymmetric_algorithm(),
+            bytes: self.verifying_key.to_encoded_point(false).as_bytes()[1..].into(),
+        };
+
+        ssh::public_key_openssh(&public_key, comment)
+    }
+
+    /// Sign the canonical encoding of a structured message (i.e. its
+    /// [`Signable::signing_bytes`])
+    pub fn sign_signable<S>(&self, msg: &impl Signable) -> Result<S, Error>
+    where
+        Self: signature::Signer<S>,
+    {
+        signature::Signer::try_sign(self, &msg.signing_bytes())
+    }
 }
 
 impl<C> Signer<C>
@@ -77,12 +210,107 @@ where
     SignatureSize<C>: ArrayLength<u8>,
     ecdsa::der::MaxSize<C>: ArrayLength<u8>,
     <FieldBytesSize<C> as Add>::Output: Add<ecdsa::der::MaxOverhead> + ArrayLength<u8>,
+    VerifyingKey<C>: PrehashVerifier<Signature<C>>,
 {
+    /// Compute an ECDSA signature of the given message using the digest
+    /// algorithm `D` instead of the curve's default (e.g. SHA-384 or SHA-512
+    /// with NIST P-256).
+    ///
+    /// Digests must be 32 to 64 bytes long. To match the way ECDSA verifiers
+    /// convert digests to integers, the digest is normalized to the size of
+    /// the curve's field before it's sent to the HSM:
+    ///
+    /// - digests longer than the field size are truncated to their leftmost
+    ///   bytes (e.g. SHA-512 with P-256 uses the first 32 bytes)
+    /// - digests shorter than the field size are left-padded with zeros
+    ///   (e.g. SHA-256 with P-384)
+    ///
+    /// The HSM therefore always receives a field-sized digest, so signatures
+    /// don't depend on how a given firmware version handles other lengths.
+    pub fn sign_with_digest<D>(&self, msg: &[u8]) -> Result<Signature<C>, Error>
+    where
+        D: Digest,
+        Self: PrehashSigner<Signature<C>>,
+    {
+        let digest = D::digest(msg);
+        let field_size = FieldBytesSize::<C>::USIZE;
+
+        if !(32..=64).contains(&digest.len()) {
+            return Err(Error::new());
+        }
+
+        if digest.len() >= field_size {
+            self.sign_prehash(&digest[..field_size])
+        } else {
+            let mut prehash = vec![0u8; field_size];
+            prehash[field_size - digest.len()..].copy_from_slice(&digest);
+            self.sign_prehash(&prehash)
+        }
+    }
+
+    /// Sign the given prehash with the HSM, checking the signature it
+    /// returns is well-formed (i.e. strict DER, with `r` and `s` nonzero and
+    /// less than the curve's order) and, if `verify_responses` is enabled,
+    /// valid.
+    ///
+    /// Sending the command is retried according to the client's
+    /// [`RetryPolicy`](client::RetryPolicy) if it fails with a transient
+    /// error. Invalid signatures are never retried.
+    ///
+    /// Fails with `client::ErrorKind::InvalidSignature` as the error's
+    /// source if it isn't.
     fn sign_prehash_ecdsa(&self, prehash: &[u8]) -> Result<Signature<C>, Error> {
-        self.client
-            .sign_ecdsa_prehash_raw(self.signing_key_id, prehash)
-            .map_err(Error::from_source)
-            .and_then(|der| Signature::from_der(&der))
+        let der = self
+            .client
+            .retry_policy()
+            .retry(command::Code::SignEcdsa, || {
+                self.client
+                    .sign_ecdsa_prehash_raw(self.signing_key_id, prehash)
+            })
+            .map_err(Error::from_source)?;
+
+        let signature = Signature::from_der(&der).map_err(|e| {
+            Error::from_source(client::Error::from(
+                client::ErrorKind::InvalidSignature.context(e),
+            ))
+        })?;
+
+        if self.verify_responses {
+            self.verifying_key
+                .verify_prehash(prehash, &signature)
+                .map_err(|_| {
+                    Error::from_source(client::Error::from(format_err!(
+                        client::ErrorKind::InvalidSignature,
+                        "HSM returned a signature which doesn't verify under key {}",
+                        self.signing_key_id
+                    )))
+                })?;
+        }
+
+        Ok(signature)
+    }
+}
+
+impl<C> Debug for Signer<C>
+where
+    C: CurveAlgorithm + CurveArithmetic + PointCompression + PrimeCurve,
+    FieldBytesSize<C>: sec1::ModulusSize,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Only show the public parts of the signer (e.g. not the client's
+        // credentials)
+        write!(
+            f,
+            "EcdsaSigner {{ key_id: {}, algorithm: {:?}, public_key: ",
+            self.signing_key_id,
+            C::asymmetric_algorithm()
+        )?;
+
+        for byte in self.public_key.as_bytes() {
+            write!(f, "{byte:02x}")?;
+        }
+
+        write!(f, " }}")
     }
 }
 
@@ -108,6 +336,22 @@ where
     }
 }
 
+/// Allows the signer to be used with X.509 certificate and CSR builders,
+/// identifying signatures as ECDSA with the curve's default digest
+/// (e.g. `ecdsa-with-SHA256` for NIST P-256).
+#[cfg(feature = "pkcs8")]
+impl<C> SignatureAlgorithmIdentifier for Signer<C>
+where
+    C: CurveAlgorithm + CurveArithmetic + PointCompression + PrimeCurve,
+    FieldBytesSize<C>: sec1::ModulusSize,
+    VerifyingKey<C>: SignatureAlgorithmIdentifier,
+{
+    type Params = <VerifyingKey<C> as SignatureAlgorithmIdentifier>::Params;
+
+    const SIGNATURE_ALGORITHM_IDENTIFIER: AlgorithmIdentifier<Self::Params> =
+        VerifyingKey::<C>::SIGNATURE_ALGORITHM_IDENTIFIER;
+}
+
 impl<C> KeypairRef for Signer<C>
 where
     C: CurveAlgorithm + CurveArithmetic + PointCompression + PrimeCurve,
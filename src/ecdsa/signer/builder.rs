@@ -0,0 +1,62 @@
+//! Builder for ECDSA `Signer`s with non-default settings
+
+use super::Signer;
+use crate::{ecdsa::algorithm::CurveAlgorithm, object, Client};
+use ecdsa::elliptic_curve::{
+    point::PointCompression,
+    sec1::{self, FromEncodedPoint, ToEncodedPoint},
+    AffinePoint, CurveArithmetic, FieldBytesSize, PrimeCurve,
+};
+use signature::Error;
+use std::marker::PhantomData;
+
+/// Builder for ECDSA [`Signer`]s with non-default settings
+pub struct Builder<C> {
+    /// Client used to sign
+    client: Client,
+
+    /// ID of the ECDSA key to sign with
+    signing_key_id: object::Id,
+
+    /// Verify signatures returned by the HSM against the public key
+    verify_responses: bool,
+
+    /// Curve of the signing key
+    curve: PhantomData<C>,
+}
+
+impl<C> Builder<C>
+where
+    C: CurveAlgorithm + CurveArithmetic + PointCompression + PrimeCurve,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: sec1::ModulusSize,
+{
+    /// Create a new builder for a signer using the given client and key
+    pub fn new(client: Client, signing_key_id: object::Id) -> Self {
+        Self {
+            client,
+            signing_key_id,
+            verify_responses: false,
+            curve: PhantomData,
+        }
+    }
+
+    /// Verify each signature returned by the HSM against the signer's
+    /// public key before returning it (default: disabled).
+    ///
+    /// Signatures are always checked to be well-formed DER with `r` and `s`
+    /// in range. Verifying them as well catches signatures which are
+    /// well-formed but wrong (e.g. because of faults in the HSM or on the
+    /// way from it), at the cost of a verification per signature.
+    pub fn verify_responses(mut self, verify: bool) -> Self {
+        self.verify_responses = verify;
+        self
+    }
+
+    /// Create the signer (failing like [`Signer::create`])
+    pub fn create(self) -> Result<Signer<C>, Error> {
+        let mut signer = Signer::create(self.client, self.signing_key_id)?;
+        signer.verify_responses = self.verify_responses;
+        Ok(signer)
+    }
+}
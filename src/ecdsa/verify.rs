@@ -0,0 +1,75 @@
+//! Verifying ECDSA signatures produced by the HSM (without a `Signer`)
+
+use super::algorithm::CurveAlgorithm;
+use ecdsa::{
+    der,
+    elliptic_curve::{
+        generic_array::ArrayLength,
+        sec1::{self, FromEncodedPoint, ToEncodedPoint},
+        AffinePoint, CurveArithmetic, FieldBytesSize, PrimeCurve,
+    },
+    Signature, SignatureSize, VerifyingKey,
+};
+use signature::{Error, Verifier};
+use std::ops::Add;
+
+/// Verify a signature over `message` using the given public key, where the
+/// signature is raw `r || s` bytes.
+///
+/// The public key is an uncompressed point, either as returned by the HSM
+/// (i.e. `x || y`, see [`asymmetric::PublicKey`]) or SEC1-encoded (i.e.
+/// prefixed with `0x04`).
+///
+/// [`asymmetric::PublicKey`]: crate::asymmetric::PublicKey
+pub fn verify_external<C>(
+    public_key_bytes: &[u8],
+    message: &[u8],
+    signature_bytes: &[u8],
+) -> Result<(), Error>
+where
+    C: CurveAlgorithm + CurveArithmetic + PrimeCurve,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: sec1::ModulusSize,
+    SignatureSize<C>: ArrayLength<u8>,
+    VerifyingKey<C>: Verifier<Signature<C>>,
+{
+    let signature = Signature::<C>::from_slice(signature_bytes)?;
+    verifying_key::<C>(public_key_bytes)?.verify(message, &signature)
+}
+
+/// Verify a signature over `message` using the given public key (as in
+/// [`verify_external`]), where the signature is ASN.1 DER-encoded.
+pub fn verify_external_der<C>(
+    public_key_bytes: &[u8],
+    message: &[u8],
+    signature_der: &[u8],
+) -> Result<(), Error>
+where
+    C: CurveAlgorithm + CurveArithmetic + PrimeCurve,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: sec1::ModulusSize,
+    der::MaxSize<C>: ArrayLength<u8>,
+    <FieldBytesSize<C> as Add>::Output: Add<der::MaxOverhead> + ArrayLength<u8>,
+    VerifyingKey<C>: Verifier<der::Signature<C>>,
+{
+    let signature = der::Signature::<C>::from_bytes(signature_der)?;
+    verifying_key::<C>(public_key_bytes)?.verify(message, &signature)
+}
+
+/// Parse an uncompressed public key, with or without the SEC1 `0x04` tag
+fn verifying_key<C>(public_key_bytes: &[u8]) -> Result<VerifyingKey<C>, Error>
+where
+    C: CurveArithmetic + PrimeCurve,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: sec1::ModulusSize,
+{
+    // Untagged points (as returned by the HSM) are an even number of bytes
+    let point = if public_key_bytes.len() % 2 == 0 {
+        sec1::EncodedPoint::<C>::from_bytes([&[0x04], public_key_bytes].concat())
+    } else {
+        sec1::EncodedPoint::<C>::from_bytes(public_key_bytes)
+    }
+    .map_err(|_| Error::new())?;
+
+    VerifyingKey::from_encoded_point(&point)
+}
@@ -23,6 +23,13 @@ pub(crate) struct SignEddsaCommand {
 
 impl Command for SignEddsaCommand {
     type ResponseType = SignEddsaResponse;
+
+    fn object(&self) -> Option<object::Handle> {
+        Some(object::Handle::new(
+            self.key_id,
+            object::Type::AsymmetricKey,
+        ))
+    }
 }
 
 /// Ed25519 signature (64-bytes) response
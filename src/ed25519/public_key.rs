@@ -2,6 +2,7 @@
 
 // TODO(tarcieri): move this upstream into the `ed25519` crate
 
+use crate::client::{Error, ErrorKind};
 use std::fmt::{self, Debug};
 
 /// Size of an Ed25519 public key in bytes (256-bits)
@@ -31,6 +32,28 @@ impl PublicKey {
         }
     }
 
+    /// Parse an Ed25519 public key as returned by the HSM (i.e. the 32-byte
+    /// compressed Edwards-y encoding), checking that it's the correct length
+    /// and (with the `ed25519-dalek` feature) decodes to a valid curve point.
+    ///
+    /// Fails with `ErrorKind::ProtocolError` if it doesn't.
+    pub fn from_yubihsm_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let public_key = Self::from_bytes(bytes).ok_or_else(|| {
+            format_err!(
+                ErrorKind::ProtocolError,
+                "invalid Ed25519 public key length (expected {}, got {})",
+                PUBLIC_KEY_SIZE,
+                bytes.len()
+            )
+        })?;
+
+        #[cfg(feature = "ed25519-dalek")]
+        ed25519_dalek::VerifyingKey::from_bytes(public_key.as_bytes())
+            .map_err(|e| ErrorKind::ProtocolError.context(e))?;
+
+        Ok(public_key)
+    }
+
     /// Obtain public key as a byte array reference
     #[inline]
     pub fn as_bytes(&self) -> &[u8; PUBLIC_KEY_SIZE] {
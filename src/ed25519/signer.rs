@@ -3,8 +3,16 @@
 //! To use this provider, first establish a session with the `YubiHSM 2`, then
 //! call the appropriate signer methods to obtain signers.
 
-use crate::{ed25519::PublicKey, object, Client};
+use crate::{
+    asymmetric, client,
+    ed25519::{PublicKey, Signature},
+    object, Client,
+};
 use signature::Error;
+use std::fmt::{self, Debug};
+
+#[cfg(feature = "ssh")]
+use crate::ssh;
 
 /// Ed25519 signature provider for yubihsm-client
 pub struct Signer {
@@ -20,11 +28,34 @@ pub struct Signer {
 
 impl Signer {
     /// Create a new YubiHSM-backed Ed25519 signer
+    ///
+    /// Fails with `client::ErrorKind::AlgorithmUnsupported` as the error's
+    /// source if the HSM's cached device info (see
+    /// [`Client::authenticate`]) says it doesn't support Ed25519, with
+    /// `client::ErrorKind::AlgorithmMismatch` if the key isn't an Ed25519
+    /// key, or with `client::ErrorKind::ProtocolError` if its public key
+    /// isn't a valid Ed25519 point.
     pub fn create(client: Client, signing_key_id: object::Id) -> Result<Self, Error> {
-        let public_key = client
-            .get_public_key(signing_key_id)?
-            .ed25519()
-            .ok_or_else(Error::new)?;
+        client
+            .ensure_algorithm_supported(asymmetric::Algorithm::Ed25519)
+            .map_err(Error::from_source)?;
+
+        let public_key = client.get_public_key(signing_key_id)?;
+
+        if public_key.algorithm != asymmetric::Algorithm::Ed25519 {
+            return Err(client::Error::from(format_err!(
+                client::ErrorKind::AlgorithmMismatch {
+                    expected: asymmetric::Algorithm::Ed25519,
+                    actual: public_key.algorithm
+                },
+                "key 0x{:04x} is a {:?} key, not Ed25519",
+                signing_key_id,
+                public_key.algorithm
+            ))
+            .into());
+        }
+
+        let public_key = PublicKey::from_yubihsm_bytes(&public_key.bytes)?;
 
         Ok(Self {
             client,
@@ -37,6 +68,42 @@ impl Signer {
     pub fn public_key(&self) -> &PublicKey {
         &self.public_key
     }
+
+    /// Compute an Ed25519 signature over the given message, returning the raw
+    /// 64-byte `R || s` encoding
+    pub fn sign_raw(&self, msg: &[u8]) -> Result<[u8; Signature::BYTE_SIZE], Error> {
+        signature::Signer::<Signature>::try_sign(self, msg).map(|sig| sig.to_bytes())
+    }
+
+    /// Get the public key in OpenSSH `authorized_keys` format
+    #[cfg(feature = "ssh")]
+    pub fn ssh_public_key(&self, comment: &str) -> String {
+        let public_key = asymmetric::PublicKey {
+            algorithm: asymmetric::Algorithm::Ed25519,
+            bytes: self.public_key.as_bytes().to_vec(),
+        };
+
+        ssh::public_key_openssh(&public_key, comment).expect("OpenSSH supports Ed25519")
+    }
+}
+
+impl Debug for Signer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Only show the public parts of the signer (e.g. not the client's
+        // credentials)
+        write!(
+            f,
+            "Ed25519Signer {{ key_id: {}, algorithm: {:?}, public_key: ",
+            self.signing_key_id,
+            asymmetric::Algorithm::Ed25519
+        )?;
+
+        for byte in self.public_key.as_bytes() {
+            write!(f, "{byte:02x}")?;
+        }
+
+        write!(f, " }}")
+    }
 }
 
 impl From<&Signer> for PublicKey {
@@ -45,8 +112,19 @@ impl From<&Signer> for PublicKey {
     }
 }
 
-impl signature::Signer<ed25519::Signature> for Signer {
-    fn try_sign(&self, msg: &[u8]) -> Result<ed25519::Signature, Error> {
+impl signature::Signer<Signature> for Signer {
+    fn try_sign(&self, msg: &[u8]) -> Result<Signature, Error> {
         Ok(self.client.sign_ed25519(self.signing_key_id, msg)?)
     }
 }
+
+/// Only available with the `ed25519-dalek` feature
+#[cfg(feature = "ed25519-dalek")]
+impl signature::Verifier<Signature> for Signer {
+    /// Verify a signature using the cached public key (in software, without
+    /// a round trip to the HSM)
+    fn verify(&self, msg: &[u8], signature: &Signature) -> Result<(), Error> {
+        ed25519_dalek::VerifyingKey::from_bytes(self.public_key.as_bytes())?
+            .verify_strict(msg, signature)
+    }
+}
@@ -45,8 +45,9 @@ macro_rules! ensure {
 /// as an `std::error::Error::source`.
 pub type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
 
-/// Error context: stores an error source (as a [`BoxError`]) and backtrace
-/// along with an error `Kind`.
+/// Error context: stores an error source (as a [`BoxError`])
+/// along with an error `Kind`, and optionally extended details about the
+/// failure which are included by its alternate (`{:#}`) formatting.
 #[derive(Debug)]
 pub struct Context<Kind>
 where
@@ -58,9 +59,8 @@ where
     /// Source of the error
     source: Option<BoxError>,
 
-    /// Backtrace where error occurred
-    #[cfg(feature = "backtrace")]
-    backtrace: Option<Backtrace>,
+    /// Extended (multi-line) details about the failure
+    detail: Option<String>,
 }
 
 impl<Kind> Context<Kind>
@@ -72,8 +72,7 @@ where
         Context {
             kind,
             source,
-            #[cfg(feature = "backtrace")]
-            backtrace: Some(Backtrace::new()),
+            detail: None,
         }
     }
 
@@ -81,12 +80,6 @@ where
     pub fn kind(&self) -> &Kind {
         &self.kind
     }
-
-    /// Get the backtrace associated with this error (if available)
-    #[cfg(feature = "backtrace")]
-    pub fn backtrace(&self) -> Option<&Backtrace> {
-        self.backtrace.as_ref()
-    }
 }
 
 impl<Kind> Display for Context<Kind>
@@ -100,6 +93,12 @@ where
             write!(f, ": {source}")?;
         }
 
+        if f.alternate() {
+            if let Some(ref detail) = self.detail {
+                write!(f, "\n{detail}")?;
+            }
+        }
+
         Ok(())
     }
 }
@@ -165,6 +164,22 @@ pub struct Error<K>(Box<Context<K>>)
 where
     K: Clone + Debug + Display + Eq + PartialEq + Into<BoxError>;
 
+impl<K> Error<K>
+where
+    K: Clone + Debug + Display + Eq + PartialEq + Into<BoxError>,
+{
+    /// Consume this error, returning its source (if any)
+    pub(crate) fn into_source(self) -> Option<BoxError> {
+        self.0.source
+    }
+
+    /// Attach extended details about the failure
+    pub(crate) fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.0.detail = Some(detail.into());
+        self
+    }
+}
+
 impl<K> Deref for Error<K>
 where
     K: Clone + Debug + Display + Eq + PartialEq + Into<BoxError>,
@@ -181,7 +196,7 @@ where
     K: Clone + Debug + Display + Eq + PartialEq + Into<BoxError>,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0)
+        Display::fmt(&self.0, f)
     }
 }
 
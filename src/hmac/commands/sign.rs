@@ -21,6 +21,10 @@ pub(crate) struct SignHmacCommand {
 
 impl Command for SignHmacCommand {
     type ResponseType = SignHmacResponse;
+
+    fn object(&self) -> Option<object::Handle> {
+        Some(object::Handle::new(self.key_id, object::Type::HmacKey))
+    }
 }
 
 /// Sign HMAC response
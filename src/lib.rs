@@ -71,11 +71,12 @@ pub mod ecdsa;
 pub mod ed25519;
 pub mod hmac;
 #[cfg(feature = "mockhsm")]
-pub(crate) mod mockhsm;
+pub mod mockhsm;
 pub mod object;
 pub mod opaque;
 pub mod otp;
 pub mod response;
+pub mod rng;
 pub mod rsa;
 pub mod session;
 #[cfg(feature = "setup")]
@@ -87,6 +88,8 @@ pub mod wrap;
 
 #[cfg(feature = "http")]
 pub use crate::connector::HttpConfig;
+#[cfg(all(feature = "http", unix))]
+pub use crate::connector::UnixConfig;
 #[cfg(feature = "usb")]
 pub use crate::connector::UsbConfig;
 
@@ -29,6 +29,13 @@ macro_rules! impl_algorithm_serializers {
                     fn visit_u8<E: de::Error>(self, value: u8) -> Result<$alg, E> {
                         $alg::from_u8(value).or_else(|e| Err(E::custom(format!("{}", e))))
                     }
+
+                    // Self-describing formats (e.g. JSON) deserialize integers as `u64`
+                    fn visit_u64<E: de::Error>(self, value: u64) -> Result<$alg, E> {
+                        let byte = u8::try_from(value)
+                            .map_err(|_| E::custom(format!("invalid algorithm tag: {}", value)))?;
+                        self.visit_u8(byte)
+                    }
                 }
 
                 deserializer.deserialize_u8(AlgorithmVisitor)
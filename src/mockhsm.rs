@@ -6,21 +6,26 @@ compile_error!("MockHsm is not intended for use in release builds");
 
 use std::sync::{Arc, Mutex};
 
+mod attestation;
 mod audit;
+mod builder;
 mod command;
 mod connection;
-mod digest;
 mod error;
+mod nonce;
 mod object;
 mod session;
 mod state;
 
 use self::state::State;
 pub use self::{
+    builder::Builder,
     connection::MockConnection,
     error::{Error, ErrorKind},
+    object::{ObjectsSnapshot, SnapshotObject},
 };
 use crate::connector::{self, Connectable, Connection};
+pub use crate::device::FirmwareVersion;
 
 /// Mock serial number for the MockHsm
 pub const MOCK_SERIAL_NUMBER: &str = "0123456789";
@@ -38,10 +43,98 @@ pub const MOCK_SERIAL_NUMBER: &str = "0123456789";
 pub struct MockHsm(Arc<Mutex<State>>);
 
 impl MockHsm {
-    /// Create a new MockHsm
+    /// Create a new MockHsm with the default settings (see [`Builder`])
     pub fn new() -> Self {
-        MockHsm(Arc::new(Mutex::new(State::new())))
+        Builder::new().build()
     }
+
+    /// IDs of the sessions currently open with this MockHsm
+    pub fn session_ids(&self) -> Vec<crate::session::Id> {
+        self.0.lock().unwrap().session_ids()
+    }
+
+    /// Close the session with the given ID as if it had timed out (e.g.
+    /// because the client which opened it crashed), returning whether it
+    /// was open.
+    ///
+    /// The YubiHSM 2 has no command for closing sessions other than the
+    /// current one, so this is only available on the MockHsm.
+    pub fn expire_session(&self, id: crate::session::Id) -> bool {
+        self.0.lock().unwrap().expire_session(id)
+    }
+
+    /// Corrupt the MAC of the next encrypted command sent to this MockHsm,
+    /// as if it had been damaged in transit, to simulate protocol errors.
+    ///
+    /// The MockHsm rejects the command and closes its session.
+    pub fn corrupt_next_command(&self) {
+        self.0.lock().unwrap().corrupt_next_command = true;
+    }
+
+    /// Fail the next `n` encrypted commands sent to this MockHsm with
+    /// `connector::ErrorKind::ConnectionFailed` without processing them, as
+    /// if the connection to the HSM dropped, to simulate transient errors
+    pub fn fail_next_commands(&self, n: usize) {
+        self.0.lock().unwrap().fail_next_commands = n;
+    }
+
+    /// Take a snapshot of the objects stored in this MockHsm, e.g. to
+    /// save them with [`ObjectsSnapshot::save`] so later test runs can
+    /// [`MockHsm::restore_objects`] instead of regenerating keys
+    pub fn snapshot_objects(&self) -> ObjectsSnapshot {
+        self.0.lock().unwrap().objects.to_snapshot()
+    }
+
+    /// Replace the objects stored in this MockHsm with the ones in the
+    /// given snapshot, failing with `ErrorKind::InvalidData` (leaving the
+    /// stored objects unchanged) if it's malformed
+    pub fn restore_objects(&self, snapshot: &ObjectsSnapshot) -> Result<(), Error> {
+        let objects = object::Objects::from_snapshot(snapshot)?;
+        let mut state = self.0.lock().unwrap();
+        state.objects = objects;
+        state.ecdsa_nonces = Default::default();
+        Ok(())
+    }
+
+    /// Corrupt the next ECDSA signature returned by this MockHsm in the
+    /// given way, to simulate faults in the HSM
+    pub fn corrupt_next_signature(&self, fault: SignatureFault) {
+        self.0.lock().unwrap().signature_fault = Some(fault);
+    }
+
+    /// Corrupt the key stored by the next authentication key change (i.e.
+    /// flip a bit of it), as if the HSM accepted the command but stored
+    /// the wrong key, so sessions can't authenticate with the new one
+    pub fn corrupt_next_key_change(&self) {
+        self.0.lock().unwrap().corrupt_next_key_change = true;
+    }
+
+    /// Sign ECDSA signatures with the given big endian nonce `k` (which
+    /// must be nonzero and less than the curve's order) instead of a random
+    /// one until this is called with `None`, to simulate a faulty RNG and
+    /// exercise the check behind [`MockHsm::ecdsa_nonce_reuses`]
+    pub fn fix_ecdsa_nonce(&self, k: Option<[u8; 32]>) {
+        self.0.lock().unwrap().fixed_ecdsa_nonce = k;
+    }
+
+    /// Number of times this MockHsm has reused an ECDSA nonce to sign a
+    /// different message with the same key (which would leak the key), as
+    /// a check of its own signing implementation. This should always be 0
+    /// unless [`MockHsm::fix_ecdsa_nonce`] is used.
+    pub fn ecdsa_nonce_reuses(&self) -> usize {
+        self.0.lock().unwrap().ecdsa_nonces.reuses()
+    }
+}
+
+/// Ways [`MockHsm::corrupt_next_signature`] can corrupt signatures
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SignatureFault {
+    /// Flip a bit of `s`, leaving a well-formed signature which doesn't
+    /// verify
+    Invalid,
+
+    /// Replace `s` with zero, which is out of range
+    OutOfRange,
 }
 
 impl Connectable for MockHsm {
@@ -54,6 +147,10 @@ impl Connectable for MockHsm {
     fn connect(&self) -> Result<Box<dyn Connection>, connector::Error> {
         Ok(Box::new(MockConnection::new(self)))
     }
+
+    fn max_message_size(&self) -> usize {
+        self.0.lock().unwrap().config.max_message_size
+    }
 }
 
 impl Default for MockHsm {
@@ -0,0 +1,209 @@
+//! Synthetic X.509 attestation certificates issued by the `MockHsm`
+
+use super::{builder::Config, object::Object};
+use crate::{
+    asymmetric::{self, PublicKey},
+    attestation::x509::{self, context_specific},
+};
+use der::{
+    asn1::{AnyRef, BitStringRef, ObjectIdentifier, OctetStringRef, Utf8StringRef},
+    Encode, Tag, TagNumber,
+};
+use p256::ecdsa::{Signature, SigningKey};
+use rand_core::CryptoRngCore;
+use signature::Signer;
+
+/// `commonName` attribute type (X.520)
+const COMMON_NAME: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.4.3");
+
+/// Subject of the device attestation certificate
+const DEVICE_ATTESTATION_SUBJECT: &str = "YubiHSM Attestation (MockHsm)";
+
+/// Device attestation key and its (self-signed) certificate
+pub(crate) struct DeviceAttestation {
+    /// Device attestation key
+    signing_key: SigningKey,
+
+    /// DER-encoded device attestation certificate
+    certificate: Vec<u8>,
+}
+
+impl DeviceAttestation {
+    /// Generate a random device attestation key and certificate
+    pub fn generate(rng: &mut impl CryptoRngCore) -> Self {
+        let signing_key = SigningKey::random(rng);
+        let name = name(DEVICE_ATTESTATION_SUBJECT);
+        let public_key = signing_key.verifying_key().to_encoded_point(false);
+        let spki = PublicKey {
+            algorithm: asymmetric::Algorithm::EcP256,
+            bytes: public_key.as_bytes()[1..].into(),
+        }
+        .to_spki_der()
+        .unwrap();
+        let certificate = issue(&signing_key, &name, &name, &spki, None, rng);
+
+        Self {
+            signing_key,
+            certificate,
+        }
+    }
+
+    /// Get the DER-encoded device attestation certificate
+    pub fn certificate(&self) -> &[u8] {
+        &self.certificate
+    }
+
+    /// Attest to the given key using the device attestation key
+    pub fn attest(
+        &self,
+        subject: &Object,
+        config: &Config,
+        rng: &mut impl CryptoRngCore,
+    ) -> Option<Vec<u8>> {
+        let issuer = x509::Certificate::parse(&self.certificate).unwrap().subject;
+        attest(subject, &self.signing_key, issuer, config, rng)
+    }
+}
+
+impl std::fmt::Debug for DeviceAttestation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeviceAttestation").finish_non_exhaustive()
+    }
+}
+
+/// Issue an attestation certificate for `subject`, signed by `signing_key`
+/// on behalf of the given (DER-encoded) `issuer` name, and describing the
+/// device as configured. Returns `None` if the subject isn't an asymmetric
+/// key we support.
+pub(crate) fn attest(
+    subject: &Object,
+    signing_key: &SigningKey,
+    issuer: &[u8],
+    config: &Config,
+    rng: &mut impl CryptoRngCore,
+) -> Option<Vec<u8>> {
+    let spki = PublicKey {
+        algorithm: subject.algorithm().asymmetric()?,
+        bytes: subject.payload.public_key_bytes()?,
+    }
+    .to_spki_der()?;
+
+    let info = subject.info();
+    let serial_number = u32::from(config.serial_number);
+    let firmware_version = config.firmware_version.to_bytes();
+
+    let extensions = [
+        extension(
+            x509::YUBICO_FIRMWARE_VERSION,
+            OctetStringRef::new(&firmware_version)
+                .unwrap()
+                .to_der()
+                .unwrap(),
+        ),
+        extension(x509::YUBICO_SERIAL_NUMBER, serial_number.to_der().unwrap()),
+        extension(x509::YUBICO_ORIGIN, bit_string(&[info.origin.to_u8()])),
+        extension(
+            x509::YUBICO_DOMAINS,
+            bit_string(&info.domains.bits().to_be_bytes()),
+        ),
+        extension(
+            x509::YUBICO_CAPABILITIES,
+            bit_string(&info.capabilities.bits().to_be_bytes()),
+        ),
+        extension(x509::YUBICO_OBJECT_ID, info.object_id.to_der().unwrap()),
+        extension(
+            x509::YUBICO_LABEL,
+            Utf8StringRef::new(info.label.try_as_str().unwrap_or_default())
+                .unwrap()
+                .to_der()
+                .unwrap(),
+        ),
+    ];
+
+    let subject_name = name(&format!("YubiHSM Attestation id:0x{:04x}", info.object_id));
+    Some(issue(
+        signing_key,
+        issuer,
+        &subject_name,
+        &spki,
+        Some(&extensions),
+        rng,
+    ))
+}
+
+/// Build and sign a certificate with ECDSA/P-256
+fn issue(
+    signing_key: &SigningKey,
+    issuer: &[u8],
+    subject: &[u8],
+    spki: &[u8],
+    extensions: Option<&[Vec<u8>]>,
+    rng: &mut impl CryptoRngCore,
+) -> Vec<u8> {
+    let signature_algorithm = sequence(&[x509::ECDSA_WITH_SHA256.to_der().unwrap()]);
+
+    let mut serial_number = [0u8; 16];
+    rng.fill_bytes(&mut serial_number);
+    serial_number[0] &= 0x7f;
+
+    let validity = sequence(&[
+        tlv(Tag::UtcTime, b"230101000000Z"),
+        tlv(Tag::GeneralizedTime, b"99991231235959Z"),
+    ]);
+
+    let mut tbs_fields = vec![
+        tlv(context_specific(TagNumber::N0), &2u8.to_der().unwrap()),
+        tlv(Tag::Integer, &serial_number),
+        signature_algorithm.clone(),
+        issuer.to_vec(),
+        validity,
+        subject.to_vec(),
+        spki.to_vec(),
+    ];
+
+    if let Some(extensions) = extensions {
+        tbs_fields.push(tlv(context_specific(TagNumber::N3), &sequence(extensions)));
+    }
+
+    let tbs_certificate = sequence(&tbs_fields);
+    let signature: Signature = signing_key.sign(&tbs_certificate);
+
+    sequence(&[
+        tbs_certificate,
+        signature_algorithm,
+        bit_string(signature.to_der().as_bytes()),
+    ])
+}
+
+/// Encode a `Name` consisting of a single `commonName`
+fn name(common_name: &str) -> Vec<u8> {
+    let attribute = sequence(&[
+        COMMON_NAME.to_der().unwrap(),
+        Utf8StringRef::new(common_name).unwrap().to_der().unwrap(),
+    ]);
+
+    sequence(&[tlv(Tag::Set, &attribute)])
+}
+
+/// Encode a non-critical `Extension` with the given DER-encoded value
+fn extension(id: ObjectIdentifier, value: Vec<u8>) -> Vec<u8> {
+    sequence(&[
+        id.to_der().unwrap(),
+        OctetStringRef::new(&value).unwrap().to_der().unwrap(),
+    ])
+}
+
+/// Encode a `BIT STRING` with no unused bits
+fn bit_string(bytes: &[u8]) -> Vec<u8> {
+    BitStringRef::from_bytes(bytes).unwrap().to_der().unwrap()
+}
+
+/// Encode a `SEQUENCE` of already DER-encoded values
+fn sequence(values: &[Vec<u8>]) -> Vec<u8> {
+    tlv(Tag::Sequence, &values.concat())
+}
+
+/// Encode an arbitrary tag-length-value
+fn tlv(tag: Tag, value: &[u8]) -> Vec<u8> {
+    AnyRef::new(tag, value).unwrap().to_der().unwrap()
+}
@@ -1,9 +1,23 @@
 //! (Partial) support for audit logging within the MockHsm
 //!
-//! No logging is performed and these settings are not yet enforced
+//! Audited commands are logged, but the force audit setting is not yet enforced
 
-use crate::{audit::*, command, serialization::serialize};
-use std::collections::BTreeMap;
+use crate::{
+    audit::{commands::*, *},
+    command, object, response,
+    serialization::serialize,
+};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    time::Instant,
+};
+
+/// Maximum number of entries held in the audit log
+pub const LOG_CAPACITY: usize = 62;
+
+/// Key ID logged for entries where a key field is not applicable
+pub const NO_KEY: object::Id = 0xffff;
 
 /// Default per-command auditing options
 pub const DEFAULT_COMMAND_AUDIT_OPTIONS: &[AuditCommand] = &[
@@ -81,6 +95,14 @@ impl CommandAuditOptions {
     pub fn put(&mut self, command_type: command::Code, audit_option: AuditOption) {
         self.0.insert(command_type, audit_option);
     }
+
+    /// Should the given command be logged?
+    pub fn is_audited(&self, command_type: command::Code) -> bool {
+        self.0
+            .get(&command_type)
+            .map(|option| *option != AuditOption::Off)
+            .unwrap_or(false)
+    }
 }
 
 impl Default for CommandAuditOptions {
@@ -94,3 +116,99 @@ impl Default for CommandAuditOptions {
         CommandAuditOptions(result)
     }
 }
+
+/// Audit log of commands performed by the MockHsm
+#[derive(Debug)]
+pub struct AuditLog {
+    /// Entries which haven't been consumed via `SetLogIndex`
+    entries: VecDeque<LogEntry>,
+
+    /// Item number of the most recently logged entry
+    last_item: u16,
+
+    /// Digest of the most recently logged entry
+    last_digest: [u8; LOG_DIGEST_SIZE],
+
+    /// Time the log was started (used to compute ticks)
+    started_at: Instant,
+}
+
+impl AuditLog {
+    /// Append an entry to the log, discarding the oldest one if it's full
+    pub fn record(
+        &mut self,
+        cmd: command::Code,
+        length: u16,
+        session_key: object::Id,
+        target_key: object::Id,
+        second_key: object::Id,
+        result: response::Code,
+    ) {
+        self.last_item = self.last_item.wrapping_add(1);
+
+        let mut entry = LogEntry {
+            item: self.last_item,
+            cmd,
+            length,
+            session_key,
+            target_key,
+            second_key,
+            result,
+            tick: self.started_at.elapsed().as_millis() as u32,
+            digest: LogDigest([0u8; LOG_DIGEST_SIZE]),
+        };
+
+        // Each digest covers the entry (sans digest) and the previous digest
+        let mut entry_bytes = serialize(&entry).unwrap();
+        entry_bytes.truncate(entry_bytes.len() - LOG_DIGEST_SIZE);
+
+        let digest = Sha256::new()
+            .chain_update(&entry_bytes)
+            .chain_update(self.last_digest)
+            .finalize();
+
+        self.last_digest.copy_from_slice(&digest[..LOG_DIGEST_SIZE]);
+        entry.digest = LogDigest(self.last_digest);
+
+        if self.entries.len() == LOG_CAPACITY {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(entry);
+    }
+
+    /// Get the unconsumed entries in the log
+    pub fn entries(&self) -> LogEntries {
+        LogEntries {
+            unlogged_boot_events: 0,
+            unlogged_auth_events: 0,
+            num_entries: self.entries.len() as u8,
+            entries: self.entries.iter().cloned().collect(),
+        }
+    }
+
+    /// Number of unconsumed entries in the log
+    pub fn used(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Mark all entries up to and including the given index as consumed
+    pub fn set_index(&mut self, log_index: u16) {
+        // Item numbers wrap, so compare distances from the most recent item
+        let consumed = self.last_item.wrapping_sub(log_index);
+
+        self.entries
+            .retain(|entry| self.last_item.wrapping_sub(entry.item) < consumed);
+    }
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        Self {
+            entries: VecDeque::new(),
+            last_item: 0,
+            last_digest: [0u8; LOG_DIGEST_SIZE],
+            started_at: Instant::now(),
+        }
+    }
+}
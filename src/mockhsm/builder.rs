@@ -0,0 +1,204 @@
+//! Builder for `MockHsm`s with non-default settings
+
+use super::{state::State, MockHsm, MOCK_SERIAL_NUMBER};
+use crate::{
+    algorithm::Algorithm,
+    asymmetric,
+    authentication::{self, DEFAULT_AUTHENTICATION_KEY_ID},
+    command,
+    device::{self, FirmwareVersion},
+    ecdh, ecdsa, hmac, object, opaque, otp, rsa, template, wrap,
+};
+use std::{
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+/// Maximum number of objects stored by the YubiHSM 2
+pub const DEFAULT_MAX_OBJECTS: usize = 256;
+
+/// Maximum number of concurrent sessions supported by the YubiHSM 2
+pub const DEFAULT_MAX_SESSIONS: u8 = 16;
+
+/// Algorithms reported by a YubiHSM 2 (and by default the `MockHsm`, whether
+/// or not it implements them)
+const DEVICE_ALGORITHMS: &[Algorithm] = &[
+    Algorithm::Rsa(rsa::Algorithm::Pkcs1(rsa::pkcs1::Algorithm::Sha1)),
+    Algorithm::Rsa(rsa::Algorithm::Pkcs1(rsa::pkcs1::Algorithm::Sha256)),
+    Algorithm::Rsa(rsa::Algorithm::Pkcs1(rsa::pkcs1::Algorithm::Sha384)),
+    Algorithm::Rsa(rsa::Algorithm::Pkcs1(rsa::pkcs1::Algorithm::Sha512)),
+    Algorithm::Rsa(rsa::Algorithm::Pss(rsa::pss::Algorithm::Sha1)),
+    Algorithm::Rsa(rsa::Algorithm::Pss(rsa::pss::Algorithm::Sha256)),
+    Algorithm::Rsa(rsa::Algorithm::Pss(rsa::pss::Algorithm::Sha384)),
+    Algorithm::Rsa(rsa::Algorithm::Pss(rsa::pss::Algorithm::Sha512)),
+    Algorithm::Asymmetric(asymmetric::Algorithm::Rsa2048),
+    Algorithm::Asymmetric(asymmetric::Algorithm::Rsa3072),
+    Algorithm::Asymmetric(asymmetric::Algorithm::Rsa4096),
+    Algorithm::Asymmetric(asymmetric::Algorithm::EcP256),
+    Algorithm::Asymmetric(asymmetric::Algorithm::EcP384),
+    Algorithm::Asymmetric(asymmetric::Algorithm::EcP521),
+    Algorithm::Asymmetric(asymmetric::Algorithm::EcK256),
+    Algorithm::Asymmetric(asymmetric::Algorithm::EcBp256),
+    Algorithm::Asymmetric(asymmetric::Algorithm::EcBp384),
+    Algorithm::Asymmetric(asymmetric::Algorithm::EcBp512),
+    Algorithm::Hmac(hmac::Algorithm::Sha1),
+    Algorithm::Hmac(hmac::Algorithm::Sha256),
+    Algorithm::Hmac(hmac::Algorithm::Sha384),
+    Algorithm::Hmac(hmac::Algorithm::Sha512),
+    Algorithm::Ecdsa(ecdsa::Algorithm::Sha1),
+    Algorithm::Ecdh(ecdh::Algorithm::Ecdh),
+    Algorithm::Rsa(rsa::Algorithm::Oaep(rsa::oaep::Algorithm::Sha1)),
+    Algorithm::Rsa(rsa::Algorithm::Oaep(rsa::oaep::Algorithm::Sha256)),
+    Algorithm::Rsa(rsa::Algorithm::Oaep(rsa::oaep::Algorithm::Sha384)),
+    Algorithm::Rsa(rsa::Algorithm::Oaep(rsa::oaep::Algorithm::Sha512)),
+    Algorithm::Wrap(wrap::Algorithm::Aes128Ccm),
+    Algorithm::Opaque(opaque::Algorithm::Data),
+    Algorithm::Opaque(opaque::Algorithm::X509Certificate),
+    Algorithm::Mgf(rsa::mgf::Algorithm::Sha1),
+    Algorithm::Mgf(rsa::mgf::Algorithm::Sha256),
+    Algorithm::Mgf(rsa::mgf::Algorithm::Sha384),
+    Algorithm::Mgf(rsa::mgf::Algorithm::Sha512),
+    Algorithm::Template(template::Algorithm::Ssh),
+    Algorithm::YubicoOtp(otp::Algorithm::Aes128),
+    Algorithm::Authentication(authentication::Algorithm::YubicoAes),
+    Algorithm::YubicoOtp(otp::Algorithm::Aes192),
+    Algorithm::YubicoOtp(otp::Algorithm::Aes256),
+    Algorithm::Wrap(wrap::Algorithm::Aes192Ccm),
+    Algorithm::Wrap(wrap::Algorithm::Aes256Ccm),
+    Algorithm::Ecdsa(ecdsa::Algorithm::Sha256),
+    Algorithm::Ecdsa(ecdsa::Algorithm::Sha384),
+    Algorithm::Ecdsa(ecdsa::Algorithm::Sha512),
+    Algorithm::Asymmetric(asymmetric::Algorithm::Ed25519),
+    Algorithm::Asymmetric(asymmetric::Algorithm::EcP224),
+];
+
+/// Settings the `MockHsm` was built with
+#[derive(Clone, Debug)]
+pub(crate) struct Config {
+    /// ID of the authentication key present after a reset
+    pub default_auth_key_id: object::Id,
+
+    /// Authentication key present after a reset
+    pub auth_key: authentication::Key,
+
+    /// Maximum number of objects which can be stored
+    pub max_objects: usize,
+
+    /// Maximum number of concurrent sessions
+    pub max_sessions: u8,
+
+    /// Firmware version reported by `device_info` and attestations
+    pub firmware_version: FirmwareVersion,
+
+    /// Serial number reported by `device_info` and attestations
+    pub serial_number: device::SerialNumber,
+
+    /// Algorithms reported by `device_info`
+    pub algorithms: Vec<Algorithm>,
+
+    /// Seed for the random number generator (random if unset)
+    pub seed: Option<[u8; 32]>,
+
+    /// Maximum size of messages sent to or received from the `MockHsm`
+    pub max_message_size: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            default_auth_key_id: DEFAULT_AUTHENTICATION_KEY_ID,
+            auth_key: authentication::Key::default(),
+            max_objects: DEFAULT_MAX_OBJECTS,
+            max_sessions: DEFAULT_MAX_SESSIONS,
+            firmware_version: FirmwareVersion::default(),
+            serial_number: device::SerialNumber::from_str(MOCK_SERIAL_NUMBER).unwrap(),
+            algorithms: DEVICE_ALGORITHMS.to_vec(),
+            seed: None,
+            max_message_size: command::MAX_MSG_SIZE,
+        }
+    }
+}
+
+/// Builder for [`MockHsm`]s with non-default settings
+#[derive(Clone, Debug, Default)]
+pub struct Builder {
+    /// Settings for the `MockHsm`
+    config: Config,
+}
+
+impl Builder {
+    /// Create a new builder with the same settings as `MockHsm::new()`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the ID of the default authentication key
+    pub fn default_auth_key_id(mut self, id: object::Id) -> Self {
+        self.config.default_auth_key_id = id;
+        self
+    }
+
+    /// Set the password the default authentication key is derived from
+    pub fn auth_key_password(mut self, password: &str) -> Self {
+        self.config.auth_key = authentication::Key::derive_from_password(password.as_bytes());
+        self
+    }
+
+    /// Set the maximum number of objects which can be stored. Commands which
+    /// would exceed it fail with `device::ErrorKind::StorageFailed`.
+    pub fn max_objects(mut self, n: usize) -> Self {
+        self.config.max_objects = n;
+        self
+    }
+
+    /// Set the maximum number of concurrent sessions. Opening more fails
+    /// with `device::ErrorKind::SessionsFull`.
+    pub fn max_sessions(mut self, n: u8) -> Self {
+        self.config.max_sessions = n;
+        self
+    }
+
+    /// Set the firmware version reported by the `MockHsm`
+    pub fn firmware_version(mut self, version: FirmwareVersion) -> Self {
+        self.config.firmware_version = version;
+        self
+    }
+
+    /// Set the serial number reported by the `MockHsm`
+    pub fn serial_number(mut self, n: u32) -> Self {
+        self.config.serial_number = n.into();
+        self
+    }
+
+    /// Set the algorithms reported by the `MockHsm`'s `device_info` (by
+    /// default, all those a YubiHSM 2 reports). Generating asymmetric keys
+    /// with algorithms missing from them fails with
+    /// `device::ErrorKind::InvalidData`.
+    pub fn algorithms(mut self, algorithms: impl IntoIterator<Item = Algorithm>) -> Self {
+        self.config.algorithms = algorithms.into_iter().collect();
+        self
+    }
+
+    /// Seed the `MockHsm`'s random number generator, making its challenges,
+    /// generated keys, random data, signatures, and attestation certificates
+    /// reproducible
+    pub fn seed(mut self, entropy: [u8; 32]) -> Self {
+        self.config.seed = Some(entropy);
+        self
+    }
+
+    /// Set the maximum size of messages sent to or received from the
+    /// `MockHsm` (reported by its `Connector::max_message_size`). Larger
+    /// commands are rejected with `connector::ErrorKind::RequestError`, and
+    /// commands whose responses would be larger with
+    /// `connector::ErrorKind::ResponseError`.
+    pub fn max_message_size(mut self, size: usize) -> Self {
+        self.config.max_message_size = size;
+        self
+    }
+
+    /// Build a `MockHsm` with these settings
+    pub fn build(self) -> MockHsm {
+        MockHsm(Arc::new(Mutex::new(State::new(self.config))))
+    }
+}
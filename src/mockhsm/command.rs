@@ -1,26 +1,29 @@
 //! Commands supported by the `MockHsm`
 
-use super::{object::Payload, state::State, MOCK_SERIAL_NUMBER};
+use super::{
+    attestation,
+    audit::{self, NO_KEY},
+    object::Payload,
+    state::State,
+    SignatureFault,
+};
 use crate::{
-    algorithm::*,
     asymmetric::{self, commands::*, PublicKey},
+    attestation::{commands::*, x509, Certificate, DEVICE_ATTESTATION_KEY_ID},
     audit::{commands::*, AuditCommand, AuditOption, AuditTag},
-    authentication::{self, commands::*},
+    authentication::commands::*,
     command::{Code, Message},
     connector,
-    device::{self, commands::*, SerialNumber, StorageInfo},
-    ecdh,
-    ecdsa::{self, commands::*},
+    device::{self, commands::*, StorageInfo},
+    ecdsa::commands::*,
     ed25519::commands::*,
     hmac::{self, commands::*},
     object::{self, commands::*},
-    opaque::{self, commands::*},
-    otp,
+    opaque::commands::*,
     response::{self, Response},
-    rsa,
     serialization::deserialize,
-    session::{self, commands::*},
-    template,
+    session::{self, commands::*, securechannel},
+    template::commands::*,
     wrap::{self, commands::*},
     Capability,
 };
@@ -29,12 +32,15 @@ use ::ecdsa::{
     hazmat::SignPrimitive,
 };
 use ::hmac::{Hmac, Mac};
-use rand_core::{OsRng, RngCore};
+use rand_core::RngCore;
 use sha2::Sha256;
 use signature::Signer;
-use std::{io::Cursor, str::FromStr};
+use std::io::Cursor;
 use subtle::ConstantTimeEq;
 
+#[cfg(feature = "untested")]
+use crate::{algorithm::Algorithm, ecdsa, rsa};
+
 /// Create a new HSM session
 pub(crate) fn create_session(
     state: &mut State,
@@ -43,15 +49,21 @@ pub(crate) fn create_session(
     let cmd: CreateSessionCommand = deserialize(cmd_message.data.as_ref())
         .unwrap_or_else(|e| panic!("error parsing CreateSession command data: {e:?}"));
 
-    let session = state.create_session(cmd.authentication_key_id, cmd.host_challenge);
+    let response = match state.create_session(cmd.authentication_key_id, cmd.host_challenge) {
+        Ok(session) => {
+            let mut response = CreateSessionResponse {
+                card_challenge: *session.card_challenge(),
+                card_cryptogram: session.card_cryptogram(),
+            }
+            .serialize();
 
-    let mut response = CreateSessionResponse {
-        card_challenge: *session.card_challenge(),
-        card_cryptogram: session.card_cryptogram(),
-    }
-    .serialize();
+            response.session_id = Some(session.id);
+            response
+        }
+        Err(kind) => kind.into(),
+    };
 
-    response.session_id = Some(session.id);
+    audit(state, cmd_message, cmd.authentication_key_id, &response);
     Ok(response.into())
 }
 
@@ -64,12 +76,21 @@ pub(crate) fn authenticate_session(
         .session_id
         .unwrap_or_else(|| panic!("no session ID in command: {:?}", command.command_type));
 
-    Ok(state
-        .get_session(session_id)?
-        .channel
-        .verify_authenticate_session(command)
-        .unwrap()
-        .into())
+    let session = state.get_session(session_id)?;
+    let authentication_key_id = session.authentication_key_id;
+
+    // Sessions which fail to authenticate can't be used again, so free
+    // their slot like the real device does
+    let response = match session.authenticate(command) {
+        Ok(response) => response,
+        Err(kind) => {
+            state.close_session(session_id);
+            kind.into()
+        }
+    };
+
+    audit(state, command, authentication_key_id, &response);
+    Ok(response.into())
 }
 
 /// Encrypted session messages
@@ -84,21 +105,43 @@ pub(crate) fn session_message(
         )
     });
 
-    let command = state
-        .get_session(session_id)?
-        .decrypt_command(encrypted_command);
+    // Like the real device, answer messages for sessions we don't know about
+    // (e.g. ones closed by a device reset) with an unencrypted error response
+    let command = match state.get_session(session_id) {
+        Ok(session) => match session.decrypt_command(encrypted_command) {
+            Ok(command) => command,
+            Err(kind) => {
+                // The session's MAC chain is broken, so it can't be used again
+                state.close_session(session_id);
+                return Ok(response::Message::from(kind).into());
+            }
+        },
+        Err(_) => return Ok(response::Message::from(device::ErrorKind::InvalidSession).into()),
+    };
+
+    let authentication_key_id = state.get_session(session_id)?.authentication_key_id;
 
     let response = match command.command_type {
+        _ if !has_delegated_capabilities(state, &command, authentication_key_id) => {
+            device::ErrorKind::InsufficientPermissions.into()
+        }
+        _ if command.command_type.creates_object() && state.storage_full() => {
+            debug!("storage full: {:?}", command.command_type);
+            device::ErrorKind::StorageFailed.into()
+        }
         Code::BlinkDevice => BlinkDeviceResponse {}.serialize(),
-        Code::CloseSession => return close_session(state, session_id),
+        Code::ChangeAuthenticationKey => {
+            change_authentication_key(state, authentication_key_id, &command.data)
+        }
+        Code::CloseSession => return close_session(state, session_id, &command),
         Code::DeleteObject => delete_object(state, &command.data),
-        Code::DeviceInfo => device_info(),
+        Code::DeviceInfo => device_info(state),
         Code::Echo => echo(&command.data),
         Code::ExportWrapped => export_wrapped(state, &command.data),
         Code::GenerateAsymmetricKey => gen_asymmetric_key(state, &command.data),
         Code::GenerateHmacKey => gen_hmac_key(state, &command.data),
         Code::GenerateWrapKey => gen_wrap_key(state, &command.data),
-        Code::GetLogEntries => get_log_entries(),
+        Code::GetLogEntries => get_log_entries(state),
         Code::GetObjectInfo => get_object_info(state, &command.data),
         Code::GetOpaqueObject => get_opaque(state, &command.data),
         Code::GetOption => get_option(state, &command.data),
@@ -114,30 +157,192 @@ pub(crate) fn session_message(
         Code::SetOption => put_option(state, &command.data),
         Code::PutWrapKey => put_wrap_key(state, &command.data),
         Code::ResetDevice => return Ok(reset_device(state, session_id)),
-        Code::SetLogIndex => SetLogIndexResponse {}.serialize(),
+        Code::SetLogIndex => set_log_index(state, &command.data),
+        Code::SignAttestationCertificate => sign_attestation_certificate(state, &command.data),
         Code::SignEcdsa => sign_ecdsa(state, &command.data),
         Code::SignEddsa => sign_eddsa(state, &command.data),
-        Code::GetStorageInfo => get_storage_info(),
+        #[cfg(feature = "untested")]
+        Code::SignPss => sign_pss(state, &command.data),
+        Code::GetStorageInfo => get_storage_info(state),
+        Code::GetTemplate => get_template(state, &command.data),
+        Code::PutTemplate => put_template(state, &command.data),
+        #[cfg(feature = "untested")]
+        Code::SignSshCertificate => sign_ssh_certificate(state, &command.data),
         Code::VerifyHmac => verify_hmac(state, &command.data),
         unsupported => panic!("unsupported command type: {unsupported:?}"),
     };
 
+    audit(state, &command, authentication_key_id, &response);
+
     Ok(state
         .get_session(session_id)?
         .encrypt_response(response)
         .into())
 }
 
+/// Are the capabilities of objects created by this command delegated by the
+/// session's authentication key?
+fn has_delegated_capabilities(
+    state: &State,
+    command: &Message,
+    authentication_key_id: object::Id,
+) -> bool {
+    /// Size of the `object::put::Params` every object creation command begins with:
+    /// ID (2-bytes), label (40-bytes), domains (2-bytes), capabilities (8-bytes),
+    /// and algorithm (1-byte)
+    const PARAMS_SIZE: usize = 53;
+
+    match command.command_type {
+        Code::GenerateAsymmetricKey
+        | Code::GenerateHmacKey
+        | Code::GenerateOtpAead
+        | Code::GenerateWrapKey
+        | Code::PutAsymmetricKey
+        | Code::PutAuthenticationKey
+        | Code::PutHmacKey
+        | Code::PutOpaqueObject
+        | Code::PutOtpAead
+        | Code::PutWrapKey => (),
+        _ => return true,
+    }
+
+    // Leave reporting malformed commands to their handlers
+    let params: object::put::Params = match command.data.get(..PARAMS_SIZE).map(deserialize) {
+        Some(Ok(params)) => params,
+        _ => return true,
+    };
+
+    state
+        .objects
+        .get(authentication_key_id, object::Type::AuthenticationKey)
+        .map(|key| {
+            key.object_info
+                .delegated_capabilities
+                .contains(params.capabilities)
+        })
+        .unwrap_or(false)
+}
+
+/// Record a command in the audit log (if auditing is enabled for it)
+fn audit(
+    state: &mut State,
+    command: &Message,
+    session_key: object::Id,
+    response: &response::Message,
+) {
+    if !state.command_audit_options.is_audited(command.command_type) {
+        return;
+    }
+
+    // Key IDs are the first field of commands which operate on keys
+    let key_id_at = |offset: usize| {
+        command
+            .data
+            .get(offset..offset + 2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+            .unwrap_or(NO_KEY)
+    };
+
+    let (target_key, second_key) = match command.command_type {
+        Code::SignAttestationCertificate => (key_id_at(0), key_id_at(2)),
+        // Wrap key ID, then the type and ID of the object being exported
+        Code::ExportWrapped => (key_id_at(0), key_id_at(3)),
+        Code::ChangeAuthenticationKey
+        | Code::DecryptOaep
+        | Code::DecryptPkcs1
+        | Code::DeleteObject
+        | Code::DeriveEcdh
+        | Code::GenerateAsymmetricKey
+        | Code::GenerateHmacKey
+        | Code::GenerateWrapKey
+        | Code::GetObjectInfo
+        | Code::GetOpaqueObject
+        | Code::GetPublicKey
+        | Code::ImportWrapped
+        | Code::PutAsymmetricKey
+        | Code::PutAuthenticationKey
+        | Code::PutHmacKey
+        | Code::PutOpaqueObject
+        | Code::PutWrapKey
+        | Code::SignEcdsa
+        | Code::SignEddsa
+        | Code::SignHmac
+        | Code::SignPkcs1
+        | Code::SignPss
+        | Code::VerifyHmac => (key_id_at(0), NO_KEY),
+        _ => (NO_KEY, NO_KEY),
+    };
+
+    // Device errors are logged using their response codes, which are
+    // numbered downwards from `DeviceOk`
+    let result = match device::ErrorKind::from_response_message(response) {
+        Some(kind) => response::Code::from_u8(response::Code::DeviceOk.to_u8() - kind.to_u8())
+            .unwrap_or(response.code),
+        None => response.code,
+    };
+
+    state.audit_log.record(
+        command.command_type,
+        command.data.len() as u16,
+        session_key,
+        target_key,
+        second_key,
+        result,
+    );
+}
+
 /// Close an active session
-fn close_session(state: &mut State, session_id: session::Id) -> Result<Vec<u8>, connector::Error> {
-    let response = state
-        .get_session(session_id)?
-        .encrypt_response(CloseSessionResponse {}.serialize());
+fn close_session(
+    state: &mut State,
+    session_id: session::Id,
+    command: &Message,
+) -> Result<Vec<u8>, connector::Error> {
+    let response = CloseSessionResponse {}.serialize();
+    let authentication_key_id = state.get_session(session_id)?.authentication_key_id;
+    audit(state, command, authentication_key_id, &response);
+
+    let response = state.get_session(session_id)?.encrypt_response(response);
 
     state.close_session(session_id);
     Ok(response.into())
 }
 
+/// Change the key material of the session's authentication key
+fn change_authentication_key(
+    state: &mut State,
+    authentication_key_id: object::Id,
+    cmd_data: &[u8],
+) -> response::Message {
+    let ChangeAuthenticationKeyCommand {
+        key_id,
+        algorithm: _,
+        mut authentication_key,
+    } = deserialize(cmd_data)
+        .unwrap_or_else(|e| panic!("error parsing Code::ChangeAuthenticationKey: {e:?}"));
+
+    // Sessions can only change their own authentication key
+    if key_id != authentication_key_id {
+        debug!(
+            "session key 0x{:04x} can't change key 0x{:04x}",
+            authentication_key_id, key_id
+        );
+        return device::ErrorKind::InvalidId.into();
+    }
+
+    if std::mem::take(&mut state.corrupt_next_key_change) {
+        authentication_key.0[0] ^= 1;
+    }
+
+    if !state
+        .objects
+        .change_authentication_key(key_id, authentication_key)
+    {
+        return device::ErrorKind::ObjectNotFound.into();
+    }
+
+    ChangeAuthenticationKeyResponse { key_id }.serialize()
+}
+
 /// Delete an object
 fn delete_object(state: &mut State, cmd_data: &[u8]) -> response::Message {
     let command: DeleteObjectCommand =
@@ -148,6 +353,10 @@ fn delete_object(state: &mut State, cmd_data: &[u8]) -> response::Message {
         .remove(command.object_id, command.object_type)
         .is_some()
     {
+        if command.object_type == object::Type::AsymmetricKey {
+            state.ecdsa_nonces.forget(command.object_id);
+        }
+
         DeleteObjectResponse {}.serialize()
     } else {
         debug!("no such object ID: {:?}", command.object_id);
@@ -156,63 +365,15 @@ fn delete_object(state: &mut State, cmd_data: &[u8]) -> response::Message {
 }
 
 /// Generate a mock device information report
-fn device_info() -> response::Message {
+fn device_info(state: &State) -> response::Message {
     let info = device::Info {
-        major_version: 2,
-        minor_version: 0,
-        build_version: 0,
-        serial_number: SerialNumber::from_str(MOCK_SERIAL_NUMBER).unwrap(),
-        log_store_capacity: 62,
-        log_store_used: 62,
-        algorithms: vec![
-            Algorithm::Rsa(rsa::Algorithm::Pkcs1(rsa::pkcs1::Algorithm::Sha1)),
-            Algorithm::Rsa(rsa::Algorithm::Pkcs1(rsa::pkcs1::Algorithm::Sha256)),
-            Algorithm::Rsa(rsa::Algorithm::Pkcs1(rsa::pkcs1::Algorithm::Sha384)),
-            Algorithm::Rsa(rsa::Algorithm::Pkcs1(rsa::pkcs1::Algorithm::Sha512)),
-            Algorithm::Rsa(rsa::Algorithm::Pss(rsa::pss::Algorithm::Sha1)),
-            Algorithm::Rsa(rsa::Algorithm::Pss(rsa::pss::Algorithm::Sha256)),
-            Algorithm::Rsa(rsa::Algorithm::Pss(rsa::pss::Algorithm::Sha384)),
-            Algorithm::Rsa(rsa::Algorithm::Pss(rsa::pss::Algorithm::Sha512)),
-            Algorithm::Asymmetric(asymmetric::Algorithm::Rsa2048),
-            Algorithm::Asymmetric(asymmetric::Algorithm::Rsa3072),
-            Algorithm::Asymmetric(asymmetric::Algorithm::Rsa4096),
-            Algorithm::Asymmetric(asymmetric::Algorithm::EcP256),
-            Algorithm::Asymmetric(asymmetric::Algorithm::EcP384),
-            Algorithm::Asymmetric(asymmetric::Algorithm::EcP521),
-            Algorithm::Asymmetric(asymmetric::Algorithm::EcK256),
-            Algorithm::Asymmetric(asymmetric::Algorithm::EcBp256),
-            Algorithm::Asymmetric(asymmetric::Algorithm::EcBp384),
-            Algorithm::Asymmetric(asymmetric::Algorithm::EcBp512),
-            Algorithm::Hmac(hmac::Algorithm::Sha1),
-            Algorithm::Hmac(hmac::Algorithm::Sha256),
-            Algorithm::Hmac(hmac::Algorithm::Sha384),
-            Algorithm::Hmac(hmac::Algorithm::Sha512),
-            Algorithm::Ecdsa(ecdsa::Algorithm::Sha1),
-            Algorithm::Ecdh(ecdh::Algorithm::Ecdh),
-            Algorithm::Rsa(rsa::Algorithm::Oaep(rsa::oaep::Algorithm::Sha1)),
-            Algorithm::Rsa(rsa::Algorithm::Oaep(rsa::oaep::Algorithm::Sha256)),
-            Algorithm::Rsa(rsa::Algorithm::Oaep(rsa::oaep::Algorithm::Sha384)),
-            Algorithm::Rsa(rsa::Algorithm::Oaep(rsa::oaep::Algorithm::Sha512)),
-            Algorithm::Wrap(wrap::Algorithm::Aes128Ccm),
-            Algorithm::Opaque(opaque::Algorithm::Data),
-            Algorithm::Opaque(opaque::Algorithm::X509Certificate),
-            Algorithm::Mgf(rsa::mgf::Algorithm::Sha1),
-            Algorithm::Mgf(rsa::mgf::Algorithm::Sha256),
-            Algorithm::Mgf(rsa::mgf::Algorithm::Sha384),
-            Algorithm::Mgf(rsa::mgf::Algorithm::Sha512),
-            Algorithm::Template(template::Algorithm::Ssh),
-            Algorithm::YubicoOtp(otp::Algorithm::Aes128),
-            Algorithm::Authentication(authentication::Algorithm::YubicoAes),
-            Algorithm::YubicoOtp(otp::Algorithm::Aes192),
-            Algorithm::YubicoOtp(otp::Algorithm::Aes256),
-            Algorithm::Wrap(wrap::Algorithm::Aes192Ccm),
-            Algorithm::Wrap(wrap::Algorithm::Aes256Ccm),
-            Algorithm::Ecdsa(ecdsa::Algorithm::Sha256),
-            Algorithm::Ecdsa(ecdsa::Algorithm::Sha384),
-            Algorithm::Ecdsa(ecdsa::Algorithm::Sha512),
-            Algorithm::Asymmetric(asymmetric::Algorithm::Ed25519),
-            Algorithm::Asymmetric(asymmetric::Algorithm::EcP224),
-        ],
+        major_version: state.config.firmware_version.major,
+        minor_version: state.config.firmware_version.minor,
+        build_version: state.config.firmware_version.build,
+        serial_number: state.config.serial_number,
+        log_store_capacity: audit::LOG_CAPACITY as u8,
+        log_store_used: state.audit_log.used() as u8,
+        algorithms: state.config.algorithms.clone(),
     };
 
     DeviceInfoResponse(info).serialize()
@@ -233,12 +394,21 @@ fn export_wrapped(state: &mut State, cmd_data: &[u8]) -> response::Message {
         .unwrap_or_else(|e| panic!("error parsing Code::ExportWrapped: {e:?}"));
 
     let nonce = wrap::Nonce::generate();
+    let max_len = securechannel::max_data_len(state.config.max_message_size);
 
     match state
         .objects
-        .wrap_obj(wrap_key_id, object_id, object_type, &nonce)
+        .wrap_obj(wrap_key_id, object_id, object_type, &nonce, max_len)
     {
         Ok(ciphertext) => ExportWrappedResponse(wrap::Message { nonce, ciphertext }).serialize(),
+        Err(e) if *e.kind() == super::ErrorKind::PayloadTooLarge => {
+            debug!("error wrapping object: {}", e);
+            device::ErrorKind::WrongLength.into()
+        }
+        Err(e) if *e.kind() == super::ErrorKind::AccessDenied => {
+            debug!("error wrapping object: {}", e);
+            device::ErrorKind::InsufficientPermissions.into()
+        }
         Err(e) => {
             debug!("error wrapping object: {}", e);
             device::ErrorKind::InvalidCommand.into()
@@ -251,7 +421,39 @@ fn gen_asymmetric_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
     let GenAsymmetricKeyCommand(command) = deserialize(cmd_data)
         .unwrap_or_else(|e| panic!("error parsing Code::GenAsymmetricKey: {e:?}"));
 
-    state.objects.generate(
+    if state
+        .objects
+        .get(command.key_id, object::Type::AsymmetricKey)
+        .is_some()
+    {
+        return device::ErrorKind::ObjectExists.into();
+    }
+
+    if !state.config.algorithms.contains(&command.algorithm) {
+        debug!("algorithm not enabled: {:?}", command.algorithm);
+        return device::ErrorKind::InvalidData.into();
+    }
+
+    match command.algorithm.asymmetric() {
+        Some(
+            asymmetric::Algorithm::EcK256
+            | asymmetric::Algorithm::EcP256
+            | asymmetric::Algorithm::EcP384
+            | asymmetric::Algorithm::EcBp256
+            | asymmetric::Algorithm::EcBp384
+            | asymmetric::Algorithm::EcBp512
+            | asymmetric::Algorithm::Ed25519
+            | asymmetric::Algorithm::Rsa2048
+            | asymmetric::Algorithm::Rsa3072
+            | asymmetric::Algorithm::Rsa4096,
+        ) => (),
+        _ => {
+            debug!("unsupported asymmetric algorithm: {:?}", command.algorithm);
+            return device::ErrorKind::InvalidData.into();
+        }
+    }
+
+    if let Err(e) = state.objects.generate(
         command.key_id,
         object::Type::AsymmetricKey,
         command.algorithm,
@@ -259,7 +461,11 @@ fn gen_asymmetric_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
         command.capabilities,
         Capability::default(),
         command.domains,
-    );
+        &mut state.rng,
+    ) {
+        debug!("error generating object: {}", e);
+        return device::ErrorKind::InvalidData.into();
+    }
 
     GenAsymmetricKeyResponse {
         key_id: command.key_id,
@@ -272,7 +478,7 @@ fn gen_hmac_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
     let GenHmacKeyCommand(command) =
         deserialize(cmd_data).unwrap_or_else(|e| panic!("error parsing Code::GenHMACKey: {e:?}"));
 
-    state.objects.generate(
+    if let Err(e) = state.objects.generate(
         command.key_id,
         object::Type::HmacKey,
         command.algorithm,
@@ -280,7 +486,11 @@ fn gen_hmac_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
         command.capabilities,
         Capability::default(),
         command.domains,
-    );
+        &mut state.rng,
+    ) {
+        debug!("error generating object: {}", e);
+        return device::ErrorKind::InvalidData.into();
+    }
 
     GenHmacKeyResponse {
         key_id: command.key_id,
@@ -295,7 +505,7 @@ fn gen_wrap_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
         delegated_capabilities,
     } = deserialize(cmd_data).unwrap_or_else(|e| panic!("error parsing Code::GenWrapKey: {e:?}"));
 
-    state.objects.generate(
+    if let Err(e) = state.objects.generate(
         params.key_id,
         object::Type::WrapKey,
         params.algorithm,
@@ -303,7 +513,11 @@ fn gen_wrap_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
         params.capabilities,
         delegated_capabilities,
         params.domains,
-    );
+        &mut state.rng,
+    ) {
+        debug!("error generating object: {}", e);
+        return device::ErrorKind::InvalidData.into();
+    }
 
     GenWrapKeyResponse {
         key_id: params.key_id,
@@ -312,15 +526,8 @@ fn gen_wrap_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
 }
 
 /// Get mock log information
-fn get_log_entries() -> response::Message {
-    // TODO: mimic the YubiHSM's actual audit log
-    LogEntries {
-        unlogged_boot_events: 0,
-        unlogged_auth_events: 0,
-        num_entries: 0,
-        entries: vec![],
-    }
-    .serialize()
+fn get_log_entries(state: &State) -> response::Message {
+    state.audit_log.entries().serialize()
 }
 
 /// Get detailed info about a specific object
@@ -346,32 +553,41 @@ fn get_opaque(state: &State, cmd_data: &[u8]) -> response::Message {
 
     if let Some(obj) = state.objects.get(command.object_id, object::Type::Opaque) {
         GetOpaqueResponse(obj.payload.to_bytes()).serialize()
+    } else if command.object_id == DEVICE_ATTESTATION_KEY_ID {
+        GetOpaqueResponse(state.attestation.certificate().into()).serialize()
     } else {
         debug!("no such opaque object ID: {:?}", command.object_id);
         device::ErrorKind::ObjectNotFound.into()
     }
 }
 
-/// Get an auditing option
+/// Get an auditing (or other device) option
 fn get_option(state: &State, cmd_data: &[u8]) -> response::Message {
     let command: GetOptionCommand = deserialize(cmd_data)
         .unwrap_or_else(|e| panic!("error parsing Code::GetOpaqueObject: {e:?}"));
 
-    let results = match command.tag {
-        AuditTag::Command => state.command_audit_options.serialize(),
-        AuditTag::Force => vec![state.force_audit.to_u8()],
+    let results = match AuditTag::from_u8(command.tag) {
+        Ok(AuditTag::Command) => state.command_audit_options.serialize(),
+        Ok(AuditTag::Force) => vec![state.force_audit.to_u8()],
+        Err(_) => match state.options.get(&command.tag) {
+            Some(value) => value.clone(),
+            None => {
+                debug!("option not set: 0x{:02x}", command.tag);
+                return device::ErrorKind::InvalidData.into();
+            }
+        },
     };
 
     GetOptionResponse(results).serialize()
 }
 
 /// Get bytes of random data
-fn get_pseudo_random(_state: &State, cmd_data: &[u8]) -> response::Message {
+fn get_pseudo_random(state: &mut State, cmd_data: &[u8]) -> response::Message {
     let command: GetPseudoRandomCommand = deserialize(cmd_data)
         .unwrap_or_else(|e| panic!("error parsing Code::GetPseudoRandom: {e:?}"));
 
     let mut bytes = vec![0u8; command.bytes as usize];
-    OsRng.fill_bytes(&mut bytes);
+    state.rng.fill_bytes(&mut bytes);
 
     GetPseudoRandomResponse { bytes }.serialize()
 }
@@ -385,11 +601,17 @@ fn get_public_key(state: &State, cmd_data: &[u8]) -> response::Message {
         .objects
         .get(command.key_id, object::Type::AsymmetricKey)
     {
-        GetPublicKeyResponse(PublicKey {
-            algorithm: obj.algorithm().asymmetric().unwrap(),
-            bytes: obj.payload.public_key_bytes().unwrap(),
-        })
-        .serialize()
+        match obj.payload.public_key_bytes() {
+            Some(bytes) => GetPublicKeyResponse(PublicKey {
+                algorithm: obj.algorithm().asymmetric().unwrap(),
+                bytes,
+            })
+            .serialize(),
+            None => {
+                debug!("can't compute public key for: {:?}", obj.algorithm());
+                device::ErrorKind::InvalidData.into()
+            }
+        }
     } else {
         debug!("no such object ID: {:?}", command.key_id);
         device::ErrorKind::ObjectNotFound.into()
@@ -397,19 +619,42 @@ fn get_public_key(state: &State, cmd_data: &[u8]) -> response::Message {
 }
 
 /// Generate a mock storage status report
-fn get_storage_info() -> response::Message {
-    // TODO: model actual free storage
-    let info = StorageInfo {
-        total_records: 256,
-        free_records: 256,
+fn get_storage_info(state: &State) -> response::Message {
+    // Each object occupies one record, and pages for its data (these are
+    // reported, but not enforced)
+    let max_objects = state.config.max_objects;
+
+    let mut info = StorageInfo {
+        total_records: max_objects as u16,
+        free_records: max_objects.saturating_sub(state.objects.len()) as u16,
         total_pages: 1024,
         free_pages: 1024,
         page_size: 126,
     };
 
+    let used_pages: usize = state
+        .objects
+        .iter()
+        .map(|(_, object)| info.footprint(object.info().length.into()).pages)
+        .sum();
+
+    info.free_pages = info.total_pages.saturating_sub(used_pages as u16);
     GetStorageInfoResponse(info).serialize()
 }
 
+/// Get a certificate template
+fn get_template(state: &State, cmd_data: &[u8]) -> response::Message {
+    let command: GetTemplateCommand =
+        deserialize(cmd_data).unwrap_or_else(|e| panic!("error parsing Code::GetTemplate: {e:?}"));
+
+    if let Some(obj) = state.objects.get(command.object_id, object::Type::Template) {
+        GetTemplateResponse(obj.payload.to_bytes()).serialize()
+    } else {
+        debug!("no such template ID: {:?}", command.object_id);
+        device::ErrorKind::ObjectNotFound.into()
+    }
+}
+
 /// Import an object encrypted under a wrap key into the HSM
 fn import_wrapped(state: &mut State, cmd_data: &[u8]) -> response::Message {
     let ImportWrappedCommand {
@@ -425,6 +670,17 @@ fn import_wrapped(state: &mut State, cmd_data: &[u8]) -> response::Message {
             object_id: obj.object_id,
         }
         .serialize(),
+        Err(e) if *e.kind() == super::ErrorKind::ObjectExists => {
+            device::ErrorKind::ObjectExists.into()
+        }
+        Err(e) if *e.kind() == super::ErrorKind::AccessDenied => {
+            debug!("error unwrapping object: {}", e);
+            device::ErrorKind::InsufficientPermissions.into()
+        }
+        Err(e) if *e.kind() == super::ErrorKind::InvalidData => {
+            debug!("error unwrapping object: {}", e);
+            device::ErrorKind::InvalidData.into()
+        }
         Err(e) => {
             debug!("error unwrapping object: {}", e);
             device::ErrorKind::InvalidCommand.into()
@@ -475,7 +731,7 @@ fn put_asymmetric_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
     let PutAsymmetricKeyCommand { params, data } = deserialize(cmd_data)
         .unwrap_or_else(|e| panic!("error parsing Code::PutAsymmetricKey: {e:?}"));
 
-    state.objects.put(
+    if let Err(e) = state.objects.put(
         params.id,
         object::Type::AsymmetricKey,
         params.algorithm,
@@ -484,7 +740,10 @@ fn put_asymmetric_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
         Capability::default(),
         params.domains,
         &data,
-    );
+    ) {
+        debug!("error putting object: {}", e);
+        return device::ErrorKind::InvalidData.into();
+    }
 
     PutAsymmetricKeyResponse { key_id: params.id }.serialize()
 }
@@ -498,7 +757,7 @@ fn put_authentication_key(state: &mut State, cmd_data: &[u8]) -> response::Messa
     } = deserialize(cmd_data)
         .unwrap_or_else(|e| panic!("error parsing Code::PutAuthenticationKey: {e:?}"));
 
-    state.objects.put(
+    if let Err(e) = state.objects.put(
         params.id,
         object::Type::AuthenticationKey,
         params.algorithm,
@@ -507,7 +766,10 @@ fn put_authentication_key(state: &mut State, cmd_data: &[u8]) -> response::Messa
         delegated_capabilities,
         params.domains,
         &authentication_key.0,
-    );
+    ) {
+        debug!("error putting object: {}", e);
+        return device::ErrorKind::InvalidData.into();
+    }
 
     PutAuthenticationKeyResponse { key_id: params.id }.serialize()
 }
@@ -517,7 +779,7 @@ fn put_hmac_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
     let PutHmacKeyCommand { params, hmac_key } =
         deserialize(cmd_data).unwrap_or_else(|e| panic!("error parsing Code::PutHMACKey: {e:?}"));
 
-    state.objects.put(
+    if let Err(e) = state.objects.put(
         params.id,
         object::Type::HmacKey,
         params.algorithm,
@@ -526,7 +788,10 @@ fn put_hmac_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
         Capability::default(),
         params.domains,
         &hmac_key,
-    );
+    ) {
+        debug!("error putting object: {}", e);
+        return device::ErrorKind::InvalidData.into();
+    }
 
     PutHmacKeyResponse { key_id: params.id }.serialize()
 }
@@ -536,7 +801,7 @@ fn put_opaque(state: &mut State, cmd_data: &[u8]) -> response::Message {
     let PutOpaqueCommand { params, data } = deserialize(cmd_data)
         .unwrap_or_else(|e| panic!("error parsing Code::PutOpaqueObject: {e:?}"));
 
-    state.objects.put(
+    if let Err(e) = state.objects.put(
         params.id,
         object::Type::Opaque,
         params.algorithm,
@@ -545,7 +810,10 @@ fn put_opaque(state: &mut State, cmd_data: &[u8]) -> response::Message {
         Capability::default(),
         params.domains,
         &data,
-    );
+    ) {
+        debug!("error putting object: {}", e);
+        return device::ErrorKind::InvalidData.into();
+    }
 
     PutOpaqueResponse {
         object_id: params.id,
@@ -553,30 +821,66 @@ fn put_opaque(state: &mut State, cmd_data: &[u8]) -> response::Message {
     .serialize()
 }
 
-/// Change an HSM auditing setting
+/// Change an HSM auditing (or other device) setting
 fn put_option(state: &mut State, cmd_data: &[u8]) -> response::Message {
     let SetOptionCommand { tag, length, value } =
         deserialize(cmd_data).unwrap_or_else(|e| panic!("error parsing Code::PutOption: {e:?}"));
 
-    match tag {
-        AuditTag::Force => {
+    assert_eq!(usize::from(length), value.len());
+
+    match AuditTag::from_u8(tag) {
+        Ok(AuditTag::Force) => {
             assert_eq!(length, 1);
             state.force_audit = AuditOption::from_u8(value[0]).unwrap()
         }
-        AuditTag::Command => {
+        Ok(AuditTag::Command) => {
             assert_eq!(length, 2);
             let audit_cmd: AuditCommand =
                 deserialize(&value).unwrap_or_else(|e| panic!("error parsing AuditCommand: {e:?}"));
 
+            if !audit_cmd.command_type().is_auditable() {
+                debug!("command can't be audited: {}", audit_cmd.command_type());
+                return device::ErrorKind::InvalidData.into();
+            }
+
             state
                 .command_audit_options
                 .put(audit_cmd.command_type(), audit_cmd.audit_option());
         }
+        // Store options the MockHsm doesn't otherwise implement verbatim
+        Err(_) => {
+            state.options.insert(tag, value);
+        }
     }
 
     PutOptionResponse {}.serialize()
 }
 
+/// Put a certificate template
+fn put_template(state: &mut State, cmd_data: &[u8]) -> response::Message {
+    let PutTemplateCommand { params, data } =
+        deserialize(cmd_data).unwrap_or_else(|e| panic!("error parsing Code::PutTemplate: {e:?}"));
+
+    if let Err(e) = state.objects.put(
+        params.id,
+        object::Type::Template,
+        params.algorithm,
+        params.label,
+        params.capabilities,
+        Capability::default(),
+        params.domains,
+        &data,
+    ) {
+        debug!("error putting object: {}", e);
+        return device::ErrorKind::InvalidData.into();
+    }
+
+    PutTemplateResponse {
+        object_id: params.id,
+    }
+    .serialize()
+}
+
 /// Put an existing wrap (i.e. AES-CCM) key into the HSM
 fn put_wrap_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
     let PutWrapKeyCommand {
@@ -585,7 +889,19 @@ fn put_wrap_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
         data,
     } = deserialize(cmd_data).unwrap_or_else(|e| panic!("error parsing Code::PutWrapKey: {e:?}"));
 
-    state.objects.put(
+    match params.algorithm.wrap() {
+        Some(alg) if data.len() == alg.key_len() => (),
+        Some(alg) => {
+            debug!("invalid key length for {:?}: {}", alg, data.len());
+            return device::ErrorKind::WrongLength.into();
+        }
+        None => {
+            debug!("not a wrap algorithm: {:?}", params.algorithm);
+            return device::ErrorKind::InvalidData.into();
+        }
+    }
+
+    if let Err(e) = state.objects.put(
         params.id,
         object::Type::WrapKey,
         params.algorithm,
@@ -594,7 +910,10 @@ fn put_wrap_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
         delegated_capabilities,
         params.domains,
         &data,
-    );
+    ) {
+        debug!("error putting object: {}", e);
+        return device::ErrorKind::InvalidData.into();
+    }
 
     PutWrapKeyResponse { key_id: params.id }.serialize()
 }
@@ -611,8 +930,81 @@ fn reset_device(state: &mut State, session_id: session::Id) -> Vec<u8> {
     response
 }
 
+/// Mark audit log entries as consumed
+fn set_log_index(state: &mut State, cmd_data: &[u8]) -> response::Message {
+    let command: SetLogIndexCommand =
+        deserialize(cmd_data).unwrap_or_else(|e| panic!("error parsing Code::SetLogIndex: {e:?}"));
+
+    state.audit_log.set_index(command.log_index);
+    SetLogIndexResponse {}.serialize()
+}
+
+/// Generate an attestation certificate for an asymmetric key
+fn sign_attestation_certificate(state: &mut State, cmd_data: &[u8]) -> response::Message {
+    let command: SignAttestationCertificateCommand = deserialize(cmd_data)
+        .unwrap_or_else(|e| panic!("error parsing Code::SignAttestationCertificate: {e:?}"));
+
+    let subject = match state
+        .objects
+        .get(command.key_id, object::Type::AsymmetricKey)
+    {
+        Some(obj) => obj,
+        None => {
+            debug!("no such object ID: {:?}", command.key_id);
+            return device::ErrorKind::ObjectNotFound.into();
+        }
+    };
+
+    let certificate = if command.attestation_key_id == DEVICE_ATTESTATION_KEY_ID {
+        state
+            .attestation
+            .attest(subject, &state.config, &mut state.rng)
+    } else {
+        // Custom attestation keys must be accompanied by a certificate stored
+        // as an opaque object with the same ID, which names the issuer
+        let attestation_key = state
+            .objects
+            .get(command.attestation_key_id, object::Type::AsymmetricKey);
+        let attestation_cert = state
+            .objects
+            .get(command.attestation_key_id, object::Type::Opaque);
+
+        match (attestation_key, attestation_cert) {
+            (Some(key), Some(cert)) => match &key.payload {
+                Payload::EcdsaNistP256(secret_key) => {
+                    let cert_der = cert.payload.to_bytes();
+                    let issuer = x509::Certificate::parse(&cert_der).unwrap().subject;
+                    attestation::attest(
+                        subject,
+                        &secret_key.into(),
+                        issuer,
+                        &state.config,
+                        &mut state.rng,
+                    )
+                }
+                _ => {
+                    debug!("unsupported attestation key: {:?}", key.algorithm());
+                    return device::ErrorKind::InvalidData.into();
+                }
+            },
+            _ => {
+                debug!("no such attestation key: {:?}", command.attestation_key_id);
+                return device::ErrorKind::ObjectNotFound.into();
+            }
+        }
+    };
+
+    match certificate {
+        Some(certificate) => Certificate(certificate).serialize(),
+        None => {
+            debug!("can't attest to key: {:?}", subject.algorithm());
+            device::ErrorKind::InvalidData.into()
+        }
+    }
+}
+
 /// Sign a message using the ECDSA signature algorithm
-fn sign_ecdsa(state: &State, cmd_data: &[u8]) -> response::Message {
+fn sign_ecdsa(state: &mut State, cmd_data: &[u8]) -> response::Message {
     let command: SignEcdsaCommand =
         deserialize(cmd_data).unwrap_or_else(|e| panic!("error parsing Code::SignEcdsa: {e:?}"));
 
@@ -620,9 +1012,12 @@ fn sign_ecdsa(state: &State, cmd_data: &[u8]) -> response::Message {
         .objects
         .get(command.key_id, object::Type::AsymmetricKey)
     {
-        match &obj.payload {
+        let (r, der): (Vec<u8>, Vec<u8>) = match &obj.payload {
             Payload::EcdsaNistP256(secret_key) => {
-                let k = p256::Scalar::random(&mut OsRng);
+                let k = match &state.fixed_ecdsa_nonce {
+                    Some(k) => p256::Scalar::reduce_bytes(GenericArray::from_slice(k)),
+                    None => p256::Scalar::random(&mut state.rng),
+                };
                 let z = p256::Scalar::reduce_bytes(GenericArray::from_slice(&command.digest))
                     .to_bytes();
                 let signature = secret_key
@@ -631,10 +1026,18 @@ fn sign_ecdsa(state: &State, cmd_data: &[u8]) -> response::Message {
                     .expect("ECDSA failure!")
                     .0;
 
-                SignEcdsaResponse(signature.to_der().as_ref().into()).serialize()
+                (
+                    signature.r().to_bytes().to_vec(),
+                    signature.to_der().as_ref().into(),
+                )
             }
             Payload::EcdsaSecp256k1(secret_key) => {
-                let k = k256::Scalar::random(&mut OsRng);
+                let k = match &state.fixed_ecdsa_nonce {
+                    Some(k) => {
+                        <k256::Scalar as Reduce<U256>>::reduce_bytes(GenericArray::from_slice(k))
+                    }
+                    None => k256::Scalar::random(&mut state.rng),
+                };
                 let z = <k256::Scalar as Reduce<U256>>::reduce_bytes(GenericArray::from_slice(
                     &command.digest,
                 ))
@@ -645,12 +1048,24 @@ fn sign_ecdsa(state: &State, cmd_data: &[u8]) -> response::Message {
                     .expect("ECDSA failure!")
                     .0;
 
-                SignEcdsaResponse(signature.to_der().as_ref().into()).serialize()
+                (
+                    signature.r().to_bytes().to_vec(),
+                    signature.to_der().as_ref().into(),
+                )
             }
             _ => {
                 debug!("not an ECDSA key: {:?}", obj.algorithm());
-                device::ErrorKind::InvalidCommand.into()
+                return device::ErrorKind::InvalidCommand.into();
             }
+        };
+
+        state
+            .ecdsa_nonces
+            .record(command.key_id, &r, &command.digest);
+
+        match state.signature_fault.take() {
+            Some(fault) => SignEcdsaResponse(corrupt_signature(der, fault)).serialize(),
+            None => SignEcdsaResponse(der).serialize(),
         }
     } else {
         debug!("no such object ID: {:?}", command.key_id);
@@ -658,6 +1073,25 @@ fn sign_ecdsa(state: &State, cmd_data: &[u8]) -> response::Message {
     }
 }
 
+/// Corrupt an ASN.1 DER encoded ECDSA signature in the given way
+fn corrupt_signature(mut der: Vec<u8>, fault: SignatureFault) -> Vec<u8> {
+    match fault {
+        // The last byte of the signature is the least significant byte of `s`
+        SignatureFault::Invalid => {
+            *der.last_mut().unwrap() ^= 0x01;
+            der
+        }
+        SignatureFault::OutOfRange => {
+            // SEQUENCE { INTEGER r, INTEGER 0 }
+            let r = &der[2..4 + der[3] as usize];
+            let mut corrupted = vec![0x30, r.len() as u8 + 3];
+            corrupted.extend_from_slice(r);
+            corrupted.extend_from_slice(&[0x02, 0x01, 0x00]);
+            corrupted
+        }
+    }
+}
+
 /// Sign a message using the Ed25519 signature algorithm
 fn sign_eddsa(state: &State, cmd_data: &[u8]) -> response::Message {
     let command: SignEddsaCommand =
@@ -680,6 +1114,147 @@ fn sign_eddsa(state: &State, cmd_data: &[u8]) -> response::Message {
     }
 }
 
+/// Sign a message digest using RSASSA-PSS
+#[cfg(feature = "untested")]
+fn sign_pss(state: &mut State, cmd_data: &[u8]) -> response::Message {
+    use ::rsa::pss::SigningKey;
+    use rsa::pss::commands::{SignPssCommand, SignPssResponse};
+    use sha2::{Sha384, Sha512};
+    use signature::{hazmat::RandomizedPrehashSigner, SignatureEncoding};
+
+    let command: SignPssCommand =
+        deserialize(cmd_data).unwrap_or_else(|e| panic!("error parsing Code::SignPss: {e:?}"));
+
+    let obj = match state
+        .objects
+        .get(command.key_id, object::Type::AsymmetricKey)
+    {
+        Some(obj) => obj,
+        None => {
+            debug!("no such object ID: {:?}", command.key_id);
+            return device::ErrorKind::ObjectNotFound.into();
+        }
+    };
+
+    let private_key = match &obj.payload {
+        Payload::RsaKey(private_key) => private_key.clone(),
+        _ => {
+            debug!("not an RSA key: {:?}", obj.algorithm());
+            return device::ErrorKind::InvalidCommand.into();
+        }
+    };
+
+    let salt_len = command.salt_len as usize;
+    let digest = &command.digest;
+
+    let result = match command.mgf1_hash_alg {
+        rsa::mgf::Algorithm::Sha256 if digest.len() == 32 => {
+            SigningKey::<Sha256>::new_with_salt_len(private_key, salt_len)
+                .sign_prehash_with_rng(&mut state.rng, digest)
+        }
+        rsa::mgf::Algorithm::Sha384 if digest.len() == 48 => {
+            SigningKey::<Sha384>::new_with_salt_len(private_key, salt_len)
+                .sign_prehash_with_rng(&mut state.rng, digest)
+        }
+        rsa::mgf::Algorithm::Sha512 if digest.len() == 64 => {
+            SigningKey::<Sha512>::new_with_salt_len(private_key, salt_len)
+                .sign_prehash_with_rng(&mut state.rng, digest)
+        }
+        alg => {
+            debug!("unsupported MGF1 hash or digest length: {:?}", alg);
+            return device::ErrorKind::InvalidData.into();
+        }
+    };
+
+    match result {
+        Ok(signature) => SignPssResponse(rsa::pss::Signature(signature.to_vec())).serialize(),
+        Err(e) => {
+            debug!("RSASSA-PSS signing failed: {}", e);
+            device::ErrorKind::InvalidData.into()
+        }
+    }
+}
+
+/// Sign an OpenSSH certificate with a CA key.
+///
+/// Unlike the real device, the template's constraints and the request's
+/// timestamp signature aren't checked: the certificate is signed as-is.
+#[cfg(feature = "untested")]
+fn sign_ssh_certificate(state: &State, cmd_data: &[u8]) -> response::Message {
+    use crate::ssh::{
+        self,
+        commands::*,
+        encoding::{put_mpint, put_string},
+    };
+
+    let command: SignSshCertificateCommand = deserialize(cmd_data)
+        .unwrap_or_else(|e| panic!("error parsing Code::SignSshCertificate: {e:?}"));
+
+    if state
+        .objects
+        .get(command.template_id, object::Type::Template)
+        .is_none()
+    {
+        debug!("no such template ID: {:?}", command.template_id);
+        return device::ErrorKind::ObjectNotFound.into();
+    }
+
+    let obj = match state
+        .objects
+        .get(command.key_id, object::Type::AsymmetricKey)
+    {
+        Some(obj) => obj,
+        None => {
+            debug!("no such object ID: {:?}", command.key_id);
+            return device::ErrorKind::ObjectNotFound.into();
+        }
+    };
+
+    // Skip the timestamp and its signature
+    let header_len = 4 + ssh::TIMESTAMP_SIGNATURE_SIZE;
+
+    if command.request.len() <= header_len {
+        return device::ErrorKind::WrongLength.into();
+    }
+
+    let body = &command.request[header_len..];
+    let mut signature = vec![];
+
+    match (&obj.payload, command.algorithm) {
+        (
+            Payload::Ed25519Key(signing_key),
+            Algorithm::Asymmetric(asymmetric::Algorithm::Ed25519),
+        ) => {
+            put_string(&mut signature, b"ssh-ed25519");
+            put_string(&mut signature, &signing_key.sign(body).to_bytes());
+        }
+        (Payload::EcdsaNistP256(secret_key), Algorithm::Ecdsa(ecdsa::Algorithm::Sha256)) => {
+            let ecdsa_signature: p256::ecdsa::Signature =
+                p256::ecdsa::SigningKey::from(secret_key).sign(body);
+
+            let mut blob = vec![];
+            put_mpint(&mut blob, &ecdsa_signature.r().to_bytes());
+            put_mpint(&mut blob, &ecdsa_signature.s().to_bytes());
+
+            put_string(&mut signature, b"ecdsa-sha2-nistp256");
+            put_string(&mut signature, &blob);
+        }
+        (payload, algorithm) => {
+            debug!(
+                "can't sign SSH certificates with {:?} using {:?}",
+                payload.algorithm(),
+                algorithm
+            );
+            return device::ErrorKind::InvalidCommand.into();
+        }
+    }
+
+    let mut certificate = body.to_vec();
+    put_string(&mut certificate, &signature);
+
+    SignSshCertificateResponse(ssh::Certificate::from_bytes(certificate)).serialize()
+}
+
 /// Compute the HMAC tag for the given data
 fn sign_hmac(state: &State, cmd_data: &[u8]) -> response::Message {
     let command: SignHmacCommand =
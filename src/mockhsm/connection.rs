@@ -3,9 +3,17 @@
 use super::{command, state::State, MockHsm};
 use crate::{
     command::Code,
-    connector::{self, Connection, ErrorKind::ConnectionFailed, Message},
+    connector::{
+        self, Connection,
+        ErrorKind::{ConnectionFailed, RequestError, ResponseError},
+        Message,
+    },
+    session::securechannel::Mac,
+};
+use std::{
+    mem,
+    sync::{Arc, Mutex},
 };
-use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
 /// A mocked connection to the MockHsm
@@ -21,7 +29,8 @@ impl MockConnection {
 impl Connection for MockConnection {
     /// Send a message to the MockHsm
     fn send_message(&self, _uuid: Uuid, message: Message) -> Result<Message, connector::Error> {
-        let command = message
+        let message_len = message.as_ref().len();
+        let mut command = message
             .parse()
             .map_err(|e| format_err!(ConnectionFailed, "error parsing command: {}", e))?;
 
@@ -30,12 +39,152 @@ impl Connection for MockConnection {
             .lock()
             .map_err(|e| format_err!(ConnectionFailed, "error obtaining state lock: {}", e))?;
 
-        match command.command_type {
+        let max_message_size = state.config.max_message_size;
+
+        ensure!(
+            message_len <= max_message_size,
+            RequestError,
+            "command too long: {} bytes (max {})",
+            message_len,
+            max_message_size
+        );
+
+        if command.command_type == Code::SessionMessage && state.fail_next_commands > 0 {
+            state.fail_next_commands -= 1;
+            fail!(ConnectionFailed, "simulated connection failure");
+        }
+
+        if command.command_type == Code::SessionMessage
+            && mem::take(&mut state.corrupt_next_command)
+        {
+            corrupt_mac(&mut command);
+        }
+
+        let response = match command.command_type {
             Code::CreateSession => command::create_session(&mut state, &command),
             Code::AuthenticateSession => command::authenticate_session(&mut state, &command),
             Code::SessionMessage => command::session_message(&mut state, command),
             unsupported => fail!(ConnectionFailed, "unsupported command: {:?}", unsupported),
         }
-        .map(Message::from)
+        .map(Message::from)?;
+
+        ensure!(
+            response.as_ref().len() <= max_message_size,
+            ResponseError,
+            "response too long: {} bytes (max {})",
+            response.as_ref().len(),
+            max_message_size
+        );
+
+        Ok(response)
+    }
+}
+
+/// Flip a bit in a command's C-MAC
+fn corrupt_mac(command: &mut crate::command::Message) {
+    let mut mac = Vec::from(command.mac.as_ref().unwrap().as_slice());
+    mac[0] ^= 0x01;
+    command.mac = Some(Mac::from_slice(&mac));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::MockHsm, corrupt_mac};
+    use crate::{
+        command, connector::Connector, device, device::commands::EchoCommand, response,
+        session::securechannel::SecureChannel, Credentials,
+    };
+    /// Open a channel to the given MockHsm (without authenticating it)
+    fn open_channel(connector: &Connector) -> SecureChannel {
+        SecureChannel::open(connector, &Credentials::default()).unwrap()
+    }
+
+    /// Send a command to the MockHsm, returning its (unverified) response
+    fn send(connector: &Connector, command: command::Message) -> response::Message {
+        let response = connector.send_message(command.uuid, command.into());
+        response::Message::parse(response.unwrap()).unwrap()
+    }
+
+    /// Open and authenticate a channel to the given MockHsm
+    fn authenticated_channel(connector: &Connector) -> SecureChannel {
+        let mut channel = open_channel(connector);
+        let response = send(connector, channel.authenticate_session().unwrap());
+        channel.finish_authenticate_session(&response).unwrap();
+        channel
+    }
+
+    /// Encrypt an `Echo` command
+    fn echo_command(channel: &mut SecureChannel) -> command::Message {
+        let echo = command::Message::from(&EchoCommand {
+            message: b"hello".to_vec(),
+        });
+
+        channel.encrypt_command(echo).unwrap()
+    }
+
+    /// Assert a response is the given device error
+    fn assert_device_error(response: &response::Message, expected: device::ErrorKind) {
+        assert!(response.is_err());
+        assert_eq!(
+            device::ErrorKind::from_response_message(response),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn valid_command_mac_test() {
+        let connector = Connector::mockhsm();
+        let mut channel = authenticated_channel(&connector);
+
+        let response = send(&connector, echo_command(&mut channel));
+        let response = channel.decrypt_response(response).unwrap();
+        assert!(!response.is_err());
+        assert_eq!(response.data, b"hello");
+    }
+
+    #[test]
+    fn corrupted_command_mac_test() {
+        let connector = Connector::mockhsm();
+        let mut channel = authenticated_channel(&connector);
+
+        let mut command = echo_command(&mut channel);
+        corrupt_mac(&mut command);
+
+        let response = send(&connector, command);
+        assert_device_error(&response, device::ErrorKind::AuthenticationFailed);
+
+        // The session is unusable after a MAC failure
+        let response = send(&connector, echo_command(&mut channel));
+        assert_device_error(&response, device::ErrorKind::InvalidSession);
+    }
+
+    #[test]
+    fn replayed_command_test() {
+        let connector = Connector::mockhsm();
+        let mut channel = authenticated_channel(&connector);
+
+        let frame = echo_command(&mut channel).serialize();
+        let command = command::Message::parse(frame.clone()).unwrap();
+        let replayed = command::Message::parse(frame).unwrap();
+
+        let response = send(&connector, command);
+        channel.decrypt_response(response).unwrap();
+
+        let response = send(&connector, replayed);
+        assert_device_error(&response, device::ErrorKind::AuthenticationFailed);
+    }
+
+    #[test]
+    fn corrupted_authenticate_session_mac_test() {
+        let hsm = MockHsm::new();
+        let connector = Connector::from(hsm.clone());
+        let mut channel = open_channel(&connector);
+
+        let mut command = channel.authenticate_session().unwrap();
+        corrupt_mac(&mut command);
+
+        let response = send(&connector, command);
+        assert_device_error(&response, device::ErrorKind::AuthenticationFailed);
+        assert!(hsm.session_ids().is_empty());
     }
 }
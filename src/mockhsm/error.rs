@@ -13,17 +13,33 @@ pub enum ErrorKind {
     #[error("access denied")]
     AccessDenied,
 
+    /// Algorithm can't be used with the type of object being created
+    #[error("algorithm not valid for object type")]
+    AlgorithmTypeMismatch,
+
     /// Crypto error
     #[error("crypto error")]
     CryptoError,
 
+    /// Error reading or writing a file (e.g. a snapshot)
+    #[error("I/O error")]
+    IoError,
+
+    /// Malformed data (e.g. a corrupted wrapped object)
+    #[error("invalid data")]
+    InvalidData,
+
+    /// Object already exists
+    #[error("object already exists")]
+    ObjectExists,
+
     /// Object does not exist
     #[error("object not found")]
     ObjectNotFound,
 
-    /// Unsupported algorithm
-    #[error("unsupported algorithm")]
-    UnsupportedAlgorithm,
+    /// Data too large to fit in a message (e.g. a wrapped object)
+    #[error("payload too large")]
+    PayloadTooLarge,
 }
 
 impl ErrorKind {
@@ -0,0 +1,109 @@
+//! Detection of ECDSA nonce reuse by the MockHsm's signing implementation
+//!
+//! Signing two different messages with the same key and nonce `k` leaks the
+//! private key, so the MockHsm records the `r` component of each signature
+//! (which is determined by `k`) and flags any repeats.
+//!
+//! Only the most recent [`MAX_NONCES`] signatures are remembered, and a
+//! key's signatures are forgotten when it's deleted (so a new key with the
+//! same ID doesn't inherit them).
+
+use crate::object;
+use std::collections::{BTreeMap, VecDeque};
+
+/// Maximum number of signatures remembered by a [`NonceLog`]
+pub(crate) const MAX_NONCES: usize = 0x10000;
+
+/// Log of the nonces used by each ECDSA key
+#[derive(Debug, Default)]
+pub(crate) struct NonceLog {
+    /// Digest signed with each (key ID, `r`) pair
+    digests: BTreeMap<(object::Id, Vec<u8>), Vec<u8>>,
+
+    /// (key ID, `r`) pairs in the order they were recorded, oldest first
+    order: VecDeque<(object::Id, Vec<u8>)>,
+
+    /// Number of times a nonce was reused for a different digest
+    reuses: usize,
+}
+
+impl NonceLog {
+    /// Record a signature of `digest` by the given key with the given `r`,
+    /// returning whether its nonce had already been used to sign a
+    /// different digest.
+    ///
+    /// Signing the same digest again with the same nonce produces the same
+    /// signature (as deterministic nonces do), so it isn't flagged.
+    pub fn record(&mut self, key_id: object::Id, r: &[u8], digest: &[u8]) -> bool {
+        let entry = (key_id, r.to_vec());
+
+        match self.digests.get(&entry) {
+            Some(previous) if previous.as_slice() == digest => false,
+            Some(_) => {
+                debug!("ECDSA nonce reused by key 0x{:04x}!", key_id);
+                self.reuses += 1;
+                true
+            }
+            None => {
+                if self.order.len() == MAX_NONCES {
+                    let oldest = self.order.pop_front().unwrap();
+                    self.digests.remove(&oldest);
+                }
+
+                self.order.push_back(entry.clone());
+                self.digests.insert(entry, digest.into());
+                false
+            }
+        }
+    }
+
+    /// Forget the nonces used by the given key (e.g. when it's deleted)
+    pub fn forget(&mut self, key_id: object::Id) {
+        self.digests.retain(|(id, _), _| *id != key_id);
+        self.order.retain(|(id, _)| *id != key_id);
+    }
+
+    /// Number of nonce reuses detected
+    pub fn reuses(&self) -> usize {
+        self.reuses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_reuse_for_different_digests() {
+        let mut log = NonceLog::default();
+
+        assert!(!log.record(1, b"r1", b"digest1"));
+        assert!(!log.record(1, b"r1", b"digest1"));
+        assert!(!log.record(1, b"r2", b"digest2"));
+        assert!(!log.record(2, b"r1", b"digest2"));
+        assert_eq!(log.reuses(), 0);
+
+        assert!(log.record(1, b"r1", b"digest2"));
+        assert_eq!(log.reuses(), 1);
+
+        log.forget(1);
+        assert!(!log.record(1, b"r1", b"digest2"));
+        assert!(log.record(2, b"r1", b"digest1"));
+        assert_eq!(log.reuses(), 2);
+    }
+
+    #[test]
+    fn forgets_oldest_nonces() {
+        let mut log = NonceLog::default();
+
+        for i in 0..=MAX_NONCES as u32 {
+            assert!(!log.record(1, &i.to_be_bytes(), b"digest1"));
+        }
+
+        assert_eq!(log.digests.len(), MAX_NONCES);
+
+        // The first nonce has been forgotten, but the second hasn't
+        assert!(!log.record(1, &0u32.to_be_bytes(), b"digest2"));
+        assert!(log.record(1, &2u32.to_be_bytes(), b"digest2"));
+    }
+}
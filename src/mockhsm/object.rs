@@ -4,10 +4,11 @@
 
 mod objects;
 mod payload;
+mod snapshot;
 
+pub use self::snapshot::{ObjectsSnapshot, SnapshotObject};
 pub(crate) use self::{objects::Objects, payload::Payload};
 use crate::{object, Algorithm};
-use serde::{Deserialize, Serialize};
 
 /// Label for the default auth key
 const DEFAULT_AUTHENTICATION_KEY_LABEL: &str = "DEFAULT AUTHKEY CHANGE THIS ASAP";
@@ -31,22 +32,6 @@ impl Object {
     }
 }
 
-/// A serialized object which can be exported/imported
-#[derive(Serialize, Deserialize, Debug)]
-pub(crate) struct WrappedObject {
-    pub object_info: object::Info,
-    pub data: Vec<u8>,
-}
-
-impl<'a> From<&'a Object> for WrappedObject {
-    fn from(obj: &'a Object) -> Self {
-        Self {
-            object_info: obj.object_info.clone(),
-            data: obj.payload.to_bytes(),
-        }
-    }
-}
-
 impl<'a> From<&'a Object> for object::Entry {
     fn from(obj: &'a Object) -> Self {
         object::Entry {
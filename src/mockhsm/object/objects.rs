@@ -1,34 +1,67 @@
 //! Objects stored in the `MockHsm`
 
-use super::{Object, Payload, WrappedObject, DEFAULT_AUTHENTICATION_KEY_LABEL};
+use super::{Object, ObjectsSnapshot, Payload, SnapshotObject, DEFAULT_AUTHENTICATION_KEY_LABEL};
 use crate::{
-    authentication::{self, DEFAULT_AUTHENTICATION_KEY_ID},
+    authentication,
     mockhsm::{Error, ErrorKind},
     object::{Handle, Id, Info, Label, Origin, Type},
-    serialization::{deserialize, serialize},
     wrap, Algorithm, Capability, Domain,
 };
 use aes::cipher::consts::{U13, U8};
 use ccm::aead::{AeadInPlace, KeyInit};
+use rand_core::CryptoRngCore;
 use std::collections::{btree_map::Iter as MapIter, BTreeMap as Map};
 
+/// Size of the AES-CCM authentication tag (i.e. `U8`)
+const CCM_TAG_SIZE: usize = 8;
+
 /// AES-CCM with a 128-bit key
 pub(crate) type Aes128Ccm = ccm::Ccm<aes::Aes128, U8, U13>;
 
+/// AES-CCM with a 192-bit key
+pub(crate) type Aes192Ccm = ccm::Ccm<aes::Aes192, U8, U13>;
+
 /// AES-CCM with a 256-bit key
 pub(crate) type Aes256Ccm = ccm::Ccm<aes::Aes256, U8, U13>;
 
 /// AES-CCM key
-#[allow(clippy::large_enum_variant)]
+#[allow(clippy::enum_variant_names, clippy::large_enum_variant)]
 pub(crate) enum AesCcmKey {
     /// AES-CCM with a 128-bit key
     Aes128Ccm(Aes128Ccm),
 
+    /// AES-CCM with a 192-bit key
+    Aes192Ccm(Aes192Ccm),
+
     /// AES-CCM with a 256-bit key
     Aes256Ccm(Aes256Ccm),
 }
 
 impl AesCcmKey {
+    /// Initialize AES-CCM with the given algorithm and key
+    pub fn new(algorithm: wrap::Algorithm, key: &[u8]) -> Result<Self, Error> {
+        ensure!(
+            key.len() == algorithm.key_len(),
+            ErrorKind::CryptoError,
+            "invalid key length for {:?}: {} (expected {})",
+            algorithm,
+            key.len(),
+            algorithm.key_len()
+        );
+
+        Ok(match algorithm {
+            wrap::Algorithm::Aes128Ccm => {
+                AesCcmKey::Aes128Ccm(Aes128Ccm::new_from_slice(key).unwrap())
+            }
+            wrap::Algorithm::Aes192Ccm => {
+                AesCcmKey::Aes192Ccm(Aes192Ccm::new_from_slice(key).unwrap())
+            }
+            wrap::Algorithm::Aes256Ccm => {
+                AesCcmKey::Aes256Ccm(Aes256Ccm::new_from_slice(key).unwrap())
+            }
+        })
+    }
+
     /// Encrypt data in-place.
     #[allow(clippy::ptr_arg)]
     pub fn encrypt_in_place(
@@ -41,6 +74,9 @@ impl AesCcmKey {
             AesCcmKey::Aes128Ccm(ccm) => {
                 ccm.encrypt_in_place(&nonce.0.into(), associated_data, buffer)
             }
+            AesCcmKey::Aes192Ccm(ccm) => {
+                ccm.encrypt_in_place(&nonce.0.into(), associated_data, buffer)
+            }
             AesCcmKey::Aes256Ccm(ccm) => {
                 ccm.encrypt_in_place(&nonce.0.into(), associated_data, buffer)
             }
@@ -60,6 +96,9 @@ impl AesCcmKey {
             AesCcmKey::Aes128Ccm(ccm) => {
                 ccm.decrypt_in_place(&nonce.0.into(), associated_data, buffer)
             }
+            AesCcmKey::Aes192Ccm(ccm) => {
+                ccm.decrypt_in_place(&nonce.0.into(), associated_data, buffer)
+            }
             AesCcmKey::Aes256Ccm(ccm) => {
                 ccm.decrypt_in_place(&nonce.0.into(), associated_data, buffer)
             }
@@ -72,16 +111,17 @@ impl AesCcmKey {
 #[derive(Debug)]
 pub(crate) struct Objects(Map<Handle, Object>);
 
-impl Default for Objects {
-    fn default() -> Self {
+impl Objects {
+    /// Create the objects present after a reset: just the default
+    /// authentication key
+    pub fn new(authentication_key_id: Id, authentication_key: authentication::Key) -> Self {
         let mut objects = Map::new();
 
         // Insert default authentication key
-        let authentication_key_handle =
-            Handle::new(DEFAULT_AUTHENTICATION_KEY_ID, Type::AuthenticationKey);
+        let authentication_key_handle = Handle::new(authentication_key_id, Type::AuthenticationKey);
 
         let authentication_key_info = Info {
-            object_id: DEFAULT_AUTHENTICATION_KEY_ID,
+            object_id: authentication_key_id,
             object_type: Type::AuthenticationKey,
             algorithm: Algorithm::Authentication(authentication::Algorithm::YubicoAes),
             capabilities: Capability::all(),
@@ -93,7 +133,7 @@ impl Default for Objects {
             label: DEFAULT_AUTHENTICATION_KEY_LABEL.into(),
         };
 
-        let authentication_key_payload = Payload::AuthenticationKey(authentication::Key::default());
+        let authentication_key_payload = Payload::AuthenticationKey(authentication_key);
 
         let _ = objects.insert(
             authentication_key_handle,
@@ -105,10 +145,78 @@ impl Default for Objects {
 
         Objects(objects)
     }
-}
 
-impl Objects {
-    /// Generate a new object in the MockHsm
+    /// Restore objects from a snapshot taken with [`Objects::to_snapshot`],
+    /// failing with `ErrorKind::InvalidData` if any of them are malformed
+    pub fn from_snapshot(snapshot: &ObjectsSnapshot) -> Result<Self, Error> {
+        let mut objects = Map::new();
+
+        for (handle, object) in &snapshot.0 {
+            let info = &object.object_info;
+
+            ensure!(
+                *handle == Handle::new(info.object_id, info.object_type),
+                ErrorKind::InvalidData,
+                "handle {:?} doesn't match object info: {:?}",
+                handle,
+                info
+            );
+
+            info.object_type
+                .ensure_algorithm(info.algorithm)
+                .map_err(|e| ErrorKind::InvalidData.context(e))?;
+
+            ensure!(
+                usize::from(info.length) == object.payload_bytes.len(),
+                ErrorKind::InvalidData,
+                "object {:?} is {} bytes long, but its info says {}",
+                handle,
+                object.payload_bytes.len(),
+                info.length
+            );
+
+            let payload = Payload::try_new(info.algorithm, &object.payload_bytes)?;
+
+            ensure!(
+                objects
+                    .insert(
+                        handle.clone(),
+                        Object {
+                            object_info: info.clone(),
+                            payload,
+                        },
+                    )
+                    .is_none(),
+                ErrorKind::InvalidData,
+                "duplicate object in snapshot: {:?}",
+                handle
+            );
+        }
+
+        Ok(Objects(objects))
+    }
+
+    /// Take a snapshot of the objects (including their payloads)
+    pub fn to_snapshot(&self) -> ObjectsSnapshot {
+        ObjectsSnapshot(
+            self.0
+                .iter()
+                .map(|(handle, object)| {
+                    let snapshot_object = SnapshotObject {
+                        object_info: object.object_info.clone(),
+                        payload_bytes: object.payload.to_bytes(),
+                    };
+
+                    (handle.clone(), snapshot_object)
+                })
+                .collect(),
+        )
+    }
+
+    /// Generate a new object in the MockHsm, failing with
+    /// `ErrorKind::AlgorithmTypeMismatch` if the algorithm isn't valid for
+    /// the object type
+    #[allow(clippy::too_many_arguments)]
     pub fn generate(
         &mut self,
         object_id: Id,
@@ -118,8 +226,13 @@ impl Objects {
         capabilities: Capability,
         delegated_capabilities: Capability,
         domains: Domain,
-    ) {
-        let payload = Payload::generate(algorithm);
+        rng: &mut impl CryptoRngCore,
+    ) -> Result<(), Error> {
+        object_type
+            .ensure_algorithm(algorithm)
+            .map_err(|e| ErrorKind::AlgorithmTypeMismatch.context(e))?;
+
+        let payload = Payload::generate(algorithm, rng);
         let length = payload.len();
 
         let object_info = Info {
@@ -143,6 +256,7 @@ impl Objects {
         };
 
         assert!(self.0.insert(handle, object).is_none());
+        Ok(())
     }
 
     /// Get an object
@@ -150,7 +264,9 @@ impl Objects {
         self.0.get(&Handle::new(object_id, object_type))
     }
 
-    /// Put a new object in the MockHsm
+    /// Put a new object in the MockHsm, failing with
+    /// `ErrorKind::AlgorithmTypeMismatch` if the algorithm isn't valid for
+    /// the object type
     pub fn put(
         &mut self,
         object_id: Id,
@@ -161,8 +277,12 @@ impl Objects {
         delegated_capabilities: Capability,
         domains: Domain,
         data: &[u8],
-    ) {
-        let payload = Payload::new(algorithm, data);
+    ) -> Result<(), Error> {
+        object_type
+            .ensure_algorithm(algorithm)
+            .map_err(|e| ErrorKind::AlgorithmTypeMismatch.context(e))?;
+
+        let payload = Payload::try_new(algorithm, data)?;
         let length = payload.len();
 
         let object_info = Info {
@@ -186,6 +306,32 @@ impl Objects {
         };
 
         assert!(self.0.insert(handle, object).is_none());
+        Ok(())
+    }
+
+    /// Replace the key material of an authentication key in one step (so
+    /// sessions either authenticate with the old key or the new one),
+    /// returning whether the key exists
+    pub fn change_authentication_key(
+        &mut self,
+        object_id: Id,
+        authentication_key: authentication::Key,
+    ) -> bool {
+        match self
+            .0
+            .get_mut(&Handle::new(object_id, Type::AuthenticationKey))
+        {
+            Some(object) => {
+                object.payload = Payload::AuthenticationKey(authentication_key);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Number of objects stored
+    pub fn len(&self) -> usize {
+        self.0.len()
     }
 
     /// Remove an object
@@ -193,13 +339,17 @@ impl Objects {
         self.0.remove(&Handle::new(object_id, object_type))
     }
 
-    /// Encrypt and serialize an object as ciphertext
+    /// Encrypt and serialize an object as ciphertext.
+    ///
+    /// Fails with `ErrorKind::PayloadTooLarge` if the wrapped object (along
+    /// with its nonce) would exceed `max_len`, i.e. not fit in a response.
     pub fn wrap_obj(
         &mut self,
         wrap_key_id: Id,
         object_id: Id,
         object_type: Type,
         nonce: &wrap::Nonce,
+        max_len: usize,
     ) -> Result<Vec<u8>, Error> {
         let wrap_key = self.get_wrap_key(wrap_key_id)?;
 
@@ -226,23 +376,27 @@ impl Objects {
             );
         }
 
-        let mut object_info = object_to_wrap.object_info.clone();
+        self.ensure_delegated_by_wrap_key(wrap_key_id, object_to_wrap.object_info.capabilities)?;
 
-        match object_info.origin {
-            Origin::Generated => object_info.origin = Origin::WrappedGenerated,
-            Origin::Imported => object_info.origin = Origin::WrappedImported,
-            Origin::WrappedGenerated | Origin::WrappedImported => (),
-        }
+        let mut wrapped_object = wrap::ObjectHeader::from(object_to_wrap.object_info.clone())
+            .to_bytes(wrap::HeaderFormat::V1);
+        wrapped_object.extend_from_slice(&object_to_wrap.payload.to_bytes());
 
-        let mut wrapped_object = serialize(&WrappedObject {
-            object_info,
-            data: object_to_wrap.payload.to_bytes(),
-        })
-        .unwrap();
+        let wrapped_len = nonce.0.len() + wrapped_object.len() + CCM_TAG_SIZE;
+
+        ensure!(
+            wrapped_len <= max_len,
+            ErrorKind::PayloadTooLarge,
+            "wrapped {:?} object {:?} too large: {} bytes (max {})",
+            object_type,
+            object_id,
+            wrapped_len,
+            max_len
+        );
 
         wrap_key
             .encrypt_in_place(nonce, b"", &mut wrapped_object)
-            .unwrap();
+            .map_err(|e| format_err!(ErrorKind::CryptoError, "error wrapping object: {}", e))?;
 
         Ok(wrapped_object)
     }
@@ -258,24 +412,37 @@ impl Objects {
         let mut wrapped_data: Vec<u8> = ciphertext.into();
         wrap_key.decrypt_in_place(nonce, b"", &mut wrapped_data)?;
 
-        let unwrapped_object: WrappedObject = deserialize(&wrapped_data).unwrap();
+        let (header, data) = wrap::ObjectHeader::parse(wrap::HeaderFormat::V1, &wrapped_data)
+            .map_err(|e| ErrorKind::InvalidData.context(e))?;
 
-        let payload = Payload::new(
-            unwrapped_object.object_info.algorithm,
-            &unwrapped_object.data,
-        );
+        let mut object_info = Info::from(header);
+        self.ensure_delegated_by_wrap_key(wrap_key_id, object_info.capabilities)?;
 
-        let object_key = Handle::new(
-            unwrapped_object.object_info.object_id,
-            unwrapped_object.object_info.object_type,
+        let payload = Payload::try_new(object_info.algorithm, data)?;
+        let object_key = Handle::new(object_info.object_id, object_info.object_type);
+
+        ensure!(
+            !self.0.contains_key(&object_key),
+            ErrorKind::ObjectExists,
+            "object already exists: {:?}",
+            object_key
         );
 
+        // Objects restored from wrapped form are marked as such, while
+        // retaining whether they were originally generated or imported
+
+        match object_info.origin {
+            Origin::Generated => object_info.origin = Origin::WrappedGenerated,
+            Origin::Imported => object_info.origin = Origin::WrappedImported,
+            Origin::WrappedGenerated | Origin::WrappedImported => (),
+        }
+
         let object = Object {
-            object_info: unwrapped_object.object_info,
+            object_info,
             payload,
         };
 
-        assert!(self.0.insert(object_key.clone(), object).is_none());
+        self.0.insert(object_key.clone(), object);
 
         Ok(object_key)
     }
@@ -285,6 +452,30 @@ impl Objects {
         self.0.iter()
     }
 
+    /// Ensure objects with the given capabilities can be wrapped (or
+    /// unwrapped) under a wrap key, i.e. they're all among its delegated
+    /// capabilities
+    fn ensure_delegated_by_wrap_key(
+        &self,
+        wrap_key_id: Id,
+        capabilities: Capability,
+    ) -> Result<(), Error> {
+        let delegated = self
+            .get(wrap_key_id, Type::WrapKey)
+            .map(|wrap_key| wrap_key.object_info.delegated_capabilities)
+            .unwrap_or_default();
+
+        ensure!(
+            delegated.contains(capabilities),
+            ErrorKind::AccessDenied,
+            "wrap key {:?} doesn't delegate capabilities: {:?}",
+            wrap_key_id,
+            capabilities - delegated
+        );
+
+        Ok(())
+    }
+
     /// Get a wrapping key
     fn get_wrap_key(&self, wrap_key_id: Id) -> Result<AesCcmKey, Error> {
         let wrap_key = match self.get(wrap_key_id, Type::WrapKey) {
@@ -296,21 +487,169 @@ impl Objects {
             ),
         };
 
-        match wrap_key.algorithm().wrap().unwrap() {
-            wrap::Algorithm::Aes128Ccm => Ok(AesCcmKey::Aes128Ccm(
-                Aes128Ccm::new_from_slice(&wrap_key.payload.to_bytes()).unwrap(),
-            )),
-            wrap::Algorithm::Aes256Ccm => Ok(AesCcmKey::Aes256Ccm(
-                Aes256Ccm::new_from_slice(&wrap_key.payload.to_bytes()).unwrap(),
-            )),
-            unsupported => fail!(
-                ErrorKind::UnsupportedAlgorithm,
-                "unsupported wrap key algorithm: {:?}",
-                unsupported
-            ),
-        }
+        AesCcmKey::new(
+            wrap_key.algorithm().wrap().unwrap(),
+            &wrap_key.payload.to_bytes(),
+        )
     }
 }
 
 /// Iterator over objects
 pub(crate) type Iter<'a> = MapIter<'a, Handle, Object>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{asymmetric, hmac, opaque};
+    use rand_core::OsRng;
+
+    fn objects() -> Objects {
+        Objects::new(1, authentication::Key::default())
+    }
+
+    fn generate(
+        objects: &mut Objects,
+        object_type: Type,
+        algorithm: Algorithm,
+    ) -> Result<(), Error> {
+        objects.generate(
+            2,
+            object_type,
+            algorithm,
+            Label::default(),
+            Capability::default(),
+            Capability::default(),
+            Domain::DOM1,
+            &mut OsRng,
+        )
+    }
+
+    fn put(
+        objects: &mut Objects,
+        object_type: Type,
+        algorithm: Algorithm,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        objects.put(
+            3,
+            object_type,
+            algorithm,
+            Label::default(),
+            Capability::default(),
+            Capability::default(),
+            Domain::DOM1,
+            data,
+        )
+    }
+
+    #[test]
+    fn algorithm_type_mismatch_test() {
+        let mut objects = objects();
+
+        for (object_type, algorithm) in [
+            (Type::WrapKey, asymmetric::Algorithm::EcP256.into()),
+            (Type::AsymmetricKey, wrap::Algorithm::Aes128Ccm.into()),
+            (Type::HmacKey, opaque::Algorithm::Data.into()),
+            (Type::Opaque, hmac::Algorithm::Sha256.into()),
+        ] {
+            let err = generate(&mut objects, object_type, algorithm).unwrap_err();
+            assert_eq!(*err.kind(), ErrorKind::AlgorithmTypeMismatch);
+
+            let err = put(&mut objects, object_type, algorithm, &[0; 32]).unwrap_err();
+            assert_eq!(*err.kind(), ErrorKind::AlgorithmTypeMismatch);
+        }
+
+        assert_eq!(objects.len(), 1);
+    }
+
+    #[test]
+    fn algorithm_type_match_test() {
+        let mut objects = objects();
+
+        generate(
+            &mut objects,
+            Type::AsymmetricKey,
+            asymmetric::Algorithm::Ed25519.into(),
+        )
+        .unwrap();
+        put(
+            &mut objects,
+            Type::Opaque,
+            opaque::Algorithm::Data.into(),
+            b"hello",
+        )
+        .unwrap();
+        put(
+            &mut objects,
+            Type::WrapKey,
+            wrap::Algorithm::Aes128Ccm.into(),
+            &[0; 16],
+        )
+        .unwrap();
+
+        assert_eq!(objects.len(), 4);
+    }
+
+    #[test]
+    fn invalid_payload_test() {
+        let mut objects = objects();
+
+        for (algorithm, data) in [
+            (asymmetric::Algorithm::EcP256, &[0x42; 31][..]),
+            (asymmetric::Algorithm::EcP256, &[0; 32]),
+            (asymmetric::Algorithm::Ed25519, &[0x42; 33]),
+            (asymmetric::Algorithm::Rsa2048, &[0; 256]),
+        ] {
+            let err = put(&mut objects, Type::AsymmetricKey, algorithm.into(), data).unwrap_err();
+            assert_eq!(*err.kind(), ErrorKind::InvalidData);
+        }
+
+        let err = put(
+            &mut objects,
+            Type::WrapKey,
+            wrap::Algorithm::Aes256Ccm.into(),
+            &[0; 16],
+        )
+        .unwrap_err();
+        assert_eq!(*err.kind(), ErrorKind::InvalidData);
+
+        assert_eq!(objects.len(), 1);
+    }
+
+    #[test]
+    fn invalid_snapshot_test() {
+        let mut objects = objects();
+        generate(
+            &mut objects,
+            Type::AsymmetricKey,
+            asymmetric::Algorithm::EcP256.into(),
+        )
+        .unwrap();
+
+        let snapshot = objects.to_snapshot();
+        assert!(Objects::from_snapshot(&snapshot).is_ok());
+
+        // Duplicate objects
+        let mut duplicated = snapshot.clone();
+        duplicated.0.push(duplicated.0[0].clone());
+
+        // Payloads which don't match their length
+        let mut truncated = snapshot.clone();
+        truncated.0[1].1.payload_bytes.pop();
+
+        // Payloads which aren't valid keys
+        let mut zeroed = snapshot.clone();
+        zeroed.0[1].1.payload_bytes = vec![0; 32];
+
+        // Payloads which are the right length for their info, but not their
+        // algorithm
+        let mut wrong_length = snapshot;
+        wrong_length.0[1].1.payload_bytes.pop();
+        wrong_length.0[1].1.object_info.length = 31;
+
+        for snapshot in [duplicated, truncated, zeroed, wrong_length] {
+            let err = Objects::from_snapshot(&snapshot).err().unwrap();
+            assert_eq!(*err.kind(), ErrorKind::InvalidData);
+        }
+    }
+}
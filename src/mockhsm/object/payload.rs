@@ -1,10 +1,23 @@
 //! Object "payloads" in the MockHsm are instances of software implementations
 //! of supported cryptographic primitives, already initialized with a private key
 
-use crate::{algorithm::Algorithm, asymmetric, authentication, hmac, opaque, wrap};
+use crate::{
+    algorithm::Algorithm,
+    asymmetric, authentication, hmac,
+    mockhsm::{Error, ErrorKind},
+    opaque, template, wrap,
+};
 use ecdsa::elliptic_curve::sec1::ToEncodedPoint;
 use ed25519_dalek as ed25519;
-use rand_core::{OsRng, RngCore};
+use num_bigint::{BigUint, ModInverse};
+use rand_core::CryptoRngCore;
+use rsa::{
+    traits::{PrivateKeyParts, PublicKeyParts},
+    RsaPrivateKey,
+};
+
+/// RSA public exponent used by the YubiHSM (F4)
+const RSA_PUBLIC_EXPONENT: u32 = 65537;
 
 /// Loaded instances of a cryptographic primitives in the MockHsm
 #[derive(Debug)]
@@ -15,9 +28,17 @@ pub(crate) enum Payload {
     /// ECDSA/P-256 signing key
     EcdsaNistP256(p256::SecretKey),
 
+    /// ECDSA/P-384 key (which the MockHsm can't sign with yet)
+    EcdsaNistP384(p384::SecretKey),
+
     /// ECDSA/secp256k1 signing key,
     EcdsaSecp256k1(k256::SecretKey),
 
+    /// ECDSA/Brainpool signing key. These are stored as raw scalars, as
+    /// there's no curve arithmetic for them: they can be listed, wrapped and
+    /// deleted, but not used.
+    EcdsaBrainpool(asymmetric::Algorithm, Vec<u8>),
+
     /// Ed25519 signing key
     Ed25519Key(ed25519::SigningKey),
 
@@ -27,58 +48,135 @@ pub(crate) enum Payload {
     /// Opaque data
     Opaque(opaque::Algorithm, Vec<u8>),
 
+    /// RSA private key
+    RsaKey(RsaPrivateKey),
+
+    /// Certificate template (e.g. for an SSH CA)
+    Template(template::Algorithm, Vec<u8>),
+
     /// Wrapping (i.e. symmetric encryption keys)
     WrapKey(wrap::Algorithm, Vec<u8>),
 }
 
 impl Payload {
-    /// Create a new payload from the given algorithm and data
-    pub fn new(algorithm: Algorithm, data: &[u8]) -> Self {
-        match algorithm {
+    /// Create a new payload from the given algorithm and data, failing with
+    /// `ErrorKind::InvalidData` if the data isn't a valid key (or other
+    /// object) of the given algorithm, e.g. because its length is wrong
+    pub fn try_new(algorithm: Algorithm, data: &[u8]) -> Result<Self, Error> {
+        let expected_len = match algorithm {
+            Algorithm::Asymmetric(alg) => Some(alg.key_len()),
+            Algorithm::Authentication(_) => Some(authentication::key::SIZE),
+            Algorithm::Wrap(alg) => Some(alg.key_len()),
+            _ => None,
+        };
+
+        if let Some(expected_len) = expected_len {
+            ensure!(
+                data.len() == expected_len,
+                ErrorKind::InvalidData,
+                "expected {}-byte {:?} object data (got {})",
+                expected_len,
+                algorithm,
+                data.len()
+            );
+        }
+
+        Ok(match algorithm {
             Algorithm::Wrap(alg) => Payload::WrapKey(alg, data.into()),
             Algorithm::Asymmetric(asymmetric_alg) => match asymmetric_alg {
-                asymmetric::Algorithm::EcP256 => {
-                    assert_eq!(data.len(), 32);
-                    Payload::EcdsaNistP256(p256::SecretKey::from_slice(data).unwrap())
-                }
-                asymmetric::Algorithm::EcK256 => {
-                    assert_eq!(data.len(), 32);
-                    Payload::EcdsaSecp256k1(k256::SecretKey::from_slice(data).unwrap())
-                }
-                asymmetric::Algorithm::Ed25519 => {
-                    assert_eq!(data.len(), ed25519::SECRET_KEY_LENGTH);
-                    Payload::Ed25519Key(ed25519::SigningKey::try_from(data).unwrap())
+                asymmetric::Algorithm::EcP256 => Payload::EcdsaNistP256(
+                    p256::SecretKey::from_slice(data)
+                        .map_err(|e| ErrorKind::InvalidData.context(e))?,
+                ),
+                asymmetric::Algorithm::EcP384 => Payload::EcdsaNistP384(
+                    p384::SecretKey::from_slice(data)
+                        .map_err(|e| ErrorKind::InvalidData.context(e))?,
+                ),
+                asymmetric::Algorithm::EcK256 => Payload::EcdsaSecp256k1(
+                    k256::SecretKey::from_slice(data)
+                        .map_err(|e| ErrorKind::InvalidData.context(e))?,
+                ),
+                asymmetric::Algorithm::EcBp256
+                | asymmetric::Algorithm::EcBp384
+                | asymmetric::Algorithm::EcBp512 => {
+                    Payload::EcdsaBrainpool(asymmetric_alg, data.into())
                 }
-                _ => {
-                    panic!("MockHsm doesn't support this asymmetric algorithm: {asymmetric_alg:?}")
+                asymmetric::Algorithm::Ed25519 => Payload::Ed25519Key(
+                    ed25519::SigningKey::try_from(data)
+                        .map_err(|e| ErrorKind::InvalidData.context(e))?,
+                ),
+                asymmetric::Algorithm::Rsa2048
+                | asymmetric::Algorithm::Rsa3072
+                | asymmetric::Algorithm::Rsa4096 => {
+                    // RSA keys are imported as the primes `p` and `q`
+                    let (p, q) = data.split_at(data.len() / 2);
+                    let key =
+                        rsa_key_from_primes(BigUint::from_bytes_be(p), BigUint::from_bytes_be(q))?;
+
+                    ensure!(
+                        key.size() == data.len(),
+                        ErrorKind::InvalidData,
+                        "RSA primes don't produce a {}-bit modulus",
+                        data.len() * 8
+                    );
+
+                    Payload::RsaKey(key)
                 }
+                _ => fail!(
+                    ErrorKind::InvalidData,
+                    "MockHsm doesn't support this asymmetric algorithm: {:?}",
+                    asymmetric_alg
+                ),
             },
             Algorithm::Hmac(alg) => Payload::HmacKey(alg, data.into()),
             Algorithm::Opaque(alg) => Payload::Opaque(alg, data.into()),
-            Algorithm::Authentication(_) => {
-                Payload::AuthenticationKey(authentication::Key::from_slice(data).unwrap())
-            }
-            _ => panic!("MockHsm does not support putting {algorithm:?} objects"),
-        }
+            Algorithm::Template(alg) => Payload::Template(alg, data.into()),
+            Algorithm::Authentication(_) => Payload::AuthenticationKey(
+                authentication::Key::from_slice(data)
+                    .map_err(|e| ErrorKind::InvalidData.context(e))?,
+            ),
+            _ => fail!(
+                ErrorKind::InvalidData,
+                "MockHsm does not support putting {:?} objects",
+                algorithm
+            ),
+        })
     }
 
     /// Generate a new key with the given algorithm
-    pub fn generate(algorithm: Algorithm) -> Self {
+    pub fn generate(algorithm: Algorithm, rng: &mut impl CryptoRngCore) -> Self {
         match algorithm {
             Algorithm::Wrap(wrap_alg) => {
                 let mut bytes = vec![0u8; wrap_alg.key_len()];
-                OsRng.fill_bytes(&mut bytes);
+                rng.fill_bytes(&mut bytes);
                 Payload::WrapKey(wrap_alg, bytes)
             }
             Algorithm::Asymmetric(asymmetric_alg) => match asymmetric_alg {
                 asymmetric::Algorithm::EcP256 => {
-                    Payload::EcdsaNistP256(p256::SecretKey::random(&mut OsRng))
+                    Payload::EcdsaNistP256(p256::SecretKey::random(rng))
+                }
+                asymmetric::Algorithm::EcP384 => {
+                    Payload::EcdsaNistP384(p384::SecretKey::random(rng))
                 }
                 asymmetric::Algorithm::EcK256 => {
-                    Payload::EcdsaSecp256k1(k256::SecretKey::random(&mut OsRng))
+                    Payload::EcdsaSecp256k1(k256::SecretKey::random(rng))
+                }
+                asymmetric::Algorithm::EcBp256
+                | asymmetric::Algorithm::EcBp384
+                | asymmetric::Algorithm::EcBp512 => {
+                    // Not reduced modulo the curve order, which doesn't
+                    // matter as long as the key can't be used
+                    let mut bytes = vec![0u8; asymmetric_alg.key_len()];
+                    rng.fill_bytes(&mut bytes);
+                    Payload::EcdsaBrainpool(asymmetric_alg, bytes)
                 }
                 asymmetric::Algorithm::Ed25519 => {
-                    Payload::Ed25519Key(ed25519::SigningKey::generate(&mut OsRng))
+                    Payload::Ed25519Key(ed25519::SigningKey::generate(rng))
+                }
+                asymmetric::Algorithm::Rsa2048
+                | asymmetric::Algorithm::Rsa3072
+                | asymmetric::Algorithm::Rsa4096 => {
+                    Payload::RsaKey(RsaPrivateKey::new(rng, asymmetric_alg.key_len() * 8).unwrap())
                 }
                 _ => {
                     panic!("MockHsm doesn't support this asymmetric algorithm: {asymmetric_alg:?}")
@@ -86,7 +184,7 @@ impl Payload {
             },
             Algorithm::Hmac(hmac_alg) => {
                 let mut bytes = vec![0u8; hmac_alg.key_len()];
-                OsRng.fill_bytes(&mut bytes);
+                rng.fill_bytes(&mut bytes);
                 Payload::HmacKey(hmac_alg, bytes)
             }
             _ => panic!("MockHsm does not support generating {algorithm:?} objects"),
@@ -100,10 +198,19 @@ impl Payload {
                 Algorithm::Authentication(authentication::Algorithm::YubicoAes)
             }
             Payload::EcdsaNistP256(_) => Algorithm::Asymmetric(asymmetric::Algorithm::EcP256),
+            Payload::EcdsaNistP384(_) => Algorithm::Asymmetric(asymmetric::Algorithm::EcP384),
             Payload::EcdsaSecp256k1(_) => Algorithm::Asymmetric(asymmetric::Algorithm::EcK256),
+            Payload::EcdsaBrainpool(alg, _) => Algorithm::Asymmetric(alg),
             Payload::Ed25519Key(_) => Algorithm::Asymmetric(asymmetric::Algorithm::Ed25519),
             Payload::HmacKey(alg, _) => alg.into(),
             Payload::Opaque(alg, _) => alg.into(),
+            Payload::RsaKey(ref k) => Algorithm::Asymmetric(match k.size() {
+                256 => asymmetric::Algorithm::Rsa2048,
+                384 => asymmetric::Algorithm::Rsa3072,
+                512 => asymmetric::Algorithm::Rsa4096,
+                size => panic!("unsupported RSA key size: {size}"),
+            }),
+            Payload::Template(alg, _) => alg.into(),
             Payload::WrapKey(alg, _) => alg.into(),
         }
     }
@@ -113,9 +220,13 @@ impl Payload {
         let l = match self {
             Payload::AuthenticationKey(_) => authentication::key::SIZE,
             Payload::EcdsaNistP256(_) | Payload::EcdsaSecp256k1(_) => 32,
+            Payload::EcdsaNistP384(_) => 48,
+            Payload::EcdsaBrainpool(_, ref data) => data.len(),
             Payload::Ed25519Key(_) => ed25519::SECRET_KEY_LENGTH,
             Payload::HmacKey(_, ref data) => data.len(),
             Payload::Opaque(_, ref data) => data.len(),
+            Payload::RsaKey(ref k) => k.size(),
+            Payload::Template(_, ref data) => data.len(),
             Payload::WrapKey(_, ref data) => data.len(),
         };
         l as u16
@@ -127,10 +238,14 @@ impl Payload {
             Payload::EcdsaNistP256(secret_key) => {
                 Some(secret_key.public_key().to_encoded_point(false).as_bytes()[1..].into())
             }
+            Payload::EcdsaNistP384(secret_key) => {
+                Some(secret_key.public_key().to_encoded_point(false).as_bytes()[1..].into())
+            }
             Payload::EcdsaSecp256k1(secret_key) => {
                 Some(secret_key.public_key().to_encoded_point(false).as_bytes()[1..].into())
             }
             Payload::Ed25519Key(signing_key) => Some(signing_key.verifying_key().to_bytes().into()),
+            Payload::RsaKey(k) => Some(pad(k.n().to_bytes_be(), k.size())),
             _ => None,
         }
     }
@@ -148,11 +263,47 @@ impl Payload {
         match self {
             Payload::AuthenticationKey(k) => k.0.as_ref().into(),
             Payload::EcdsaNistP256(k) => k.to_bytes().to_vec(),
+            Payload::EcdsaNistP384(k) => k.to_bytes().to_vec(),
             Payload::EcdsaSecp256k1(k) => k.to_bytes().to_vec(),
-            Payload::Ed25519Key(k) => k.verifying_key().to_bytes().into(),
+            Payload::EcdsaBrainpool(_, data) => data.clone(),
+            Payload::Ed25519Key(k) => k.to_bytes().into(),
             Payload::HmacKey(_, data) => data.clone(),
             Payload::Opaque(_, data) => data.clone(),
+            Payload::RsaKey(k) => k
+                .primes()
+                .iter()
+                .flat_map(|p| pad(p.to_bytes_be(), k.size() / 2))
+                .collect(),
+            Payload::Template(_, data) => data.clone(),
             Payload::WrapKey(_, data) => data.clone(),
         }
     }
 }
+
+/// Reconstruct an RSA private key from its primes
+fn rsa_key_from_primes(p: BigUint, q: BigUint) -> Result<RsaPrivateKey, Error> {
+    let one = BigUint::from(1u8);
+
+    ensure!(
+        p > one && q > one,
+        ErrorKind::InvalidData,
+        "invalid RSA primes"
+    );
+
+    let e = BigUint::from(RSA_PUBLIC_EXPONENT);
+    let totient = (&p - &one) * (&q - &one);
+    let d = match (&e).mod_inverse(&totient).and_then(|d| d.to_biguint()) {
+        Some(d) => d,
+        None => fail!(ErrorKind::InvalidData, "invalid RSA primes"),
+    };
+
+    Ok(RsaPrivateKey::from_components(&p * &q, e, d, vec![p, q])
+        .map_err(|e| ErrorKind::InvalidData.context(e))?)
+}
+
+/// Left-pad a big endian integer with zeroes to the given length
+fn pad(bytes: Vec<u8>, len: usize) -> Vec<u8> {
+    let mut padded = vec![0u8; len.saturating_sub(bytes.len())];
+    padded.extend(bytes);
+    padded
+}
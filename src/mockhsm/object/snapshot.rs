@@ -0,0 +1,77 @@
+//! Snapshots of the objects stored in the `MockHsm`, for persisting them
+//! between test runs
+
+use crate::{
+    mockhsm::{Error, ErrorKind},
+    object::{Handle, Info},
+};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+/// Snapshot of all of the objects stored in a `MockHsm` (see
+/// [`MockHsm::snapshot_objects`](crate::mockhsm::MockHsm::snapshot_objects)),
+/// which can be saved to and loaded from a JSON file.
+///
+/// Snapshots contain the objects' secret keys in plaintext, so they must
+/// only ever be used with test keys.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ObjectsSnapshot(pub(crate) Vec<(Handle, SnapshotObject)>);
+
+impl ObjectsSnapshot {
+    /// Load a snapshot from a JSON file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path).map_err(|e| {
+            format_err!(
+                ErrorKind::IoError,
+                "couldn't read {}: {}",
+                path.display(),
+                e
+            )
+        })?;
+
+        serde_json::from_str(&json).map_err(|e| ErrorKind::InvalidData.context(e).into())
+    }
+
+    /// Save this snapshot to a JSON file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let json = serde_json::to_string(self).unwrap();
+
+        fs::write(path, json).map_err(|e| {
+            format_err!(
+                ErrorKind::IoError,
+                "couldn't write {}: {}",
+                path.display(),
+                e
+            )
+            .into()
+        })
+    }
+
+    /// Number of objects in the snapshot
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Is the snapshot empty?
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterate over the objects in the snapshot
+    pub fn iter(&self) -> impl Iterator<Item = (&Handle, &SnapshotObject)> {
+        self.0.iter().map(|(handle, object)| (handle, object))
+    }
+}
+
+/// An object in an [`ObjectsSnapshot`]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SnapshotObject {
+    /// Information about the object
+    pub object_info: Info,
+
+    /// The object's payload (e.g. secret key) in the same serialized form
+    /// as when it's exported under wrap
+    pub payload_bytes: Vec<u8>,
+}
@@ -3,7 +3,7 @@
 use std::fmt::{self, Debug};
 
 use crate::{
-    command, response,
+    command, device, object, response,
     session::{
         securechannel::{Challenge, Cryptogram, SecureChannel},
         Id,
@@ -15,6 +15,9 @@ pub(crate) struct HsmSession {
     /// ID of the session
     pub id: Id,
 
+    /// ID of the authentication key this session was opened with
+    pub authentication_key_id: object::Id,
+
     /// Card challenge for this session
     pub card_challenge: Challenge,
 
@@ -24,9 +27,15 @@ pub(crate) struct HsmSession {
 
 impl HsmSession {
     /// Create a new session
-    pub fn new(id: Id, card_challenge: Challenge, channel: SecureChannel) -> Self {
+    pub fn new(
+        id: Id,
+        authentication_key_id: object::Id,
+        card_challenge: Challenge,
+        channel: SecureChannel,
+    ) -> Self {
         Self {
             id,
+            authentication_key_id,
             card_challenge,
             channel,
         }
@@ -42,9 +51,28 @@ impl HsmSession {
         self.channel.card_cryptogram()
     }
 
-    /// Decrypt an incoming command
-    pub fn decrypt_command(&mut self, command: command::Message) -> command::Message {
-        self.channel.decrypt_command(command).unwrap()
+    /// Verify the host cryptogram and C-MAC of an `AuthenticateSession`
+    /// command, returning the device error the YubiHSM 2 would respond with
+    /// if either is incorrect
+    pub fn authenticate(
+        &mut self,
+        command: &command::Message,
+    ) -> Result<response::Message, device::ErrorKind> {
+        self.channel
+            .verify_authenticate_session(command)
+            .map_err(|e| {
+                debug!("session authentication failed: {}", e);
+                device::ErrorKind::AuthenticationFailed
+            })
+    }
+
+    /// Decrypt an incoming command, returning the device error the YubiHSM 2
+    /// would respond with if it's malformed
+    pub fn decrypt_command(
+        &mut self,
+        command: command::Message,
+    ) -> Result<command::Message, device::ErrorKind> {
+        self.channel.decrypt_command(command)
     }
 
     /// Encrypt an outgoing response
@@ -55,6 +83,11 @@ impl HsmSession {
 
 impl Debug for HsmSession {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "mockhsm::Session {{ id: {} }}", self.id.to_u8())
+        write!(
+            f,
+            "mockhsm::Session {{ id: {}, authentication_key_id: {} }}",
+            self.id.to_u8(),
+            self.authentication_key_id
+        )
     }
 }
@@ -1,20 +1,36 @@
 //! `MockHsm` presents a thread-safe API by locking interior mutable state,
 //! contained in the `State` struct defined in this module.
 
-use super::{audit::CommandAuditOptions, object::Objects, session::HsmSession};
+use super::{
+    attestation::DeviceAttestation,
+    audit::{AuditLog, CommandAuditOptions},
+    builder::Config,
+    nonce::NonceLog,
+    object::Objects,
+    session::HsmSession,
+    SignatureFault,
+};
 use crate::{
     audit::AuditOption,
-    connector, object,
+    connector, device, object,
     session::{
         self,
-        securechannel::{Challenge, SecureChannel},
+        securechannel::{Challenge, SecureChannel, CHALLENGE_SIZE},
     },
 };
+use rand_chacha::ChaCha20Rng;
+use rand_core::{RngCore, SeedableRng};
 use std::collections::BTreeMap;
 
 /// Mutable interior state of the `MockHsm`
 #[derive(Debug)]
 pub(crate) struct State {
+    /// Device attestation key and certificate
+    pub(super) attestation: DeviceAttestation,
+
+    /// Audit log of commands performed
+    pub(super) audit_log: AuditLog,
+
     /// Command-specific audit options
     pub(super) command_audit_options: CommandAuditOptions,
 
@@ -22,21 +38,68 @@ pub(crate) struct State {
     /// via the `SetLogIndex` command.
     pub(super) force_audit: AuditOption,
 
+    /// Values of other device options (keyed by option ID)
+    pub(super) options: BTreeMap<u8, Vec<u8>>,
+
     /// Active sessions with the MockHsm
     sessions: BTreeMap<session::Id, HsmSession>,
 
     /// Objects within the MockHsm (i.e. keys)
     pub(super) objects: Objects,
+
+    /// Settings the MockHsm was built with
+    pub(super) config: Config,
+
+    /// Random number generator (seeded from the config, if configured)
+    pub(super) rng: ChaCha20Rng,
+
+    /// Corrupt the MAC of the next session message (to simulate errors)
+    pub(super) corrupt_next_command: bool,
+
+    /// Number of session messages to fail as if the connection dropped (to
+    /// simulate transient errors)
+    pub(super) fail_next_commands: usize,
+
+    /// Corrupt the next ECDSA signature in the given way (to simulate
+    /// faults)
+    pub(super) signature_fault: Option<SignatureFault>,
+
+    /// Corrupt the key stored by the next authentication key change (to
+    /// simulate faults)
+    pub(super) corrupt_next_key_change: bool,
+
+    /// Nonces used by ECDSA signatures (to detect reuse)
+    pub(super) ecdsa_nonces: NonceLog,
+
+    /// Nonce `k` to sign ECDSA signatures with instead of a random one (to
+    /// simulate a faulty RNG)
+    pub(super) fixed_ecdsa_nonce: Option<[u8; 32]>,
 }
 
 impl State {
     /// Create a new instance of the server's mutable interior state
-    pub fn new() -> Self {
+    pub fn new(config: Config) -> Self {
+        let mut rng = match config.seed {
+            Some(seed) => ChaCha20Rng::from_seed(seed),
+            None => ChaCha20Rng::from_entropy(),
+        };
+
         Self {
+            attestation: DeviceAttestation::generate(&mut rng),
+            audit_log: AuditLog::default(),
             command_audit_options: CommandAuditOptions::default(),
             force_audit: AuditOption::Off,
+            options: BTreeMap::new(),
             sessions: BTreeMap::new(),
-            objects: Objects::default(),
+            objects: Objects::new(config.default_auth_key_id, config.auth_key.clone()),
+            config,
+            rng,
+            corrupt_next_command: false,
+            fail_next_commands: 0,
+            signature_fault: None,
+            corrupt_next_key_change: false,
+            ecdsa_nonces: NonceLog::default(),
+            fixed_ecdsa_nonce: None,
         }
     }
 
@@ -45,24 +108,23 @@ impl State {
         &mut self,
         authentication_key_id: object::Id,
         host_challenge: Challenge,
-    ) -> &HsmSession {
+    ) -> Result<&HsmSession, device::ErrorKind> {
         // Generate a random card challenge to send back to the client
-        let card_challenge = Challenge::new();
+        let mut challenge_bytes = [0u8; CHALLENGE_SIZE];
+        self.rng.fill_bytes(&mut challenge_bytes);
+        let card_challenge = Challenge::from_slice(&challenge_bytes);
 
-        let session_id = self
-            .sessions
-            .keys()
-            .max()
-            .map(|id| id.succ().expect("session count exceeded"))
-            .unwrap_or_else(|| session::Id::from_u8(0).unwrap());
+        // Allocate the lowest free session slot
+        let session_id = (0..self.config.max_sessions)
+            .map(|id| session::Id::from_u8(id).unwrap())
+            .find(|id| !self.sessions.contains_key(id))
+            .ok_or(device::ErrorKind::SessionsFull)?;
 
         let channel = {
             let authentication_key_obj = self
                 .objects
                 .get(authentication_key_id, object::Type::AuthenticationKey)
-                .unwrap_or_else(|| {
-                    panic!("MockHsm has no authentication::Key in slot {authentication_key_id:?}")
-                });
+                .ok_or(device::ErrorKind::ObjectNotFound)?;
 
             SecureChannel::new(
                 session_id,
@@ -75,10 +137,10 @@ impl State {
             )
         };
 
-        let session = HsmSession::new(session_id, card_challenge, channel);
+        let session = HsmSession::new(session_id, authentication_key_id, card_challenge, channel);
         assert!(self.sessions.insert(session_id, session).is_none());
 
-        self.get_session(session_id).unwrap()
+        Ok(self.get_session(session_id).unwrap())
     }
 
     /// Obtain the channel for a session by its ID
@@ -93,15 +155,37 @@ impl State {
         })
     }
 
+    /// IDs of the active sessions
+    pub fn session_ids(&self) -> Vec<session::Id> {
+        self.sessions.keys().copied().collect()
+    }
+
     /// Close an active session
     pub fn close_session(&mut self, id: session::Id) {
         assert!(self.sessions.remove(&id).is_some());
     }
 
+    /// Close the session with the given ID (if it's open), returning whether
+    /// it was
+    pub fn expire_session(&mut self, id: session::Id) -> bool {
+        self.sessions.remove(&id).is_some()
+    }
+
     /// Reset the internal HSM state, closing all connections
     pub fn reset(&mut self) {
+        self.audit_log = AuditLog::default();
         self.command_audit_options = CommandAuditOptions::default();
+        self.options = BTreeMap::new();
+        self.ecdsa_nonces = NonceLog::default();
         self.sessions = BTreeMap::new();
-        self.objects = Objects::default();
+        self.objects = Objects::new(
+            self.config.default_auth_key_id,
+            self.config.auth_key.clone(),
+        );
+    }
+
+    /// Is there no room left to store another object?
+    pub fn storage_full(&self) -> bool {
+        self.objects.len() >= self.config.max_objects
     }
 }
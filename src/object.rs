@@ -10,7 +10,7 @@ mod filter;
 pub(crate) mod generate;
 mod handle;
 mod info;
-mod label;
+pub(crate) mod label;
 mod origins;
 pub mod put;
 mod types;
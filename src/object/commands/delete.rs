@@ -21,6 +21,10 @@ pub(crate) struct DeleteObjectCommand {
 
 impl Command for DeleteObjectCommand {
     type ResponseType = DeleteObjectResponse;
+
+    fn object(&self) -> Option<object::Handle> {
+        Some(object::Handle::new(self.object_id, self.object_type))
+    }
 }
 
 /// Response from `command::delete_object`
@@ -15,6 +15,10 @@ pub(crate) struct GetObjectInfoCommand(pub(crate) object::Handle);
 
 impl Command for GetObjectInfoCommand {
     type ResponseType = GetObjectInfoResponse;
+
+    fn object(&self) -> Option<object::Handle> {
+        Some(self.0.clone())
+    }
 }
 
 /// Response from `command::get_object_info`
@@ -2,9 +2,13 @@
 
 use crate::object;
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 
-/// Brief information about an object as included in `ListObjectsCommand`
-#[derive(Clone, Debug, Deserialize, Serialize)]
+/// Brief information about an object as included in `ListObjectsCommand`.
+///
+/// Entries are ordered by object ID, then type (and lastly sequence), so
+/// sorting a listing orders it the same way regardless of the HSM's order.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct Entry {
     /// Object identifier
     pub object_id: object::Id,
@@ -16,3 +20,50 @@ pub struct Entry {
     /// previously existed
     pub sequence: object::SequenceId,
 }
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.object_id, self.object_type, self.sequence).cmp(&(
+            other.object_id,
+            other.object_type,
+            other.sequence,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sort_by_id_then_type() {
+        let entry = |object_id, object_type| Entry {
+            object_id,
+            object_type,
+            sequence: 0,
+        };
+
+        let mut entries = vec![
+            entry(2, object::Type::AsymmetricKey),
+            entry(1, object::Type::WrapKey),
+            entry(1, object::Type::AuthenticationKey),
+        ];
+
+        entries.sort();
+
+        assert_eq!(
+            entries,
+            [
+                entry(1, object::Type::AuthenticationKey),
+                entry(1, object::Type::WrapKey),
+                entry(2, object::Type::AsymmetricKey),
+            ]
+        );
+    }
+}
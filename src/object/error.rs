@@ -9,6 +9,14 @@ pub type Error = crate::Error<ErrorKind>;
 /// Kinds of `Object`-related errors
 #[derive(Copy, Clone, Debug, Eq, Error, PartialEq)]
 pub enum ErrorKind {
+    /// Algorithm can't be used with this type of object
+    #[error("algorithm not valid for object type")]
+    AlgorithmTypeMismatch,
+
+    /// Invalid object handle (i.e. ID/type combination)
+    #[error("invalid object handle")]
+    HandleInvalid,
+
     /// Invalid label
     #[error("invalid label")]
     LabelInvalid,
@@ -1,6 +1,6 @@
 //! Object handles
 
-use crate::object;
+use crate::object::{self, Error, ErrorKind};
 use serde::{Deserialize, Serialize};
 
 /// Objects in the HSM are keyed by a tuple of their type an object::Id
@@ -14,8 +14,29 @@ pub struct Handle {
     pub object_type: object::Type,
 }
 
+/// Highest ID usable for user objects (`0xffff` denotes "no object",
+/// e.g. in audit log entries)
+const MAX_USER_OBJECT_ID: object::Id = 0xfffe;
+
 impl Handle {
-    /// Create a new object handle
+    /// Create a new object handle, ensuring the ID can refer to a user object.
+    ///
+    /// ID 0 is reserved: it's used to request the HSM assign an ID when
+    /// generating or putting objects, and refers to the device attestation
+    /// key (the only object with a fixed ID). ID `0xffff` is also reserved.
+    pub fn try_new(object_id: object::Id, object_type: object::Type) -> Result<Self, Error> {
+        ensure!(
+            object_id != 0 && object_id <= MAX_USER_OBJECT_ID,
+            ErrorKind::HandleInvalid,
+            "object ID 0x{:04x} is reserved (type: {:?})",
+            object_id,
+            object_type
+        );
+
+        Ok(Self::new(object_id, object_type))
+    }
+
+    /// Create a new object handle (without validating it)
     pub fn new(object_id: object::Id, object_type: object::Type) -> Self {
         Self {
             object_id,
@@ -23,3 +44,23 @@ impl Handle {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_new_test() {
+        for (object_id, object_type) in [
+            (0, object::Type::AsymmetricKey),
+            (0, object::Type::AuthenticationKey),
+            (0xffff, object::Type::Opaque),
+        ] {
+            let err = Handle::try_new(object_id, object_type).unwrap_err();
+            assert_eq!(*err.kind(), ErrorKind::HandleInvalid);
+        }
+
+        assert!(Handle::try_new(1, object::Type::AuthenticationKey).is_ok());
+        assert!(Handle::try_new(MAX_USER_OBJECT_ID, object::Type::WrapKey).is_ok());
+    }
+}
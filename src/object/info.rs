@@ -3,9 +3,17 @@
 use super::SequenceId;
 use crate::{object, Algorithm, Capability, Domain};
 use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
 
 /// Information about an object
-#[derive(Serialize, Deserialize, Clone, Debug)]
+///
+/// Its `Display` impl summarizes the object's access control settings on one
+/// line, e.g. for debugging permission issues:
+///
+/// ```text
+/// id=0x0064 type=asymmetric-key algorithm=Asymmetric(Ed25519) domains=1-3 capabilities=[exportable-under-wrap, sign-eddsa] delegated=[]
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
 pub struct Info {
     /// Capabilities (bitfield)
     pub capabilities: Capability,
@@ -35,6 +43,57 @@ pub struct Info {
     /// Label of object
     pub label: object::Label,
 
-    /// Delegated Capabilities (bitfield)
+    /// Delegated Capabilities (bitfield): for authentication keys, the
+    /// capabilities objects they create may have, and for wrap keys, the
+    /// capabilities objects exported or imported under them may have
     pub delegated_capabilities: Capability,
 }
+
+impl Display for Info {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let domains = if self.domains.is_empty() {
+            "none".to_owned()
+        } else {
+            self.domains.to_range_string()
+        };
+
+        write!(
+            f,
+            "id=0x{:04x} type={} algorithm={:?} domains={} capabilities=[{}] delegated=[{}]",
+            self.object_id,
+            self.object_type,
+            self.algorithm,
+            domains,
+            self.capabilities.names().join(", "),
+            self.delegated_capabilities.names().join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asymmetric;
+
+    #[test]
+    fn display_info() {
+        let info = Info {
+            capabilities: Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP,
+            object_id: 100,
+            length: 32,
+            domains: Domain::DOM1 | Domain::DOM2 | Domain::DOM3 | Domain::DOM5,
+            object_type: object::Type::AsymmetricKey,
+            algorithm: Algorithm::Asymmetric(asymmetric::Algorithm::Ed25519),
+            sequence: 0,
+            origin: object::Origin::Generated,
+            label: "yubihsm.rs test key".into(),
+            delegated_capabilities: Capability::empty(),
+        };
+
+        assert_eq!(
+            info.to_string(),
+            "id=0x0064 type=asymmetric-key algorithm=Asymmetric(Ed25519) domains=1-3,5 \
+             capabilities=[exportable-under-wrap, sign-eddsa] delegated=[]"
+        );
+    }
+}
@@ -101,6 +101,8 @@ impl PartialEq for Label {
     }
 }
 
+impl Eq for Label {}
+
 impl Deref for Label {
     type Target = [u8; LABEL_SIZE];
 
@@ -116,3 +118,46 @@ impl DerefMut for Label {
 }
 
 impl_array_serializers!(Label, LABEL_SIZE);
+
+/// Serialize labels as strings (rather than as their raw bytes, like the
+/// HSM's wire format) in human-readable reports
+pub(crate) mod text {
+    use super::Label;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(label: &Label, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(label)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Label, D::Error> {
+        let label = String::deserialize(deserializer)?;
+        label
+            .parse()
+            .map_err(|_| de::Error::custom(format!("invalid label: {label}")))
+    }
+
+    /// Serialize optional labels as strings (or none)
+    pub(crate) mod option {
+        use super::Label;
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        pub fn serialize<S: Serializer>(
+            label: &Option<Label>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            label
+                .as_ref()
+                .map(ToString::to_string)
+                .serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Label>, D::Error> {
+            #[derive(Deserialize)]
+            struct Text(#[serde(with = "super")] Label);
+
+            Ok(Option::<Text>::deserialize(deserializer)?.map(|Text(label)| label))
+        }
+    }
+}
@@ -67,6 +67,16 @@ impl<'de> Deserialize<'de> for Origin {
             {
                 Origin::from_u8(value).map_err(E::custom)
             }
+
+            // Self-describing formats (e.g. JSON) deserialize integers as `u64`
+            fn visit_u64<E>(self, value: u64) -> Result<Origin, E>
+            where
+                E: de::Error,
+            {
+                u8::try_from(value)
+                    .map_err(|_| E::custom(format!("invalid object origin: {value}")))
+                    .and_then(|value| self.visit_u8(value))
+            }
         }
 
         deserializer.deserialize_u8(OriginVisitor)
@@ -1,6 +1,7 @@
 //! Types of objects
 
 use super::{Error, ErrorKind};
+use crate::Algorithm;
 use serde::{de, ser, Deserialize, Serialize};
 use std::{fmt, str::FromStr};
 
@@ -48,6 +49,31 @@ impl Type {
     pub fn to_u8(self) -> u8 {
         self as u8
     }
+
+    /// Ensure objects of this type can have the given algorithm (e.g. wrap
+    /// keys must have a `wrap::Algorithm`), failing with
+    /// `ErrorKind::AlgorithmTypeMismatch` if not
+    pub fn ensure_algorithm(self, algorithm: Algorithm) -> Result<(), Error> {
+        let compatible = match self {
+            Type::Opaque => matches!(algorithm, Algorithm::Opaque(_)),
+            Type::AuthenticationKey => matches!(algorithm, Algorithm::Authentication(_)),
+            Type::AsymmetricKey => matches!(algorithm, Algorithm::Asymmetric(_)),
+            Type::WrapKey => matches!(algorithm, Algorithm::Wrap(_)),
+            Type::HmacKey => matches!(algorithm, Algorithm::Hmac(_)),
+            Type::Template => matches!(algorithm, Algorithm::Template(_)),
+            Type::OtpAeadKey => matches!(algorithm, Algorithm::YubicoOtp(_)),
+        };
+
+        ensure!(
+            compatible,
+            ErrorKind::AlgorithmTypeMismatch,
+            "{:?} is not a valid algorithm for {} objects",
+            algorithm,
+            self
+        );
+
+        Ok(())
+    }
 }
 
 impl fmt::Display for Type {
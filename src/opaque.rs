@@ -1,6 +1,7 @@
 //! Opaque object support: store arbitrary data in the HSM
 
 mod algorithm;
+pub(crate) mod chunked;
 pub(crate) mod commands;
 
-pub use self::algorithm::Algorithm;
+pub use self::{algorithm::Algorithm, chunked::MAX_CHUNKED_SIZE};
@@ -0,0 +1,118 @@
+//! Payloads too large for a single opaque object, split across opaque
+//! objects with consecutive IDs (see [`Client::put_opaque_chunked`])
+//!
+//! [`Client::put_opaque_chunked`]: crate::Client::put_opaque_chunked
+
+/// Maximum total size of a chunked payload
+pub const MAX_CHUNKED_SIZE: usize = 64 * 1024;
+
+/// Size of the header at the start of each chunk: its index, the total
+/// number of chunks, and the length and CRC-32 of the whole payload
+pub(crate) const HEADER_SIZE: usize = 12;
+
+/// Header prefixed to each chunk of a chunked payload
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Header {
+    /// Index of this chunk
+    pub index: u16,
+
+    /// Total number of chunks
+    pub total: u16,
+
+    /// Length of the whole payload
+    pub len: u32,
+
+    /// CRC-32 checksum of the whole payload
+    pub checksum: u32,
+}
+
+impl Header {
+    /// Parse the header from the start of a chunk, returning it along with
+    /// the chunk's data
+    pub fn parse(chunk: &[u8]) -> Option<(Self, &[u8])> {
+        if chunk.len() < HEADER_SIZE {
+            return None;
+        }
+
+        let (header, data) = chunk.split_at(HEADER_SIZE);
+        let header = Self {
+            index: u16::from_be_bytes([header[0], header[1]]),
+            total: u16::from_be_bytes([header[2], header[3]]),
+            len: u32::from_be_bytes(header[4..8].try_into().unwrap()),
+            checksum: u32::from_be_bytes(header[8..12].try_into().unwrap()),
+        };
+
+        if header.index >= header.total {
+            return None;
+        }
+
+        Some((header, data))
+    }
+
+    /// Serialize this header followed by the chunk's data
+    pub fn to_chunk(self, data: &[u8]) -> Vec<u8> {
+        let mut chunk = Vec::with_capacity(HEADER_SIZE + data.len());
+        chunk.extend_from_slice(&self.index.to_be_bytes());
+        chunk.extend_from_slice(&self.total.to_be_bytes());
+        chunk.extend_from_slice(&self.len.to_be_bytes());
+        chunk.extend_from_slice(&self.checksum.to_be_bytes());
+        chunk.extend_from_slice(data);
+        chunk
+    }
+}
+
+/// Compute the CRC-32 (IEEE 802.3) checksum of the given data
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for &byte in data {
+        crc ^= u32::from(byte);
+
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn header_round_trip() {
+        let header = Header {
+            index: 1,
+            total: 3,
+            len: 5000,
+            checksum: 0xdead_beef,
+        };
+
+        let chunk = header.to_chunk(b"data");
+        assert_eq!(chunk.len(), HEADER_SIZE + 4);
+        assert_eq!(Header::parse(&chunk), Some((header, b"data".as_ref())));
+    }
+
+    #[test]
+    fn invalid_index() {
+        let header = Header {
+            index: 3,
+            total: 3,
+            len: 0,
+            checksum: 0,
+        };
+
+        assert_eq!(Header::parse(&header.to_chunk(&[])), None);
+        assert_eq!(Header::parse(&[0; HEADER_SIZE - 1]), None);
+    }
+}
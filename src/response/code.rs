@@ -107,10 +107,7 @@ impl Code {
         let code = i16::from(byte).checked_sub(0x80).unwrap() as i8;
 
         Ok(match code {
-            0..=0x7F => Code::Success(
-                command::Code::from_u8(code as u8)
-                    .map_err(|e| format_err!(ErrorKind::CodeInvalid, "{}", e.kind()))?,
-            ),
+            0..=0x7F => Code::Success(command::Code::from_u8(code as u8)),
             -1 => Code::MemoryError,
             -2 => Code::InitError,
             -3 => Code::ConnectionError,
@@ -148,7 +145,7 @@ impl Code {
     /// Convert a Code back into its original byte form
     pub fn to_u8(self) -> u8 {
         let code: i8 = match self {
-            Code::Success(cmd_type) => cmd_type as i8,
+            Code::Success(cmd_type) => cmd_type.to_u8() as i8,
             Code::MemoryError => -1,
             Code::InitError => -2,
             Code::ConnectionError => -3,
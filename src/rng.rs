@@ -0,0 +1,114 @@
+//! Random number generation with the HSM, via the [`rand_core`] traits
+//!
+//! This allows randomness for e.g. software key generation to be sourced
+//! from the HSM's (audited) Get Pseudo Random command.
+//!
+//! <https://developers.yubico.com/YubiHSM2/Commands/Get_Pseudo_Random.html>
+
+use crate::Client;
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroizing;
+
+/// Default number of random bytes fetched from the HSM at a time
+pub const DEFAULT_BLOCK_SIZE: usize = 1024;
+
+/// Random number generator backed by the HSM's Get Pseudo Random command.
+///
+/// Random data is fetched from the HSM in blocks (of [`DEFAULT_BLOCK_SIZE`]
+/// bytes unless configured otherwise with [`HsmRng::block_size`]) and handed
+/// out from a buffer, so each block is only requested once and every byte
+/// is used exactly once. Unused bytes are zeroized when the RNG is dropped.
+///
+/// Errors communicating with the HSM are returned from
+/// [`RngCore::try_fill_bytes`]. The infallible [`RngCore`] methods
+/// (`next_u32`, `next_u64` and `fill_bytes`) panic on errors instead, since
+/// falling back to some other source of randomness would defeat the point,
+/// so prefer `try_fill_bytes` where the HSM may be unavailable.
+pub struct HsmRng {
+    /// Client used to fetch random data
+    client: Client,
+
+    /// Number of bytes to fetch from the HSM at a time
+    block_size: usize,
+
+    /// Most recently fetched block
+    buffer: Zeroizing<Vec<u8>>,
+
+    /// Position of the next unused byte in `buffer`
+    position: usize,
+}
+
+impl HsmRng {
+    /// Create a new RNG which fetches random data with the given client
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            block_size: DEFAULT_BLOCK_SIZE,
+            buffer: Zeroizing::new(vec![]),
+            position: 0,
+        }
+    }
+
+    /// Set the number of bytes fetched from the HSM at a time. Any bytes
+    /// already buffered are still used before the next block is fetched.
+    ///
+    /// # Panics
+    ///
+    /// If `block_size` is zero.
+    pub fn block_size(mut self, block_size: usize) -> Self {
+        assert!(block_size > 0, "block size must be nonzero");
+        self.block_size = block_size;
+        self
+    }
+
+    /// Number of fetched bytes which haven't been used yet
+    pub fn buffered(&self) -> usize {
+        self.buffer.len() - self.position
+    }
+}
+
+impl RngCore for HsmRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        if let Err(e) = self.try_fill_bytes(dest) {
+            panic!("error getting random data from HSM: {e}");
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        let mut filled = 0;
+
+        while filled < dest.len() {
+            if self.buffered() == 0 {
+                self.buffer = Zeroizing::new(
+                    self.client
+                        .get_pseudo_random(self.block_size)
+                        .map_err(rand_core::Error::new)?,
+                );
+                self.position = 0;
+            }
+
+            let len = self.buffered().min(dest.len() - filled);
+            dest[filled..filled + len]
+                .copy_from_slice(&self.buffer[self.position..self.position + len]);
+
+            self.position += len;
+            filled += len;
+        }
+
+        Ok(())
+    }
+}
+
+impl CryptoRng for HsmRng {}
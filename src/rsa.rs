@@ -8,6 +8,8 @@
 // TODO(tarcieri): finalize and test RSA support
 
 mod algorithm;
+#[cfg(feature = "rsa-import")]
+pub mod import;
 pub mod mgf;
 
 pub mod oaep;
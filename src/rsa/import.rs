@@ -0,0 +1,218 @@
+//! Importing existing RSA private keys into the HSM.
+//!
+//! The YubiHSM 2's `Put Asymmetric Key` command takes RSA keys as their
+//! prime factors `p` and `q`. This module converts keys from the formats
+//! they're usually exported in: PKCS#1 or PKCS#8 (PEM or DER), or the
+//! modulus along with the public and private exponents (in which case the
+//! primes are recovered from the private exponent).
+
+mod error;
+
+pub use self::error::{Error, ErrorKind};
+
+use crate::asymmetric;
+use rsa::{
+    pkcs1::DecodeRsaPrivateKey,
+    pkcs8::DecodePrivateKey,
+    traits::{PrivateKeyParts, PublicKeyParts},
+    BigUint, RsaPrivateKey,
+};
+use std::fmt::{self, Debug};
+use zeroize::Zeroizing;
+
+/// Public exponent supported by the YubiHSM 2 (F4)
+const PUBLIC_EXPONENT: u32 = 65537;
+
+/// Bases to try when recovering the prime factors of the modulus. Each one
+/// succeeds with probability at least 1/2, so running out is practically
+/// impossible for a valid key.
+const MAX_FACTORING_BASE: u32 = 256;
+
+/// RSA private key in the form the YubiHSM 2 imports it: its primes `p` and
+/// `q` (with `p > q`), each left-padded to half the size of the modulus
+#[derive(Clone)]
+pub struct Components {
+    /// Asymmetric algorithm corresponding to the key size
+    algorithm: asymmetric::Algorithm,
+
+    /// Concatenated primes `p || q`
+    primes: Zeroizing<Vec<u8>>,
+}
+
+impl Components {
+    /// Asymmetric algorithm to import the key as (based on its size)
+    pub fn algorithm(&self) -> asymmetric::Algorithm {
+        self.algorithm
+    }
+
+    /// Borrow the concatenated primes `p || q` as sent to the HSM
+    pub fn as_secret_slice(&self) -> &[u8] {
+        &self.primes
+    }
+}
+
+impl Debug for Components {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Avoid leaking secrets in debug messages
+        write!(f, "yubihsm::rsa::import::Components({:?})", self.algorithm)
+    }
+}
+
+impl TryFrom<&RsaPrivateKey> for Components {
+    type Error = Error;
+
+    fn try_from(key: &RsaPrivateKey) -> Result<Self, Error> {
+        let algorithm = match key.n().bits() {
+            2048 => asymmetric::Algorithm::Rsa2048,
+            3072 => asymmetric::Algorithm::Rsa3072,
+            4096 => asymmetric::Algorithm::Rsa4096,
+            bits => fail!(
+                ErrorKind::KeySizeUnsupported,
+                "{}-bit RSA keys are unsupported (must be 2048, 3072, or 4096 bits)",
+                bits
+            ),
+        };
+
+        ensure!(
+            *key.e() == BigUint::from(PUBLIC_EXPONENT),
+            ErrorKind::ExponentUnsupported,
+            "public exponent must be {}",
+            PUBLIC_EXPONENT
+        );
+
+        ensure!(
+            key.primes().len() == 2,
+            ErrorKind::KeyInvalid,
+            "multi-prime RSA keys are unsupported (got {} primes)",
+            key.primes().len()
+        );
+
+        // Order the primes `p > q` (as in PKCS#1) regardless of how the key
+        // was obtained, so the same key always imports identically
+        let mut primes = [&key.primes()[0], &key.primes()[1]];
+        primes.sort_by(|a, b| b.cmp(a));
+
+        let prime_len = algorithm.key_len() / 2;
+        let mut bytes = Zeroizing::new(vec![0u8; prime_len * 2]);
+
+        for (prime, chunk) in primes.iter().zip(bytes.chunks_mut(prime_len)) {
+            let prime_bytes = Zeroizing::new(prime.to_bytes_be());
+
+            ensure!(
+                prime_bytes.len() <= prime_len,
+                ErrorKind::KeyInvalid,
+                "prime factor is larger than {} bytes",
+                prime_len
+            );
+
+            chunk[prime_len - prime_bytes.len()..].copy_from_slice(&prime_bytes);
+        }
+
+        Ok(Self {
+            algorithm,
+            primes: bytes,
+        })
+    }
+}
+
+/// Import a key from its big endian modulus `n`, public exponent `e`, and
+/// private exponent `d`, recovering its primes `p` and `q`.
+///
+/// The primes are recovered by factoring `n` with the method from
+/// NIST SP 800-56B Rev. 2 Appendix C.2, which uses the fact that `d * e - 1`
+/// is a multiple of `lcm(p - 1, q - 1)`.
+pub fn from_components(n: &[u8], e: &[u8], d: &[u8]) -> Result<Components, Error> {
+    let n = BigUint::from_bytes_be(n);
+    let e = BigUint::from_bytes_be(e);
+    let d = BigUint::from_bytes_be(d);
+    let (p, q) = recover_primes(&n, &e, &d)?;
+
+    let key = RsaPrivateKey::from_components(n, e, d, vec![p, q])
+        .map_err(|e| ErrorKind::KeyInvalid.context(e))?;
+
+    key.validate()
+        .map_err(|e| ErrorKind::KeyInvalid.context(e))?;
+
+    Components::try_from(&key)
+}
+
+/// Import a PEM-encoded PKCS#8 (`BEGIN PRIVATE KEY`) or PKCS#1
+/// (`BEGIN RSA PRIVATE KEY`) private key
+pub fn from_pem(pem: &str) -> Result<Components, Error> {
+    let key = RsaPrivateKey::from_pkcs8_pem(pem)
+        .or_else(|_| RsaPrivateKey::from_pkcs1_pem(pem))
+        .map_err(|e| ErrorKind::KeyInvalid.context(e))?;
+
+    Components::try_from(&key)
+}
+
+/// Import a DER-encoded PKCS#8 or PKCS#1 private key
+pub fn from_der(der: &[u8]) -> Result<Components, Error> {
+    let key = RsaPrivateKey::from_pkcs8_der(der)
+        .or_else(|_| RsaPrivateKey::from_pkcs1_der(der))
+        .map_err(|e| ErrorKind::KeyInvalid.context(e))?;
+
+    Components::try_from(&key)
+}
+
+/// Factor the modulus `n` given the public and private exponents
+fn recover_primes(n: &BigUint, e: &BigUint, d: &BigUint) -> Result<(BigUint, BigUint), Error> {
+    let one = BigUint::from(1u8);
+
+    ensure!(
+        *n > one && *e > one && *d > one,
+        ErrorKind::KeyInvalid,
+        "inconsistent RSA key components"
+    );
+
+    let n_minus_one = n - &one;
+
+    // Write `d * e - 1` as `2^t * r` with `r` odd
+    let k = d * e - &one;
+    let t = match k.trailing_zeros() {
+        Some(t) if t > 0 => t,
+        _ => fail!(ErrorKind::KeyInvalid, "inconsistent RSA key components"),
+    };
+    let r = &k >> t;
+
+    for g in 2..MAX_FACTORING_BASE {
+        let mut y = BigUint::from(g).modpow(&r, n);
+
+        if y == one || y == n_minus_one {
+            continue;
+        }
+
+        for _ in 0..t {
+            let x = y.modpow(&BigUint::from(2u8), n);
+
+            if x == one {
+                // `y` is a nontrivial square root of 1, so `gcd(y - 1, n)`
+                // is a prime factor of `n`
+                let p = gcd(&y - &one, n.clone());
+                let q = n / &p;
+                return Ok((p, q));
+            }
+
+            if x == n_minus_one {
+                break;
+            }
+
+            y = x;
+        }
+    }
+
+    fail!(ErrorKind::KeyInvalid, "couldn't factor RSA modulus");
+}
+
+/// Greatest common divisor (Euclid's algorithm)
+fn gcd(mut a: BigUint, mut b: BigUint) -> BigUint {
+    let zero = BigUint::from(0u8);
+
+    while b != zero {
+        let remainder = &a % &b;
+        a = b;
+        b = remainder;
+    }
+
+    a
+}
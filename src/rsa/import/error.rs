@@ -0,0 +1,30 @@
+//! RSA key import errors
+
+use crate::error::{BoxError, Context};
+use thiserror::Error;
+
+/// RSA key import errors
+pub type Error = crate::Error<ErrorKind>;
+
+/// Kinds of RSA key import errors
+#[derive(Copy, Clone, Debug, Eq, Error, PartialEq)]
+pub enum ErrorKind {
+    /// Public exponent isn't supported by the YubiHSM 2
+    #[error("unsupported public exponent")]
+    ExponentUnsupported,
+
+    /// Key couldn't be decoded or its components are inconsistent
+    #[error("invalid RSA key")]
+    KeyInvalid,
+
+    /// Modulus isn't one of the sizes supported by the YubiHSM 2
+    #[error("unsupported key size")]
+    KeySizeUnsupported,
+}
+
+impl ErrorKind {
+    /// Create an error context from this error
+    pub fn context(self, source: impl Into<BoxError>) -> Context<ErrorKind> {
+        Context::new(self, Some(source.into()))
+    }
+}
@@ -4,7 +4,7 @@ mod de;
 mod error;
 mod ser;
 
-pub use self::error::{Error, ErrorKind};
+pub use self::error::Error;
 use std::io::Cursor;
 
 /// Serialize a message into a byte vector
@@ -45,6 +45,9 @@ impl de::Error for Error {
 
 impl From<io::Error> for Error {
     fn from(err: io::Error) -> Self {
-        ErrorKind::Io.context(err).into()
+        match err.kind() {
+            io::ErrorKind::UnexpectedEof => ErrorKind::UnexpectedEof.context(err).into(),
+            _ => ErrorKind::Io.context(err).into(),
+        }
     }
 }
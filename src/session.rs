@@ -9,6 +9,7 @@ mod macros;
 pub(crate) mod commands;
 mod error;
 mod guard;
+mod health;
 mod id;
 pub(crate) mod securechannel;
 mod timeout;
@@ -16,19 +17,28 @@ mod timeout;
 pub use self::{
     error::{Error, ErrorKind},
     guard::Guard,
+    health::Health,
     id::Id,
     timeout::Timeout,
 };
 
-use self::{commands::CloseSessionCommand, securechannel::SecureChannel};
+use self::{
+    commands::CloseSessionCommand, health::HEALTH_CHECK_MESSAGE_SIZE, securechannel::SecureChannel,
+};
 use crate::{
     authentication::Credentials,
+    client::ConnectionStats,
     command::{self, Command},
-    connector::Connector,
-    device, response,
+    connector::{self, Connector},
+    device::{self, commands::EchoCommand},
+    object, response,
     serialization::deserialize,
 };
-use std::time::{Duration, Instant};
+use rand_core::{OsRng, RngCore};
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 /// Timeout fuzz factor: to avoid races/skew with the YubiHSM's clock,
 /// we consider sessions to be timed out slightly earlier than the actual
@@ -40,12 +50,17 @@ const TIMEOUT_FUZZ_FACTOR: Duration = Duration::from_secs(1);
 /// Authenticated and encrypted (SCP03) `Session` with the HSM. A `Session` is
 /// needed to perform any command.
 ///
-/// `Session`s are automatically closed on `Drop`, releasing HSM session
-/// resources and wiping the ephemeral keys used to encrypt the session.
+/// Dropping a `Session` only aborts it, wiping the ephemeral keys used to
+/// encrypt the session without releasing its slot on the HSM (which would
+/// block on a round trip). Use [`Session::close`] to release it; a `Client`
+/// does this when its last clone is dropped.
 pub struct Session {
     /// ID for this session
     id: Id,
 
+    /// ID of the authentication key used to open this session
+    authentication_key_id: object::Id,
+
     /// Connector which communicates with the HSM (HTTP or USB)
     connector: Connector,
 
@@ -60,14 +75,23 @@ pub struct Session {
 
     /// Inactivity timeout for this session
     timeout: Timeout,
+
+    /// Maximum time to wait for the HSM to respond to each message (if any)
+    command_timeout: Option<Duration>,
+
+    /// Statistics to record each command sent to the HSM in (if any)
+    stats: Option<Arc<Mutex<ConnectionStats>>>,
 }
 
 impl Session {
-    /// Connect to the HSM using the given configuration and credentials
+    /// Connect to the HSM using the given configuration and credentials,
+    /// recording the commands sent (including the ones which open the
+    /// session) in `stats` (if given)
     pub(super) fn open(
         connector: Connector,
         credentials: &Credentials,
         timeout: Timeout,
+        stats: Option<Arc<Mutex<ConnectionStats>>>,
     ) -> Result<Self, Error> {
         ensure!(
             timeout.duration() > TIMEOUT_FUZZ_FACTOR,
@@ -76,16 +100,21 @@ impl Session {
             TIMEOUT_FUZZ_FACTOR
         );
 
-        let channel = SecureChannel::open(&connector, credentials)?;
+        let channel = record_command(stats.as_ref(), command::Code::CreateSession, || {
+            SecureChannel::open(&connector, credentials)
+        })?;
         let now = Instant::now();
 
         let mut session = Session {
             id: channel.id(),
+            authentication_key_id: credentials.authentication_key_id,
             connector,
             secure_channel: Some(channel),
             created_at: now,
             last_active: now,
             timeout,
+            command_timeout: None,
+            stats,
         };
 
         session.authenticate(credentials)?;
@@ -93,6 +122,13 @@ impl Session {
         Ok(session)
     }
 
+    /// Bound the time spent waiting on the HSM for each message. Sessions
+    /// where this elapses are aborted, as the HSM may still process the
+    /// command (leaving the MAC chain out of sync).
+    pub(crate) fn set_command_timeout(&mut self, command_timeout: Option<Duration>) {
+        self.command_timeout = command_timeout;
+    }
+
     /// Is this `Session` still open?
     pub fn is_open(&self) -> bool {
         self.secure_channel.is_some() && !self.is_timed_out()
@@ -103,6 +139,11 @@ impl Session {
         self.id
     }
 
+    /// ID of the authentication key used to open this session
+    pub fn authentication_key_id(&self) -> object::Id {
+        self.authentication_key_id
+    }
+
     /// How long has this session been open?
     pub fn duration(&self) -> Duration {
         Instant::now().duration_since(self.created_at)
@@ -116,24 +157,91 @@ impl Session {
             .map(SecureChannel::counter)
     }
 
+    /// How long has it been since this session was last active?
+    pub fn idle_time(&self) -> Duration {
+        Instant::now().duration_since(self.last_active)
+    }
+
     /// Has this session timed out?
     pub fn is_timed_out(&self) -> bool {
-        let idle_time = Instant::now().duration_since(self.last_active);
         let timeout_with_fuzz = self.timeout.duration() - TIMEOUT_FUZZ_FACTOR;
-        idle_time >= timeout_with_fuzz
+        self.idle_time() >= timeout_with_fuzz
     }
 
-    /// Close this session, consuming it in the process.
-    pub fn close(mut self) -> Result<(), Error> {
-        // Only attempt to close the session if we have an active secure
-        // channel and our session hasn't already timed out
-        if self.secure_channel.is_none() || self.is_timed_out() {
-            return Ok(());
+    /// Check this session is still usable by having the HSM echo random data,
+    /// ensuring the echoed payload matches and the MAC chain advanced by
+    /// exactly one message. Sessions found to be stale are aborted.
+    pub(crate) fn verify(&mut self) -> Health {
+        if self.is_timed_out() {
+            self.abort();
         }
 
-        session_debug!(self, "closing session");
-        self.send_command(&CloseSessionCommand {})?;
-        Ok(())
+        let messages_sent = match self.messages_sent() {
+            Ok(n) => n,
+            Err(reason) => return Health::SessionStale { reason },
+        };
+
+        let mut message = vec![0u8; HEALTH_CHECK_MESSAGE_SIZE];
+        OsRng.fill_bytes(&mut message);
+
+        let response = match self.send_command(&EchoCommand {
+            message: message.clone(),
+        }) {
+            Ok(response) => response,
+            Err(err) if *err.kind() == ErrorKind::ProtocolError => {
+                return match err.into_source().map(|e| e.downcast::<connector::Error>()) {
+                    Some(Ok(source)) => Health::ConnectorDown { source: *source },
+                    Some(Err(source)) => Health::SessionStale {
+                        reason: ErrorKind::ProtocolError.context(source).into(),
+                    },
+                    None => Health::SessionStale {
+                        reason: ErrorKind::ProtocolError.into(),
+                    },
+                };
+            }
+            Err(reason) => {
+                self.abort();
+                return Health::SessionStale { reason };
+            }
+        };
+
+        if response.0 != message {
+            session_error!(self, "health check failed: echo payload mismatch");
+            self.abort();
+
+            return Health::SessionStale {
+                reason: format_err!(ErrorKind::VerifyFailed, "echo payload mismatch").into(),
+            };
+        }
+
+        match self.messages_sent() {
+            Ok(n) if n == messages_sent + 1 => Health::Healthy,
+            Ok(n) => {
+                session_error!(
+                    self,
+                    "health check failed: MAC chain at {} (expected {})",
+                    n,
+                    messages_sent + 1
+                );
+                self.abort();
+
+                Health::SessionStale {
+                    reason: format_err!(
+                        ErrorKind::VerifyFailed,
+                        "MAC chain at {} after echo (expected {})",
+                        n,
+                        messages_sent + 1
+                    )
+                    .into(),
+                }
+            }
+            Err(reason) => Health::SessionStale { reason },
+        }
+    }
+
+    /// Close this session, consuming it in the process.
+    pub fn close(mut self) -> Result<(), Error> {
+        self.close_channel()
     }
 
     /// Abort this session, terminating it without closing it
@@ -141,13 +249,28 @@ impl Session {
         self.secure_channel = None;
     }
 
+    /// Close this session (best effort) after an error which leaves its
+    /// secure channel in doubt, e.g. a MAC failure or a malformed message.
+    ///
+    /// The HSM may have already closed the session, or may reject the close
+    /// command because the MAC chains are no longer in sync, so errors are
+    /// only logged. Either way the session is terminated afterwards.
+    pub(crate) fn reset(&mut self) {
+        session_debug!(self, "resetting session after protocol error");
+
+        if let Err(e) = self.close_channel() {
+            session_debug!(self, "error closing session: {}", e);
+        }
+
+        self.abort();
+    }
+
     /// Encrypt a command, send it to the HSM, then read and decrypt the response
     pub(crate) fn send_command<C: Command>(
         &mut self,
         command: &C,
     ) -> Result<C::ResponseType, Error> {
         let plaintext_cmd = command::Message::from(command);
-        let cmd_type = plaintext_cmd.command_type;
 
         let encrypted_cmd = self
             .secure_channel()?
@@ -167,7 +290,34 @@ impl Session {
             C::COMMAND_CODE
         );
 
-        let encrypted_response = self.send_message(encrypted_cmd)?;
+        let stats = self.stats.clone();
+        record_command(stats.as_ref(), C::COMMAND_CODE, || {
+            self.send_encrypted_command::<C>(encrypted_cmd)
+        })
+    }
+
+    /// Send an encrypted command to the HSM, then decrypt and parse the
+    /// response
+    fn send_encrypted_command<C: Command>(
+        &mut self,
+        encrypted_cmd: command::Message,
+    ) -> Result<C::ResponseType, Error> {
+        let uuid = encrypted_cmd.uuid;
+        let cmd_type = C::COMMAND_CODE;
+        let encrypted_response = self.send_message(encrypted_cmd).map_err(|e| {
+            // The HSM may have processed the command even though we didn't
+            // receive a response, so it isn't necessarily safe to retry
+            if C::COMMAND_CODE.mutates_state() {
+                session_error!(
+                    self,
+                    "uuid={} no response to {} (it may have been applied): {}",
+                    uuid,
+                    C::COMMAND_CODE,
+                    e
+                );
+            }
+            e
+        })?;
 
         let response = self
             .secure_channel()?
@@ -188,9 +338,19 @@ impl Session {
             }
         }
 
+        // A response for a different command means we've desynchronized from
+        // the HSM (or the response was tampered with) so the session is unusable
         if response.command() != Some(C::COMMAND_CODE) {
+            session_error!(
+                self,
+                "uuid={} bad response code={:?} (expected {:?})",
+                uuid,
+                response.code,
+                C::COMMAND_CODE
+            );
+            self.abort();
             fail!(
-                ErrorKind::ResponseError,
+                ErrorKind::ProtocolError,
                 "bad command type in response: {:?} (expected {:?})",
                 response.command(),
                 C::COMMAND_CODE,
@@ -217,8 +377,31 @@ impl Session {
             );
         }
 
-        let response = match self.connector.send_message(uuid, cmd.into()) {
-            Ok(response_bytes) => response::Message::parse(response_bytes)?,
+        let cmd_bytes: connector::Message = cmd.into();
+
+        if let Some(stats) = &self.stats {
+            stats.lock().unwrap().record_sent(cmd_bytes.as_ref().len());
+        }
+
+        let result = match self.command_timeout {
+            Some(timeout) => {
+                self.connector
+                    .send_message_with_deadline(uuid, cmd_bytes, Instant::now() + timeout)
+            }
+            None => self.connector.send_message(uuid, cmd_bytes),
+        };
+
+        let response = match result {
+            Ok(response_bytes) => {
+                if let Some(stats) = &self.stats {
+                    stats
+                        .lock()
+                        .unwrap()
+                        .record_received(response_bytes.as_ref().len());
+                }
+
+                response::Message::parse(response_bytes)?
+            }
             Err(e) => {
                 // Abort the session in the event of errors
                 self.abort();
@@ -228,6 +411,14 @@ impl Session {
 
         if response.is_err() {
             session_error!(self, "uuid={} error={:?}", &uuid, response.code);
+
+            // The HSM no longer knows about this session (e.g. it was reset)
+            if device::ErrorKind::from_response_message(&response)
+                == Some(device::ErrorKind::InvalidSession)
+            {
+                self.abort();
+            }
+
             fail!(
                 ErrorKind::ResponseError,
                 "HSM error (session: {})",
@@ -248,12 +439,13 @@ impl Session {
         );
 
         let command = self.secure_channel()?.authenticate_session()?;
-        let response = self.send_message(command)?;
+        let stats = self.stats.clone();
 
-        if let Err(e) = self
-            .secure_channel()?
-            .finish_authenticate_session(&response)
-        {
+        if let Err(e) = record_command(stats.as_ref(), command::Code::AuthenticateSession, || {
+            let response = self.send_message(command)?;
+            self.secure_channel()?
+                .finish_authenticate_session(&response)
+        }) {
             session_error!(
                 self,
                 "failed={:?} key={} err={:?}",
@@ -269,6 +461,21 @@ impl Session {
         Ok(())
     }
 
+    /// Close the secure channel (if it's still open), releasing the session
+    /// slot on the HSM
+    fn close_channel(&mut self) -> Result<(), Error> {
+        // Only attempt to close the session if we have an active secure
+        // channel and our session hasn't already timed out
+        if self.secure_channel.is_none() || self.is_timed_out() {
+            return Ok(());
+        }
+
+        session_debug!(self, "closing session");
+        let result = self.send_command(&CloseSessionCommand {});
+        self.abort();
+        result.map(|_| ())
+    }
+
     /// Get the underlying channel or return an error
     fn secure_channel(&mut self) -> Result<&mut SecureChannel, Error> {
         self.secure_channel
@@ -276,3 +483,382 @@ impl Session {
             .ok_or_else(|| format_err!(ErrorKind::ClosedError, "session is already closed").into())
     }
 }
+
+/// Send the command with the given code to the HSM using `send`, recording
+/// it in `stats` (if any). Every round trip to the HSM is counted here.
+fn record_command<T>(
+    stats: Option<&Arc<Mutex<ConnectionStats>>>,
+    code: command::Code,
+    send: impl FnOnce() -> Result<T, Error>,
+) -> Result<T, Error> {
+    let started_at = Instant::now();
+    let result = send();
+
+    if let Some(stats) = stats {
+        stats
+            .lock()
+            .unwrap()
+            .record_command(code, started_at.elapsed(), result.is_ok());
+    }
+
+    result
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        // Closing the session would block on a round trip to the HSM, so
+        // that's left to `Session::close` (e.g. when the last `Client` handle
+        // is dropped) and the session is only aborted here
+        if self.secure_channel.is_some() {
+            session_debug!(self, "aborting session without closing it");
+            self.abort();
+        }
+    }
+}
+
+#[cfg(all(test, feature = "mockhsm"))]
+mod tests {
+    use super::{ErrorKind, Health};
+    use crate::{
+        asymmetric, client, command,
+        connector::{self, Connectable, Connection, Connector},
+        mockhsm::MockHsm,
+        response, Capability, Client, Credentials, Domain,
+    };
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Mutex,
+        },
+        thread,
+        time::{Duration, Instant},
+    };
+    use uuid::Uuid;
+
+    /// Ways in which `TamperingConnector` modifies encrypted session traffic
+    #[derive(Copy, Clone, Debug)]
+    enum Tamper {
+        /// Flip bits in the R-MAC tag
+        CorruptMac,
+
+        /// Flip bits in the byte at the given offset from the end of the
+        /// response (i.e. within the R-MAC tag or the encrypted payload)
+        FlipByte(usize),
+
+        /// Rewrite the session ID byte
+        WrongSessionId,
+
+        /// Rewrite the response code so the response no longer carries an R-MAC
+        StripMac,
+
+        /// Answer each command with the response to the previous one
+        Replay,
+
+        /// Capture each raw session command and send it a second time,
+        /// answering with the response to the resent copy
+        ReplayCommand,
+
+        /// Fail every request as if the HSM were unreachable
+        Disconnect,
+
+        /// Never respond, as with a wedged connector (failing once the
+        /// request's deadline passes, as a socket timeout would)
+        Stall,
+    }
+
+    /// Malicious connector which sits between the client and the `MockHsm`,
+    /// tampering with session traffic once it has been armed
+    #[derive(Clone)]
+    struct TamperingConnector {
+        hsm: MockHsm,
+        tamper: Tamper,
+        armed: Arc<AtomicBool>,
+    }
+
+    impl Connectable for TamperingConnector {
+        fn box_clone(&self) -> Box<dyn Connectable> {
+            Box::new(self.clone())
+        }
+
+        fn connect(&self) -> Result<Box<dyn Connection>, connector::Error> {
+            Ok(Box::new(TamperingConnection {
+                inner: self.hsm.connect()?,
+                tamper: self.tamper,
+                armed: self.armed.clone(),
+                last_response: Mutex::new(None),
+            }))
+        }
+    }
+
+    struct TamperingConnection {
+        inner: Box<dyn Connection>,
+        tamper: Tamper,
+        armed: Arc<AtomicBool>,
+        last_response: Mutex<Option<Vec<u8>>>,
+    }
+
+    impl Connection for TamperingConnection {
+        fn send_message(
+            &self,
+            uuid: Uuid,
+            msg: connector::Message,
+        ) -> Result<connector::Message, connector::Error> {
+            if self.armed.load(Ordering::SeqCst) && matches!(self.tamper, Tamper::Disconnect) {
+                return Err(connector::ErrorKind::ConnectionFailed.into());
+            }
+
+            if self.armed.load(Ordering::SeqCst) && matches!(self.tamper, Tamper::Stall) {
+                panic!("stalled connection used without a deadline");
+            }
+
+            if self.armed.load(Ordering::SeqCst)
+                && matches!(self.tamper, Tamper::ReplayCommand)
+                && msg.as_ref()[0] == command::Code::SessionMessage.to_u8()
+            {
+                self.inner.send_message(uuid, msg.clone())?;
+                return self.inner.send_message(uuid, msg);
+            }
+
+            let mut bytes = Vec::from(self.inner.send_message(uuid, msg)?);
+            let session_message = response::Code::Success(command::Code::SessionMessage).to_u8();
+
+            if !self.armed.load(Ordering::SeqCst) || bytes[0] != session_message {
+                return Ok(bytes.into());
+            }
+
+            match self.tamper {
+                Tamper::CorruptMac => *bytes.last_mut().unwrap() ^= 0xAA,
+                Tamper::FlipByte(offset) => {
+                    let index = bytes.len() - 1 - offset;
+                    bytes[index] ^= 0x01;
+                }
+                Tamper::WrongSessionId => bytes[3] += 1,
+                Tamper::StripMac => {
+                    bytes[0] = response::Code::Success(command::Code::CreateSession).to_u8()
+                }
+                Tamper::Replay => {
+                    let mut last_response = self.last_response.lock().unwrap();
+                    bytes = last_response.replace(bytes.clone()).unwrap_or(bytes);
+                }
+                Tamper::ReplayCommand | Tamper::Disconnect | Tamper::Stall => unreachable!(),
+            }
+
+            Ok(bytes.into())
+        }
+
+        fn send_message_with_deadline(
+            &self,
+            uuid: Uuid,
+            msg: connector::Message,
+            deadline: Instant,
+        ) -> Result<connector::Message, connector::Error> {
+            if self.armed.load(Ordering::SeqCst) && matches!(self.tamper, Tamper::Stall) {
+                thread::sleep(deadline.saturating_duration_since(Instant::now()));
+                return Err(connector::ErrorKind::IoError.into());
+            }
+
+            self.send_message(uuid, msg)
+        }
+    }
+
+    /// Number of random bytes requested in MAC verification tests
+    const RANDOM_LEN: usize = 32;
+
+    /// Bytes following the session ID in an encrypted `GetPseudoRandom`
+    /// response for `RANDOM_LEN` bytes: the padded ciphertext of the inner
+    /// response (3-byte header + data) followed by the 8-byte R-MAC tag
+    const RANDOM_RESPONSE_TAMPERABLE_LEN: usize = 48 + 8;
+
+    /// Get the kind of the session error underlying the given client error
+    fn session_error_kind(err: &client::Error) -> Option<ErrorKind> {
+        use std::error::Error;
+        err.source()?
+            .downcast_ref::<super::Error>()
+            .map(|e| *e.kind())
+    }
+
+    /// Get the kind of the connector error underlying the given client error
+    fn connector_error_kind(err: &client::Error) -> Option<connector::ErrorKind> {
+        use std::error::Error;
+        err.source()?
+            .downcast_ref::<super::Error>()?
+            .source()?
+            .downcast_ref::<connector::Error>()
+            .map(|e| *e.kind())
+    }
+
+    /// Open a client (without reconnection) whose responses will be tampered with
+    fn tampered_client(tamper: Tamper) -> Client {
+        let armed = Arc::new(AtomicBool::new(false));
+        let driver: Box<dyn Connectable> = Box::new(TamperingConnector {
+            hsm: MockHsm::new(),
+            tamper,
+            armed: armed.clone(),
+        });
+
+        let client = Client::open(Connector::from(driver), Credentials::default(), false).unwrap();
+        armed.store(true, Ordering::SeqCst);
+        client
+    }
+
+    /// Connector to the given (shared) `MockHsm`
+    fn mockhsm_connector(hsm: &MockHsm) -> Connector {
+        let driver: Box<dyn Connectable> = Box::new(hsm.clone());
+        Connector::from(driver)
+    }
+
+    /// Ensure a tampered response is rejected and the session is unusable afterward
+    fn assert_refuses_to_proceed(client: &Client) {
+        let err = client.echo(b"tampered").unwrap_err();
+        assert_eq!(*err.kind(), client::ErrorKind::ProtocolError);
+
+        let err = client.echo(b"after tampering").unwrap_err();
+        assert_eq!(*err.kind(), client::ErrorKind::AuthenticationError);
+    }
+
+    #[test]
+    fn corrupted_mac_test() {
+        assert_refuses_to_proceed(&tampered_client(Tamper::CorruptMac));
+    }
+
+    #[test]
+    fn strict_mac_verification_test() {
+        // Corrupting any byte of the R-MAC or encrypted payload must cause
+        // the response to be rejected before anything is decrypted
+        for offset in 0..RANDOM_RESPONSE_TAMPERABLE_LEN {
+            let client = tampered_client(Tamper::FlipByte(offset));
+            let err = client.get_pseudo_random(RANDOM_LEN).unwrap_err();
+            assert_eq!(*err.kind(), client::ErrorKind::ProtocolError);
+            assert_eq!(
+                session_error_kind(&err),
+                Some(ErrorKind::VerifyFailed),
+                "offset {offset} was not rejected by R-MAC verification"
+            );
+
+            // The session must not be used again after a MAC failure
+            let err = client.get_pseudo_random(RANDOM_LEN).unwrap_err();
+            assert_eq!(*err.kind(), client::ErrorKind::AuthenticationError);
+        }
+    }
+
+    #[test]
+    fn wrong_session_id_test() {
+        assert_refuses_to_proceed(&tampered_client(Tamper::WrongSessionId));
+    }
+
+    #[test]
+    fn stripped_mac_test() {
+        assert_refuses_to_proceed(&tampered_client(Tamper::StripMac));
+    }
+
+    #[test]
+    fn replayed_response_test() {
+        let client = tampered_client(Tamper::Replay);
+        assert_eq!(client.echo(b"first").unwrap(), b"first");
+        assert_refuses_to_proceed(&client);
+    }
+
+    #[test]
+    fn replayed_command_test() {
+        let hsm = MockHsm::new();
+        let key_id = 1;
+
+        Client::open(mockhsm_connector(&hsm), Credentials::default(), false)
+            .unwrap()
+            .generate_asymmetric_key(
+                key_id,
+                Default::default(),
+                Domain::DOM1,
+                Capability::SIGN_EDDSA,
+                asymmetric::Algorithm::Ed25519,
+            )
+            .unwrap();
+
+        let armed = Arc::new(AtomicBool::new(false));
+        let driver: Box<dyn Connectable> = Box::new(TamperingConnector {
+            hsm,
+            tamper: Tamper::ReplayCommand,
+            armed: armed.clone(),
+        });
+
+        let client = Client::open(Connector::from(driver), Credentials::default(), false).unwrap();
+        armed.store(true, Ordering::SeqCst);
+
+        // The original sign command succeeds, but the MockHsm answers the
+        // replayed copy with an (unencrypted) error response
+        let err = client.sign_ed25519(key_id, b"replayed").unwrap_err();
+        assert_eq!(session_error_kind(&err), Some(ErrorKind::ResponseError));
+
+        // The MockHsm terminated the session upon detecting the replay
+        armed.store(false, Ordering::SeqCst);
+        assert!(client.echo(b"after replay").is_err());
+    }
+
+    #[test]
+    fn verify_session_test() {
+        let hsm = MockHsm::new();
+        let client = Client::open(mockhsm_connector(&hsm), Credentials::default(), true).unwrap();
+        assert!(client.verify_session().is_healthy());
+
+        // Reset the device from another client, invalidating the first client's session
+        Client::open(mockhsm_connector(&hsm), Credentials::default(), false)
+            .unwrap()
+            .reset_device()
+            .unwrap();
+
+        match client.verify_session() {
+            Health::SessionStale { reason } => {
+                assert_eq!(*reason.kind(), ErrorKind::ResponseError)
+            }
+            other => panic!("expected stale session, got {other:?}"),
+        }
+
+        // The stale session was aborted so the next command opens a new one
+        assert_eq!(client.echo(b"reconnected").unwrap(), b"reconnected");
+        assert!(client.verify_session().is_healthy());
+    }
+
+    #[test]
+    fn verify_session_connector_down_test() {
+        let client = tampered_client(Tamper::Disconnect);
+
+        match client.verify_session() {
+            Health::ConnectorDown { source } => {
+                assert_eq!(*source.kind(), connector::ErrorKind::ConnectionFailed)
+            }
+            other => panic!("expected connector down, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn command_timeout_test() {
+        const COMMAND_TIMEOUT: Duration = Duration::from_millis(200);
+
+        let armed = Arc::new(AtomicBool::new(false));
+        let driver: Box<dyn Connectable> = Box::new(TamperingConnector {
+            hsm: MockHsm::new(),
+            tamper: Tamper::Stall,
+            armed: armed.clone(),
+        });
+
+        let client = Client::builder(Connector::from(driver))
+            .command_timeout(COMMAND_TIMEOUT)
+            .open()
+            .unwrap();
+
+        armed.store(true, Ordering::SeqCst);
+
+        let started_at = Instant::now();
+        let err = client.echo(b"stalled").unwrap_err();
+        assert!(started_at.elapsed() < COMMAND_TIMEOUT * 2);
+        assert_eq!(
+            connector_error_kind(&err),
+            Some(connector::ErrorKind::Timeout)
+        );
+
+        // The session was aborted, so the client re-authenticates once the
+        // connector responds again
+        armed.store(false, Ordering::SeqCst);
+        assert_eq!(client.echo(b"recovered").unwrap(), b"recovered");
+    }
+}
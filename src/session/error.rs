@@ -38,6 +38,10 @@ pub enum ErrorKind {
     #[error("protocol error")]
     ProtocolError,
 
+    /// A previously processed command was received again
+    #[error("replayed command")]
+    ReplayDetected,
+
     /// Error response from HSM we can't further specify
     #[error("HSM response error")]
     ResponseError,
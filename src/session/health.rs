@@ -0,0 +1,36 @@
+//! Session health checks
+
+use super::Error;
+use crate::connector;
+
+/// Number of random bytes echoed by the HSM when verifying a session
+pub(crate) const HEALTH_CHECK_MESSAGE_SIZE: usize = 32;
+
+/// Result of checking a `Session` is still usable (see [`Client::verify_session`])
+///
+/// [`Client::verify_session`]: crate::Client::verify_session
+#[derive(Debug)]
+pub enum Health {
+    /// Session is authenticated and the HSM answered as expected
+    Healthy,
+
+    /// Session is no longer usable (e.g. it was closed or invalidated by the
+    /// HSM, or the response failed verification) and must be reopened
+    SessionStale {
+        /// Why the session is considered stale
+        reason: Error,
+    },
+
+    /// HSM couldn't be reached through the connector
+    ConnectorDown {
+        /// Error returned by the connector
+        source: connector::Error,
+    },
+}
+
+impl Health {
+    /// Is the session healthy?
+    pub fn is_healthy(&self) -> bool {
+        matches!(self, Health::Healthy)
+    }
+}
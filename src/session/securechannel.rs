@@ -70,6 +70,17 @@ const AES_BLOCK_SIZE: usize = 16;
 type Aes128CbcEnc = cbc::Encryptor<Aes128>;
 type Aes128CbcDec = cbc::Decryptor<Aes128>;
 
+/// Maximum length of the data of a command (or response) which, once
+/// encrypted, fits in a session message of at most `max_message_size` bytes.
+///
+/// Session messages consist of a 3-byte header, the session ID, the
+/// ciphertext, and the MAC. The ciphertext contains the encrypted message's
+/// own 3-byte header, and at least 1 byte of padding to a whole block.
+pub(crate) fn max_data_len(max_message_size: usize) -> usize {
+    let ciphertext_len = max_message_size.saturating_sub(3 + 1 + Mac::BYTE_SIZE);
+    (ciphertext_len - ciphertext_len % AES_BLOCK_SIZE).saturating_sub(3 + 1)
+}
+
 /// SCP03 Secure Channel
 pub(crate) struct SecureChannel {
     /// ID of this channel (a.k.a. session ID)
@@ -364,6 +375,14 @@ impl SecureChannel {
             );
         }
 
+        let expected_mac = match response.mac.as_ref() {
+            Some(mac) => mac,
+            None => {
+                self.terminate();
+                fail!(ErrorKind::ProtocolError, "missing R-MAC tag in response");
+            }
+        };
+
         let mut mac = <Cmac<Aes128> as KeyInit>::new_from_slice(self.rmac_key.as_ref()).unwrap();
         mac.update(&self.mac_chaining_value);
         mac.update(&[response.code.to_u8()]);
@@ -373,13 +392,7 @@ impl SecureChannel {
         mac.update(&[session_id.to_u8()]);
         mac.update(&response.data);
 
-        if response
-            .mac
-            .as_ref()
-            .expect("missing R-MAC tag!")
-            .verify(&mac.finalize().into_bytes())
-            .is_err()
-        {
+        if expected_mac.verify(&mac.finalize().into_bytes()).is_err() {
             self.terminate();
             fail!(ErrorKind::VerifyFailed, "R-MAC mismatch!");
         }
@@ -433,70 +446,105 @@ impl SecureChannel {
         ))
     }
 
-    /// Verify and decrypt a command from the host
+    /// Verify and decrypt a command from the host.
+    ///
+    /// Malformed frames are rejected (terminating the session) with the
+    /// error code the YubiHSM 2 returns for each violation:
+    ///
+    /// - `WrongLength`: encrypted payload isn't a whole number of AES blocks,
+    ///   or the decrypted command's length field doesn't match its data
+    /// - `AuthenticationFailed`: C-MAC doesn't verify, or the frame replays
+    ///   the previous command (the YubiHSM 2 has no dedicated error code for
+    ///   this, as its chained MAC simply fails to verify)
+    /// - `InvalidData`: ISO 7816-4 padding is malformed
+    /// - `InvalidSession`: session is not (or no longer) authenticated
     #[cfg(feature = "mockhsm")]
     pub fn decrypt_command(
         &mut self,
         encrypted_command: command::Message,
-    ) -> Result<command::Message, session::Error> {
-        assert_eq!(self.security_level, SecurityLevel::Authenticated);
+    ) -> Result<command::Message, device::ErrorKind> {
+        if self.security_level != SecurityLevel::Authenticated
+            || encrypted_command.session_id != Some(self.id)
+        {
+            return Err(device::ErrorKind::InvalidSession);
+        }
 
-        let cipher = Aes128::new_from_slice(&self.enc_key).unwrap();
-        let icv = compute_icv(&cipher, self.counter);
+        let data_len = encrypted_command.data.len();
+
+        if data_len == 0 || data_len % AES_BLOCK_SIZE != 0 {
+            self.terminate();
+            return Err(device::ErrorKind::WrongLength);
+        }
 
-        self.verify_command_mac(&encrypted_command)?;
+        if self.verify_command_mac(&encrypted_command).is_err() {
+            return Err(device::ErrorKind::AuthenticationFailed);
+        }
 
         let cipher = Aes128::new_from_slice(&self.enc_key).unwrap();
+        let icv = compute_icv(&cipher, self.counter);
         let cbc_decryptor = Aes128CbcDec::inner_iv_init(cipher, &icv);
 
+        // `Iso7816` requires the last non-zero byte to be exactly `0x80`
         let mut command_data = encrypted_command.data;
-        let command_len = cbc_decryptor
-            .decrypt_padded_mut::<Iso7816>(&mut command_data)
-            .map_err(|e| {
+        let command_len = match cbc_decryptor.decrypt_padded_mut::<Iso7816>(&mut command_data) {
+            Ok(plaintext) => plaintext.len(),
+            Err(_) => {
                 self.terminate();
-                format_err!(
-                    ErrorKind::ProtocolError,
-                    "error decrypting command: {:?}",
-                    e
-                )
-            })?
-            .len();
+                return Err(device::ErrorKind::InvalidData);
+            }
+        };
 
         command_data.truncate(command_len);
-        let mut decrypted_command = command::Message::parse(command_data)?;
-        decrypted_command.session_id = encrypted_command.session_id;
 
+        let mut decrypted_command = command::Message::parse(command_data).map_err(|_| {
+            self.terminate();
+            device::ErrorKind::WrongLength
+        })?;
+
+        decrypted_command.session_id = encrypted_command.session_id;
         Ok(decrypted_command)
     }
 
     /// Verify a Command MAC (C-MAC) value, updating the internal session state
     #[cfg(feature = "mockhsm")]
     pub fn verify_command_mac(&mut self, command: &command::Message) -> Result<(), session::Error> {
-        assert_eq!(
-            command.session_id.unwrap(),
-            self.id,
+        ensure!(
+            command.session_id == Some(self.id),
+            ErrorKind::MismatchError,
             "session ID mismatch: {:?}",
             command.session_id
         );
 
+        let command_mac = match command.mac.as_ref() {
+            Some(mac) => mac,
+            None => {
+                self.terminate();
+                fail!(ErrorKind::ProtocolError, "missing C-MAC tag");
+            }
+        };
+
+        // The MAC chaining value is the full C-MAC of the last command, so a
+        // command carrying its truncated form is that command being resent
+        let previous_mac = Mac::from_slice(&self.mac_chaining_value[..Mac::BYTE_SIZE]);
+
+        if bool::from(command_mac.ct_eq(&previous_mac)) {
+            self.terminate();
+            fail!(ErrorKind::ReplayDetected, "replayed command");
+        }
+
         let mut mac = <Cmac<Aes128> as KeyInit>::new_from_slice(self.mac_key.as_ref()).unwrap();
         mac.update(&self.mac_chaining_value);
         mac.update(&[command.command_type.to_u8()]);
 
         let length = command.len() as u16;
         mac.update(&length.to_be_bytes());
-        mac.update(&[command.session_id.unwrap().to_u8()]);
+        mac.update(&[self.id.to_u8()]);
         mac.update(&command.data);
 
         let tag = mac.finalize().into_bytes();
 
-        if command
-            .mac
-            .as_ref()
-            .expect("missing C-MAC tag!")
-            .verify(&tag)
-            .is_err()
-        {
+        // C-MAC is the tag truncated to its first 8 bytes
+        if command_mac.verify(&tag).is_err() {
             self.terminate();
             fail!(ErrorKind::VerifyFailed, "C-MAC mismatch!");
         }
@@ -629,6 +677,7 @@ fn compute_icv(cipher: &Aes128, counter: u32) -> GenericArray<u8, U16> {
 mod tests {
     use super::*;
     use crate::authentication;
+    use aes::cipher::block_padding::NoPadding;
 
     const PASSWORD: &[u8] = b"password";
     const HOST_CHALLENGE: &[u8] = &[0u8; 8];
@@ -734,4 +783,203 @@ mod tests {
             "cryptographic verification failed: R-MAC mismatch!"
         );
     }
+
+    #[test]
+    fn max_data_len_test() {
+        assert_eq!(max_data_len(command::MAX_MSG_SIZE), 2028);
+
+        for max_message_size in [64, 100, 1000, command::MAX_MSG_SIZE] {
+            let (mut host_channel, _) = create_channel_pair();
+            let data_len = max_data_len(max_message_size);
+
+            let fits = host_channel
+                .encrypt_command(command::Message::create(COMMAND_CODE, vec![0; data_len]).unwrap())
+                .unwrap();
+
+            let overflows = host_channel
+                .encrypt_command(
+                    command::Message::create(COMMAND_CODE, vec![0; data_len + 1]).unwrap(),
+                )
+                .unwrap();
+
+            assert!(fits.serialize().len() <= max_message_size);
+            assert!(overflows.serialize().len() > max_message_size);
+        }
+    }
+
+    /// Serialize the test command and apply the given padding to it
+    fn padded_command(padding: &[u8]) -> Vec<u8> {
+        let mut plaintext = command::Message::create(COMMAND_CODE, Vec::from(COMMAND_DATA))
+            .unwrap()
+            .serialize();
+
+        plaintext.extend_from_slice(padding);
+        assert_eq!(plaintext.len() % AES_BLOCK_SIZE, 0);
+        plaintext
+    }
+
+    /// ISO 7816-4 padding for the test command
+    fn valid_padding() -> Vec<u8> {
+        let mut padding = vec![0x80];
+        padding.resize(
+            AES_BLOCK_SIZE - (3 + COMMAND_DATA.len()) % AES_BLOCK_SIZE,
+            0,
+        );
+        padding
+    }
+
+    /// Encrypt and MAC already-padded plaintext as the host would, allowing
+    /// frames with malformed contents to be constructed
+    fn encrypt_raw(host_channel: &mut SecureChannel, plaintext: &[u8]) -> command::Message {
+        let cipher = Aes128::new_from_slice(&host_channel.enc_key).unwrap();
+        let icv = compute_icv(&cipher, host_channel.counter);
+
+        let mut ciphertext = plaintext.to_vec();
+        Aes128CbcEnc::inner_iv_init(cipher, &icv)
+            .encrypt_padded_mut::<NoPadding>(&mut ciphertext, plaintext.len())
+            .unwrap();
+
+        host_channel
+            .command_with_mac(command::Code::SessionMessage, &ciphertext)
+            .unwrap()
+    }
+
+    /// Ensure the card rejects the given frame, and refuses any further commands
+    fn assert_rejected(
+        host_channel: &mut SecureChannel,
+        card_channel: &mut SecureChannel,
+        frame: command::Message,
+        expected: device::ErrorKind,
+    ) {
+        assert_eq!(card_channel.decrypt_command(frame).unwrap_err(), expected);
+        assert_eq!(card_channel.security_level, SecurityLevel::Terminated);
+
+        let next_frame = encrypt_raw(host_channel, &padded_command(&valid_padding()));
+        assert_eq!(
+            card_channel.decrypt_command(next_frame).unwrap_err(),
+            device::ErrorKind::InvalidSession
+        );
+    }
+
+    #[test]
+    fn valid_raw_frame_test() {
+        let (mut host_channel, mut card_channel) = create_channel_pair();
+        let frame = encrypt_raw(&mut host_channel, &padded_command(&valid_padding()));
+
+        let command = card_channel.decrypt_command(frame).unwrap();
+        assert_eq!(command.command_type, COMMAND_CODE);
+        assert_eq!(&command.data[..], COMMAND_DATA);
+    }
+
+    #[test]
+    fn wrong_padding_byte_test() {
+        let (mut host_channel, mut card_channel) = create_channel_pair();
+        let mut padding = valid_padding();
+        padding[0] = 0x81;
+
+        let frame = encrypt_raw(&mut host_channel, &padded_command(&padding));
+        let expected = device::ErrorKind::InvalidData;
+        assert_rejected(&mut host_channel, &mut card_channel, frame, expected);
+    }
+
+    #[test]
+    fn trailing_padding_byte_test() {
+        let (mut host_channel, mut card_channel) = create_channel_pair();
+        let mut padding = valid_padding();
+        *padding.last_mut().unwrap() = 0x01;
+
+        let frame = encrypt_raw(&mut host_channel, &padded_command(&padding));
+        let expected = device::ErrorKind::InvalidData;
+        assert_rejected(&mut host_channel, &mut card_channel, frame, expected);
+    }
+
+    #[test]
+    fn short_mac_test() {
+        let (mut host_channel, mut card_channel) = create_channel_pair();
+        let frame = encrypt_raw(&mut host_channel, &padded_command(&valid_padding()));
+
+        // Drop the last byte of the C-MAC, fixing up the frame's length field
+        let mut bytes = frame.serialize();
+        bytes.pop();
+        let length = (bytes.len() - 3) as u16;
+        bytes[1..3].copy_from_slice(&length.to_be_bytes());
+
+        let frame = command::Message::parse(bytes).unwrap();
+        let expected = device::ErrorKind::WrongLength;
+        assert_rejected(&mut host_channel, &mut card_channel, frame, expected);
+    }
+
+    #[test]
+    fn flipped_mac_byte_test() {
+        let (mut host_channel, mut card_channel) = create_channel_pair();
+        let mut frame = encrypt_raw(&mut host_channel, &padded_command(&valid_padding()));
+
+        let mut bad_mac = Vec::from(frame.mac.as_ref().unwrap().as_slice());
+        bad_mac[Mac::BYTE_SIZE - 1] ^= 0x01;
+        frame.mac = Some(Mac::from_slice(&bad_mac));
+
+        let expected = device::ErrorKind::AuthenticationFailed;
+        assert_rejected(&mut host_channel, &mut card_channel, frame, expected);
+    }
+
+    #[test]
+    fn flipped_ciphertext_byte_test() {
+        let (mut host_channel, mut card_channel) = create_channel_pair();
+        let mut frame = encrypt_raw(&mut host_channel, &padded_command(&valid_padding()));
+        frame.data[0] ^= 0x01;
+
+        let expected = device::ErrorKind::AuthenticationFailed;
+        assert_rejected(&mut host_channel, &mut card_channel, frame, expected);
+    }
+
+    #[test]
+    fn wrong_chaining_value_test() {
+        let (mut host_channel, mut card_channel) = create_channel_pair();
+
+        // MAC computed over a stale chaining value
+        host_channel.mac_chaining_value = [0u8; Mac::BYTE_SIZE * 2];
+        let frame = encrypt_raw(&mut host_channel, &padded_command(&valid_padding()));
+
+        let expected = device::ErrorKind::AuthenticationFailed;
+        assert_rejected(&mut host_channel, &mut card_channel, frame, expected);
+    }
+
+    #[test]
+    fn unaligned_payload_test() {
+        let (mut host_channel, mut card_channel) = create_channel_pair();
+
+        // Validly MAC'd, but the encrypted payload isn't a whole number of blocks
+        let frame = host_channel
+            .command_with_mac(command::Code::SessionMessage, &[0u8; AES_BLOCK_SIZE - 1])
+            .unwrap();
+
+        let expected = device::ErrorKind::WrongLength;
+        assert_rejected(&mut host_channel, &mut card_channel, frame, expected);
+    }
+
+    #[test]
+    fn inner_length_mismatch_test() {
+        let (mut host_channel, mut card_channel) = create_channel_pair();
+        let mut plaintext = padded_command(&valid_padding());
+        plaintext[2] += 1;
+
+        let frame = encrypt_raw(&mut host_channel, &plaintext);
+        let expected = device::ErrorKind::WrongLength;
+        assert_rejected(&mut host_channel, &mut card_channel, frame, expected);
+    }
+
+    #[test]
+    fn replayed_command_test() {
+        let (mut host_channel, mut card_channel) = create_channel_pair();
+        let bytes = encrypt_raw(&mut host_channel, &padded_command(&valid_padding())).serialize();
+
+        card_channel
+            .decrypt_command(command::Message::parse(bytes.clone()).unwrap())
+            .unwrap();
+
+        let replayed = command::Message::parse(bytes).unwrap();
+        let err = card_channel.verify_command_mac(&replayed).unwrap_err();
+        assert_eq!(*err.kind(), ErrorKind::ReplayDetected);
+        assert_eq!(card_channel.security_level, SecurityLevel::Terminated);
+    }
 }
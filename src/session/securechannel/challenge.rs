@@ -21,7 +21,7 @@ impl Challenge {
     /// Create a new challenge from a slice
     ///
     /// Panics if the slice is not 8-bytes
-    #[cfg(all(test, feature = "mockhsm"))]
+    #[cfg(feature = "mockhsm")]
     pub fn from_slice(slice: &[u8]) -> Self {
         assert_eq!(slice.len(), 8, "challenge must be 8-bytes long");
 
@@ -31,7 +31,6 @@ impl Challenge {
     }
 
     /// Borrow the challenge value as a slice
-    #[cfg_attr(clippy, allow(clippy::trivially_copy_pass_by_ref))]
     pub fn as_slice(&self) -> &[u8] {
         &self.0
     }
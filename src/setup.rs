@@ -28,7 +28,7 @@ pub fn erase_device_and_init_with_profile(
     profile: Profile,
 ) -> Result<Report, Error> {
     // Reset the device
-    let mut client = Client::open(connector, credentials, false)?;
+    let client = Client::open(connector, credentials, false)?;
     client.reset_device_and_reconnect(profile.reset_device_timeout)?;
     init_with_profile(client, profile)
 }
@@ -112,7 +112,7 @@ pub fn init_with_profile(client: Client, profile: Profile) -> Result<Report, Err
             setup_auth_key_id
         );
         client
-            .delete_object(setup_auth_key_id, object::Type::AuthenticationKey)
+            .delete_object_force(setup_auth_key_id, object::Type::AuthenticationKey)
             .map_err(|e| {
                 format_err!(
                     ErrorKind::SetupFailed,
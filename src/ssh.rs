@@ -4,10 +4,25 @@
 //! confirmed to actually work! USE AT YOUR OWN RISK!
 //!
 //! You will need to enable the `untested` cargo feature to use it.
+//!
+//! Conversion of public keys to and from the OpenSSH `authorized_keys` format
+//! is available with the `ssh` cargo feature.
 
 mod certificate;
 #[cfg(feature = "untested")]
 pub(crate) mod commands;
+#[cfg(any(feature = "ssh", feature = "untested"))]
+pub(crate) mod encoding;
+#[cfg(feature = "ssh")]
+mod public_key;
+#[cfg(feature = "untested")]
+mod request;
 mod template;
 
 pub use self::{certificate::Certificate, template::Template};
+
+#[cfg(feature = "untested")]
+pub use self::request::{CertificateRequest, CertificateType, TIMESTAMP_SIGNATURE_SIZE};
+
+#[cfg(feature = "ssh")]
+pub use self::public_key::{parse_public_key_openssh, public_key_openssh};
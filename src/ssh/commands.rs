@@ -23,16 +23,11 @@ pub(crate) struct SignSshCertificateCommand {
     /// Object ID of the SSH certificate template
     pub template_id: object::Id,
 
-    /// Algorithm
+    /// Algorithm to sign the certificate with
     pub algorithm: Algorithm,
 
-    /// Timestamp
-    pub timestamp: u32,
-
-    /// Signature over the request and timestamp
-    pub signature: [u8; 32],
-
-    /// Data to be signed
+    /// Request timestamp, its signature by the template's timestamp key,
+    /// and the certificate to sign (see `ssh::CertificateRequest`)
     pub request: Vec<u8>,
 }
 
@@ -42,7 +37,7 @@ impl Command for SignSshCertificateCommand {
 
 /// Signed SSH certificates
 #[derive(Serialize, Deserialize, Debug)]
-pub struct SignSshCertificateResponse(ssh::Certificate);
+pub struct SignSshCertificateResponse(pub(crate) ssh::Certificate);
 
 impl Response for SignSshCertificateResponse {
     const COMMAND_CODE: command::Code = command::Code::SignSshCertificate;
@@ -0,0 +1,77 @@
+//! SSH wire encoding (RFC 4251 § 5) of public keys and certificates
+
+use crate::asymmetric::{self, PublicKey};
+
+/// RSA public exponent used by the YubiHSM (F4)
+pub(crate) const RSA_PUBLIC_EXPONENT: &[u8] = &[0x01, 0x00, 0x01];
+
+/// Get the OpenSSH key type for the given algorithm
+pub(crate) fn key_type(algorithm: asymmetric::Algorithm) -> Option<&'static str> {
+    Some(match algorithm {
+        asymmetric::Algorithm::EcP256 => "ecdsa-sha2-nistp256",
+        asymmetric::Algorithm::EcP384 => "ecdsa-sha2-nistp384",
+        asymmetric::Algorithm::Ed25519 => "ssh-ed25519",
+        asymmetric::Algorithm::Rsa2048
+        | asymmetric::Algorithm::Rsa3072
+        | asymmetric::Algorithm::Rsa4096 => "ssh-rsa",
+        _ => return None,
+    })
+}
+
+/// Serialize a public key as an SSH public key blob (i.e. its key type
+/// followed by the algorithm-specific fields), or `None` if OpenSSH doesn't
+/// support its algorithm
+pub(crate) fn public_key_blob(public_key: &PublicKey) -> Option<Vec<u8>> {
+    let key_type = key_type(public_key.algorithm)?;
+    let mut blob = vec![];
+    put_string(&mut blob, key_type.as_bytes());
+
+    match public_key.algorithm {
+        asymmetric::Algorithm::EcP256 | asymmetric::Algorithm::EcP384 => {
+            let curve = key_type.trim_start_matches("ecdsa-sha2-");
+            let len = public_key.algorithm.key_len() * 2;
+
+            if public_key.len() != len {
+                return None;
+            }
+
+            put_string(&mut blob, curve.as_bytes());
+            put_string(&mut blob, &[&[0x04], public_key.as_slice()].concat());
+        }
+        asymmetric::Algorithm::Ed25519 => {
+            if public_key.len() != public_key.algorithm.key_len() {
+                return None;
+            }
+
+            put_string(&mut blob, public_key.as_slice());
+        }
+        _ => {
+            if public_key.len() != public_key.algorithm.key_len() {
+                return None;
+            }
+
+            put_mpint(&mut blob, RSA_PUBLIC_EXPONENT);
+            put_mpint(&mut blob, public_key.as_slice());
+        }
+    }
+
+    Some(blob)
+}
+
+/// Append a length-prefixed `string` (RFC 4251 § 5)
+pub(crate) fn put_string(blob: &mut Vec<u8>, bytes: &[u8]) {
+    blob.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    blob.extend_from_slice(bytes);
+}
+
+/// Append an unsigned big endian integer as an `mpint` (RFC 4251 § 5)
+pub(crate) fn put_mpint(blob: &mut Vec<u8>, bytes: &[u8]) {
+    let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+    let bytes = &bytes[start..];
+
+    if bytes.first().map_or(false, |&b| b & 0x80 != 0) {
+        put_string(blob, &[&[0], bytes].concat());
+    } else {
+        put_string(blob, bytes);
+    }
+}
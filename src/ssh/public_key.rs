@@ -0,0 +1,188 @@
+//! OpenSSH public keys (i.e. the `authorized_keys` format)
+
+use super::encoding::{key_type, public_key_blob, RSA_PUBLIC_EXPONENT};
+use crate::asymmetric::{self, PublicKey};
+use base64ct::{Base64, Encoding};
+
+/// Serialize a public key in the OpenSSH `authorized_keys` format, i.e.
+/// `<algorithm> <base64 key blob> [comment]`.
+///
+/// Supports `ecdsa-sha2-nistp256`, `ecdsa-sha2-nistp384`, `ssh-ed25519`,
+/// and `ssh-rsa` keys. Returns `None` for algorithms OpenSSH doesn't support.
+pub fn public_key_openssh(public_key: &PublicKey, comment: &str) -> Option<String> {
+    let key_type = key_type(public_key.algorithm)?;
+    let blob = public_key_blob(public_key)?;
+
+    let mut line = format!("{} {}", key_type, Base64::encode_string(&blob));
+
+    if !comment.is_empty() {
+        line.push(' ');
+        line.push_str(comment);
+    }
+
+    Some(line)
+}
+
+/// Parse a public key in the OpenSSH `authorized_keys` format (the inverse
+/// of [`public_key_openssh`]). Any comment is ignored.
+pub fn parse_public_key_openssh(line: &str) -> Option<PublicKey> {
+    let mut fields = line.split_whitespace();
+    let key_type = fields.next()?;
+    let blob = Base64::decode_vec(fields.next()?).ok()?;
+    let mut reader = blob.as_slice();
+
+    if get_string(&mut reader)? != key_type.as_bytes() {
+        return None;
+    }
+
+    let (algorithm, bytes) = match key_type {
+        "ecdsa-sha2-nistp256" | "ecdsa-sha2-nistp384" => {
+            let algorithm = if key_type.ends_with("256") {
+                asymmetric::Algorithm::EcP256
+            } else {
+                asymmetric::Algorithm::EcP384
+            };
+
+            let curve = key_type.trim_start_matches("ecdsa-sha2-");
+
+            if get_string(&mut reader)? != curve.as_bytes() {
+                return None;
+            }
+
+            match get_string(&mut reader)? {
+                [0x04, point @ ..] if point.len() == algorithm.key_len() * 2 => {
+                    (algorithm, point.to_vec())
+                }
+                _ => return None,
+            }
+        }
+        "ssh-ed25519" => {
+            let algorithm = asymmetric::Algorithm::Ed25519;
+            let bytes = get_string(&mut reader)?;
+
+            if bytes.len() != algorithm.key_len() {
+                return None;
+            }
+
+            (algorithm, bytes.to_vec())
+        }
+        "ssh-rsa" => {
+            if get_mpint(&mut reader)? != RSA_PUBLIC_EXPONENT {
+                return None;
+            }
+
+            let modulus = get_mpint(&mut reader)?;
+            let algorithm = match modulus.len() {
+                256 => asymmetric::Algorithm::Rsa2048,
+                384 => asymmetric::Algorithm::Rsa3072,
+                512 => asymmetric::Algorithm::Rsa4096,
+                _ => return None,
+            };
+
+            (algorithm, modulus.to_vec())
+        }
+        _ => return None,
+    };
+
+    if !reader.is_empty() {
+        return None;
+    }
+
+    Some(PublicKey { algorithm, bytes })
+}
+
+/// Read a length-prefixed `string`
+fn get_string<'a>(reader: &mut &'a [u8]) -> Option<&'a [u8]> {
+    if reader.len() < 4 {
+        return None;
+    }
+
+    let (len, rest) = reader.split_at(4);
+    let len = u32::from_be_bytes(len.try_into().unwrap()) as usize;
+
+    if rest.len() < len {
+        return None;
+    }
+
+    let (bytes, rest) = rest.split_at(len);
+    *reader = rest;
+    Some(bytes)
+}
+
+/// Read a non-negative `mpint`, stripping its leading zero byte (if any)
+fn get_mpint<'a>(reader: &mut &'a [u8]) -> Option<&'a [u8]> {
+    match get_string(reader)? {
+        [first, ..] if first & 0x80 != 0 => None,
+        [0, rest @ ..] => Some(rest),
+        bytes => Some(bytes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Key fixtures generated with OpenSSL and converted with `ssh-keygen -i -m PKCS8`
+    /// (or generated directly with `ssh-keygen -t ed25519` in the case of Ed25519)
+    const FIXTURES: &[(asymmetric::Algorithm, &[u8], &str)] = &[
+        (
+            asymmetric::Algorithm::EcP256,
+            b"\x1F\x2B\xAE\xB5\x01\x1F\x5E\xCC\x47\x20\x21\x5B\x3F\x5B\xF8\x8C\x42\x7D\x14\xB0\x6D\x5D\xFB\x99\xB2\xA0\xD0\xB6\x13\xDA\xF1\x4E\xE2\x80\xF6\xD9\x20\x04\x28\x6E\x96\x16\x6A\x62\xB7\x94\x93\x34\x3F\xEF\xD8\xBC\xCE\xE1\x66\x4D\x56\x5F\xC3\x7D\x2F\xBE\x72\xC0",
+            "ecdsa-sha2-nistp256 AAAAE2VjZHNhLXNoYTItbmlzdHAyNTYAAAAIbmlzdHAyNTYAAABBBB8rrrUBH17MRyAhWz9b+IxCfRSwbV37mbKg0LYT2vFO4oD22SAEKG6WFmpit5STND/v2LzO4WZNVl/DfS++csA=",
+        ),
+        (
+            asymmetric::Algorithm::EcP384,
+            b"\x00\xB7\xFF\xC1\x7E\x0A\x18\x5D\xA4\x59\x3C\xF3\x76\xAD\x3F\x70\x32\x6E\xB4\x60\x5A\x4D\x18\xCF\x47\xD1\xF0\xB7\xA4\x40\x84\x58\xE1\xD0\x8B\xCA\x16\xE5\x6C\x98\x9D\x5B\x5B\x0C\xC0\xCB\xF6\x24\x93\x3C\x56\xC6\x02\x9E\x39\xE9\x4B\xAA\x1E\x26\x28\xFF\xCB\xED\xB7\x7B\x0E\x9A\xCE\x39\xEB\x13\xFA\xB0\x7C\x10\xA4\xFB\x18\x1B\xEB\x3F\xBE\xC9\xED\xA0\xF1\xC7\xD7\xD1\x5C\x40\x03\x5D\x1F\x44",
+            "ecdsa-sha2-nistp384 AAAAE2VjZHNhLXNoYTItbmlzdHAzODQAAAAIbmlzdHAzODQAAABhBAC3/8F+ChhdpFk883atP3AybrRgWk0Yz0fR8LekQIRY4dCLyhblbJidW1sMwMv2JJM8VsYCnjnpS6oeJij/y+23ew6azjnrE/qwfBCk+xgb6z++ye2g8cfX0VxAA10fRA==",
+        ),
+        (
+            asymmetric::Algorithm::Ed25519,
+            b"\xEC\xAB\x79\x34\xFA\xBD\xEB\x26\x3F\x14\xA3\x99\x1D\x8E\xB6\xDB\xDC\x0C\x0F\xAF\x26\x32\x73\x82\x66\x66\x88\x31\x7A\xD5\xDA\x49",
+            "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOyreTT6vesmPxSjmR2OttvcDA+vJjJzgmZmiDF61dpJ yubihsm.rs test key",
+        ),
+        (
+            asymmetric::Algorithm::Rsa2048,
+            b"\x96\xDA\xA2\xF1\x3F\x36\x74\x26\x0A\x7F\x75\xD4\x47\xA6\x04\x7B\x1C\x42\xA7\xB1\x82\x74\x9A\xBE\xD3\xC4\x06\xB7\x3E\xEC\x74\xAD\x8D\x01\x5A\x9E\x5B\x73\x15\xC3\xFD\x6D\xDA\xA2\xB9\xD1\xDB\x6B\x60\x7A\xBB\xC8\x21\xCD\x75\x7A\xD0\x34\x55\x5D\xA8\x19\x57\xFC\xEB\x3A\x8C\x3A\xD8\xAB\x32\xE1\x61\x19\x1F\xE6\xFC\xEC\x90\x0F\x25\x08\x07\xF3\x27\xD3\x2D\xDA\x1E\xB1\x32\x55\xC5\x82\xB3\xBB\xBD\xD4\x29\x38\x42\xCF\x1C\x49\x0F\x28\x9E\xB1\xC6\xE6\x93\x1E\x9A\x57\xD1\x11\xB8\xD1\x75\x1E\x35\x3A\xCC\x61\x05\xE4\xE0\xC4\x44\x85\x88\x9C\xD7\x67\x63\x2E\x1F\xEE\x4D\x60\xFB\x3E\x7F\x9E\x27\xA3\x2E\x62\x9D\xC3\x05\x99\x92\xBE\x20\xD7\x3C\xA3\x4A\xBA\x03\xC7\x22\x76\x0F\xE2\x89\x20\xE5\xB5\x04\xE5\x7B\x37\xE7\x25\xFE\x4F\x6A\x98\xF7\x88\x09\x57\x3F\x68\x9F\x21\x93\x70\x1F\xC6\x48\xEC\xF3\x3F\x2F\xA4\x1F\x0D\x0E\x8D\x2E\x43\x4F\xE1\x47\x27\x90\x5E\x08\xBD\xDF\x3C\x3F\xBA\xC7\x99\x04\x1A\xC3\xC9\x01\xDD\xBF\x1F\x41\xBA\x45\x93\x39\x0F\xA9\x89\xB4\x98\x41\xCE\xB2\x75\x97\xDE\x0B\xEE\x52\x6B\x55\x59\x2F\x7E\xF1\x75\xA0\xD8\x50\xD7",
+            "ssh-rsa AAAAB3NzaC1yc2EAAAADAQABAAABAQCW2qLxPzZ0Jgp/ddRHpgR7HEKnsYJ0mr7TxAa3Pux0rY0BWp5bcxXD/W3aornR22tgervIIc11etA0VV2oGVf86zqMOtirMuFhGR/m/OyQDyUIB/Mn0y3aHrEyVcWCs7u91Ck4Qs8cSQ8onrHG5pMemlfREbjRdR41OsxhBeTgxESFiJzXZ2MuH+5NYPs+f54noy5incMFmZK+INc8o0q6A8cidg/iiSDltQTlezfnJf5Papj3iAlXP2ifIZNwH8ZI7PM/L6QfDQ6NLkNP4UcnkF4Ivd88P7rHmQQaw8kB3b8fQbpFkzkPqYm0mEHOsnWX3gvuUmtVWS9+8XWg2FDX",
+        ),
+    ];
+
+    #[test]
+    fn public_key_openssh_matches_ssh_keygen() {
+        for &(algorithm, bytes, line) in FIXTURES {
+            let public_key = PublicKey {
+                algorithm,
+                bytes: bytes.into(),
+            };
+
+            let comment = line.splitn(3, ' ').nth(2).unwrap_or_default();
+            assert_eq!(public_key_openssh(&public_key, comment).unwrap(), line);
+        }
+    }
+
+    #[test]
+    fn parse_public_key_openssh_round_trips() {
+        for &(algorithm, bytes, line) in FIXTURES {
+            let public_key = parse_public_key_openssh(line).unwrap();
+            assert_eq!(public_key.algorithm, algorithm);
+            assert_eq!(public_key.bytes, bytes);
+        }
+    }
+
+    #[test]
+    fn unsupported_algorithm() {
+        let public_key = PublicKey {
+            algorithm: asymmetric::Algorithm::EcK256,
+            bytes: vec![0u8; 64],
+        };
+
+        assert!(public_key_openssh(&public_key, "").is_none());
+    }
+
+    #[test]
+    fn mismatched_key_type() {
+        let (_, _, line) = FIXTURES[0];
+        let mismatched = line.replacen("nistp256", "nistp384", 1);
+        assert!(parse_public_key_openssh(&mismatched).is_none());
+    }
+}
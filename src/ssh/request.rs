@@ -0,0 +1,168 @@
+//! Requests to sign OpenSSH certificates (see `PROTOCOL.certkeys` in the
+//! OpenSSH sources)
+
+use super::encoding::{key_type, public_key_blob, put_string};
+use crate::asymmetric::PublicKey;
+use rand_core::{OsRng, RngCore};
+use std::collections::BTreeMap;
+
+/// Size of the signature over a request made by the template's timestamp
+/// key (an RSA-2048 PKCS#1v1.5 signature)
+pub const TIMESTAMP_SIGNATURE_SIZE: usize = 256;
+
+/// Size of the random nonce included in each certificate
+const NONCE_SIZE: usize = 32;
+
+/// Types of OpenSSH certificates
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum CertificateType {
+    /// User certificate (`SSH_CERT_TYPE_USER`)
+    User = 1,
+
+    /// Host certificate (`SSH_CERT_TYPE_HOST`)
+    Host = 2,
+}
+
+/// Request to sign an OpenSSH certificate with a CA key using
+/// [`Client::sign_ssh_certificate`](crate::Client::sign_ssh_certificate).
+///
+/// The HSM checks the request against the SSH template, which also names
+/// the timestamp key whose signature over the request must be included.
+#[derive(Clone, Debug)]
+pub struct CertificateRequest {
+    /// Public key being certified
+    pub public_key: PublicKey,
+
+    /// Random nonce (generated by [`CertificateRequest::new`])
+    pub nonce: Vec<u8>,
+
+    /// Serial number of the certificate
+    pub serial: u64,
+
+    /// Type of the certificate
+    pub cert_type: CertificateType,
+
+    /// Key ID (a free-form identifier logged by `sshd`)
+    pub key_id: String,
+
+    /// Usernames (for user certificates) or hostnames (for host
+    /// certificates) the certificate is valid for
+    pub principals: Vec<String>,
+
+    /// Start of the validity period (seconds since the UNIX epoch)
+    pub valid_after: u64,
+
+    /// End of the validity period (seconds since the UNIX epoch)
+    pub valid_before: u64,
+
+    /// Critical options (e.g. `force-command`), by name
+    pub critical_options: BTreeMap<String, String>,
+
+    /// Extensions (e.g. `permit-pty`, with an empty value), by name
+    pub extensions: BTreeMap<String, String>,
+
+    /// Timestamp the request was signed at (seconds since the UNIX epoch)
+    pub timestamp: u32,
+
+    /// Signature over the request by the template's timestamp key
+    pub timestamp_signature: Vec<u8>,
+}
+
+impl CertificateRequest {
+    /// Create a request to certify the given public key, with a random
+    /// nonce, no principals or options, and an unbounded validity period.
+    pub fn new(
+        public_key: PublicKey,
+        cert_type: CertificateType,
+        key_id: impl Into<String>,
+    ) -> Self {
+        let mut nonce = vec![0u8; NONCE_SIZE];
+        OsRng.fill_bytes(&mut nonce);
+
+        Self {
+            public_key,
+            nonce,
+            serial: 0,
+            cert_type,
+            key_id: key_id.into(),
+            principals: vec![],
+            valid_after: 0,
+            valid_before: u64::MAX,
+            critical_options: BTreeMap::new(),
+            extensions: BTreeMap::new(),
+            timestamp: 0,
+            timestamp_signature: vec![0u8; TIMESTAMP_SIGNATURE_SIZE],
+        }
+    }
+
+    /// Serialize the certificate to be signed by the given CA key, i.e.
+    /// every field but the signature, or `None` if OpenSSH doesn't support
+    /// either key's algorithm
+    pub fn certificate_body(&self, ca_public_key: &PublicKey) -> Option<Vec<u8>> {
+        let key_type = key_type(self.public_key.algorithm)?;
+        let key_blob = public_key_blob(&self.public_key)?;
+        let ca_key_blob = public_key_blob(ca_public_key)?;
+
+        let mut body = vec![];
+        put_string(
+            &mut body,
+            format!("{key_type}-cert-v01@openssh.com").as_bytes(),
+        );
+        put_string(&mut body, &self.nonce);
+
+        // The certified key's fields follow its key type in the key blob
+        body.extend_from_slice(&key_blob[4 + key_type.len()..]);
+
+        body.extend_from_slice(&self.serial.to_be_bytes());
+        body.extend_from_slice(&(self.cert_type as u32).to_be_bytes());
+        put_string(&mut body, self.key_id.as_bytes());
+
+        let mut principals = vec![];
+        for principal in &self.principals {
+            put_string(&mut principals, principal.as_bytes());
+        }
+        put_string(&mut body, &principals);
+
+        body.extend_from_slice(&self.valid_after.to_be_bytes());
+        body.extend_from_slice(&self.valid_before.to_be_bytes());
+        put_string(&mut body, &encode_options(&self.critical_options));
+        put_string(&mut body, &encode_options(&self.extensions));
+
+        // Reserved
+        put_string(&mut body, &[]);
+        put_string(&mut body, &ca_key_blob);
+
+        Some(body)
+    }
+
+    /// Serialize this request as the HSM expects it: the timestamp and its
+    /// signature, followed by the certificate body
+    pub(crate) fn to_bytes(&self, ca_public_key: &PublicKey) -> Option<Vec<u8>> {
+        let mut bytes = self.timestamp.to_be_bytes().to_vec();
+        bytes.extend_from_slice(&self.timestamp_signature);
+        bytes.extend(self.certificate_body(ca_public_key)?);
+        Some(bytes)
+    }
+}
+
+/// Serialize critical options or extensions: each is a name followed by
+/// its data, which is empty for flags and otherwise contains the value as a
+/// `string`. Entries must be sorted by name, as they are in a `BTreeMap`.
+fn encode_options(options: &BTreeMap<String, String>) -> Vec<u8> {
+    let mut encoded = vec![];
+
+    for (name, value) in options {
+        put_string(&mut encoded, name.as_bytes());
+
+        if value.is_empty() {
+            put_string(&mut encoded, &[]);
+        } else {
+            let mut data = vec![];
+            put_string(&mut data, value.as_bytes());
+            put_string(&mut encoded, &data);
+        }
+    }
+
+    encoded
+}
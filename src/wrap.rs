@@ -4,14 +4,27 @@
 mod algorithm;
 pub(crate) mod commands;
 mod error;
+mod header;
+mod kcv;
+mod kdf;
 mod key;
+mod manifest;
 mod message;
 mod nonce;
+#[cfg(feature = "software-wrap")]
+mod software;
 
 pub use self::{
     algorithm::Algorithm,
     error::{Error, ErrorKind},
+    header::{HeaderFormat, ObjectHeader, HEADER_SIZE},
+    kcv::{WrapKeyKcv, KCV_SIZE},
+    kdf::{KdfParams, DEFAULT_KDF_ITERATIONS, MIN_SALT_SIZE},
     key::Key,
+    manifest::{BackupManifest, ManifestEntry},
     message::Message,
     nonce::Nonce,
 };
+
+#[cfg(feature = "software-wrap")]
+pub use self::software::{SoftwareKey, WrappedObject};
@@ -47,3 +47,15 @@ impl Algorithm {
 }
 
 impl_algorithm_serializers!(Algorithm);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_len() {
+        assert_eq!(Algorithm::Aes128Ccm.key_len(), 16);
+        assert_eq!(Algorithm::Aes192Ccm.key_len(), 24);
+        assert_eq!(Algorithm::Aes256Ccm.key_len(), 32);
+    }
+}
@@ -25,6 +25,10 @@ pub(crate) struct ExportWrappedCommand {
 
 impl Command for ExportWrappedCommand {
     type ResponseType = ExportWrappedResponse;
+
+    fn object(&self) -> Option<object::Handle> {
+        Some(object::Handle::new(self.object_id, self.object_type))
+    }
 }
 
 /// Response from `command::export_wrapped`
@@ -9,6 +9,14 @@ pub type Error = crate::Error<ErrorKind>;
 /// Kinds of wrap-related errors
 #[derive(Copy, Clone, Debug, Eq, Error, PartialEq)]
 pub enum ErrorKind {
+    /// Encrypting or decrypting a wrapped object failed
+    #[error("wrap crypto failed")]
+    CryptoFailed,
+
+    /// Parameters for deriving a wrap key from a passphrase are invalid
+    #[error("invalid key derivation parameters")]
+    KdfParamsInvalid,
+
     /// Wrap message is an invalid length
     #[error("invalid message length")]
     LengthInvalid,
@@ -0,0 +1,191 @@
+//! Headers of wrapped objects: the object information which precedes the
+//! object's data in the plaintext of a wrap message
+
+use super::{Error, ErrorKind};
+use crate::{
+    object,
+    serialization::{deserialize, serialize},
+};
+
+/// Size of a [`HeaderFormat::V1`] object header in bytes
+pub const HEADER_SIZE: usize = 66;
+
+/// Layouts of wrapped object headers
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum HeaderFormat {
+    /// The layout used by YubiHSM 2 firmware since 2.0: the object's info,
+    /// exactly as returned by `Get Object Info` ([`HEADER_SIZE`] bytes)
+    #[default]
+    V1,
+}
+
+impl HeaderFormat {
+    /// Size of headers in this format in bytes
+    pub fn header_size(self) -> usize {
+        match self {
+            HeaderFormat::V1 => HEADER_SIZE,
+        }
+    }
+}
+
+/// Header of a wrapped object, as found at the beginning of the plaintext of
+/// a wrap message (i.e. after decrypting it with the wrap key).
+///
+/// In a [`HeaderFormat::V1`] header this is the object's [`object::Info`],
+/// serialized exactly as in a `Get Object Info` response. Integers are big
+/// endian, and the fields are at these byte offsets:
+///
+/// | Bytes | Field                    |
+/// |-------|--------------------------|
+/// | 0-7   | `capabilities`           |
+/// | 8-9   | `object_id`              |
+/// | 10-11 | `length` (of the data)   |
+/// | 12-13 | `domains`                |
+/// | 14    | `object_type`            |
+/// | 15    | `algorithm`              |
+/// | 16    | `sequence`               |
+/// | 17    | `origin` (when wrapped)  |
+/// | 18-57 | `label`                  |
+/// | 58-65 | `delegated_capabilities` |
+///
+/// The object's data immediately follows the header, and is exactly
+/// `length` bytes long.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ObjectHeader(pub object::Info);
+
+impl ObjectHeader {
+    /// Parse the header at the beginning of the given wrapped object
+    /// plaintext, returning it along with the object's data.
+    ///
+    /// Fails with `ErrorKind::LengthInvalid` if the header is malformed, or
+    /// if the length of the data doesn't match the header.
+    pub fn parse(format: HeaderFormat, plaintext: &[u8]) -> Result<(Self, &[u8]), Error> {
+        let header_size = format.header_size();
+
+        ensure!(
+            plaintext.len() >= header_size,
+            ErrorKind::LengthInvalid,
+            "wrapped object too short: {} bytes",
+            plaintext.len()
+        );
+
+        let (header_bytes, data) = plaintext.split_at(header_size);
+
+        let info: object::Info = deserialize(header_bytes)
+            .map_err(|e| format_err!(ErrorKind::LengthInvalid, "malformed object header: {}", e))?;
+
+        ensure!(
+            data.len() == usize::from(info.length),
+            ErrorKind::LengthInvalid,
+            "wrapped object header says its data is {} bytes long (got {})",
+            info.length,
+            data.len()
+        );
+
+        Ok((ObjectHeader(info), data))
+    }
+
+    /// Serialize this header in the given format
+    pub fn to_bytes(&self, format: HeaderFormat) -> Vec<u8> {
+        match format {
+            HeaderFormat::V1 => serialize(&self.0).expect("object header serialization failed"),
+        }
+    }
+}
+
+impl From<object::Info> for ObjectHeader {
+    fn from(info: object::Info) -> Self {
+        ObjectHeader(info)
+    }
+}
+
+impl From<ObjectHeader> for object::Info {
+    fn from(header: ObjectHeader) -> Self {
+        header.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{asymmetric, Algorithm, Capability, Domain};
+
+    /// Header of a wrapped Ed25519 key (v1 layout). Changing the layout
+    /// breaks compatibility with objects wrapped by real devices, so this
+    /// must only change along with a new `HeaderFormat`.
+    const GOLDEN_HEADER: [u8; HEADER_SIZE] = [
+        // capabilities: sign-eddsa | exportable-under-wrap
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01, 0x00, //
+        // object_id: 0x0064
+        0x00, 0x64, //
+        // length: 32
+        0x00, 0x20, //
+        // domains: 1-2
+        0x00, 0x03, //
+        // object_type: asymmetric-key
+        0x03, //
+        // algorithm: ed25519
+        0x2e, //
+        // sequence: 7
+        0x07, //
+        // origin: generated
+        0x01, //
+        // label: "backup"
+        b'b', b'a', b'c', b'k', b'u', b'p', 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, //
+        // delegated_capabilities: none
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    fn golden_header() -> ObjectHeader {
+        ObjectHeader(object::Info {
+            capabilities: Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP,
+            object_id: 0x64,
+            length: 32,
+            domains: Domain::DOM1 | Domain::DOM2,
+            object_type: object::Type::AsymmetricKey,
+            algorithm: Algorithm::Asymmetric(asymmetric::Algorithm::Ed25519),
+            sequence: 7,
+            origin: object::Origin::Generated,
+            label: "backup".into(),
+            delegated_capabilities: Capability::empty(),
+        })
+    }
+
+    #[test]
+    fn golden_layout() {
+        let mut plaintext = GOLDEN_HEADER.to_vec();
+        plaintext.extend_from_slice(&[0x42; 32]);
+
+        let (header, data) = ObjectHeader::parse(HeaderFormat::V1, &plaintext).unwrap();
+        assert_eq!(header, golden_header());
+        assert_eq!(data, [0x42; 32]);
+
+        assert_eq!(header.to_bytes(HeaderFormat::V1), GOLDEN_HEADER);
+    }
+
+    #[test]
+    fn reject_wrong_lengths() {
+        let err =
+            ObjectHeader::parse(HeaderFormat::V1, &GOLDEN_HEADER[..HEADER_SIZE - 1]).unwrap_err();
+        assert_eq!(*err.kind(), ErrorKind::LengthInvalid);
+
+        // The header says there are 32 bytes of data: no fewer...
+        let mut plaintext = GOLDEN_HEADER.to_vec();
+        plaintext.extend_from_slice(&[0x42; 31]);
+        let err = ObjectHeader::parse(HeaderFormat::V1, &plaintext).unwrap_err();
+        assert_eq!(*err.kind(), ErrorKind::LengthInvalid);
+
+        // ...and no more
+        plaintext.extend_from_slice(&[0x42; 2]);
+        let err = ObjectHeader::parse(HeaderFormat::V1, &plaintext).unwrap_err();
+        assert_eq!(*err.kind(), ErrorKind::LengthInvalid);
+    }
+
+    #[test]
+    fn object_info_round_trip() {
+        let info = object::Info::from(golden_header());
+        assert_eq!(ObjectHeader::from(info), golden_header());
+    }
+}
@@ -0,0 +1,129 @@
+//! Key check values (KCVs) of wrap keys, so operators loading the same wrap
+//! key into several HSMs (e.g. in a key ceremony) can confirm they did so
+//! without revealing the key
+
+use super::{Algorithm, Error, ErrorKind};
+use aes::{
+    cipher::{generic_array::GenericArray, BlockEncrypt, KeyInit},
+    Aes128, Aes192, Aes256,
+};
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+/// Size of a key check value in bytes
+pub const KCV_SIZE: usize = 3;
+
+/// Key check value of a wrap key: the first [`KCV_SIZE`] bytes of an
+/// all-zero block encrypted with the key (using raw AES).
+///
+/// These are displayed (and parsed) as upper-case hex, e.g. `66E94B`.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct WrapKeyKcv([u8; KCV_SIZE]);
+
+impl WrapKeyKcv {
+    /// Compute the key check value of the given wrap key
+    pub fn compute(algorithm: Algorithm, key: &[u8]) -> Result<Self, Error> {
+        ensure!(
+            key.len() == algorithm.key_len(),
+            ErrorKind::LengthInvalid,
+            "expected {}-byte key for {:?} (got {})",
+            algorithm.key_len(),
+            algorithm,
+            key.len()
+        );
+
+        let mut block = GenericArray::default();
+
+        match algorithm {
+            Algorithm::Aes128Ccm => Aes128::new_from_slice(key)
+                .unwrap()
+                .encrypt_block(&mut block),
+            Algorithm::Aes192Ccm => Aes192::new_from_slice(key)
+                .unwrap()
+                .encrypt_block(&mut block),
+            Algorithm::Aes256Ccm => Aes256::new_from_slice(key)
+                .unwrap()
+                .encrypt_block(&mut block),
+        }
+
+        let mut kcv = [0u8; KCV_SIZE];
+        kcv.copy_from_slice(&block[..KCV_SIZE]);
+        Ok(Self(kcv))
+    }
+
+    /// Get the bytes of this key check value
+    pub fn as_bytes(&self) -> &[u8; KCV_SIZE] {
+        &self.0
+    }
+}
+
+impl From<[u8; KCV_SIZE]> for WrapKeyKcv {
+    fn from(bytes: [u8; KCV_SIZE]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl Display for WrapKeyKcv {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{byte:02X}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for WrapKeyKcv {
+    type Err = Error;
+
+    /// Parse a key check value from hex (in either case)
+    fn from_str(s: &str) -> Result<Self, Error> {
+        ensure!(
+            s.len() == KCV_SIZE * 2 && s.bytes().all(|b| b.is_ascii_hexdigit()),
+            ErrorKind::LengthInvalid,
+            "expected {} hex digits for key check value: {:?}",
+            KCV_SIZE * 2,
+            s
+        );
+
+        let mut kcv = [0u8; KCV_SIZE];
+
+        for (i, byte) in kcv.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).unwrap();
+        }
+
+        Ok(Self(kcv))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// AES encryptions of an all-zero block under an all-zero key
+    #[test]
+    fn known_answer_test() {
+        for (algorithm, kcv) in [
+            (Algorithm::Aes128Ccm, "66E94B"),
+            (Algorithm::Aes192Ccm, "AAE069"),
+            (Algorithm::Aes256Ccm, "DC95C0"),
+        ] {
+            let key = vec![0u8; algorithm.key_len()];
+            let computed = WrapKeyKcv::compute(algorithm, &key).unwrap();
+            assert_eq!(computed.to_string(), kcv);
+            assert_eq!(kcv.to_lowercase().parse::<WrapKeyKcv>().unwrap(), computed);
+        }
+    }
+
+    #[test]
+    fn reject_invalid() {
+        let err = WrapKeyKcv::compute(Algorithm::Aes256Ccm, &[0u8; 16]).unwrap_err();
+        assert_eq!(*err.kind(), ErrorKind::LengthInvalid);
+
+        for s in ["", "66E9", "66E94BD4", "66E94G"] {
+            assert!(s.parse::<WrapKeyKcv>().is_err(), "{s:?}");
+        }
+    }
+}
@@ -32,6 +32,17 @@ impl Key {
         Self::from_bytes(key_id, &bytes).unwrap()
     }
 
+    /// Derive a wrap key from a passphrase (see [`wrap::KdfParams`])
+    #[cfg(feature = "passwords")]
+    pub fn derive_from_passphrase(
+        key_id: object::Id,
+        passphrase: &str,
+        params: &wrap::KdfParams,
+    ) -> Result<Self, wrap::Error> {
+        let bytes = params.derive_key(passphrase)?;
+        Ok(Self::from_bytes(key_id, &bytes).unwrap())
+    }
+
     /// Create a new `wrap::Key` instance. Must be 16, 24, or 32-bytes long.
     pub fn from_bytes(key_id: object::Id, bytes: &[u8]) -> Result<Self, device::Error> {
         let alg = match bytes.len() {
@@ -79,6 +90,13 @@ impl Key {
         self
     }
 
+    /// Compute the key check value of this key, e.g. to record it so other
+    /// operators can check they load the same key
+    pub fn kcv(&self) -> wrap::WrapKeyKcv {
+        let algorithm = self.import_params.algorithm.wrap().unwrap();
+        wrap::WrapKeyKcv::compute(algorithm, &self.data).unwrap()
+    }
+
     /// Create this key within the HSM
     pub fn create(&self, client: &Client) -> Result<(), client::Error> {
         let algorithm = self.import_params.algorithm.wrap().unwrap();
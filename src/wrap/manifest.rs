@@ -0,0 +1,142 @@
+//! Backup manifests: human-auditable listings of the objects exported by
+//! [`Client::backup_all`](crate::Client::backup_all)
+
+use super::{nonce, KdfParams, Message, Nonce};
+use crate::{object, object::label, Algorithm};
+use serde::{Deserialize, Serialize};
+
+/// Metadata for each object in a backup (i.e. a set of wrapped objects
+/// exported under the same wrap key), suitable for serializing to e.g. JSON
+/// and storing alongside the wrapped objects.
+///
+/// The manifest contains no key material: wrapped objects are matched to
+/// their entries by nonce, allowing a chosen subset to be restored.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BackupManifest {
+    /// ID of the wrap key the objects were exported under
+    pub wrap_key_id: object::Id,
+
+    /// Entries for each wrapped object in the backup
+    pub entries: Vec<ManifestEntry>,
+
+    /// Parameters for re-deriving the wrap key from its passphrase, if it
+    /// was derived from one (see [`Client::put_wrap_key_from_passphrase`])
+    ///
+    /// [`Client::put_wrap_key_from_passphrase`]: crate::Client::put_wrap_key_from_passphrase
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kdf: Option<KdfParams>,
+}
+
+impl BackupManifest {
+    /// Create an empty manifest for a backup under the given wrap key
+    pub fn new(wrap_key_id: object::Id) -> Self {
+        Self {
+            wrap_key_id,
+            entries: vec![],
+            kdf: None,
+        }
+    }
+
+    /// Find the manifest entry for a wrapped object, if it's in this backup
+    pub fn entry_for(&self, message: &Message) -> Option<&ManifestEntry> {
+        self.entries.iter().find(|entry| entry.matches(message))
+    }
+}
+
+/// Metadata about a wrapped object in a [`BackupManifest`]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ManifestEntry {
+    /// ID of the wrapped object
+    pub object_id: object::Id,
+
+    /// Type of the wrapped object (serialized by name, e.g. `asymmetric-key`)
+    #[serde(with = "type_name")]
+    pub object_type: object::Type,
+
+    /// Algorithm of the wrapped object
+    pub algorithm: Algorithm,
+
+    /// Label of the wrapped object (serialized as a string)
+    #[serde(default, with = "label::text")]
+    pub label: object::Label,
+
+    /// Nonce the object was wrapped with (serialized as hex)
+    #[serde(with = "nonce_hex")]
+    pub nonce: Nonce,
+}
+
+impl ManifestEntry {
+    /// Create a manifest entry for an object exported as the given message
+    pub fn new(info: &object::Info, message: &Message) -> Self {
+        Self {
+            object_id: info.object_id,
+            object_type: info.object_type,
+            algorithm: info.algorithm,
+            label: info.label.clone(),
+            nonce: message.nonce.clone(),
+        }
+    }
+
+    /// Handle of the wrapped object
+    pub fn handle(&self) -> object::Handle {
+        object::Handle::new(self.object_id, self.object_type)
+    }
+
+    /// Is the given wrapped object the one described by this entry?
+    pub fn matches(&self, message: &Message) -> bool {
+        self.nonce == message.nonce
+    }
+}
+
+/// Serialize object types by name
+mod type_name {
+    use crate::object;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        object_type: &object::Type,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(object_type)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<object::Type, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse()
+            .map_err(|_| de::Error::custom(format!("invalid object type: {name}")))
+    }
+}
+
+/// Serialize nonces as lower-case hex
+mod nonce_hex {
+    use super::{nonce, Nonce};
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(nonce: &Nonce, serializer: S) -> Result<S::Ok, S::Error> {
+        let hex: String = nonce.0.iter().map(|byte| format!("{byte:02x}")).collect();
+        serializer.serialize_str(&hex)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Nonce, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        let mut bytes = [0u8; nonce::SIZE];
+
+        if hex.len() != nonce::SIZE * 2 || !hex.is_ascii() {
+            return Err(de::Error::custom(format!(
+                "nonce must be {} hex characters",
+                nonce::SIZE * 2
+            )));
+        }
+
+        for (byte, digits) in bytes.iter_mut().zip(hex.as_bytes().chunks(2)) {
+            *byte = std::str::from_utf8(digits)
+                .ok()
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                .ok_or_else(|| de::Error::custom(format!("invalid hex in nonce: {hex}")))?;
+        }
+
+        Ok(Nonce(bytes))
+    }
+}
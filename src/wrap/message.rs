@@ -1,9 +1,12 @@
 //! Wrap messages
 
 use super::nonce::{self, Nonce};
-use super::{Error, ErrorKind};
+use super::{Error, ErrorKind, HEADER_SIZE};
 use serde::{Deserialize, Serialize};
 
+/// Size of the AES-CCM authentication tag which ends every wrap message
+pub(crate) const TAG_SIZE: usize = 8;
+
 /// Wrap wessage (encrypted HSM object or arbitrary data) encrypted under a wrap key
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Message {
@@ -43,6 +46,29 @@ impl Message {
         }
     }
 
+    /// Length of this message when serialized (i.e. its nonce and
+    /// ciphertext)
+    pub fn len(&self) -> usize {
+        nonce::SIZE + self.ciphertext.len()
+    }
+
+    /// Is the ciphertext empty?
+    pub fn is_empty(&self) -> bool {
+        self.ciphertext.is_empty()
+    }
+
+    /// Estimate the length of the wrapped object's data (e.g. key material)
+    /// without decrypting it, i.e. the ciphertext's length less the object
+    /// info and authentication tag.
+    ///
+    /// This is exact for messages produced by the HSM's Export Wrapped
+    /// command, and is the length the object will occupy once imported
+    /// (see [`device::StorageInfo::footprint`](crate::device::StorageInfo::footprint)).
+    /// It's meaningless for messages wrapping arbitrary data.
+    pub fn decoded_len_estimate(&self) -> usize {
+        self.ciphertext.len().saturating_sub(HEADER_SIZE + TAG_SIZE)
+    }
+
     /// Convert this message into a byte vector
     pub fn into_vec(self) -> Vec<u8> {
         self.into()
@@ -7,7 +7,7 @@ use rand_core::{OsRng, RngCore};
 pub const SIZE: usize = 13;
 
 /// Nonces for AES-CCM keywrapping
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Nonce(pub [u8; SIZE]);
 
 impl Nonce {
@@ -32,7 +32,7 @@ impl From<[u8; SIZE]> for Nonce {
     }
 }
 
-impl<'a> From<&'a [u8]> for Nonce {
+impl From<&[u8]> for Nonce {
     fn from(bytes: &[u8]) -> Nonce {
         assert_eq!(
             bytes.len(),
@@ -0,0 +1,145 @@
+//! Software encryption and decryption of wrapped objects.
+//!
+//! The YubiHSM 2 never reveals the plaintext of wrapped objects, however
+//! anyone holding the wrap key can decrypt them. Doing so in software exposes
+//! the object's contents (e.g. private keys) to host memory, so this is only
+//! available through a [`SoftwareKey`] which must be explicitly constructed
+//! from the wrap key's bytes.
+
+use super::{nonce, Error, ErrorKind, HeaderFormat, Key, Message, Nonce, ObjectHeader};
+use crate::{
+    device::{StorageFootprint, StorageInfo},
+    object,
+};
+use aes::cipher::{
+    consts::{U13, U8},
+    generic_array::GenericArray,
+};
+use ccm::aead::{AeadInPlace, KeyInit};
+use rand_core::{OsRng, RngCore};
+use std::fmt::{self, Debug};
+use zeroize::Zeroizing;
+
+/// AES-CCM with a 128-bit key
+type Aes128Ccm = ccm::Ccm<aes::Aes128, U8, U13>;
+
+/// AES-CCM with a 192-bit key
+type Aes192Ccm = ccm::Ccm<aes::Aes192, U8, U13>;
+
+/// AES-CCM with a 256-bit key
+type Aes256Ccm = ccm::Ccm<aes::Aes256, U8, U13>;
+
+/// Wrap key held in host memory, used to encrypt and decrypt wrapped objects
+/// in software.
+///
+/// **WARNING:** decrypting wrapped objects outside of the HSM exposes their
+/// plaintext (including any private keys) to this process. Only use this on
+/// hosts trusted with the wrap key and every object wrapped under it.
+pub struct SoftwareKey(Key);
+
+impl SoftwareKey {
+    /// Opt in to encrypting and decrypting wrapped objects in software
+    /// using the given wrap key.
+    pub fn new(key: Key) -> Self {
+        SoftwareKey(key)
+    }
+
+    /// ID of this wrap key within the HSM
+    pub fn id(&self) -> object::Id {
+        self.0.import_params.id
+    }
+
+    /// Decrypt a wrapped object
+    pub fn decrypt(&self, message: &Message) -> Result<WrappedObject, Error> {
+        let mut plaintext = Zeroizing::new(message.ciphertext.clone());
+
+        match self.0.data.len() {
+            16 => decrypt_in_place::<Aes128Ccm>(&self.0.data, &message.nonce, &mut plaintext),
+            24 => decrypt_in_place::<Aes192Ccm>(&self.0.data, &message.nonce, &mut plaintext),
+            32 => decrypt_in_place::<Aes256Ccm>(&self.0.data, &message.nonce, &mut plaintext),
+            _ => unreachable!("wrap key length checked by wrap::Key::from_bytes"),
+        }?;
+
+        let (header, data) = ObjectHeader::parse(HeaderFormat::V1, &plaintext)?;
+
+        Ok(WrappedObject {
+            object_info: header.into(),
+            data: Zeroizing::new(data.to_vec()),
+        })
+    }
+
+    /// Encrypt an object under this wrap key (using a random nonce)
+    pub fn encrypt(&self, object: &WrappedObject) -> Result<Message, Error> {
+        let mut nonce = Nonce([0u8; nonce::SIZE]);
+        OsRng.fill_bytes(&mut nonce.0);
+
+        let mut ciphertext =
+            ObjectHeader::from(object.object_info.clone()).to_bytes(HeaderFormat::V1);
+        ciphertext.extend_from_slice(&object.data);
+
+        match self.0.data.len() {
+            16 => encrypt_in_place::<Aes128Ccm>(&self.0.data, &nonce, &mut ciphertext),
+            24 => encrypt_in_place::<Aes192Ccm>(&self.0.data, &nonce, &mut ciphertext),
+            32 => encrypt_in_place::<Aes256Ccm>(&self.0.data, &nonce, &mut ciphertext),
+            _ => unreachable!("wrap key length checked by wrap::Key::from_bytes"),
+        }?;
+
+        Ok(Message::new(nonce, ciphertext))
+    }
+}
+
+impl Debug for SoftwareKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "yubihsm::wrap::SoftwareKey({:?})", self.0)
+    }
+}
+
+/// Decrypted contents of a wrapped object
+pub struct WrappedObject {
+    /// Information about the object (including its ID and label)
+    pub object_info: object::Info,
+
+    /// Serialized object data (e.g. key material)
+    pub data: Zeroizing<Vec<u8>>,
+}
+
+impl WrappedObject {
+    /// Storage this object will occupy in an HSM with the given storage
+    /// layout once imported
+    pub fn storage_footprint(&self, storage: &StorageInfo) -> StorageFootprint {
+        storage.footprint(self.data.len())
+    }
+}
+
+impl Debug for WrappedObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Avoid leaking secrets in debug messages
+        write!(
+            f,
+            "yubihsm::wrap::WrappedObject {{ object_info: {:?}, data: ... }}",
+            self.object_info
+        )
+    }
+}
+
+/// Encrypt data in-place using AES-CCM
+fn encrypt_in_place<C>(key: &[u8], nonce: &Nonce, buffer: &mut Vec<u8>) -> Result<(), Error>
+where
+    C: AeadInPlace<NonceSize = U13> + KeyInit,
+{
+    C::new_from_slice(key)
+        .unwrap()
+        .encrypt_in_place(GenericArray::from_slice(&nonce.0), b"", buffer)
+        .map_err(|_| format_err!(ErrorKind::CryptoFailed, "error encrypting wrapped object").into())
+}
+
+/// Decrypt data in-place using AES-CCM
+fn decrypt_in_place<C>(key: &[u8], nonce: &Nonce, buffer: &mut Vec<u8>) -> Result<(), Error>
+where
+    C: AeadInPlace<NonceSize = U13> + KeyInit,
+{
+    C::new_from_slice(key)
+        .unwrap()
+        .decrypt_in_place(GenericArray::from_slice(&nonce.0), b"", buffer)
+        .map_err(|_| format_err!(ErrorKind::CryptoFailed, "error decrypting wrapped object").into())
+}
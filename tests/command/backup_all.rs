@@ -0,0 +1,118 @@
+use crate::{clear_test_key_slot, TEST_DOMAINS, TEST_KEY_ID, TEST_KEY_LABEL};
+use yubihsm::{asymmetric, object, wrap, Capability};
+
+/// Back up every exportable object, then restore a selection of them using
+/// the (serialized) backup manifest
+#[test]
+fn backup_all_test() {
+    let client = crate::get_hsm_client();
+
+    clear_test_key_slot(&client, object::Type::WrapKey);
+    client
+        .put_wrap_key(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::EXPORT_WRAPPED | Capability::IMPORT_WRAPPED,
+            Capability::all(),
+            wrap::Algorithm::Aes128Ccm,
+            [0x42; 16],
+        )
+        .unwrap_or_else(|err| panic!("error putting wrap key: {err}"));
+
+    let keys = [
+        (360, Capability::EXPORTABLE_UNDER_WRAP),
+        (361, Capability::EXPORTABLE_UNDER_WRAP),
+        (362, Capability::empty()),
+        (363, Capability::EXPORTABLE_UNDER_WRAP),
+    ];
+
+    for (key_id, capabilities) in keys {
+        let _ = client.delete_object(key_id, object::Type::AsymmetricKey);
+
+        client
+            .generate_asymmetric_key(
+                key_id,
+                TEST_KEY_LABEL.into(),
+                TEST_DOMAINS,
+                Capability::SIGN_EDDSA | capabilities,
+                asymmetric::Algorithm::Ed25519,
+            )
+            .unwrap_or_else(|err| panic!("error generating asymmetric key: {err}"));
+    }
+
+    let (manifest, messages) = client
+        .backup_all(TEST_KEY_ID)
+        .unwrap_or_else(|err| panic!("error backing up objects: {err}"));
+
+    assert_eq!(manifest.wrap_key_id, TEST_KEY_ID);
+    assert_eq!(manifest.entries.len(), messages.len());
+
+    for (entry, message) in manifest.entries.iter().zip(&messages) {
+        assert!(entry.matches(message));
+    }
+
+    // Other tests may leave exportable objects behind, so only look at ours
+    let backed_up: Vec<_> = manifest
+        .entries
+        .iter()
+        .filter(|entry| {
+            entry.object_type == object::Type::AsymmetricKey
+                && (360..=363).contains(&entry.object_id)
+        })
+        .map(|entry| entry.object_id)
+        .collect();
+
+    assert_eq!(backed_up, [360, 361, 363]);
+
+    let json = serde_json::to_string(&manifest).unwrap();
+    assert!(json.contains(r#""object_type":"asymmetric-key""#));
+    assert!(json.contains(&format!(r#""label":"{TEST_KEY_LABEL}""#)));
+    let manifest: wrap::BackupManifest = serde_json::from_str(&json).unwrap();
+
+    for key_id in [360, 361, 363] {
+        client
+            .delete_object(key_id, object::Type::AsymmetricKey)
+            .unwrap();
+    }
+
+    // Restore only keys 361 and 363
+    for message in messages {
+        let entry = manifest.entry_for(&message).unwrap();
+
+        if entry.object_type != object::Type::AsymmetricKey
+            || ![361, 363].contains(&entry.object_id)
+        {
+            continue;
+        }
+
+        let handle = client
+            .import_wrapped(TEST_KEY_ID, message)
+            .unwrap_or_else(|err| panic!("error importing wrapped object: {err}"));
+
+        assert_eq!(handle, entry.handle());
+
+        let info = client
+            .get_object_info(entry.object_id, entry.object_type)
+            .unwrap();
+
+        assert_eq!(info.algorithm, entry.algorithm);
+        assert_eq!(info.origin, object::Origin::WrappedGenerated);
+    }
+
+    let restored = |key_id| {
+        client
+            .get_object_info(key_id, object::Type::AsymmetricKey)
+            .is_ok()
+    };
+
+    assert!(!restored(360));
+    assert!(restored(361));
+    assert!(restored(363));
+
+    for key_id in [361, 362, 363] {
+        client
+            .delete_object(key_id, object::Type::AsymmetricKey)
+            .unwrap();
+    }
+}
@@ -0,0 +1,29 @@
+use yubihsm::{device, Client, Connector, Credentials};
+
+/// Maximum number of concurrent sessions supported by the YubiHSM 2
+const MAX_SESSIONS: usize = 16;
+
+/// Ensure the MockHsm refuses to open more than 16 concurrent sessions
+#[test]
+fn sessions_full_test() {
+    // Use a dedicated MockHsm so sessions opened by other tests don't count
+    let connector = Connector::mockhsm();
+
+    let mut clients = (0..MAX_SESSIONS)
+        .map(|_| Client::open(connector.clone(), Credentials::default(), false).unwrap())
+        .collect::<Vec<_>>();
+
+    let err = Client::open(connector.clone(), Credentials::default(), false)
+        .err()
+        .expect("17th session opened");
+    assert_eq!(err.device_error(), Some(device::ErrorKind::SessionsFull));
+
+    // Dropping a client closes its session, freeing up a slot
+    drop(clients.pop());
+    clients.push(Client::open(connector.clone(), Credentials::default(), false).unwrap());
+
+    // All sessions are still usable
+    for client in &clients {
+        assert_eq!(client.echo(b"hello").unwrap(), b"hello");
+    }
+}
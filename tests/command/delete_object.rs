@@ -1,5 +1,9 @@
-use crate::{generate_asymmetric_key, TEST_KEY_ID};
-use yubihsm::{asymmetric, object, Capability};
+use crate::{
+    clear_test_key_slot, generate_asymmetric_key, TEST_DOMAINS, TEST_KEY_ID, TEST_KEY_LABEL,
+};
+use yubihsm::{
+    asymmetric, authentication, client, hmac, object, Capability, Client, Credentials, Domain,
+};
 
 /// Delete an object in the YubiHSM 2
 #[test]
@@ -22,3 +26,116 @@ fn delete_object_test() {
         .delete_object(TEST_KEY_ID, object::Type::AsymmetricKey)
         .is_err());
 }
+
+/// Delete every object in a domain, leaving other domains (and objects shared
+/// with them) untouched.
+///
+/// Uses domains no other test puts objects in, so leftovers from other
+/// tests don't affect the deletion count.
+#[test]
+fn delete_all_objects_in_domain_test() {
+    let client = crate::get_hsm_client();
+
+    let objects = (300..305)
+        .map(|key_id| (key_id, Domain::DOM15))
+        .chain(Some((305, Domain::DOM15 | Domain::DOM16)))
+        .chain((310..313).map(|key_id| (key_id, Domain::DOM16)));
+
+    for (key_id, domain) in objects {
+        let _ = client.delete_object(key_id, object::Type::HmacKey);
+
+        client
+            .generate_hmac_key(
+                key_id,
+                TEST_KEY_LABEL.into(),
+                domain,
+                Capability::SIGN_HMAC,
+                hmac::Algorithm::Sha256,
+            )
+            .unwrap_or_else(|err| panic!("error generating HMAC key: {err}"));
+    }
+
+    // Filtering by a type no objects in the domain have deletes nothing
+    let (deleted, errors) = client
+        .delete_all_objects_in_domain(Domain::DOM15, Some(object::Type::Opaque))
+        .unwrap_or_else(|err| panic!("error deleting objects: {err}"));
+
+    assert_eq!(deleted, 0);
+    assert!(errors.is_empty());
+
+    let (deleted, errors) = client
+        .delete_all_objects_in_domain(Domain::DOM15, None)
+        .unwrap_or_else(|err| panic!("error deleting objects: {err}"));
+
+    assert_eq!(deleted, 5);
+    assert!(errors.is_empty());
+
+    let remaining = client
+        .list_objects(&[object::Filter::Type(object::Type::HmacKey)])
+        .unwrap_or_else(|err| panic!("error listing objects: {err}"))
+        .into_iter()
+        .filter(|entry| (300..313).contains(&entry.object_id))
+        .map(|entry| entry.object_id)
+        .collect::<Vec<_>>();
+
+    assert_eq!(remaining, [305, 310, 311, 312]);
+
+    // Deleting a domain must never delete the session's own authentication key
+    assert!(client
+        .delete_all_objects_in_domain(Domain::DOM15, Some(object::Type::AuthenticationKey))
+        .is_ok());
+    assert!(client.whoami().is_ok());
+}
+
+/// Refuse to delete the session's own authentication key unless forced
+#[test]
+fn delete_own_authentication_key_test() {
+    let client = crate::get_hsm_client();
+    let key_id = client.whoami().unwrap().object_id;
+
+    let err = client
+        .delete_object(key_id, object::Type::AuthenticationKey)
+        .unwrap_err();
+    assert_eq!(*err.kind(), client::ErrorKind::RefusingSelfDelete);
+    assert_eq!(client.whoami().unwrap().object_id, key_id);
+
+    // Other authentication keys can be deleted as usual
+    let authentication_key = authentication::Key::random();
+    clear_test_key_slot(&client, object::Type::AuthenticationKey);
+
+    let put_key = || {
+        client
+            .put_authentication_key(
+                TEST_KEY_ID,
+                TEST_KEY_LABEL.into(),
+                TEST_DOMAINS,
+                Capability::DELETE_AUTHENTICATION_KEY,
+                Capability::empty(),
+                authentication::Algorithm::YubicoAes,
+                authentication_key.clone(),
+            )
+            .unwrap_or_else(|err| panic!("error putting auth key: {err}"));
+    };
+
+    put_key();
+    client
+        .delete_object(TEST_KEY_ID, object::Type::AuthenticationKey)
+        .unwrap_or_else(|err| panic!("error deleting auth key: {err}"));
+
+    // A session's own key can only be deleted by force (tested with another
+    // key, so the shared client's key survives)
+    put_key();
+    let credentials = Credentials::new(TEST_KEY_ID, authentication_key);
+    let other_client = Client::open(crate::HSM_CONNECTOR.clone(), credentials.clone(), false)
+        .unwrap_or_else(|err| panic!("error opening session with new auth key: {err}"));
+
+    let err = other_client
+        .delete_object(TEST_KEY_ID, object::Type::AuthenticationKey)
+        .unwrap_err();
+    assert_eq!(*err.kind(), client::ErrorKind::RefusingSelfDelete);
+
+    other_client
+        .delete_object_force(TEST_KEY_ID, object::Type::AuthenticationKey)
+        .unwrap_or_else(|err| panic!("error force deleting own auth key: {err}"));
+    assert!(Client::open(crate::HSM_CONNECTOR.clone(), credentials, false).is_err());
+}
@@ -11,3 +11,28 @@ fn device_info_test() {
     // depending on the specific YubiHSM 2 model.
     assert_eq!(device_info.major_version, 2);
 }
+
+/// Log utilization reflects the number of unconsumed log entries
+#[cfg(feature = "mockhsm")]
+#[test]
+fn log_utilization_test() {
+    use yubihsm::{Client, Connector};
+
+    // Use a dedicated MockHsm so the log only contains this test's commands
+    let client = Client::open(Connector::mockhsm(), Default::default(), false).unwrap();
+    let device_info = client.device_info().unwrap();
+    let half_full = device_info.log_size() / 2;
+
+    // `GetPseudoRandom` is audited by default
+    for _ in device_info.log_used()..half_full {
+        client.get_pseudo_random(1).unwrap();
+    }
+
+    assert_eq!(client.device_info().unwrap().log_used(), half_full);
+
+    let utilization = client.log_utilization().unwrap();
+    assert!(
+        (utilization - 0.5).abs() < 0.01,
+        "unexpected log utilization: {utilization}"
+    );
+}
@@ -2,7 +2,7 @@ use crate::{
     clear_test_key_slot, test_vectors::AESCCM_TEST_VECTORS, TEST_DOMAINS, TEST_EXPORTED_KEY_ID,
     TEST_EXPORTED_KEY_LABEL, TEST_KEY_ID, TEST_KEY_LABEL,
 };
-use yubihsm::{asymmetric, object, wrap, Capability};
+use yubihsm::{asymmetric, hmac, object, opaque, ssh, wrap, Capability};
 
 /// Test wrap key workflow using randomly generated keys
 // TODO: test against RFC 3610 vectors
@@ -78,3 +78,126 @@ fn wrap_key_test() {
         TEST_EXPORTED_KEY_LABEL
     );
 }
+
+/// Export and re-import HMAC keys, opaque objects and templates, checking
+/// their contents survive the round trip
+#[test]
+fn wrap_object_types_test() {
+    const WRAP_KEY: [u8; 32] = [0x42; 32];
+
+    let client = crate::get_hsm_client();
+    let exportable = Capability::EXPORTABLE_UNDER_WRAP;
+    let object_types = [
+        object::Type::HmacKey,
+        object::Type::Opaque,
+        object::Type::Template,
+    ];
+
+    clear_test_key_slot(&client, object::Type::WrapKey);
+    client
+        .put_wrap_key(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::EXPORT_WRAPPED | Capability::IMPORT_WRAPPED,
+            Capability::all(),
+            wrap::Algorithm::Aes256Ccm,
+            WRAP_KEY,
+        )
+        .unwrap_or_else(|err| panic!("error putting wrap key: {err}"));
+
+    for object_type in object_types {
+        let _ = client.delete_object(TEST_EXPORTED_KEY_ID, object_type);
+    }
+
+    client
+        .put_hmac_key(
+            TEST_EXPORTED_KEY_ID,
+            TEST_EXPORTED_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::SIGN_HMAC | Capability::VERIFY_HMAC | exportable,
+            hmac::Algorithm::Sha256,
+            vec![0x24; 48],
+        )
+        .unwrap_or_else(|err| panic!("error putting HMAC key: {err}"));
+
+    client
+        .put_opaque(
+            TEST_EXPORTED_KEY_ID,
+            TEST_EXPORTED_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::GET_OPAQUE | exportable,
+            opaque::Algorithm::Data,
+            vec![0x5a; 777],
+        )
+        .unwrap_or_else(|err| panic!("error putting opaque object: {err}"));
+
+    client
+        .put_template(
+            TEST_EXPORTED_KEY_ID,
+            TEST_EXPORTED_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::GET_TEMPLATE | exportable,
+            ssh::Template::from_bytes(vec![0xa5; 123]),
+        )
+        .unwrap_or_else(|err| panic!("error putting template: {err}"));
+
+    let tag = client
+        .sign_hmac(TEST_EXPORTED_KEY_ID, b"hello".as_ref())
+        .unwrap();
+    let opaque_data = client.get_opaque(TEST_EXPORTED_KEY_ID).unwrap();
+    let template = client.get_template(TEST_EXPORTED_KEY_ID).unwrap();
+
+    for object_type in object_types {
+        let info = client
+            .get_object_info(TEST_EXPORTED_KEY_ID, object_type)
+            .unwrap();
+        let message = client
+            .export_wrapped(TEST_KEY_ID, object_type, TEST_EXPORTED_KEY_ID)
+            .unwrap_or_else(|err| panic!("error exporting {object_type:?}: {err}"));
+
+        // The wrapped payload is exactly as long as the object
+        #[cfg(feature = "software-wrap")]
+        {
+            let wrap_key = wrap::Key::from_bytes(TEST_KEY_ID, &WRAP_KEY).unwrap();
+            let object = wrap::SoftwareKey::new(wrap_key).decrypt(&message).unwrap();
+            assert_eq!(object.data.len(), usize::from(info.length));
+        }
+
+        client
+            .delete_object(TEST_EXPORTED_KEY_ID, object_type)
+            .unwrap();
+
+        let handle = client
+            .import_wrapped(TEST_KEY_ID, message)
+            .unwrap_or_else(|err| panic!("error importing {object_type:?}: {err}"));
+        assert_eq!(
+            handle,
+            object::Handle::new(TEST_EXPORTED_KEY_ID, object_type)
+        );
+
+        let restored = client
+            .get_object_info(TEST_EXPORTED_KEY_ID, object_type)
+            .unwrap();
+        assert_eq!(restored.length, info.length);
+        assert_eq!(restored.algorithm, info.algorithm);
+        assert_eq!(restored.capabilities, info.capabilities);
+        assert_eq!(restored.label, info.label);
+        assert_eq!(restored.origin, object::Origin::WrappedImported);
+    }
+
+    assert!(client
+        .verify_hmac(TEST_EXPORTED_KEY_ID, b"hello".as_ref(), tag)
+        .is_ok());
+    assert_eq!(
+        client.get_opaque(TEST_EXPORTED_KEY_ID).unwrap(),
+        opaque_data
+    );
+    assert_eq!(client.get_template(TEST_EXPORTED_KEY_ID).unwrap(), template);
+
+    for object_type in object_types {
+        client
+            .delete_object(TEST_EXPORTED_KEY_ID, object_type)
+            .unwrap();
+    }
+}
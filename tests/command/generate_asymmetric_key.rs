@@ -1,5 +1,8 @@
-use crate::{generate_asymmetric_key, TEST_DOMAINS, TEST_KEY_ID, TEST_KEY_LABEL};
-use yubihsm::{asymmetric, object, Capability};
+use crate::{
+    clear_test_key_slot, generate_asymmetric_key, TEST_DOMAINS, TEST_KEY_ID, TEST_KEY_LABEL,
+    TEST_MESSAGE,
+};
+use yubihsm::{asymmetric, authentication, device, object, Capability, Client, Credentials};
 
 /// Generate an Ed25519 key
 #[test]
@@ -70,3 +73,123 @@ fn nistp256_key_test() {
     assert_eq!(object_info.origin, object::Origin::Generated);
     assert_eq!(&object_info.label.to_string(), TEST_KEY_LABEL);
 }
+
+/// Validate generating a key in a free slot, then in the same (now occupied) slot
+#[test]
+fn validate_generate_test() {
+    let client = crate::get_hsm_client();
+    let algorithm = asymmetric::Algorithm::Ed25519;
+    let capabilities = Capability::SIGN_EDDSA;
+
+    clear_test_key_slot(&client, object::Type::AsymmetricKey);
+
+    client
+        .validate_generate(TEST_KEY_ID, TEST_DOMAINS, capabilities, algorithm)
+        .unwrap_or_else(|err| panic!("error validating key generation: {err}"));
+
+    // Validation doesn't create anything
+    assert!(client
+        .get_object_info(TEST_KEY_ID, object::Type::AsymmetricKey)
+        .is_err());
+
+    generate_asymmetric_key(&client, algorithm, capabilities);
+
+    let err = client
+        .validate_generate(TEST_KEY_ID, TEST_DOMAINS, capabilities, algorithm)
+        .expect_err("validated generating key in occupied slot");
+
+    assert_eq!(err.device_error(), Some(device::ErrorKind::ObjectExists));
+
+    // Key ID 0 has the HSM pick a free ID, so it never conflicts
+    client
+        .validate_generate(0, TEST_DOMAINS, capabilities, algorithm)
+        .unwrap_or_else(|err| panic!("error validating key generation: {err}"));
+}
+
+/// Validation fails for capabilities beyond those the authentication key delegates
+#[test]
+fn validate_generate_capability_overflow_test() {
+    let client = crate::get_hsm_client();
+
+    clear_test_key_slot(&client, object::Type::AuthenticationKey);
+    clear_test_key_slot(&client, object::Type::AsymmetricKey);
+
+    let authentication_key = authentication::Key::derive_from_password(TEST_MESSAGE);
+
+    client
+        .put_authentication_key(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::GENERATE_ASYMMETRIC_KEY,
+            Capability::SIGN_ECDSA,
+            authentication::Algorithm::YubicoAes,
+            authentication_key.clone(),
+        )
+        .unwrap_or_else(|err| panic!("error putting auth key: {err}"));
+
+    let credentials = Credentials::new(TEST_KEY_ID, authentication_key);
+    let other_client = Client::open(crate::HSM_CONNECTOR.clone(), credentials, false)
+        .unwrap_or_else(|err| panic!("error opening session with new auth key: {err}"));
+
+    let algorithm = asymmetric::Algorithm::EcP256;
+
+    other_client
+        .validate_generate(TEST_KEY_ID, TEST_DOMAINS, Capability::SIGN_ECDSA, algorithm)
+        .unwrap_or_else(|err| panic!("error validating key generation: {err}"));
+
+    let err = other_client
+        .validate_generate(
+            TEST_KEY_ID,
+            TEST_DOMAINS,
+            Capability::SIGN_ECDSA | Capability::EXPORTABLE_UNDER_WRAP,
+            algorithm,
+        )
+        .expect_err("validated generating key with undelegated capabilities");
+
+    assert_eq!(
+        err.device_error(),
+        Some(device::ErrorKind::InsufficientPermissions)
+    );
+}
+
+/// Validation fails for authentication keys which can't generate keys at all
+#[test]
+fn validate_generate_missing_capability_test() {
+    let client = crate::get_hsm_client();
+
+    clear_test_key_slot(&client, object::Type::AuthenticationKey);
+    clear_test_key_slot(&client, object::Type::AsymmetricKey);
+
+    let authentication_key = authentication::Key::derive_from_password(TEST_MESSAGE);
+
+    client
+        .put_authentication_key(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::GET_OPAQUE,
+            Capability::SIGN_ECDSA,
+            authentication::Algorithm::YubicoAes,
+            authentication_key.clone(),
+        )
+        .unwrap_or_else(|err| panic!("error putting auth key: {err}"));
+
+    let credentials = Credentials::new(TEST_KEY_ID, authentication_key);
+    let other_client = Client::open(crate::HSM_CONNECTOR.clone(), credentials, false)
+        .unwrap_or_else(|err| panic!("error opening session with new auth key: {err}"));
+
+    let err = other_client
+        .validate_generate(
+            TEST_KEY_ID,
+            TEST_DOMAINS,
+            Capability::SIGN_ECDSA,
+            asymmetric::Algorithm::EcP256,
+        )
+        .expect_err("validated generating key without the generate capability");
+
+    assert_eq!(
+        err.device_error(),
+        Some(device::ErrorKind::InsufficientPermissions)
+    );
+}
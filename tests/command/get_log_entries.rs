@@ -1,3 +1,8 @@
+#[cfg(feature = "mockhsm")]
+use crate::{TEST_DOMAINS, TEST_KEY_LABEL, TEST_MESSAGE};
+#[cfg(feature = "mockhsm")]
+use yubihsm::object;
+
 /// Get audit log
 #[test]
 fn get_audit_logs_test() {
@@ -8,3 +13,96 @@ fn get_audit_logs_test() {
         .get_log_entries()
         .unwrap_or_else(|err| panic!("error getting logs: {err}"));
 }
+
+/// Aggregate key usage from the audit log of a scripted session
+#[cfg(feature = "mockhsm")]
+#[test]
+fn usage_report_test() {
+    use yubihsm::{asymmetric, audit, command, hmac, Capability, Client, Connector};
+
+    const ECDSA_KEY_ID: object::Id = 201;
+    const HMAC_KEY_ID: object::Id = 300;
+    const NUM_SIGNATURES: u64 = 5;
+    const NUM_HMACS: u64 = 3;
+
+    // Use a dedicated MockHsm so the log only contains this test's commands
+    let client = Client::open(Connector::mockhsm(), Default::default(), false).unwrap();
+
+    client
+        .generate_asymmetric_key(
+            ECDSA_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::SIGN_ECDSA,
+            asymmetric::Algorithm::EcP256,
+        )
+        .unwrap();
+
+    client
+        .generate_hmac_key(
+            HMAC_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::SIGN_HMAC,
+            hmac::Algorithm::Sha256,
+        )
+        .unwrap();
+
+    for _ in 0..NUM_SIGNATURES {
+        client
+            .sign_ecdsa_prehash_raw(ECDSA_KEY_ID, [0x42; 32])
+            .unwrap();
+    }
+
+    for _ in 0..NUM_HMACS {
+        client.sign_hmac(HMAC_KEY_ID, TEST_MESSAGE).unwrap();
+    }
+
+    // Attempt to sign with a key which doesn't exist
+    assert!(client
+        .sign_ecdsa_prehash_raw(ECDSA_KEY_ID + 1, [0x42; 32])
+        .is_err());
+
+    let log = client.get_log_entries().unwrap();
+    let mut report = audit::usage_report(log.entries.into_iter());
+    assert_eq!(report.missing_entries, 0);
+
+    let ecdsa_key = report.key(ECDSA_KEY_ID).unwrap();
+    let signatures = ecdsa_key.command(command::Code::SignEcdsa);
+    assert_eq!(signatures.succeeded, NUM_SIGNATURES);
+    assert_eq!(signatures.failed, 0);
+    assert_eq!(
+        ecdsa_key
+            .command(command::Code::GenerateAsymmetricKey)
+            .succeeded,
+        1
+    );
+
+    let hmacs = report
+        .key(HMAC_KEY_ID)
+        .unwrap()
+        .command(command::Code::SignHmac);
+    assert_eq!(hmacs.succeeded, NUM_HMACS);
+    assert_eq!(hmacs.failed, 0);
+
+    let missing_key = report.key(ECDSA_KEY_ID + 1).unwrap();
+    assert_eq!(missing_key.command(command::Code::SignEcdsa).failed, 1);
+
+    report.resolve_labels(&client.list_objects_detailed(&[]).unwrap());
+    assert_eq!(report.labels_as_of, report.last_item);
+    assert_eq!(
+        report.key(ECDSA_KEY_ID).unwrap().label,
+        Some(TEST_KEY_LABEL.into())
+    );
+
+    // Keys which don't exist (e.g. were deleted) can't be labeled
+    assert_eq!(report.key(ECDSA_KEY_ID + 1).unwrap().label, None);
+
+    let json = serde_json::to_string(&report).unwrap();
+    assert!(json.contains(&format!(r#""label":"{TEST_KEY_LABEL}""#)));
+    assert!(json.contains(r#""label":null"#));
+    assert_eq!(
+        serde_json::from_str::<audit::UsageReport>(&json).unwrap(),
+        report
+    );
+}
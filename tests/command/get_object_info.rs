@@ -30,3 +30,53 @@ fn default_authkey_test() {
         DEFAULT_AUTHENTICATION_KEY_LABEL
     );
 }
+
+/// Get object info on several objects at once
+#[test]
+fn get_object_infos_test() {
+    let client = crate::get_hsm_client();
+
+    let results = client.get_object_infos(&[
+        (
+            DEFAULT_AUTHENTICATION_KEY_ID,
+            object::Type::AuthenticationKey,
+        ),
+        (DEFAULT_AUTHENTICATION_KEY_ID, object::Type::Opaque),
+    ]);
+
+    assert_eq!(results.len(), 2);
+
+    let object_info = results[0]
+        .as_ref()
+        .unwrap_or_else(|err| panic!("error getting object info: {err}"));
+
+    assert_eq!(object_info.object_id, DEFAULT_AUTHENTICATION_KEY_ID);
+    assert_eq!(object_info.object_type, object::Type::AuthenticationKey);
+    assert!(results[1].is_err());
+}
+
+/// Get object info for every object in the HSM
+#[test]
+fn full_inventory_test() {
+    let client = crate::get_hsm_client();
+
+    let objects = client
+        .list_objects(&[])
+        .unwrap_or_else(|err| panic!("error listing objects: {err}"));
+
+    let inventory = client
+        .full_inventory()
+        .unwrap_or_else(|err| panic!("error getting inventory: {err}"));
+
+    assert_eq!(inventory.len(), objects.len());
+
+    for (entry, object_info) in objects.iter().zip(&inventory) {
+        assert_eq!(entry.object_id, object_info.object_id);
+        assert_eq!(entry.object_type, object_info.object_type);
+    }
+
+    assert!(inventory
+        .iter()
+        .any(|info| info.object_id == DEFAULT_AUTHENTICATION_KEY_ID
+            && info.object_type == object::Type::AuthenticationKey));
+}
@@ -1,3 +1,6 @@
+use rand_core::RngCore;
+use yubihsm::{command, rng::HsmRng};
+
 /// Get random bytes
 #[test]
 fn get_pseudo_random_test() {
@@ -9,3 +12,62 @@ fn get_pseudo_random_test() {
 
     assert_eq!(32, bytes.len());
 }
+
+/// Buffer random data from the HSM in blocks, fetching a new block only when
+/// the buffered data runs out
+#[test]
+fn hsm_rng_test() {
+    const BLOCK_SIZE: usize = 64;
+
+    let client = crate::get_hsm_client();
+    let mut rng = HsmRng::new(client.clone()).block_size(BLOCK_SIZE);
+    let mut output = vec![];
+
+    // Clones share their statistics, so count the blocks the RNG fetches
+    client.reset_stats();
+    let blocks_fetched = || client.stats().count(command::Code::GetPseudoRandom);
+
+    // Reads smaller than, straddling, equal to and larger than a block
+    for len in [1, 7, 63, 64, 130] {
+        let mut bytes = vec![0u8; len];
+        rng.try_fill_bytes(&mut bytes)
+            .unwrap_or_else(|err| panic!("error getting random data: {err}"));
+        output.extend_from_slice(&bytes);
+    }
+
+    assert_eq!(output.len(), 265);
+    assert_eq!(rng.buffered(), 5 * BLOCK_SIZE - 265);
+    assert_eq!(blocks_fetched(), 5);
+
+    // Draining the buffer exactly doesn't fetch another block...
+    let mut rest = vec![0u8; rng.buffered()];
+    rng.fill_bytes(&mut rest);
+    output.extend_from_slice(&rest);
+    assert_eq!(rng.buffered(), 0);
+    assert_eq!(blocks_fetched(), 5);
+
+    // ...but the next read does
+    output.extend_from_slice(&rng.next_u32().to_le_bytes());
+    assert_eq!(rng.buffered(), BLOCK_SIZE - 4);
+    assert_eq!(blocks_fetched(), 6);
+
+    // Consecutive blocks differ
+    assert_ne!(output[..BLOCK_SIZE], output[BLOCK_SIZE..2 * BLOCK_SIZE]);
+
+    // Usable with RustCrypto key generation
+    let secret_key = p256::SecretKey::random(&mut rng);
+    assert_ne!(secret_key.to_bytes().as_slice(), [0u8; 32]);
+}
+
+/// Run basic statistical tests on a sample of the HSM's random output
+#[test]
+fn rng_healthcheck_test() {
+    let client = crate::get_hsm_client();
+
+    let health = client
+        .rng_healthcheck()
+        .unwrap_or_else(|err| panic!("error checking RNG health: {err}"));
+
+    assert_eq!(health.bits, 2048 * 8);
+    assert!(health.passed(), "RNG health check failed: {health:?}");
+}
@@ -0,0 +1,207 @@
+use crate::{
+    clear_test_key_slot,
+    test_vectors::{AESCCM_TEST_VECTORS, ED25519_TEST_VECTORS, WRAPPED_ED25519_KEY},
+    TEST_DOMAINS, TEST_EXPORTED_KEY_ID, TEST_EXPORTED_KEY_LABEL, TEST_KEY_ID, TEST_KEY_LABEL,
+};
+use yubihsm::{asymmetric, object, wrap, Capability};
+
+/// IDs the exported key is restored under
+const RESTORED_KEY_IDS: [object::Id; 2] = [102, 103];
+
+/// Restore the same wrapped key twice under different IDs and labels
+#[test]
+fn import_wrapped_as_test() {
+    let client = crate::get_hsm_client();
+    let wrap_key_bytes = AESCCM_TEST_VECTORS[0].key;
+    let exported_key_type = object::Type::AsymmetricKey;
+    let exported_key_capabilities = Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP;
+
+    clear_test_key_slot(&client, object::Type::WrapKey);
+
+    client
+        .put_wrap_key(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::EXPORT_WRAPPED | Capability::IMPORT_WRAPPED,
+            Capability::all(),
+            wrap::Algorithm::Aes128Ccm,
+            wrap_key_bytes,
+        )
+        .unwrap_or_else(|err| panic!("error putting wrap key: {err}"));
+
+    let _ = client.delete_object(TEST_EXPORTED_KEY_ID, exported_key_type);
+
+    for &key_id in &RESTORED_KEY_IDS {
+        let _ = client.delete_object(key_id, exported_key_type);
+    }
+
+    client
+        .generate_asymmetric_key(
+            TEST_EXPORTED_KEY_ID,
+            TEST_EXPORTED_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            exported_key_capabilities,
+            asymmetric::Algorithm::Ed25519,
+        )
+        .unwrap_or_else(|err| panic!("error generating asymmetric key: {err}"));
+
+    let public_key = client.get_public_key(TEST_EXPORTED_KEY_ID).unwrap();
+
+    let wrap_data = client
+        .export_wrapped(TEST_KEY_ID, exported_key_type, TEST_EXPORTED_KEY_ID)
+        .unwrap_or_else(|err| panic!("error exporting key: {err}"));
+
+    let wrap_key =
+        wrap::SoftwareKey::new(wrap::Key::from_bytes(TEST_KEY_ID, wrap_key_bytes).unwrap());
+
+    for (i, &key_id) in RESTORED_KEY_IDS.iter().enumerate() {
+        let label = format!("yubihsm.rs restored key {i}");
+
+        let handle = client
+            .import_wrapped_as(&wrap_key, &wrap_data, key_id, Some(&label))
+            .unwrap_or_else(|err| panic!("error importing key: {err}"));
+
+        assert_eq!(handle.object_id, key_id);
+        assert_eq!(handle.object_type, exported_key_type);
+
+        let info = client
+            .get_object_info(key_id, exported_key_type)
+            .unwrap_or_else(|err| panic!("error getting object info: {err}"));
+
+        assert_eq!(info.object_id, key_id);
+        assert_eq!(info.capabilities, exported_key_capabilities);
+        assert_eq!(info.domains, TEST_DOMAINS);
+        assert_eq!(info.label.to_string(), label);
+        assert_eq!(client.get_public_key(key_id).unwrap(), public_key);
+    }
+
+    // The original object is unaffected
+    let original_info = client
+        .get_object_info(TEST_EXPORTED_KEY_ID, exported_key_type)
+        .unwrap();
+
+    assert_eq!(&original_info.label.to_string(), TEST_EXPORTED_KEY_LABEL);
+
+    for &key_id in &RESTORED_KEY_IDS {
+        client.delete_object(key_id, exported_key_type).unwrap();
+    }
+}
+
+/// Wrapped objects whose length field exceeds their data are rejected cleanly
+#[test]
+fn import_wrapped_truncated_test() {
+    let client = crate::get_hsm_client();
+    let wrap_key_bytes = AESCCM_TEST_VECTORS[0].key;
+    let exported_key_type = object::Type::AsymmetricKey;
+
+    clear_test_key_slot(&client, object::Type::WrapKey);
+
+    client
+        .put_wrap_key(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::EXPORT_WRAPPED | Capability::IMPORT_WRAPPED,
+            Capability::all(),
+            wrap::Algorithm::Aes128Ccm,
+            wrap_key_bytes,
+        )
+        .unwrap_or_else(|err| panic!("error putting wrap key: {err}"));
+
+    let _ = client.delete_object(TEST_EXPORTED_KEY_ID, exported_key_type);
+
+    client
+        .generate_asymmetric_key(
+            TEST_EXPORTED_KEY_ID,
+            TEST_EXPORTED_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP,
+            asymmetric::Algorithm::Ed25519,
+        )
+        .unwrap_or_else(|err| panic!("error generating asymmetric key: {err}"));
+
+    let wrap_data = client
+        .export_wrapped(TEST_KEY_ID, exported_key_type, TEST_EXPORTED_KEY_ID)
+        .unwrap_or_else(|err| panic!("error exporting key: {err}"));
+
+    let wrap_key =
+        wrap::SoftwareKey::new(wrap::Key::from_bytes(TEST_KEY_ID, wrap_key_bytes).unwrap());
+
+    let mut object = wrap_key.decrypt(&wrap_data).unwrap();
+    object.object_info.length = object.data.len() as u16 + 1;
+    let corrupted = wrap_key.encrypt(&object).unwrap();
+
+    let err = wrap_key.decrypt(&corrupted).unwrap_err();
+    assert_eq!(*err.kind(), wrap::ErrorKind::LengthInvalid);
+
+    // Delete the original so the HSM would otherwise accept the import
+    client
+        .delete_object(TEST_EXPORTED_KEY_ID, exported_key_type)
+        .unwrap();
+
+    assert!(client.import_wrapped(TEST_KEY_ID, corrupted).is_err());
+    assert!(client
+        .get_object_info(TEST_EXPORTED_KEY_ID, exported_key_type)
+        .is_err());
+}
+
+/// Decrypt and import a wrapped key which wasn't produced by this crate (see
+/// `WRAPPED_ED25519_KEY`), checking its header is parsed as documented
+#[test]
+fn import_wrapped_fixture_test() {
+    let client = crate::get_hsm_client();
+    let wrap_key_bytes = AESCCM_TEST_VECTORS[0].key;
+    let vector = &ED25519_TEST_VECTORS[0];
+    let message = wrap::Message::from_vec(WRAPPED_ED25519_KEY.to_vec()).unwrap();
+
+    let wrap_key =
+        wrap::SoftwareKey::new(wrap::Key::from_bytes(TEST_KEY_ID, wrap_key_bytes).unwrap());
+    let object = wrap_key.decrypt(&message).unwrap();
+    let info = &object.object_info;
+
+    assert_eq!(info.object_id, TEST_EXPORTED_KEY_ID);
+    assert_eq!(info.object_type, object::Type::AsymmetricKey);
+    assert_eq!(info.algorithm, asymmetric::Algorithm::Ed25519.into());
+    assert_eq!(
+        info.capabilities,
+        Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP
+    );
+    assert_eq!(info.domains, TEST_DOMAINS);
+    assert_eq!(info.length, 32);
+    assert_eq!(info.sequence, 0);
+    assert_eq!(info.origin, object::Origin::Generated);
+    assert_eq!(info.label.to_string(), TEST_EXPORTED_KEY_LABEL);
+    assert_eq!(object.data.as_slice(), vector.sk);
+
+    clear_test_key_slot(&client, object::Type::WrapKey);
+
+    client
+        .put_wrap_key(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::IMPORT_WRAPPED,
+            Capability::all(),
+            wrap::Algorithm::Aes128Ccm,
+            wrap_key_bytes,
+        )
+        .unwrap_or_else(|err| panic!("error putting wrap key: {err}"));
+
+    let _ = client.delete_object(TEST_EXPORTED_KEY_ID, object::Type::AsymmetricKey);
+
+    let handle = client
+        .import_wrapped(TEST_KEY_ID, message)
+        .unwrap_or_else(|err| panic!("error importing wrapped key: {err}"));
+
+    assert_eq!(
+        handle,
+        object::Handle::new(TEST_EXPORTED_KEY_ID, object::Type::AsymmetricKey)
+    );
+
+    let signature = client
+        .sign_ed25519(TEST_EXPORTED_KEY_ID, vector.msg)
+        .unwrap_or_else(|err| panic!("error signing with imported key: {err}"));
+
+    assert_eq!(vector.sig, &signature.to_bytes());
+}
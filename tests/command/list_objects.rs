@@ -1,5 +1,5 @@
-use crate::{generate_asymmetric_key, TEST_KEY_ID};
-use yubihsm::{asymmetric, object, Capability};
+use crate::{generate_asymmetric_key, TEST_DOMAINS, TEST_KEY_ID, TEST_KEY_LABEL};
+use yubihsm::{asymmetric, authentication, hmac, object, opaque, wrap, Capability, Domain};
 
 /// List the objects in the YubiHSM 2
 #[test]
@@ -35,3 +35,220 @@ fn list_objects_with_filter() {
         .iter()
         .all(|obj| obj.object_type == object::Type::AuthenticationKey));
 }
+
+/// List the IDs of the objects of a given type (in ascending order)
+#[test]
+fn list_object_ids_test() {
+    let client = crate::get_hsm_client();
+    let key_ids: Vec<object::Id> = (320..330).collect();
+    let opaque_id = 330;
+
+    for &key_id in key_ids.iter().rev() {
+        let _ = client.delete_object(key_id, object::Type::AsymmetricKey);
+
+        client
+            .generate_asymmetric_key(
+                key_id,
+                TEST_KEY_LABEL.into(),
+                TEST_DOMAINS,
+                Capability::SIGN_EDDSA,
+                asymmetric::Algorithm::Ed25519,
+            )
+            .unwrap_or_else(|err| panic!("error generating asymmetric key: {err}"));
+    }
+
+    let _ = client.delete_object(opaque_id, object::Type::Opaque);
+    client
+        .put_opaque(
+            opaque_id,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::empty(),
+            opaque::Algorithm::Data,
+            b"not a key".to_vec(),
+        )
+        .unwrap_or_else(|err| panic!("error putting opaque object: {err}"));
+
+    // Other tests may leave objects behind, so only look at our own IDs
+    let ids_of = |object_type| -> Vec<object::Id> {
+        client
+            .list_object_ids(object_type)
+            .unwrap_or_else(|err| panic!("error listing object IDs: {err}"))
+            .into_iter()
+            .filter(|id| (320..=opaque_id).contains(id))
+            .collect()
+    };
+
+    assert_eq!(ids_of(object::Type::AsymmetricKey), key_ids);
+    assert_eq!(ids_of(object::Type::Opaque), [opaque_id]);
+    assert!(ids_of(object::Type::WrapKey).is_empty());
+
+    for key_id in key_ids {
+        client
+            .delete_object(key_id, object::Type::AsymmetricKey)
+            .unwrap();
+    }
+
+    client
+        .delete_object(opaque_id, object::Type::Opaque)
+        .unwrap();
+}
+
+/// Group objects by domain, with objects in several domains in each group.
+///
+/// Uses domains no other test puts objects in, so leftovers from other
+/// tests don't affect the groups.
+#[test]
+fn objects_by_domain_test() {
+    let client = crate::get_hsm_client();
+
+    for (key_id, domains) in [
+        (340, Domain::DOM12 | Domain::DOM13),
+        (341, Domain::DOM13 | Domain::DOM14),
+    ] {
+        let _ = client.delete_object(key_id, object::Type::AsymmetricKey);
+
+        client
+            .generate_asymmetric_key(
+                key_id,
+                TEST_KEY_LABEL.into(),
+                domains,
+                Capability::SIGN_EDDSA,
+                asymmetric::Algorithm::Ed25519,
+            )
+            .unwrap_or_else(|err| panic!("error generating asymmetric key: {err}"));
+    }
+
+    let groups = client
+        .objects_by_domain()
+        .unwrap_or_else(|err| panic!("error grouping objects by domain: {err}"));
+
+    let keys_in = |domain: Domain| -> Vec<object::Id> {
+        groups[&domain]
+            .iter()
+            .filter(|info| info.object_type == object::Type::AsymmetricKey)
+            .map(|info| info.object_id)
+            .collect()
+    };
+
+    assert_eq!(keys_in(Domain::DOM12), [340]);
+    assert_eq!(keys_in(Domain::DOM13), [340, 341]);
+    assert_eq!(keys_in(Domain::DOM14), [341]);
+
+    // The default authentication key is in every domain
+    assert_eq!(groups.len(), 16);
+    assert!(groups.values().all(|infos| infos.iter().any(|info| {
+        info.object_id == authentication::DEFAULT_AUTHENTICATION_KEY_ID
+            && info.object_type == object::Type::AuthenticationKey
+    })));
+
+    for key_id in [340, 341] {
+        client
+            .delete_object(key_id, object::Type::AsymmetricKey)
+            .unwrap();
+    }
+}
+
+/// List objects along with their full information, ordered by ID and type
+#[test]
+fn list_objects_detailed_test() {
+    let client = crate::get_hsm_client();
+
+    let _ = client.delete_object(352, object::Type::Opaque);
+    client
+        .put_opaque(
+            352,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::default(),
+            opaque::Algorithm::Data,
+            b"opaque data".as_ref(),
+        )
+        .unwrap();
+
+    let _ = client.delete_object(352, object::Type::HmacKey);
+    client
+        .generate_hmac_key(
+            352,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::SIGN_HMAC,
+            hmac::Algorithm::Sha256,
+        )
+        .unwrap();
+
+    let _ = client.delete_object(351, object::Type::AsymmetricKey);
+    client
+        .generate_asymmetric_key(
+            351,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::SIGN_EDDSA,
+            asymmetric::Algorithm::Ed25519,
+        )
+        .unwrap();
+
+    let _ = client.delete_object(350, object::Type::WrapKey);
+    client
+        .put_wrap_key(
+            350,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::EXPORT_WRAPPED,
+            Capability::all(),
+            wrap::Algorithm::Aes128Ccm,
+            [0x42; 16],
+        )
+        .unwrap();
+
+    // Other tests may leave objects behind, so only look at our own IDs
+    let infos: Vec<_> = client
+        .list_objects_detailed(&[])
+        .unwrap_or_else(|err| panic!("error listing objects: {err}"))
+        .into_iter()
+        .filter(|info| (350..=352).contains(&info.object_id))
+        .collect();
+
+    let handles: Vec<_> = infos
+        .iter()
+        .map(|info| (info.object_id, info.object_type, info.algorithm))
+        .collect();
+
+    assert_eq!(
+        handles,
+        [
+            (
+                350,
+                object::Type::WrapKey,
+                wrap::Algorithm::Aes128Ccm.into()
+            ),
+            (
+                351,
+                object::Type::AsymmetricKey,
+                asymmetric::Algorithm::Ed25519.into()
+            ),
+            (352, object::Type::Opaque, opaque::Algorithm::Data.into()),
+            (352, object::Type::HmacKey, hmac::Algorithm::Sha256.into()),
+        ]
+    );
+
+    assert!(infos.iter().all(|info| info.label == TEST_KEY_LABEL.into()));
+
+    let hmac_keys = client
+        .list_objects_detailed(&[object::Filter::Type(object::Type::HmacKey)])
+        .unwrap_or_else(|err| panic!("error listing objects: {err}"));
+
+    assert!(hmac_keys
+        .iter()
+        .all(|info| info.object_type == object::Type::HmacKey));
+    assert!(hmac_keys.iter().any(|info| info.object_id == 352));
+
+    for (object_id, object_type) in [
+        (350, object::Type::WrapKey),
+        (351, object::Type::AsymmetricKey),
+        (352, object::Type::Opaque),
+        (352, object::Type::HmacKey),
+    ] {
+        client.delete_object(object_id, object_type).unwrap();
+    }
+}
@@ -1,6 +1,9 @@
 //! Integration tests for YubiHSM 2 commands
 
+pub mod backup_all;
 pub mod blink_device;
+#[cfg(feature = "mockhsm")]
+pub mod create_session;
 #[cfg(not(feature = "mockhsm"))]
 pub mod decrypt_oaep;
 pub mod delete_object;
@@ -14,16 +17,24 @@ pub mod get_object_info;
 pub mod get_option;
 pub mod get_pseudo_random;
 pub mod get_storage_info;
+#[cfg(feature = "software-wrap")]
+pub mod import_wrapped;
 pub mod list_objects;
 pub mod put_asymmetric_key;
 pub mod put_authentication_key;
 pub mod put_opaque;
+#[cfg(all(feature = "rsa-import", feature = "untested"))]
+pub mod put_rsa_key;
+pub mod put_wrap_key;
 #[cfg(feature = "mockhsm")]
 pub mod reset_device;
 pub mod set_option;
-#[cfg(not(feature = "mockhsm"))]
 pub mod sign_attestation_certificate;
 #[cfg(not(feature = "mockhsm"))]
 pub mod sign_ecdsa;
 pub mod sign_eddsa;
+#[cfg(feature = "untested")]
+pub mod sign_rsa_pss;
+#[cfg(all(feature = "mockhsm", feature = "untested"))]
+pub mod sign_ssh_certificate;
 pub mod verify_hmac;
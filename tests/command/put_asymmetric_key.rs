@@ -25,3 +25,44 @@ fn ed25519_key_test() {
     assert_eq!(object_info.origin, object::Origin::Imported);
     assert_eq!(&object_info.label.to_string(), TEST_KEY_LABEL);
 }
+
+/// Put a NIST P-256 key, confirming the HSM derived the expected public key
+#[test]
+fn put_asymmetric_key_verified_test() {
+    use p256::elliptic_curve::sec1::ToEncodedPoint;
+    use yubihsm::{asymmetric::PublicKey, client};
+
+    let client = crate::get_hsm_client();
+    let algorithm = asymmetric::Algorithm::EcP256;
+    let secret_key = p256::SecretKey::from_slice(&[0x42; 32]).unwrap();
+    let other_key = p256::SecretKey::from_slice(&[0x43; 32]).unwrap();
+
+    let public_key_for = |secret_key: &p256::SecretKey| PublicKey {
+        algorithm,
+        bytes: secret_key.public_key().to_encoded_point(false).as_bytes()[1..].to_vec(),
+    };
+
+    let put_key = |expected_public_key: &PublicKey| {
+        let _ = client.delete_object(TEST_KEY_ID, object::Type::AsymmetricKey);
+
+        client.put_asymmetric_key_verified(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::SIGN_ECDSA,
+            algorithm,
+            secret_key.to_bytes().as_slice(),
+            expected_public_key,
+        )
+    };
+
+    let err = put_key(&public_key_for(&other_key)).err().unwrap();
+    assert_eq!(*err.kind(), client::ErrorKind::ImportVerificationFailed);
+
+    // Mismatched keys are removed from the HSM
+    assert!(client
+        .get_object_info(TEST_KEY_ID, object::Type::AsymmetricKey)
+        .is_err());
+
+    assert_eq!(put_key(&public_key_for(&secret_key)).unwrap(), TEST_KEY_ID);
+}
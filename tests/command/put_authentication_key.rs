@@ -1,4 +1,4 @@
-use yubihsm::{authentication, object, Capability};
+use yubihsm::{asymmetric, authentication, device, hmac, object, Capability, Client, Credentials};
 
 use crate::{clear_test_key_slot, TEST_DOMAINS, TEST_KEY_ID, TEST_KEY_LABEL, TEST_MESSAGE};
 
@@ -40,3 +40,231 @@ fn put_authentication_key() {
     assert_eq!(object_info.origin, object::Origin::Imported);
     assert_eq!(&object_info.label.to_string(), TEST_KEY_LABEL);
 }
+
+/// Open a session with a newly created authentication key and check
+/// `whoami` reports it
+#[test]
+fn whoami_test() {
+    let client = crate::get_hsm_client();
+    let capabilities = Capability::GET_OPAQUE | Capability::SIGN_ECDSA;
+
+    assert_eq!(
+        client.whoami().unwrap().object_id,
+        authentication::DEFAULT_AUTHENTICATION_KEY_ID
+    );
+
+    clear_test_key_slot(&client, object::Type::AuthenticationKey);
+
+    let authentication_key = authentication::Key::derive_from_password(TEST_MESSAGE);
+
+    client
+        .put_authentication_key(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            capabilities,
+            Capability::empty(),
+            authentication::Algorithm::YubicoAes,
+            authentication_key.clone(),
+        )
+        .unwrap_or_else(|err| panic!("error putting auth key: {err}"));
+
+    let credentials = Credentials::new(TEST_KEY_ID, authentication_key);
+    let other_client = Client::open(crate::HSM_CONNECTOR.clone(), credentials, false)
+        .unwrap_or_else(|err| panic!("error opening session with new auth key: {err}"));
+
+    let object_info = other_client
+        .whoami()
+        .unwrap_or_else(|err| panic!("error getting current auth key info: {err}"));
+
+    assert_eq!(object_info.object_id, TEST_KEY_ID);
+    assert_eq!(object_info.object_type, object::Type::AuthenticationKey);
+    assert_eq!(object_info.capabilities, capabilities);
+    assert_eq!(object_info.domains, TEST_DOMAINS);
+}
+
+/// Authenticate with an authentication key derived from a password, then
+/// check the original key still works in a separate session
+#[test]
+fn password_authentication_key_test() {
+    const PASSWORD: &[u8] = b"password authentication key test";
+
+    let client = crate::get_hsm_client();
+
+    clear_test_key_slot(&client, object::Type::AuthenticationKey);
+
+    client
+        .put_authentication_key(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::GET_PSEUDO_RANDOM,
+            Capability::empty(),
+            authentication::Algorithm::YubicoAes,
+            authentication::Key::derive_from_password(PASSWORD),
+        )
+        .unwrap_or_else(|err| panic!("error putting auth key: {err}"));
+
+    let credentials = Credentials::from_password(TEST_KEY_ID, PASSWORD);
+    let password_client = Client::open(crate::HSM_CONNECTOR.clone(), credentials, false)
+        .unwrap_or_else(|err| panic!("error opening session with password auth key: {err}"));
+
+    assert_eq!(password_client.whoami().unwrap().object_id, TEST_KEY_ID);
+    assert_eq!(password_client.get_pseudo_random(16).unwrap().len(), 16);
+
+    let original_client = Client::open(crate::HSM_CONNECTOR.clone(), Credentials::default(), false)
+        .unwrap_or_else(|err| panic!("error opening session with original auth key: {err}"));
+
+    assert_eq!(
+        original_client.whoami().unwrap().object_id,
+        authentication::DEFAULT_AUTHENTICATION_KEY_ID
+    );
+}
+
+/// Derive an authentication key from an HMAC key using the inputs of the
+/// RFC 5869 (HKDF) SHA-256 test case 1, then open a session with it
+#[test]
+fn derive_authentication_key_test() {
+    let client = crate::get_hsm_client();
+    let capabilities = Capability::GET_OPAQUE;
+
+    // RFC 5869 test case 1 PRK and info
+    let prk = [
+        0x07, 0x77, 0x09, 0x36, 0x2c, 0x2e, 0x32, 0xdf, 0x0d, 0xdc, 0x3f, 0x0d, 0xc4, 0x7b, 0xba,
+        0x63, 0x90, 0xb6, 0xc7, 0x3b, 0xb5, 0x0f, 0x9c, 0x31, 0x22, 0xec, 0x84, 0x4a, 0xd7, 0xc2,
+        0xb3, 0xe5,
+    ];
+    let info = [0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9];
+
+    // First 32 bytes of the test case's OKM
+    let expected_key = [
+        0x3c, 0xb2, 0x5f, 0x25, 0xfa, 0xac, 0xd5, 0x7a, 0x90, 0x43, 0x4f, 0x64, 0xd0, 0x36, 0x2f,
+        0x2a, 0x2d, 0x2d, 0x0a, 0x90, 0xcf, 0x1a, 0x5a, 0x4c, 0x5d, 0xb0, 0x2d, 0x56, 0xec, 0xc4,
+        0xc5, 0xbf,
+    ];
+
+    clear_test_key_slot(&client, object::Type::HmacKey);
+    clear_test_key_slot(&client, object::Type::AuthenticationKey);
+
+    client
+        .put_hmac_key(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::SIGN_HMAC,
+            hmac::Algorithm::Sha256,
+            prk.to_vec(),
+        )
+        .unwrap_or_else(|err| panic!("error putting HMAC key: {err}"));
+
+    let key_id = client
+        .derive_authentication_key(
+            TEST_KEY_ID,
+            &info,
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            capabilities,
+            Capability::empty(),
+        )
+        .unwrap_or_else(|err| panic!("error deriving auth key: {err}"));
+
+    assert_eq!(key_id, TEST_KEY_ID);
+
+    let authentication_key = authentication::Key::from_slice(&expected_key).unwrap();
+    let credentials = Credentials::new(TEST_KEY_ID, authentication_key);
+    let other_client = Client::open(crate::HSM_CONNECTOR.clone(), credentials, false)
+        .unwrap_or_else(|err| panic!("error opening session with derived auth key: {err}"));
+
+    let object_info = other_client
+        .whoami()
+        .unwrap_or_else(|err| panic!("error getting current auth key info: {err}"));
+
+    assert_eq!(object_info.object_id, TEST_KEY_ID);
+    assert_eq!(object_info.capabilities, capabilities);
+}
+
+/// Open a session with a newly created authentication key delegating the given
+/// capabilities, then attempt to generate a signing key with it
+fn generate_with_delegation(
+    client: &Client,
+    delegated_capabilities: Option<Capability>,
+) -> Result<object::Id, yubihsm::client::Error> {
+    let capabilities = Capability::GENERATE_ASYMMETRIC_KEY;
+    let child_capabilities = Capability::SIGN_ECDSA;
+
+    clear_test_key_slot(client, object::Type::AuthenticationKey);
+    clear_test_key_slot(client, object::Type::AsymmetricKey);
+
+    let authentication_key = authentication::Key::derive_from_password(TEST_MESSAGE);
+
+    match delegated_capabilities {
+        Some(delegated_capabilities) => client.put_authentication_key(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            capabilities,
+            delegated_capabilities,
+            authentication::Algorithm::YubicoAes,
+            authentication_key.clone(),
+        ),
+        None => client.put_delegating_authentication_key(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            capabilities,
+            child_capabilities,
+            authentication::Algorithm::YubicoAes,
+            authentication_key.clone(),
+        ),
+    }
+    .unwrap_or_else(|err| panic!("error putting auth key: {err}"));
+
+    let credentials = Credentials::new(TEST_KEY_ID, authentication_key);
+    let other_client = Client::open(crate::HSM_CONNECTOR.clone(), credentials, false)
+        .unwrap_or_else(|err| panic!("error opening session with new auth key: {err}"));
+
+    other_client.generate_asymmetric_key(
+        TEST_KEY_ID,
+        TEST_KEY_LABEL.into(),
+        TEST_DOMAINS,
+        child_capabilities,
+        asymmetric::Algorithm::EcP256,
+    )
+}
+
+/// Keys created with a delegating authentication key can be given the
+/// requested child capabilities
+#[test]
+fn delegating_authentication_key_test() {
+    let client = crate::get_hsm_client();
+
+    let key_id = generate_with_delegation(&client, None)
+        .unwrap_or_else(|err| panic!("error generating key with delegated capabilities: {err}"));
+
+    let object_info = client
+        .get_object_info(key_id, object::Type::AsymmetricKey)
+        .unwrap_or_else(|err| panic!("error getting object info: {err}"));
+
+    assert_eq!(object_info.capabilities, Capability::SIGN_ECDSA);
+
+    let object_info = client
+        .get_object_info(TEST_KEY_ID, object::Type::AuthenticationKey)
+        .unwrap_or_else(|err| panic!("error getting object info: {err}"));
+
+    assert_eq!(object_info.delegated_capabilities, Capability::SIGN_ECDSA);
+}
+
+/// Authentication keys without delegated capabilities can't create keys
+#[test]
+fn empty_delegation_test() {
+    let client = crate::get_hsm_client();
+
+    let err = generate_with_delegation(&client, Some(Capability::empty()))
+        .expect_err("generated key without delegated capabilities");
+
+    assert_eq!(
+        err.device_error(),
+        Some(device::ErrorKind::InsufficientPermissions)
+    );
+}
@@ -1,6 +1,28 @@
-use yubihsm::{object, opaque, Capability};
+use yubihsm::{client, object, opaque, Capability, Client};
 
-use crate::{clear_test_key_slot, TEST_DOMAINS, TEST_KEY_ID, TEST_KEY_LABEL, TEST_MESSAGE};
+use crate::{
+    clear_test_key_slot, TEST_DOMAINS, TEST_EXPORTED_KEY_ID, TEST_KEY_ID, TEST_KEY_LABEL,
+    TEST_MESSAGE,
+};
+
+/// Base object ID to store chunked opaque payloads at
+const CHUNKED_BASE_ID: object::Id = 0x200;
+
+/// Delete any opaque objects left at the given IDs (e.g. by a failed test)
+fn clear_opaque_objects(client: &Client, object_ids: impl IntoIterator<Item = object::Id>) {
+    for object_id in object_ids {
+        let _ = client.delete_object(object_id, object::Type::Opaque);
+    }
+}
+
+/// Are there no opaque objects at any of the given IDs?
+fn no_opaque_objects(client: &Client, object_ids: impl IntoIterator<Item = object::Id>) -> bool {
+    object_ids.into_iter().all(|object_id| {
+        client
+            .get_object_info(object_id, object::Type::Opaque)
+            .is_err()
+    })
+}
 
 /// Put an opaque object and read it back
 #[test]
@@ -28,3 +50,168 @@ fn opaque_object_test() {
 
     assert_eq!(opaque_data, TEST_MESSAGE);
 }
+
+/// Look up opaque objects by label
+#[test]
+fn opaque_by_label_test() {
+    let client = crate::get_hsm_client();
+
+    let objects = [
+        (TEST_KEY_ID, "yubihsm.rs opaque label A", b"first".as_ref()),
+        (
+            TEST_EXPORTED_KEY_ID,
+            "yubihsm.rs opaque label B",
+            b"second".as_ref(),
+        ),
+    ];
+
+    for (object_id, label, data) in objects {
+        let _ = client.delete_object(object_id, object::Type::Opaque);
+
+        client
+            .put_opaque(
+                object_id,
+                label.into(),
+                TEST_DOMAINS,
+                Capability::default(),
+                opaque::Algorithm::Data,
+                data,
+            )
+            .unwrap_or_else(|err| panic!("error putting opaque object: {err}"));
+    }
+
+    for (object_id, label, data) in objects {
+        assert_eq!(
+            client.get_opaque_by_label(label).unwrap(),
+            Some((object_id, data.to_vec()))
+        );
+
+        assert_eq!(
+            client.get_all_opaques_by_label(label).unwrap(),
+            [(object_id, data.to_vec())]
+        );
+    }
+
+    assert_eq!(
+        client
+            .get_opaque_by_label("yubihsm.rs opaque label C")
+            .unwrap(),
+        None
+    );
+
+    for (object_id, _, _) in objects {
+        client
+            .delete_object(object_id, object::Type::Opaque)
+            .unwrap();
+    }
+}
+
+/// Store payloads too large for a single opaque object across several
+#[test]
+fn opaque_chunked_round_trip_test() {
+    let client = crate::get_hsm_client();
+
+    // Chunks hold up to 1963 bytes with the default maximum message size
+    for (len, expected_chunks) in [(1000, 1), (3000, 2), (9000, 5)] {
+        let chunk_ids = CHUNKED_BASE_ID..CHUNKED_BASE_ID + expected_chunks;
+        clear_opaque_objects(&client, chunk_ids.clone());
+
+        let payload: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+
+        let chunks = client
+            .put_opaque_chunked(
+                CHUNKED_BASE_ID,
+                TEST_KEY_LABEL.into(),
+                TEST_DOMAINS,
+                &payload,
+            )
+            .unwrap_or_else(|err| panic!("error putting chunked opaque object: {err}"));
+
+        assert_eq!(chunks, expected_chunks);
+        assert_eq!(client.get_opaque_chunked(CHUNKED_BASE_ID).unwrap(), payload);
+
+        client.delete_opaque_chunked(CHUNKED_BASE_ID).unwrap();
+        assert!(no_opaque_objects(&client, chunk_ids));
+    }
+}
+
+/// Reading a chunked payload with a missing chunk fails, but it can still be
+/// deleted
+#[test]
+fn opaque_chunked_missing_chunk_test() {
+    let client = crate::get_hsm_client();
+    let chunk_ids = CHUNKED_BASE_ID..CHUNKED_BASE_ID + 3;
+    clear_opaque_objects(&client, chunk_ids.clone());
+
+    let payload = vec![0x42; 5000];
+
+    let chunks = client
+        .put_opaque_chunked(
+            CHUNKED_BASE_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            &payload,
+        )
+        .unwrap_or_else(|err| panic!("error putting chunked opaque object: {err}"));
+
+    assert_eq!(chunks, 3);
+
+    client
+        .delete_object(CHUNKED_BASE_ID + 1, object::Type::Opaque)
+        .unwrap();
+
+    let err = client.get_opaque_chunked(CHUNKED_BASE_ID).unwrap_err();
+    assert_eq!(err.kind(), &client::ErrorKind::ProtocolError);
+    assert!(err.to_string().contains("chunk 2 of 3"), "{err}");
+
+    // Incomplete payloads can still be deleted
+    client.delete_opaque_chunked(CHUNKED_BASE_ID).unwrap();
+    assert!(no_opaque_objects(&client, chunk_ids));
+
+    let too_large = vec![0; opaque::MAX_CHUNKED_SIZE + 1];
+    let err = client
+        .put_opaque_chunked(
+            CHUNKED_BASE_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            &too_large,
+        )
+        .unwrap_err();
+    assert_eq!(err.kind(), &client::ErrorKind::ProtocolError);
+}
+
+/// Chunked payloads whose header claims chunks past the last object ID are
+/// rejected without touching any other objects
+#[test]
+fn opaque_chunked_invalid_header_test() {
+    const BASE_ID: object::Id = object::Id::MAX - 1;
+
+    let client = crate::get_hsm_client();
+    clear_opaque_objects(&client, [BASE_ID]);
+
+    // A first chunk claiming 3 chunks, which would run past the last object ID
+    let mut chunk = vec![0, 0, 0, 3, 0, 0, 0, 4, 0, 0, 0, 0];
+    chunk.extend_from_slice(b"data");
+
+    client
+        .put_opaque(
+            BASE_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::default(),
+            opaque::Algorithm::Data,
+            chunk,
+        )
+        .unwrap_or_else(|err| panic!("error putting opaque object: {err}"));
+
+    for err in [
+        client.get_opaque_chunked(BASE_ID).unwrap_err(),
+        client.delete_opaque_chunked(BASE_ID).unwrap_err(),
+    ] {
+        assert_eq!(err.kind(), &client::ErrorKind::ProtocolError);
+    }
+
+    assert!(client.get_opaque(BASE_ID).is_ok());
+
+    client.delete_object(BASE_ID, object::Type::Opaque).unwrap();
+}
@@ -0,0 +1,202 @@
+use crate::{clear_test_key_slot, TEST_DOMAINS, TEST_KEY_ID, TEST_KEY_LABEL};
+#[cfg(feature = "passwords")]
+use crate::{generate_asymmetric_key, TEST_EXPORTED_KEY_ID};
+#[cfg(feature = "passwords")]
+use yubihsm::asymmetric;
+use yubihsm::{client, object, wrap, Capability};
+
+/// Wrap key algorithms to test
+const WRAP_ALGORITHMS: &[wrap::Algorithm] = &[
+    wrap::Algorithm::Aes128Ccm,
+    wrap::Algorithm::Aes192Ccm,
+    wrap::Algorithm::Aes256Ccm,
+];
+
+/// Put a wrap key of each supported algorithm into the HSM
+#[test]
+fn put_wrap_key_test() {
+    let client = crate::get_hsm_client();
+
+    for &algorithm in WRAP_ALGORITHMS {
+        clear_test_key_slot(&client, object::Type::WrapKey);
+
+        let key_id = client
+            .put_wrap_key(
+                TEST_KEY_ID,
+                TEST_KEY_LABEL.into(),
+                TEST_DOMAINS,
+                Capability::EXPORT_WRAPPED | Capability::IMPORT_WRAPPED,
+                Capability::all(),
+                algorithm,
+                vec![0x42; algorithm.key_len()],
+            )
+            .unwrap_or_else(|err| panic!("error putting {algorithm:?} wrap key: {err}"));
+
+        assert_eq!(key_id, TEST_KEY_ID);
+
+        let object_info = client
+            .get_object_info(TEST_KEY_ID, object::Type::WrapKey)
+            .unwrap_or_else(|err| panic!("error getting object info: {err}"));
+
+        assert_eq!(object_info.algorithm, algorithm.into());
+        assert_eq!(object_info.origin, object::Origin::Imported);
+    }
+}
+
+/// Ensure wrap keys of the wrong length are rejected
+#[test]
+fn put_wrap_key_wrong_length_test() {
+    let client = crate::get_hsm_client();
+
+    for &algorithm in WRAP_ALGORITHMS {
+        for len in [algorithm.key_len() - 1, algorithm.key_len() + 1] {
+            clear_test_key_slot(&client, object::Type::WrapKey);
+
+            let err = client
+                .put_wrap_key(
+                    TEST_KEY_ID,
+                    TEST_KEY_LABEL.into(),
+                    TEST_DOMAINS,
+                    Capability::EXPORT_WRAPPED | Capability::IMPORT_WRAPPED,
+                    Capability::all(),
+                    algorithm,
+                    vec![0x42; len],
+                )
+                .unwrap_err();
+
+            assert_eq!(*err.kind(), client::ErrorKind::ProtocolError);
+        }
+    }
+}
+
+/// Ensure wrap keys are only imported if they match the expected KCV
+#[test]
+fn put_wrap_key_with_kcv_test() {
+    let client = crate::get_hsm_client();
+    let key = [0x42; 32];
+    let kcv = wrap::WrapKeyKcv::compute(wrap::Algorithm::Aes256Ccm, &key).unwrap();
+
+    clear_test_key_slot(&client, object::Type::WrapKey);
+
+    let err = client
+        .put_wrap_key_with_kcv(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::EXPORT_WRAPPED | Capability::IMPORT_WRAPPED,
+            Capability::all(),
+            wrap::Algorithm::Aes256Ccm,
+            [0x24; 32],
+            kcv,
+        )
+        .unwrap_err();
+
+    assert!(matches!(
+        err.kind(),
+        client::ErrorKind::KcvMismatch { expected, .. } if *expected == kcv
+    ));
+
+    // The mismatched key isn't imported
+    assert!(client
+        .get_object_info(TEST_KEY_ID, object::Type::WrapKey)
+        .is_err());
+
+    client
+        .put_wrap_key_with_kcv(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::EXPORT_WRAPPED | Capability::IMPORT_WRAPPED,
+            Capability::all(),
+            wrap::Algorithm::Aes256Ccm,
+            key,
+            kcv,
+        )
+        .unwrap();
+
+    assert_eq!(wrap::Key::from_bytes(TEST_KEY_ID, &key).unwrap().kcv(), kcv);
+}
+
+/// Back up a key under a wrap key derived from a passphrase, then restore it
+/// with a wrap key derived again from the passphrase and the KDF parameters
+/// recorded in the backup manifest
+#[cfg(feature = "passwords")]
+#[test]
+fn wrap_key_from_passphrase_test() {
+    const PASSPHRASE: &str = "correct horse battery staple";
+
+    let client = crate::get_hsm_client();
+    let params = wrap::KdfParams::new(wrap::Algorithm::Aes256Ccm, [0x5a; 16]).iterations(1000);
+
+    clear_test_key_slot(&client, object::Type::WrapKey);
+    client
+        .put_wrap_key_from_passphrase(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::EXPORT_WRAPPED,
+            Capability::all(),
+            PASSPHRASE,
+            &params,
+        )
+        .unwrap();
+
+    generate_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::Ed25519,
+        Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP,
+    );
+
+    let info = client
+        .get_object_info(TEST_KEY_ID, object::Type::AsymmetricKey)
+        .unwrap();
+    let message = client
+        .export_wrapped(TEST_KEY_ID, object::Type::AsymmetricKey, TEST_KEY_ID)
+        .unwrap();
+
+    let mut manifest = wrap::BackupManifest::new(TEST_KEY_ID);
+    manifest
+        .entries
+        .push(wrap::ManifestEntry::new(&info, &message));
+    manifest.kdf = Some(params);
+    let json = serde_json::to_string(&manifest).unwrap();
+
+    // Restore with only the manifest, backup, and passphrase
+    let manifest: wrap::BackupManifest = serde_json::from_str(&json).unwrap();
+    let kdf = manifest.kdf.as_ref().unwrap();
+    assert_eq!(manifest.entry_for(&message).unwrap().object_id, TEST_KEY_ID);
+
+    let derive = |passphrase| {
+        wrap::Key::derive_from_passphrase(TEST_EXPORTED_KEY_ID, passphrase, kdf).unwrap()
+    };
+
+    let wrap_key = derive(PASSPHRASE);
+    assert_ne!(
+        derive("incorrect horse battery staple").kcv(),
+        wrap_key.kcv()
+    );
+
+    let _ = client.delete_object(TEST_EXPORTED_KEY_ID, object::Type::WrapKey);
+    wrap_key
+        .label(TEST_KEY_LABEL.into())
+        .domains(TEST_DOMAINS)
+        .capabilities(Capability::IMPORT_WRAPPED)
+        .delegated_capabilities(Capability::all())
+        .create(&client)
+        .unwrap();
+
+    client
+        .delete_object(TEST_KEY_ID, object::Type::AsymmetricKey)
+        .unwrap();
+
+    let handle = client
+        .import_wrapped(TEST_EXPORTED_KEY_ID, message)
+        .unwrap();
+
+    assert_eq!(handle.object_id, TEST_KEY_ID);
+    assert_eq!(handle.object_type, object::Type::AsymmetricKey);
+
+    client
+        .delete_object(TEST_EXPORTED_KEY_ID, object::Type::WrapKey)
+        .unwrap();
+}
@@ -1,5 +1,12 @@
-use crate::{generate_asymmetric_key, EC_P256_PUBLIC_KEY_SIZE, TEST_KEY_ID};
-use yubihsm::{asymmetric, Capability};
+use crate::{
+    clear_test_key_slot, generate_asymmetric_key, EC_P256_PUBLIC_KEY_SIZE, TEST_DOMAINS,
+    TEST_EXPORTED_KEY_ID, TEST_KEY_ID, TEST_KEY_LABEL, TEST_MESSAGE,
+};
+use p256::{
+    ecdsa::{signature::Verifier, Signature, VerifyingKey},
+    NistP256,
+};
+use yubihsm::{asymmetric, attestation, object, opaque, Capability};
 
 /// Generate an attestation about a key in the HSM
 #[test]
@@ -16,6 +23,197 @@ fn attest_asymmetric_test() {
         .sign_attestation_certificate(TEST_KEY_ID, None)
         .unwrap_or_else(|err| panic!("error getting attestation certificate: {}", err));
 
-    // TODO: more tests, e.g. test that the certificate validates
     assert!(certificate.len() > EC_P256_PUBLIC_KEY_SIZE);
 }
+
+/// Verify an attestation certificate, trusting the device attestation
+/// certificate as the root (there's no Yubico-issued chain to check it against
+/// in the test suite)
+#[test]
+fn verify_attestation_test() {
+    let client = crate::get_hsm_client();
+
+    generate_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::EcP256,
+        Capability::SIGN_ECDSA,
+    );
+
+    let certificate = client
+        .sign_attestation_certificate(TEST_KEY_ID, None)
+        .unwrap_or_else(|err| panic!("error getting attestation certificate: {}", err));
+
+    let root = client
+        .get_opaque(attestation::DEVICE_ATTESTATION_KEY_ID)
+        .unwrap();
+
+    let info = client
+        .verify_attestation(certificate.as_slice(), &[], &root)
+        .unwrap_or_else(|err| panic!("error verifying attestation certificate: {}", err));
+
+    assert_eq!(info.algorithm, asymmetric::Algorithm::EcP256);
+    assert_eq!(info.object_id, TEST_KEY_ID);
+    assert_eq!(info.capabilities, Capability::SIGN_ECDSA);
+    assert_eq!(info.domains, TEST_DOMAINS);
+    assert_eq!(info.origin, object::Origin::Generated);
+    assert_eq!(info.label, TEST_KEY_LABEL.into());
+
+    // Flipping a bit in the signature must cause verification to fail
+    let mut tampered = certificate.into_vec();
+    *tampered.last_mut().unwrap() ^= 1;
+    assert!(client.verify_attestation(&tampered, &[], &root).is_err());
+}
+
+/// Verify a certificate chain ending in an RSA root, for a key whose
+/// certificate has no label extension
+#[cfg(all(feature = "rsa", feature = "sha2"))]
+#[test]
+fn verify_attestation_rsa_root_test() {
+    use crate::test_vectors::{ATTESTATION_DEVICE, ATTESTATION_LEAF, ATTESTATION_ROOT};
+
+    let certificate = attestation::Certificate(ATTESTATION_LEAF.into());
+
+    let info = certificate
+        .verify(&[ATTESTATION_DEVICE], ATTESTATION_ROOT)
+        .unwrap_or_else(|err| panic!("error verifying attestation certificate: {}", err));
+
+    assert_eq!(info.algorithm, asymmetric::Algorithm::EcP256);
+    assert_eq!(info.object_id, TEST_KEY_ID);
+    assert_eq!(info.capabilities, Capability::SIGN_ECDSA);
+    assert_eq!(info.domains, TEST_DOMAINS);
+    assert_eq!(info.origin, object::Origin::Generated);
+    assert_eq!(info.label, object::Label::default());
+
+    // The device certificate isn't trusted on its own
+    assert!(certificate.verify(&[], ATTESTATION_ROOT).is_err());
+
+    // Flipping a bit in the root's signature over the device certificate
+    let mut tampered = ATTESTATION_DEVICE.to_vec();
+    *tampered.last_mut().unwrap() ^= 1;
+    assert!(certificate.verify(&[&tampered], ATTESTATION_ROOT).is_err());
+}
+
+/// A certificate issued by an attested key (rather than a CA) is rejected,
+/// even though its signature chain is otherwise valid
+#[test]
+fn verify_attestation_non_ca_issuer_test() {
+    let client = crate::get_hsm_client();
+
+    // Attest a key which may itself sign attestation certificates, and store
+    // its certificate alongside it so the HSM accepts it as an attestation key
+    generate_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::EcP256,
+        Capability::SIGN_ECDSA | Capability::SIGN_ATTESTATION_CERTIFICATE,
+    );
+
+    let intermediate = client
+        .sign_attestation_certificate(TEST_KEY_ID, None)
+        .unwrap()
+        .into_vec();
+
+    clear_test_key_slot(&client, object::Type::Opaque);
+    client
+        .put_opaque(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::default(),
+            opaque::Algorithm::X509Certificate,
+            intermediate.clone(),
+        )
+        .unwrap();
+
+    let _ = client.delete_object(TEST_EXPORTED_KEY_ID, object::Type::AsymmetricKey);
+    client
+        .generate_asymmetric_key(
+            TEST_EXPORTED_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::SIGN_EDDSA,
+            asymmetric::Algorithm::Ed25519,
+        )
+        .unwrap();
+
+    let certificate = client
+        .sign_attestation_certificate(TEST_EXPORTED_KEY_ID, Some(TEST_KEY_ID))
+        .unwrap();
+
+    let root = client
+        .get_opaque(attestation::DEVICE_ATTESTATION_KEY_ID)
+        .unwrap();
+
+    let err = certificate.verify(&[&intermediate], &root).unwrap_err();
+
+    assert_eq!(*err.kind(), attestation::ErrorKind::VerifyFailed);
+
+    client
+        .delete_object(TEST_EXPORTED_KEY_ID, object::Type::AsymmetricKey)
+        .unwrap();
+}
+
+/// Sign a message and attest the signing key in one step
+#[test]
+fn sign_and_attest_test() {
+    let client = crate::get_hsm_client();
+
+    generate_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::EcP256,
+        Capability::SIGN_ECDSA,
+    );
+
+    let signed = client
+        .sign_and_attest(TEST_KEY_ID, None, TEST_MESSAGE)
+        .unwrap_or_else(|err| panic!("error signing and attesting: {}", err));
+
+    let public_key = client
+        .get_public_key(TEST_KEY_ID)
+        .unwrap()
+        .ecdsa::<NistP256>()
+        .unwrap();
+
+    let verify_key = VerifyingKey::from_encoded_point(&public_key).unwrap();
+    let signature = Signature::from_der(&signed.signature).unwrap();
+    assert!(verify_key.verify(TEST_MESSAGE, &signature).is_ok());
+    assert_eq!(signed.message_hash.len(), 32);
+    assert_eq!(signed.label, TEST_KEY_LABEL.into());
+
+    let json = serde_json::to_string(&signed).unwrap();
+    assert!(json.contains(&format!(r#""label":"{TEST_KEY_LABEL}""#)));
+
+    let root = client
+        .get_opaque(attestation::DEVICE_ATTESTATION_KEY_ID)
+        .unwrap();
+
+    let info = client
+        .verify_attestation(&signed.certificate_der, &[], &root)
+        .unwrap_or_else(|err| panic!("error verifying attestation certificate: {}", err));
+
+    assert_eq!(info.object_id, TEST_KEY_ID);
+
+    let certificate = attestation::Certificate(signed.certificate_der);
+    let certified_key = certificate.public_key().unwrap();
+    assert_eq!(certified_key, public_key.as_bytes());
+}
+
+/// Only ECDSA keys can be used with `sign_and_attest`
+#[test]
+fn sign_and_attest_non_ecdsa_key_test() {
+    let client = crate::get_hsm_client();
+
+    generate_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::Ed25519,
+        Capability::SIGN_EDDSA,
+    );
+
+    let err = client
+        .sign_and_attest(TEST_KEY_ID, None, TEST_MESSAGE)
+        .unwrap_err();
+
+    assert_eq!(
+        *err.kind(),
+        yubihsm::client::ErrorKind::AlgorithmUnsupported
+    );
+}
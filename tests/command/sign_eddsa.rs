@@ -5,7 +5,7 @@ use crate::{
     TEST_MESSAGE,
 };
 use ed25519_dalek::Verifier;
-use yubihsm::{asymmetric, Capability};
+use yubihsm::{asymmetric, client, ed25519, Capability};
 
 /// Test Ed25519 against RFC 8032 test vectors
 #[test]
@@ -63,3 +63,38 @@ fn generated_key_test() {
             .is_ok()
     );
 }
+
+/// Parse the public key of a generated key with `from_yubihsm_bytes` and
+/// verify a signature with it
+#[test]
+fn from_yubihsm_bytes_test() {
+    let client = crate::get_hsm_client();
+
+    generate_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::Ed25519,
+        Capability::SIGN_EDDSA,
+    );
+
+    let public_key_bytes = client
+        .get_public_key(TEST_KEY_ID)
+        .unwrap_or_else(|err| panic!("error getting public key: {err}"))
+        .into_vec();
+
+    let public_key = ed25519::PublicKey::from_yubihsm_bytes(&public_key_bytes)
+        .unwrap_or_else(|err| panic!("error parsing public key: {err}"));
+
+    let signature = client
+        .sign_ed25519(TEST_KEY_ID, TEST_MESSAGE)
+        .unwrap_or_else(|err| panic!("error performing Ed25519 signature: {err}"));
+
+    assert!(
+        ed25519_dalek::VerifyingKey::from_bytes(public_key.as_bytes())
+            .unwrap()
+            .verify(TEST_MESSAGE, &signature)
+            .is_ok()
+    );
+
+    let err = ed25519::PublicKey::from_yubihsm_bytes(&public_key_bytes[1..]).unwrap_err();
+    assert_eq!(*err.kind(), client::ErrorKind::ProtocolError);
+}
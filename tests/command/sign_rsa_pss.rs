@@ -0,0 +1,151 @@
+use crate::{put_asymmetric_key, TEST_KEY_ID, TEST_MESSAGE};
+use rsa::{
+    pss::{Signature, VerifyingKey},
+    signature::Verifier,
+    BigUint, RsaPublicKey,
+};
+use sha2::{Sha256, Sha384, Sha512};
+use yubihsm::{asymmetric, rsa::mgf, Capability, Client};
+
+/// Size of an RSA-2048 key in bytes
+const RSA_2048_KEY_SIZE: usize = 256;
+
+/// Length of a SHA-256 digest in bytes
+const SHA256_DIGEST_SIZE: usize = 32;
+
+/// Largest salt supported by an RSA-2048 key with SHA-256 (RFC 8017 § 9.1.1)
+const RSA_2048_SHA256_MAX_SALT_LEN: usize = RSA_2048_KEY_SIZE - SHA256_DIGEST_SIZE - 2;
+
+/// RSA-2048 test key (first prime factor)
+const RSA_2048_P: [u8; 128] = [
+    0xc9, 0xb0, 0xe9, 0xdc, 0x47, 0x04, 0xfc, 0x26, 0x48, 0xef, 0x49, 0x8a, 0x51, 0xc0, 0x8e, 0xcb,
+    0x7b, 0x90, 0xe1, 0x2d, 0x3a, 0x70, 0xb4, 0xdd, 0x6e, 0xf7, 0x41, 0x3b, 0x32, 0x1d, 0x5a, 0xb0,
+    0xd5, 0xf1, 0x98, 0xd2, 0xb9, 0xf0, 0x1c, 0x3b, 0x70, 0xb9, 0x88, 0xc3, 0x2b, 0xa7, 0x69, 0x7c,
+    0xc7, 0x86, 0x63, 0xcc, 0xc9, 0x02, 0x6f, 0xf9, 0x5f, 0x47, 0x58, 0x04, 0x6d, 0x2c, 0xca, 0xdd,
+    0x85, 0xcd, 0x28, 0xf0, 0x00, 0x70, 0x57, 0xe5, 0x10, 0x66, 0x19, 0xe1, 0xbf, 0x12, 0x63, 0xd8,
+    0x31, 0xcf, 0x01, 0x4e, 0xdf, 0x0c, 0x10, 0xb1, 0xca, 0x92, 0xa3, 0x6e, 0xf4, 0xbc, 0xd5, 0xce,
+    0xcf, 0x29, 0x81, 0x42, 0x7d, 0xd2, 0x90, 0x50, 0x7d, 0xc7, 0x68, 0xce, 0xea, 0x69, 0xca, 0x03,
+    0x2b, 0x0e, 0xd4, 0x7a, 0x8e, 0x11, 0xec, 0xfb, 0x9e, 0x39, 0x3c, 0x4d, 0x87, 0xb4, 0x12, 0x1d,
+];
+/// RSA-2048 test key (second prime factor)
+const RSA_2048_Q: [u8; 128] = [
+    0xbf, 0x79, 0x65, 0x57, 0x07, 0x43, 0x34, 0x27, 0x62, 0xb4, 0x08, 0xda, 0x46, 0xfa, 0x24, 0x9b,
+    0x24, 0x89, 0x9c, 0x84, 0x1d, 0xb3, 0xe2, 0x18, 0x94, 0x54, 0xb0, 0xa5, 0x21, 0x3c, 0x4d, 0xd1,
+    0xff, 0x56, 0xd2, 0xd1, 0x4f, 0x6b, 0x4b, 0x9f, 0x76, 0x45, 0x8a, 0x04, 0x2b, 0x80, 0xf1, 0xdc,
+    0xcb, 0x1c, 0xfb, 0xf6, 0xbf, 0x7c, 0x2f, 0x0c, 0xf6, 0x80, 0x06, 0x54, 0x4a, 0xc1, 0xbc, 0xbe,
+    0xc5, 0x0b, 0x51, 0xa5, 0xf4, 0x63, 0xc6, 0x82, 0xc1, 0xf2, 0xde, 0xb3, 0x96, 0xe3, 0x32, 0xba,
+    0x2c, 0x2c, 0xa6, 0xc1, 0x14, 0x19, 0xab, 0x35, 0xaf, 0x07, 0xa6, 0xce, 0x70, 0xb1, 0xa4, 0x0e,
+    0xeb, 0x8c, 0xeb, 0x96, 0x8a, 0x83, 0xd4, 0x00, 0x0f, 0xed, 0x53, 0xdb, 0x7e, 0x86, 0xe4, 0xf4,
+    0x3a, 0xde, 0x04, 0x36, 0x60, 0x5b, 0xd4, 0x76, 0xc6, 0x7d, 0xed, 0xe0, 0x5f, 0x11, 0x7c, 0x83,
+];
+
+/// Import the RSA-2048 test key and return its public key
+fn put_rsa_2048_key(client: &Client) -> RsaPublicKey {
+    put_asymmetric_key(
+        client,
+        asymmetric::Algorithm::Rsa2048,
+        Capability::SIGN_PSS,
+        [RSA_2048_P, RSA_2048_Q].concat(),
+    );
+
+    let public_key = client
+        .get_public_key(TEST_KEY_ID)
+        .unwrap_or_else(|err| panic!("error getting public key: {err}"));
+
+    assert_eq!(public_key.algorithm, asymmetric::Algorithm::Rsa2048);
+    assert_eq!(public_key.len(), RSA_2048_KEY_SIZE);
+
+    RsaPublicKey::new(
+        BigUint::from_bytes_be(public_key.as_slice()),
+        BigUint::from(65537u32),
+    )
+    .unwrap()
+}
+
+/// Sign a test message with the given parameters
+fn sign_test_message(client: &Client, salt_len: usize, hash: mgf::Algorithm) -> Signature {
+    let signature = client
+        .sign_rsa_pss(TEST_KEY_ID, salt_len, hash, TEST_MESSAGE)
+        .unwrap_or_else(|err| panic!("error computing RSASSA-PSS signature: {err}"));
+
+    assert_eq!(signature.len(), RSA_2048_KEY_SIZE);
+    Signature::try_from(signature.as_slice()).unwrap()
+}
+
+/// Test RSASSA-PSS signing with each supported hash function
+#[test]
+fn rsa_2048_pss_test() {
+    let client = crate::get_hsm_client();
+    let public_key = put_rsa_2048_key(&client);
+
+    let signature = sign_test_message(&client, 32, mgf::Algorithm::Sha256);
+    VerifyingKey::<Sha256>::new_with_salt_len(public_key.clone(), 32)
+        .verify(TEST_MESSAGE, &signature)
+        .unwrap();
+
+    let signature = sign_test_message(&client, 48, mgf::Algorithm::Sha384);
+    VerifyingKey::<Sha384>::new_with_salt_len(public_key.clone(), 48)
+        .verify(TEST_MESSAGE, &signature)
+        .unwrap();
+
+    let signature = sign_test_message(&client, 64, mgf::Algorithm::Sha512);
+    VerifyingKey::<Sha512>::new_with_salt_len(public_key, 64)
+        .verify(TEST_MESSAGE, &signature)
+        .unwrap();
+}
+
+/// Test RSASSA-PSS signing with an empty salt
+#[test]
+fn rsa_2048_pss_zero_salt_test() {
+    let client = crate::get_hsm_client();
+    let public_key = put_rsa_2048_key(&client);
+
+    let signature = sign_test_message(&client, 0, mgf::Algorithm::Sha256);
+    VerifyingKey::<Sha256>::new_with_salt_len(public_key, 0)
+        .verify(TEST_MESSAGE, &signature)
+        .unwrap();
+
+    // Without a salt RSASSA-PSS is deterministic
+    assert_eq!(
+        signature,
+        sign_test_message(&client, 0, mgf::Algorithm::Sha256)
+    );
+}
+
+/// Test RSASSA-PSS signing with the largest salt the key size allows
+#[test]
+fn rsa_2048_pss_max_salt_test() {
+    let client = crate::get_hsm_client();
+    let public_key = put_rsa_2048_key(&client);
+
+    let signature = sign_test_message(
+        &client,
+        RSA_2048_SHA256_MAX_SALT_LEN,
+        mgf::Algorithm::Sha256,
+    );
+    VerifyingKey::<Sha256>::new_with_salt_len(public_key, RSA_2048_SHA256_MAX_SALT_LEN)
+        .verify(TEST_MESSAGE, &signature)
+        .unwrap();
+
+    let result = client.sign_rsa_pss(
+        TEST_KEY_ID,
+        RSA_2048_SHA256_MAX_SALT_LEN + 1,
+        mgf::Algorithm::Sha256,
+        TEST_MESSAGE,
+    );
+    assert!(result.is_err());
+}
+
+/// SHA-1 digests can't be computed client-side, so they're refused before
+/// sending the command
+#[test]
+fn rsa_2048_pss_sha1_test() {
+    let client = crate::get_hsm_client();
+    put_rsa_2048_key(&client);
+
+    let err = client
+        .sign_rsa_pss(TEST_KEY_ID, 0, mgf::Algorithm::Sha1, TEST_MESSAGE)
+        .unwrap_err();
+
+    assert!(err.to_string().contains("no SHA-1 implementation"));
+}
@@ -0,0 +1,169 @@
+//! Sign OpenSSH certificates with a CA key in the HSM
+
+use crate::{
+    clear_test_key_slot, generate_asymmetric_key, TEST_DOMAINS, TEST_KEY_ID, TEST_KEY_LABEL,
+};
+use ed25519_dalek::Verifier;
+use yubihsm::{
+    asymmetric::{self, PublicKey},
+    object, ssh, Capability, Client,
+};
+
+/// Placeholder SSH template (the MockHsm doesn't enforce templates)
+const TEST_TEMPLATE: &[u8] = b"yubihsm.rs test SSH template";
+
+/// Read a length-prefixed `string` from an SSH wire encoding
+fn read_string<'a>(reader: &mut &'a [u8]) -> &'a [u8] {
+    let (len, rest) = reader.split_at(4);
+    let (bytes, rest) = rest.split_at(u32::from_be_bytes(len.try_into().unwrap()) as usize);
+    *reader = rest;
+    bytes
+}
+
+/// Read a `uint64` from an SSH wire encoding
+fn read_u64(reader: &mut &[u8]) -> u64 {
+    let (bytes, rest) = reader.split_at(8);
+    *reader = rest;
+    u64::from_be_bytes(bytes.try_into().unwrap())
+}
+
+/// Store the test template, and a CA key (with the given algorithm) to
+/// certify a software-generated Ed25519 key with
+fn sign_test_certificate(client: &Client, algorithm: asymmetric::Algorithm) -> Vec<u8> {
+    generate_asymmetric_key(client, algorithm, Capability::SIGN_SSH_CERTIFICATE);
+    clear_test_key_slot(client, object::Type::Template);
+
+    client
+        .put_template(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::default(),
+            ssh::Template::from_bytes(TEST_TEMPLATE),
+        )
+        .unwrap();
+
+    assert_eq!(client.get_template(TEST_KEY_ID).unwrap(), TEST_TEMPLATE);
+
+    let user_key = ed25519_dalek::SigningKey::from_bytes(&[0x42; 32]);
+    let public_key = PublicKey {
+        algorithm: asymmetric::Algorithm::Ed25519,
+        bytes: user_key.verifying_key().to_bytes().into(),
+    };
+
+    let mut request = ssh::CertificateRequest::new(public_key, ssh::CertificateType::User, "alice");
+    request.serial = 42;
+    request.principals = vec!["alice".into(), "root".into()];
+    request
+        .extensions
+        .insert("permit-pty".into(), String::new());
+
+    let certificate = client
+        .sign_ssh_certificate(TEST_KEY_ID, TEST_KEY_ID, request)
+        .unwrap();
+
+    // Check the certified fields
+    let mut reader = certificate.as_slice();
+    assert_eq!(
+        read_string(&mut reader),
+        b"ssh-ed25519-cert-v01@openssh.com"
+    );
+    assert_eq!(read_string(&mut reader).len(), 32);
+    assert_eq!(
+        read_string(&mut reader),
+        user_key.verifying_key().as_bytes()
+    );
+    assert_eq!(read_u64(&mut reader), 42);
+    assert_eq!(reader[..4], 1u32.to_be_bytes());
+    reader = &reader[4..];
+    assert_eq!(read_string(&mut reader), b"alice");
+
+    let mut principals = read_string(&mut reader);
+    assert_eq!(read_string(&mut principals), b"alice");
+    assert_eq!(read_string(&mut principals), b"root");
+    assert!(principals.is_empty());
+
+    assert_eq!(read_u64(&mut reader), 0);
+    assert_eq!(read_u64(&mut reader), u64::MAX);
+    assert!(read_string(&mut reader).is_empty());
+    assert_eq!(read_string(&mut reader), b"\0\0\0\x0apermit-pty\0\0\0\0");
+    assert!(read_string(&mut reader).is_empty());
+
+    certificate
+}
+
+/// Split a certificate into its signed body, CA key blob, and signature blob
+fn split_certificate(certificate: &[u8]) -> (&[u8], &[u8], &[u8]) {
+    let mut reader = certificate;
+
+    // Skip the key type, nonce, key, serial, type, key ID, principals, validity
+    // period, critical options, extensions, and reserved fields
+    read_string(&mut reader);
+    read_string(&mut reader);
+    read_string(&mut reader);
+    reader = &reader[12..];
+
+    for _ in 0..2 {
+        read_string(&mut reader);
+    }
+
+    reader = &reader[16..];
+
+    for _ in 0..3 {
+        read_string(&mut reader);
+    }
+
+    let ca_key = read_string(&mut reader);
+    let body_len = certificate.len() - reader.len();
+    let signature = read_string(&mut reader);
+    assert!(reader.is_empty());
+
+    (&certificate[..body_len], ca_key, signature)
+}
+
+#[test]
+fn ed25519_ca_test() {
+    let client = crate::get_hsm_client();
+    let certificate = sign_test_certificate(&client, asymmetric::Algorithm::Ed25519);
+    let (body, mut ca_key, mut signature) = split_certificate(&certificate);
+
+    let ca_public_key = client.get_public_key(TEST_KEY_ID).unwrap();
+    assert_eq!(read_string(&mut ca_key), b"ssh-ed25519");
+    assert_eq!(read_string(&mut ca_key), ca_public_key.as_slice());
+
+    assert_eq!(read_string(&mut signature), b"ssh-ed25519");
+    let signature = ed25519_dalek::Signature::from_slice(read_string(&mut signature)).unwrap();
+
+    let verifying_key = ed25519_dalek::VerifyingKey::try_from(ca_public_key.as_slice()).unwrap();
+    assert!(verifying_key.verify(body, &signature).is_ok());
+}
+
+#[test]
+fn ecdsa_p256_ca_test() {
+    use p256::ecdsa::signature::Verifier as _;
+
+    let client = crate::get_hsm_client();
+    let certificate = sign_test_certificate(&client, asymmetric::Algorithm::EcP256);
+    let (body, mut ca_key, mut signature) = split_certificate(&certificate);
+
+    let ca_public_key = client.get_public_key(TEST_KEY_ID).unwrap();
+    assert_eq!(read_string(&mut ca_key), b"ecdsa-sha2-nistp256");
+    assert_eq!(read_string(&mut ca_key), b"nistp256");
+    assert_eq!(read_string(&mut ca_key)[1..], *ca_public_key.as_slice());
+
+    assert_eq!(read_string(&mut signature), b"ecdsa-sha2-nistp256");
+    let mut rs = read_string(&mut signature);
+    let mut scalar_bytes = [0u8; 64];
+
+    for half in scalar_bytes.chunks_mut(32) {
+        let int = read_string(&mut rs);
+        let int = &int[int.len().saturating_sub(32)..];
+        half[32 - int.len()..].copy_from_slice(int);
+    }
+
+    let signature = p256::ecdsa::Signature::from_slice(&scalar_bytes).unwrap();
+    let verifying_key =
+        p256::ecdsa::VerifyingKey::from_sec1_bytes(&[&[0x04], ca_public_key.as_slice()].concat())
+            .unwrap();
+    assert!(verifying_key.verify(body, &signature).is_ok());
+}
@@ -64,6 +64,47 @@ fn create_yubihsm_key(client: &Client, key_id: object::Id, alg: yubihsm::asymmet
         .unwrap();
 }
 
+/// Check the signer's compressed public key decompresses to the point the
+/// device provided
+fn public_key_compression_test<C>(key_id: object::Id)
+where
+    C: CurveAlgorithm + CurveArithmetic + PointCompression + PrimeCurve,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: sec1::ModulusSize,
+{
+    let signer = create_signer::<C>(key_id);
+    let device_key = crate::get_hsm_client().get_public_key(key_id).unwrap();
+
+    let compressed = signer.public_key_compressed();
+    assert!(compressed.is_compressed());
+    assert_eq!(compressed.len(), 1 + device_key.len() / 2);
+
+    let decompressed = ::ecdsa::elliptic_curve::PublicKey::<C>::from_encoded_point(&compressed)
+        .unwrap()
+        .to_encoded_point(false);
+    assert_eq!(&decompressed.as_bytes()[1..], device_key.as_slice());
+
+    assert_eq!(signer.public_key_bytes(true), compressed.as_bytes());
+    assert_eq!(signer.public_key_bytes(false), decompressed.as_bytes());
+    assert_eq!(device_key.ecdsa_compressed::<C>().unwrap(), compressed);
+    assert_eq!(device_key.ecdsa_bytes(true).unwrap(), compressed.as_bytes());
+    assert_eq!(
+        device_key.ecdsa_bytes(false).unwrap(),
+        decompressed.as_bytes()
+    );
+}
+
+#[test]
+fn ecdsa_nistp256_public_key_compression_test() {
+    public_key_compression_test::<NistP256>(209);
+}
+
+#[cfg(feature = "secp256k1")]
+#[test]
+fn ecdsa_secp256k1_public_key_compression_test() {
+    public_key_compression_test::<Secp256k1>(210);
+}
+
 #[test]
 fn ecdsa_nistp256_sign_test() {
     let signer = create_signer::<NistP256>(201);
@@ -73,6 +114,154 @@ fn ecdsa_nistp256_sign_test() {
     assert!(verify_key.verify(TEST_MESSAGE, &signature).is_ok());
 }
 
+#[test]
+fn ecdsa_sign_with_digest_test() {
+    use ::ecdsa::signature::{digest::Digest, hazmat::PrehashVerifier};
+
+    let signer = create_signer::<NistP256>(206);
+    let verify_key = p256::ecdsa::VerifyingKey::from_encoded_point(signer.public_key()).unwrap();
+
+    let signature = signer
+        .sign_with_digest::<sha2::Sha384>(TEST_MESSAGE)
+        .unwrap();
+    let digest = sha2::Sha384::digest(TEST_MESSAGE);
+    assert!(verify_key.verify_prehash(&digest, &signature).is_ok());
+
+    let signature = signer
+        .sign_with_digest::<sha2::Sha512>(TEST_MESSAGE)
+        .unwrap();
+    let digest = sha2::Sha512::digest(TEST_MESSAGE);
+    assert!(verify_key.verify_prehash(&digest, &signature).is_ok());
+
+    // Signatures over a different digest of the message don't verify
+    assert!(verify_key
+        .verify_prehash(&sha2::Sha384::digest(TEST_MESSAGE), &signature)
+        .is_err());
+}
+
+/// Creating a signer for a curve the HSM doesn't support fails before any
+/// key lookup
+#[cfg(feature = "mockhsm")]
+#[test]
+fn ecdsa_unsupported_curve_test() {
+    use std::error::Error as _;
+    use yubihsm::{algorithm::Algorithm, asymmetric, client, ecdsa::NistP384, mockhsm, Connector};
+
+    let hsm = mockhsm::Builder::new()
+        .algorithms([Algorithm::Asymmetric(asymmetric::Algorithm::EcP256)])
+        .build();
+    let client = Client::open(Connector::from(hsm), Default::default(), false).unwrap();
+    assert!(!client.authenticate().unwrap().supports_curve::<NistP384>());
+
+    let err = ecdsa::Signer::<NistP384>::create(client.clone(), 201)
+        .err()
+        .unwrap();
+
+    let source = err
+        .source()
+        .and_then(|source| source.downcast_ref::<client::Error>())
+        .unwrap();
+
+    assert_eq!(*source.kind(), client::ErrorKind::AlgorithmUnsupported);
+}
+
+/// Creating a signer for a key on a different curve fails with a typed
+/// error, rather than producing signatures which never verify
+#[cfg(feature = "secp256k1")]
+#[test]
+fn ecdsa_algorithm_mismatch_test() {
+    use std::error::Error as _;
+    use yubihsm::{asymmetric, client};
+
+    let client = crate::get_hsm_client();
+    create_yubihsm_key(&client, 207, asymmetric::Algorithm::EcK256);
+
+    let err = ecdsa::Signer::<NistP256>::create(client.clone(), 207)
+        .err()
+        .unwrap();
+
+    let source = err
+        .source()
+        .and_then(|source| source.downcast_ref::<client::Error>())
+        .unwrap();
+
+    assert_eq!(
+        *source.kind(),
+        client::ErrorKind::AlgorithmMismatch {
+            expected: asymmetric::Algorithm::EcP256,
+            actual: asymmetric::Algorithm::EcK256,
+        }
+    );
+
+    assert!(ecdsa::Signer::<Secp256k1>::create(client.clone(), 207).is_ok());
+}
+
+#[test]
+fn ecdsa_sign_signable_test() {
+    /// Example structured message with a canonical encoding
+    struct Transfer {
+        amount: u64,
+        recipient: &'static str,
+    }
+
+    impl ecdsa::Signable for Transfer {
+        fn signing_bytes(&self) -> Vec<u8> {
+            let mut bytes = self.amount.to_be_bytes().to_vec();
+            bytes.extend_from_slice(self.recipient.as_bytes());
+            bytes
+        }
+    }
+
+    let signer = create_signer::<NistP256>(204);
+    let verify_key = p256::ecdsa::VerifyingKey::from_encoded_point(signer.public_key()).unwrap();
+
+    let transfer = Transfer {
+        amount: 42,
+        recipient: "cosmos1recipient",
+    };
+
+    let signature: ecdsa::Signature<NistP256> = signer.sign_signable(&transfer).unwrap();
+    let signing_bytes = ecdsa::Signable::signing_bytes(&transfer);
+    assert!(verify_key.verify(&signing_bytes, &signature).is_ok());
+}
+
+#[test]
+fn ecdsa_verify_external_test() {
+    let signer = create_signer::<NistP256>(205);
+    let public_key = crate::get_hsm_client().get_public_key(205).unwrap();
+    let signature: ecdsa::Signature<NistP256> = signer.sign(TEST_MESSAGE);
+
+    // Both untagged (as returned by the HSM) and SEC1-tagged points
+    for key_bytes in [public_key.as_ref(), signer.public_key().as_bytes()] {
+        assert!(
+            ecdsa::verify_external::<NistP256>(key_bytes, TEST_MESSAGE, &signature.to_bytes())
+                .is_ok()
+        );
+        assert!(ecdsa::verify_external_der::<NistP256>(
+            key_bytes,
+            TEST_MESSAGE,
+            signature.to_der().as_bytes()
+        )
+        .is_ok());
+    }
+
+    let mut corrupted = signature.to_bytes();
+    corrupted[0] ^= 0x01;
+    assert!(
+        ecdsa::verify_external::<NistP256>(public_key.as_ref(), TEST_MESSAGE, &corrupted).is_err()
+    );
+
+    let mut corrupted_der = signature.to_der().as_bytes().to_vec();
+    let last = corrupted_der.len() - 1;
+    corrupted_der[last] ^= 0x01;
+    assert!(ecdsa::verify_external_der::<NistP256>(
+        public_key.as_ref(),
+        TEST_MESSAGE,
+        &corrupted_der
+    )
+    .is_err());
+}
+
 #[cfg(feature = "secp256k1")]
 #[test]
 fn ecdsa_secp256k1_sign_test() {
@@ -105,3 +294,46 @@ fn ecdsa_secp256k1_sign_recover_test() {
     let signer_pk = PublicKey::from_encoded_point(signer.public_key()).unwrap();
     assert_eq!(&recovered_pk, &signer_pk);
 }
+
+#[cfg(feature = "pkcs8")]
+#[test]
+fn ecdsa_signature_algorithm_identifier_test() {
+    use ::ecdsa::elliptic_curve::pkcs8::{
+        spki::{DynSignatureAlgorithmIdentifier, SignatureAlgorithmIdentifier},
+        ObjectIdentifier,
+    };
+    use yubihsm::ecdsa::NistP384;
+
+    /// `ecdsa-with-SHA256` (RFC 5758)
+    const ECDSA_WITH_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.2");
+
+    /// `ecdsa-with-SHA384` (RFC 5758)
+    const ECDSA_WITH_SHA384: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.3");
+
+    let signer = create_signer::<NistP256>(203);
+    let algorithm = signer.signature_algorithm_identifier().unwrap();
+    assert_eq!(algorithm.oid, ECDSA_WITH_SHA256);
+    assert!(algorithm.parameters.is_none());
+
+    assert_eq!(
+        ecdsa::Signer::<NistP384>::SIGNATURE_ALGORITHM_IDENTIFIER.oid,
+        ECDSA_WITH_SHA384
+    );
+}
+
+/// The signer's `Debug` output identifies the key without any secrets
+#[test]
+fn ecdsa_signer_debug_test() {
+    let signer = create_signer::<NistP256>(211);
+    let public_key: String = signer
+        .public_key()
+        .as_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+
+    assert_eq!(
+        format!("{signer:?}"),
+        format!("EcdsaSigner {{ key_id: 211, algorithm: EcP256, public_key: {public_key} }}")
+    );
+}
@@ -49,3 +49,124 @@ fn ed25519_sign_test() {
     let verifier = VerifyingKey::from_bytes(signer.public_key().as_bytes()).unwrap();
     assert!(verifier.verify(TEST_MESSAGE, &signature).is_ok());
 }
+
+#[test]
+fn ed25519_sign_raw_test() {
+    let client = crate::get_hsm_client();
+    create_yubihsm_key(&client);
+
+    let signer = ed25519::Signer::create(client.clone(), TEST_SIGNING_KEY_ID).unwrap();
+    let raw_signature = signer.sign_raw(TEST_MESSAGE).unwrap();
+
+    // Ed25519 signatures are deterministic
+    let signature: ed25519::Signature = signer.sign(TEST_MESSAGE);
+    assert_eq!(raw_signature, signature.to_bytes());
+
+    // Round trip through the `ed25519` crate's interop type
+    assert_eq!(ed25519::Signature::from(raw_signature), signature);
+    assert_eq!(
+        ed25519::Signature::try_from(&raw_signature[..]).unwrap(),
+        signature
+    );
+    assert!(ed25519::Signature::try_from(&raw_signature[1..]).is_err());
+
+    let verifier = VerifyingKey::from_bytes(signer.public_key().as_bytes()).unwrap();
+    let dalek_signature = ed25519_dalek::Signature::from_bytes(&raw_signature);
+    assert!(verifier.verify(TEST_MESSAGE, &dalek_signature).is_ok());
+}
+
+#[cfg(feature = "ed25519-dalek")]
+#[test]
+fn ed25519_self_verify_test() {
+    let client = crate::get_hsm_client();
+    create_yubihsm_key(&client);
+
+    let signer = ed25519::Signer::create(client.clone(), TEST_SIGNING_KEY_ID).unwrap();
+    let signature: ed25519::Signature = signer.sign(TEST_MESSAGE);
+    assert!(signer.verify(TEST_MESSAGE, &signature).is_ok());
+
+    let mut corrupted = signature.to_bytes();
+    corrupted[0] ^= 0x01;
+    assert!(signer
+        .verify(TEST_MESSAGE, &ed25519::Signature::from(corrupted))
+        .is_err());
+}
+
+#[cfg(feature = "ssh")]
+#[test]
+fn ed25519_ssh_public_key_test() {
+    let client = crate::get_hsm_client();
+    create_yubihsm_key(&client);
+
+    let signer = ed25519::Signer::create(client.clone(), TEST_SIGNING_KEY_ID).unwrap();
+    let ssh_public_key = signer.ssh_public_key(TEST_SIGNING_KEY_LABEL);
+    assert!(ssh_public_key.starts_with("ssh-ed25519 "));
+    assert!(ssh_public_key.ends_with(TEST_SIGNING_KEY_LABEL));
+
+    let public_key = yubihsm::ssh::parse_public_key_openssh(&ssh_public_key).unwrap();
+    assert_eq!(
+        public_key,
+        client.get_public_key(TEST_SIGNING_KEY_ID).unwrap()
+    );
+}
+
+/// Creating a signer for a key which isn't an Ed25519 key fails with a
+/// typed error
+#[test]
+fn ed25519_algorithm_mismatch_test() {
+    use std::error::Error as _;
+    use yubihsm::{asymmetric, client};
+
+    let client = crate::get_hsm_client();
+    let key_id = 208;
+    let _ = client.delete_object(key_id, yubihsm::object::Type::AsymmetricKey);
+
+    client
+        .generate_asymmetric_key(
+            key_id,
+            TEST_SIGNING_KEY_LABEL.into(),
+            TEST_SIGNING_KEY_DOMAINS,
+            yubihsm::Capability::SIGN_ECDSA,
+            asymmetric::Algorithm::EcP256,
+        )
+        .unwrap();
+
+    let err = ed25519::Signer::create(client.clone(), key_id)
+        .err()
+        .unwrap();
+
+    let source = err
+        .source()
+        .and_then(|source| source.downcast_ref::<client::Error>())
+        .unwrap();
+
+    assert_eq!(
+        *source.kind(),
+        client::ErrorKind::AlgorithmMismatch {
+            expected: asymmetric::Algorithm::Ed25519,
+            actual: asymmetric::Algorithm::EcP256,
+        }
+    );
+}
+
+/// The signer's `Debug` output identifies the key without any secrets
+#[test]
+fn ed25519_signer_debug_test() {
+    let client = crate::get_hsm_client();
+    create_yubihsm_key(&client);
+
+    let signer = ed25519::Signer::create(client.clone(), TEST_SIGNING_KEY_ID).unwrap();
+    let public_key: String = signer
+        .public_key()
+        .as_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+
+    assert_eq!(
+        format!("{signer:?}"),
+        format!(
+            "Ed25519Signer {{ key_id: {TEST_SIGNING_KEY_ID}, algorithm: Ed25519, public_key: {public_key} }}"
+        )
+    );
+}
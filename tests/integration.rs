@@ -13,6 +13,10 @@ mod ecdsa;
 /// Ed25519 tests
 mod ed25519;
 
+/// MockHsm configuration tests
+#[cfg(feature = "mockhsm")]
+mod mockhsm;
+
 /// Cryptographic test vectors taken from standards documents
 mod test_vectors;
 
@@ -0,0 +1,1829 @@
+//! MockHsm configuration tests
+
+use std::{
+    mem, thread,
+    time::{Duration, Instant},
+};
+use yubihsm::{
+    asymmetric, authentication, command, device, hmac,
+    mockhsm::{Builder, FirmwareVersion},
+    object, opaque, wrap, AuditOption, Capability, Client, Connector, Credentials, Domain,
+};
+
+/// Open a client with the default credentials to a `MockHsm` built by `builder`
+fn open_client(builder: Builder) -> Client {
+    Client::open(
+        Connector::from(builder.build()),
+        Credentials::default(),
+        false,
+    )
+    .unwrap()
+}
+
+#[test]
+fn serial_number_test() {
+    let client = open_client(Builder::new().serial_number(1_234_567_890));
+    let info = client.device_info().unwrap();
+    assert_eq!(info.serial_number.to_string(), "1234567890");
+}
+
+#[test]
+fn firmware_version_test() {
+    let client = open_client(Builder::new().firmware_version(FirmwareVersion::new(2, 4, 1)));
+    let info = client.device_info().unwrap();
+    assert_eq!(
+        (info.major_version, info.minor_version, info.build_version),
+        (2, 4, 1)
+    );
+}
+
+#[test]
+fn require_firmware_test() {
+    let required = FirmwareVersion::new(2, 4, 0);
+
+    let client = open_client(Builder::new().firmware_version(FirmwareVersion::new(2, 0, 0)));
+    assert_eq!(
+        client.authenticate().unwrap().firmware_version(),
+        FirmwareVersion::new(2, 0, 0)
+    );
+
+    let err = client.require_firmware(required).unwrap_err();
+    assert_eq!(
+        *err.kind(),
+        yubihsm::client::ErrorKind::UnsupportedByFirmware {
+            required,
+            actual: FirmwareVersion::new(2, 0, 0),
+        }
+    );
+    assert!(err.to_string().contains("requires 2.4.0"), "{}", err);
+
+    let client = open_client(Builder::new().firmware_version(FirmwareVersion::new(2, 4, 1)));
+    assert_eq!(
+        client.require_firmware(required).unwrap(),
+        FirmwareVersion::new(2, 4, 1)
+    );
+}
+
+#[test]
+fn auth_key_test() {
+    let hsm = Builder::new()
+        .default_auth_key_id(2)
+        .auth_key_password("hunter2")
+        .build();
+
+    let connector = Connector::from(hsm);
+    assert!(Client::open(connector.clone(), Credentials::default(), false).is_err());
+
+    let credentials = Credentials::from_password(2, b"hunter2");
+    let client = Client::open(connector, credentials, false).unwrap();
+    assert_eq!(client.whoami().unwrap().object_id, 2);
+}
+
+#[test]
+fn change_authentication_key_test() {
+    let hsm = Builder::new().build();
+    let connector = Connector::from(hsm.clone());
+    let client = Client::open(connector.clone(), Credentials::default(), true).unwrap();
+    let new_credentials = Credentials::from_password(1, b"hunter2");
+
+    // The change is rejected: the old key is untouched
+    hsm.corrupt_next_command();
+    assert!(client
+        .change_authentication_key(new_credentials.authentication_key.clone())
+        .is_err());
+
+    // The new key is stored wrong: the old one is restored
+    hsm.corrupt_next_key_change();
+    let err = client
+        .change_authentication_key(new_credentials.authentication_key.clone())
+        .unwrap_err();
+    assert_eq!(*err.kind(), yubihsm::client::ErrorKind::AuthenticationError);
+    assert!(err.to_string().contains("old key restored"), "{}", err);
+
+    assert!(Client::open(connector.clone(), Credentials::default(), false).is_ok());
+    assert!(Client::open(connector.clone(), new_credentials.clone(), false).is_err());
+
+    client
+        .change_authentication_key(new_credentials.authentication_key.clone())
+        .unwrap();
+
+    assert!(Client::open(connector.clone(), Credentials::default(), false).is_err());
+    assert!(Client::open(connector, new_credentials, false).is_ok());
+
+    // Once its session expires, the client reconnects with the new key
+    for id in hsm.session_ids() {
+        hsm.expire_session(id);
+    }
+    assert!(client.ping().is_err());
+    assert!(client.ping().is_ok());
+
+    // Clients which can't reconnect can't restore the old key
+    let client = open_client(Builder::new());
+    assert!(client
+        .change_authentication_key(authentication::Key::random())
+        .is_err());
+}
+
+#[test]
+fn raw_option_test() {
+    let client = open_client(Builder::new());
+
+    // An option this crate has no typed methods for
+    client.set_option_raw(0x7f, &[1, 2, 3]).unwrap();
+    assert_eq!(client.get_option_raw(0x7f).unwrap(), [1, 2, 3]);
+
+    let err = client.get_option_raw(0x7e).unwrap_err();
+    assert_eq!(err.device_error(), Some(device::ErrorKind::InvalidData));
+
+    // Typed wrappers use the same options
+    client
+        .set_command_audit_option(command::Code::Echo, AuditOption::On)
+        .unwrap();
+    let command_options = client.get_option_raw(0x03).unwrap();
+    assert!(command_options
+        .chunks(2)
+        .any(|option| option == [command::Code::Echo.to_u8(), AuditOption::On.to_u8()]));
+
+    client
+        .set_option_raw(0x01, &[AuditOption::On.to_u8()])
+        .unwrap();
+    assert_eq!(client.get_force_audit_option().unwrap(), AuditOption::On);
+    client.set_force_audit_option(AuditOption::Off).unwrap();
+    assert_eq!(
+        client.get_option_raw(0x01).unwrap(),
+        [AuditOption::Off.to_u8()]
+    );
+}
+
+#[test]
+fn ecdsa_nonce_reuse_test() {
+    let hsm = Builder::new().build();
+    let client = Client::open(Connector::from(hsm.clone()), Credentials::default(), false).unwrap();
+
+    for key_id in [1, 2] {
+        client
+            .generate_asymmetric_key(
+                key_id,
+                "nonce reuse test key".into(),
+                Domain::DOM1,
+                Capability::SIGN_ECDSA,
+                asymmetric::Algorithm::EcP256,
+            )
+            .unwrap();
+    }
+
+    for i in 0..500u32 {
+        let mut digest = [0u8; 32];
+        digest[..4].copy_from_slice(&i.to_be_bytes());
+
+        for key_id in [1, 2] {
+            client.sign_ecdsa_prehash_raw(key_id, digest).unwrap();
+        }
+    }
+
+    assert_eq!(hsm.ecdsa_nonce_reuses(), 0);
+}
+
+#[test]
+fn ecdsa_fixed_nonce_test() {
+    let hsm = Builder::new().build();
+    let client = Client::open(Connector::from(hsm.clone()), Credentials::default(), false).unwrap();
+
+    let generate_key = || {
+        client
+            .generate_asymmetric_key(
+                1,
+                "nonce reuse test key".into(),
+                Domain::DOM1,
+                Capability::SIGN_ECDSA,
+                asymmetric::Algorithm::EcP256,
+            )
+            .unwrap()
+    };
+
+    generate_key();
+    hsm.fix_ecdsa_nonce(Some([0x42; 32]));
+
+    // Signing the same digest again with the same nonce leaks nothing...
+    let signature = client.sign_ecdsa_prehash_raw(1, [1; 32]).unwrap();
+    assert_eq!(
+        client.sign_ecdsa_prehash_raw(1, [1; 32]).unwrap(),
+        signature
+    );
+    assert_eq!(hsm.ecdsa_nonce_reuses(), 0);
+
+    // ...but signing a different one does
+    client.sign_ecdsa_prehash_raw(1, [2; 32]).unwrap();
+    assert_eq!(hsm.ecdsa_nonce_reuses(), 1);
+
+    // A new key with the same ID doesn't inherit the old key's nonces
+    client
+        .delete_object(1, object::Type::AsymmetricKey)
+        .unwrap();
+    generate_key();
+    client.sign_ecdsa_prehash_raw(1, [2; 32]).unwrap();
+    assert_eq!(hsm.ecdsa_nonce_reuses(), 1);
+
+    hsm.fix_ecdsa_nonce(None);
+    client.sign_ecdsa_prehash_raw(1, [3; 32]).unwrap();
+    assert_eq!(hsm.ecdsa_nonce_reuses(), 1);
+}
+
+#[test]
+fn audit_log_entries_test() {
+    let client = open_client(Builder::new());
+
+    // Consume the entries logged while opening the session
+    let log = client.get_log_entries().unwrap();
+    client
+        .set_log_index(log.entries.last().unwrap().item)
+        .unwrap();
+
+    client
+        .generate_hmac_key(
+            1,
+            "yubihsm.rs test key".into(),
+            Domain::DOM1,
+            Capability::SIGN_HMAC,
+            hmac::Algorithm::Sha256,
+        )
+        .unwrap();
+    client.sign_hmac(1, b"hello".as_ref()).unwrap();
+    client.get_object_info(1, object::Type::HmacKey).unwrap();
+
+    // `GetLogEntries` isn't audited by default, but `SetLogIndex` is
+    let entries = client.get_log_entries().unwrap().entries;
+    let commands: Vec<_> = entries.iter().map(|entry| entry.cmd).collect();
+    assert_eq!(
+        commands,
+        [
+            command::Code::SetLogIndex,
+            command::Code::GenerateHmacKey,
+            command::Code::SignHmac,
+            command::Code::GetObjectInfo,
+        ]
+    );
+
+    for (entry, previous) in entries[1..].iter().zip(&entries) {
+        assert!(entry.result.is_success(), "{entry:?}");
+        assert_eq!(entry.item, previous.item.wrapping_add(1));
+        assert!(entry.tick >= previous.tick);
+        assert_eq!(entry.session_key, 1);
+        assert_eq!(entry.target_key, 1);
+    }
+}
+
+#[test]
+fn connection_stats_test() {
+    let client = open_client(Builder::new());
+
+    // Opening the session took two round trips
+    assert_eq!(client.stats().commands_sent, 2);
+    client.reset_stats();
+
+    for _ in 0..10 {
+        client.ping().unwrap();
+    }
+
+    let stats = client.stats();
+    assert_eq!(stats.commands_sent, 10);
+    assert_eq!(stats.count(command::Code::Echo), 10);
+    assert_eq!(stats.errors, 0);
+    assert!(stats.bytes_sent > 0);
+    assert!(stats.bytes_received > 0);
+    assert!(stats.avg_latency_ms > 0.0);
+
+    // Clones share their statistics
+    client
+        .clone()
+        .get_object_info(100, object::Type::Opaque)
+        .unwrap_err();
+
+    let stats = client.stats();
+    assert_eq!(stats.commands_sent, 11);
+    assert_eq!(stats.errors, 1);
+
+    client.reset_stats();
+    assert_eq!(client.stats(), Default::default());
+}
+
+/// Sessions closed by the HSM are detected before reuse when
+/// `verify_idle_sessions` is enabled
+#[test]
+fn verify_idle_sessions_test() {
+    let hsm = Builder::new().build();
+    let client = Client::builder(Connector::from(hsm.clone()))
+        .verify_idle_sessions(Duration::ZERO)
+        .open()
+        .unwrap();
+
+    for id in hsm.session_ids() {
+        hsm.expire_session(id);
+    }
+
+    client.reset_stats();
+    assert!(client.ping().is_ok());
+
+    // The stale session fails verification and a new one is opened
+    let stats = client.stats();
+    assert_eq!(stats.count(command::Code::Echo), 2);
+    assert_eq!(stats.count(command::Code::CreateSession), 1);
+}
+
+/// `device_info` reports the configured algorithms, and validating or
+/// generating keys with others fails
+#[test]
+fn algorithms_test() {
+    use yubihsm::algorithm::Algorithm;
+
+    let algorithms = [
+        Algorithm::Asymmetric(asymmetric::Algorithm::EcP256),
+        Algorithm::Asymmetric(asymmetric::Algorithm::Ed25519),
+    ];
+
+    let client = open_client(Builder::new().algorithms(algorithms));
+    assert_eq!(client.device_info().unwrap().algorithms, algorithms);
+
+    let err = client
+        .validate_generate(
+            100,
+            Domain::DOM1,
+            Capability::SIGN_ECDSA,
+            asymmetric::Algorithm::EcP384,
+        )
+        .unwrap_err();
+
+    assert_eq!(err.device_error(), Some(device::ErrorKind::InvalidData));
+
+    let err = client
+        .generate_asymmetric_key(
+            100,
+            Default::default(),
+            Domain::DOM1,
+            Capability::SIGN_ECDSA,
+            asymmetric::Algorithm::EcP384,
+        )
+        .unwrap_err();
+
+    assert_eq!(err.device_error(), Some(device::ErrorKind::InvalidData));
+
+    // All algorithms a YubiHSM 2 supports are reported by default
+    let info = open_client(Builder::new()).device_info().unwrap();
+    assert!(info.supports(asymmetric::Algorithm::EcP384));
+    assert!(info.supports(asymmetric::Algorithm::EcP521));
+}
+
+#[test]
+fn stats_by_command_test() {
+    let client = Client::create(
+        Connector::from(Builder::new().build()),
+        Credentials::default(),
+    )
+    .unwrap();
+
+    assert_eq!(client.stats(), Default::default());
+
+    // Opening a session takes two round trips
+    client
+        .generate_asymmetric_key(
+            100,
+            Default::default(),
+            Domain::DOM1,
+            Capability::SIGN_EDDSA,
+            asymmetric::Algorithm::Ed25519,
+        )
+        .unwrap();
+    client.sign_ed25519(100, b"hello").unwrap();
+    client.sign_ed25519(100, b"world").unwrap();
+    client.verify_session();
+
+    // Failed commands are counted too
+    client
+        .clone()
+        .get_object_info(200, object::Type::Opaque)
+        .unwrap_err();
+
+    let stats = client.stats();
+    assert_eq!(stats.commands_sent, 7);
+    assert_eq!(stats.errors, 1);
+    assert_eq!(
+        stats
+            .by_code
+            .iter()
+            .map(|(c, n)| (*c, *n))
+            .collect::<Vec<_>>(),
+        [
+            (command::Code::Echo, 1),
+            (command::Code::CreateSession, 1),
+            (command::Code::AuthenticateSession, 1),
+            (command::Code::GenerateAsymmetricKey, 1),
+            (command::Code::GetObjectInfo, 1),
+            (command::Code::SignEddsa, 2),
+        ]
+    );
+
+    // Changing the authentication key opens (and closes) a session with the
+    // new key to verify it
+    client.reset_stats();
+    assert_eq!(client.stats(), Default::default());
+
+    client
+        .change_authentication_key(authentication::Key::random())
+        .unwrap();
+
+    let stats = client.stats();
+    assert_eq!(stats.commands_sent, 4);
+
+    for code in [
+        command::Code::ChangeAuthenticationKey,
+        command::Code::CreateSession,
+        command::Code::AuthenticateSession,
+        command::Code::CloseSession,
+    ] {
+        assert_eq!(stats.count(code), 1, "{code:?}");
+    }
+}
+
+#[test]
+fn guard_last_admin_key_test() {
+    let connector = Connector::from(Builder::new().build());
+    let admin = Client::open(connector.clone(), Credentials::default(), false).unwrap();
+    let operator_credentials = Credentials::new(3, authentication::Key::random());
+
+    admin
+        .put_authentication_key(
+            operator_credentials.authentication_key_id,
+            Default::default(),
+            Domain::DOM1,
+            Capability::DELETE_AUTHENTICATION_KEY,
+            Capability::empty(),
+            authentication::Algorithm::YubicoAes,
+            operator_credentials.authentication_key.clone(),
+        )
+        .unwrap();
+
+    let operator = Client::builder(connector)
+        .credentials(operator_credentials)
+        .guard_last_admin_key(true)
+        .open()
+        .unwrap();
+
+    // The default key is the only one which can put authentication keys
+    let default_key_id = admin.whoami().unwrap().object_id;
+    let err = operator
+        .delete_object(default_key_id, object::Type::AuthenticationKey)
+        .unwrap_err();
+    assert_eq!(
+        *err.kind(),
+        yubihsm::client::ErrorKind::RefusingLastAdminKeyDelete
+    );
+
+    admin
+        .put_authentication_key(
+            2,
+            Default::default(),
+            Domain::DOM1,
+            Capability::PUT_AUTHENTICATION_KEY,
+            Capability::empty(),
+            authentication::Algorithm::YubicoAes,
+            authentication::Key::random(),
+        )
+        .unwrap();
+
+    operator
+        .delete_object(default_key_id, object::Type::AuthenticationKey)
+        .unwrap();
+
+    let err = operator
+        .delete_object(2, object::Type::AuthenticationKey)
+        .unwrap_err();
+    assert_eq!(
+        *err.kind(),
+        yubihsm::client::ErrorKind::RefusingLastAdminKeyDelete
+    );
+}
+
+#[test]
+fn max_sessions_test() {
+    let connector = Connector::from(Builder::new().max_sessions(1).build());
+    let _client = Client::open(connector.clone(), Credentials::default(), false).unwrap();
+
+    let err = Client::open(connector, Credentials::default(), false)
+        .err()
+        .unwrap();
+
+    assert_eq!(err.device_error(), Some(device::ErrorKind::SessionsFull));
+}
+
+#[test]
+fn wait_for_session_test() {
+    let hsm = Builder::new().max_sessions(2).build();
+    let connector = Connector::from(hsm.clone());
+
+    // Simulate crashed clients which never close their sessions
+    for _ in 0..2 {
+        mem::forget(Client::open(connector.clone(), Credentials::default(), false).unwrap());
+    }
+
+    let err = Client::builder(connector.clone())
+        .wait_for_session(Duration::from_millis(200))
+        .open()
+        .err()
+        .unwrap();
+
+    assert_eq!(err.device_error(), Some(device::ErrorKind::SessionsFull));
+
+    let expirer = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(200));
+        let session_id = hsm.session_ids()[0];
+        assert!(hsm.expire_session(session_id));
+    });
+
+    let client = Client::builder(connector)
+        .wait_for_session(Duration::from_secs(5))
+        .open()
+        .unwrap();
+
+    expirer.join().unwrap();
+    assert!(client.ping().is_ok());
+}
+
+#[test]
+fn reset_session_on_error_test() {
+    let hsm = Builder::new().build();
+    let client = Client::builder(Connector::from(hsm.clone()))
+        .open()
+        .unwrap();
+
+    assert!(client.ping().is_ok());
+    let messages_sent = client.session().unwrap().messages_sent().unwrap();
+    assert!(client.ping().is_ok());
+
+    hsm.corrupt_next_command();
+    assert!(client.ping().is_err());
+
+    // The broken session was closed, so the next command opens a new one
+    assert!(client.ping().is_ok());
+    assert_eq!(
+        client.session().unwrap().messages_sent().unwrap(),
+        messages_sent
+    );
+    assert_eq!(hsm.session_ids().len(), 1);
+}
+
+#[test]
+fn reset_session_on_error_disabled_test() {
+    let hsm = Builder::new().build();
+    let client = Client::builder(Connector::from(hsm.clone()))
+        .reset_session_on_error(false)
+        .open()
+        .unwrap();
+
+    hsm.corrupt_next_command();
+    assert!(client.ping().is_err());
+
+    // Without resetting it, the client keeps using the broken session
+    let err = client.ping().err().unwrap();
+    assert!(err.to_string().contains("HSM error"), "{}", err);
+    assert!(hsm.session_ids().is_empty());
+
+    // ...until the HSM's `InvalidSession` response aborts it
+    assert!(client.ping().is_ok());
+}
+
+#[test]
+fn max_objects_test() {
+    // Room for one object besides the default authentication key
+    let client = open_client(Builder::new().max_objects(2));
+    let label = "yubihsm.rs test key";
+
+    client
+        .generate_hmac_key(
+            1,
+            label.into(),
+            Domain::DOM1,
+            Capability::SIGN_HMAC,
+            yubihsm::hmac::Algorithm::Sha256,
+        )
+        .unwrap();
+
+    let err = client
+        .generate_hmac_key(
+            2,
+            label.into(),
+            Domain::DOM1,
+            Capability::SIGN_HMAC,
+            yubihsm::hmac::Algorithm::Sha256,
+        )
+        .unwrap_err();
+
+    assert_eq!(err.device_error(), Some(device::ErrorKind::StorageFailed));
+}
+
+#[test]
+fn seed_test() {
+    let random_bytes = |seed| {
+        open_client(Builder::new().seed(seed))
+            .get_pseudo_random(32)
+            .unwrap()
+    };
+
+    assert_eq!(random_bytes([1; 32]), random_bytes([1; 32]));
+    assert_ne!(random_bytes([1; 32]), random_bytes([2; 32]));
+}
+
+#[test]
+fn seeded_signatures_test() {
+    let key_id = 100;
+
+    // ECDSA nonces and attestation certificate serials are also drawn from
+    // the seeded RNG
+    let sign = |seed| {
+        let client = open_client(Builder::new().seed(seed));
+
+        client
+            .generate_asymmetric_key(
+                key_id,
+                Default::default(),
+                Domain::DOM1,
+                Capability::SIGN_ECDSA | Capability::SIGN_ATTESTATION_CERTIFICATE,
+                asymmetric::Algorithm::EcP256,
+            )
+            .unwrap();
+
+        let signature = client.sign_ecdsa_prehash_raw(key_id, [0x42; 32]).unwrap();
+        let certificate = client.sign_attestation_certificate(key_id, None).unwrap();
+        (signature, Vec::from(certificate.as_ref()))
+    };
+
+    assert_eq!(sign([1; 32]), sign([1; 32]));
+    assert_ne!(sign([1; 32]), sign([2; 32]));
+}
+
+#[test]
+fn unwrapped_origin_test() {
+    let wrap_key_id = 1;
+    let wrap_key = [0x42; 16];
+    let capabilities = Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP;
+    let label = "yubihsm.rs test key";
+
+    let clients = [Builder::new(), Builder::new()].map(|builder| {
+        let client = open_client(builder);
+
+        client
+            .put_wrap_key(
+                wrap_key_id,
+                label.into(),
+                Domain::DOM1,
+                Capability::EXPORT_WRAPPED | Capability::IMPORT_WRAPPED,
+                Capability::all(),
+                wrap::Algorithm::Aes128Ccm,
+                wrap_key,
+            )
+            .unwrap();
+
+        client
+    });
+
+    // Keys generated and imported on the first MockHsm are moved to the second
+    clients[0]
+        .generate_asymmetric_key(
+            2,
+            label.into(),
+            Domain::DOM1,
+            capabilities,
+            asymmetric::Algorithm::Ed25519,
+        )
+        .unwrap();
+
+    clients[0]
+        .put_asymmetric_key(
+            3,
+            label.into(),
+            Domain::DOM1,
+            capabilities,
+            asymmetric::Algorithm::Ed25519,
+            [0x24; 32],
+        )
+        .unwrap();
+
+    for (key_id, origin) in [
+        (2, object::Origin::WrappedGenerated),
+        (3, object::Origin::WrappedImported),
+    ] {
+        let wrapped = clients[0]
+            .export_wrapped(wrap_key_id, object::Type::AsymmetricKey, key_id)
+            .unwrap();
+
+        clients[1].import_wrapped(wrap_key_id, wrapped).unwrap();
+
+        let info = clients[1]
+            .get_object_info(key_id, object::Type::AsymmetricKey)
+            .unwrap();
+
+        assert_eq!(info.origin, origin);
+    }
+}
+
+#[test]
+fn detailed_error_test() {
+    let label = "yubihsm.rs test key";
+    let connector = Connector::from(Builder::new().build());
+    let admin = Client::open(connector.clone(), Credentials::default(), false).unwrap();
+
+    // Authentication key which can generate keys, but only delegate ECDSA signing
+    let password = b"delegated";
+    admin
+        .put_authentication_key(
+            2,
+            label.into(),
+            Domain::DOM1,
+            Capability::GENERATE_ASYMMETRIC_KEY,
+            Capability::SIGN_ECDSA,
+            authentication::Algorithm::YubicoAes,
+            authentication::Key::derive_from_password(password),
+        )
+        .unwrap();
+
+    let client = Client::open(connector, Credentials::from_password(2, password), false).unwrap();
+
+    let err = client
+        .generate_asymmetric_key(
+            3,
+            label.into(),
+            Domain::DOM1,
+            Capability::SIGN_EDDSA,
+            asymmetric::Algorithm::Ed25519,
+        )
+        .unwrap_err();
+
+    assert_eq!(
+        err.device_error(),
+        Some(device::ErrorKind::InsufficientPermissions)
+    );
+
+    let detailed = format!("{err:#}");
+    assert!(detailed.starts_with(&err.to_string()));
+    assert!(detailed.contains("\n  command: Generate Asymmetric Key\n"));
+    assert!(detailed.contains("\n  object: 0x0003 (asymmetric-key)\n"));
+    assert!(detailed.contains("\n  authentication key: 0x0002\n"));
+    assert!(detailed.contains(
+        "\n  hint: authentication key 0x0002 needs the `generate-asymmetric-key` capability"
+    ));
+
+    // The terse formatting is unchanged
+    assert!(!err.to_string().contains('\n'));
+
+    let err = client
+        .get_object_info(4, object::Type::AsymmetricKey)
+        .unwrap_err();
+
+    assert!(format!("{err:#}").ends_with(
+        "\n  hint: object 0x0004 (asymmetric-key) doesn't exist, \
+         or shares no domains with authentication key 0x0002"
+    ));
+
+    let generate_key = || {
+        admin.generate_asymmetric_key(
+            5,
+            label.into(),
+            Domain::DOM1,
+            Capability::SIGN_EDDSA,
+            asymmetric::Algorithm::Ed25519,
+        )
+    };
+
+    generate_key().unwrap();
+    let err = generate_key().unwrap_err();
+    assert_eq!(err.device_error(), Some(device::ErrorKind::ObjectExists));
+
+    let detailed = format!("{err:#}");
+    assert!(detailed.contains("\n  authentication key: 0x0001\n"));
+    assert!(detailed.contains("\n  hint: an object with this ID and type already exists"));
+}
+
+#[test]
+fn restore_footprint_test() {
+    let wrap_key_id = 1;
+    let label = "yubihsm.rs test key";
+    let exportable = Capability::EXPORTABLE_UNDER_WRAP;
+
+    let [source, target] = [Builder::new(), Builder::new()].map(|builder| {
+        let client = open_client(builder);
+
+        client
+            .put_wrap_key(
+                wrap_key_id,
+                label.into(),
+                Domain::DOM1,
+                Capability::EXPORT_WRAPPED | Capability::IMPORT_WRAPPED,
+                Capability::all(),
+                wrap::Algorithm::Aes128Ccm,
+                [0x42; 16],
+            )
+            .unwrap();
+
+        client
+    });
+
+    for (key_id, algorithm) in [
+        (2, asymmetric::Algorithm::Ed25519),
+        (3, asymmetric::Algorithm::Rsa2048),
+    ] {
+        source
+            .generate_asymmetric_key(
+                key_id,
+                label.into(),
+                Domain::DOM1,
+                Capability::SIGN_EDDSA | Capability::SIGN_PKCS | exportable,
+                algorithm,
+            )
+            .unwrap();
+    }
+
+    source
+        .put_opaque(
+            4,
+            label.into(),
+            Domain::DOM1,
+            exportable,
+            opaque::Algorithm::Data,
+            vec![0x42; 1000],
+        )
+        .unwrap();
+
+    // Restore everything but the default authentication key (which the
+    // target already has)
+    let (manifest, messages) = source.backup_all(wrap_key_id).unwrap();
+    let messages: Vec<wrap::Message> = messages
+        .into_iter()
+        .filter(|message| {
+            manifest.entry_for(message).unwrap().object_type != object::Type::AuthenticationKey
+        })
+        .collect();
+
+    assert_eq!(messages.len(), 3);
+
+    let before = target.get_storage_info().unwrap();
+    let footprint = target.ensure_restore_fits(&messages).unwrap();
+
+    // Ed25519 and RSA-2048 keys (the latter as its 256 bytes of primes), and
+    // 1000 bytes of opaque data, in 126-byte pages
+    assert_eq!(
+        footprint,
+        device::StorageFootprint {
+            records: 3,
+            pages: 1 + 3 + 8
+        }
+    );
+
+    for message in messages.iter().cloned() {
+        target.import_wrapped(wrap_key_id, message).unwrap();
+    }
+
+    let after = target.get_storage_info().unwrap();
+    assert_eq!(
+        usize::from(before.free_records - after.free_records),
+        footprint.records
+    );
+    assert_eq!(
+        usize::from(before.free_pages - after.free_pages),
+        footprint.pages
+    );
+
+    // Restoring again needs more records than a nearly full HSM has free
+    let full = open_client(Builder::new().max_objects(3));
+    let err = full.ensure_restore_fits(&messages).unwrap_err();
+    assert_eq!(*err.kind(), yubihsm::client::ErrorKind::InsufficientStorage);
+}
+
+/// Back up objects on one `MockHsm` and restore a selection of them on another
+#[test]
+fn backup_manifest_test() {
+    let wrap_key_id = 1;
+    let label = "yubihsm.rs test key";
+
+    let clients = [Builder::new(), Builder::new()].map(|builder| {
+        let client = open_client(builder);
+
+        client
+            .put_wrap_key(
+                wrap_key_id,
+                label.into(),
+                Domain::DOM1,
+                Capability::EXPORT_WRAPPED | Capability::IMPORT_WRAPPED,
+                Capability::all(),
+                wrap::Algorithm::Aes128Ccm,
+                [0x42; 16],
+            )
+            .unwrap();
+
+        client
+    });
+
+    for (key_id, capabilities) in [
+        (
+            2,
+            Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP,
+        ),
+        (
+            3,
+            Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP,
+        ),
+        (4, Capability::SIGN_EDDSA),
+        (
+            5,
+            Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP,
+        ),
+    ] {
+        clients[0]
+            .generate_asymmetric_key(
+                key_id,
+                label.into(),
+                Domain::DOM1,
+                capabilities,
+                asymmetric::Algorithm::Ed25519,
+            )
+            .unwrap();
+    }
+
+    let (manifest, messages) = clients[0].backup_all(wrap_key_id).unwrap();
+    assert_eq!(manifest.wrap_key_id, wrap_key_id);
+    assert_eq!(manifest.entries.len(), messages.len());
+
+    // The default authentication key is exportable too
+    let mut backed_up: Vec<_> = manifest.entries.iter().map(|e| e.handle()).collect();
+    backed_up.sort();
+    assert_eq!(
+        backed_up,
+        [
+            object::Handle::new(1, object::Type::AuthenticationKey),
+            object::Handle::new(2, object::Type::AsymmetricKey),
+            object::Handle::new(3, object::Type::AsymmetricKey),
+            object::Handle::new(5, object::Type::AsymmetricKey),
+        ]
+    );
+
+    for (entry, message) in manifest.entries.iter().zip(&messages) {
+        assert!(entry.matches(message));
+
+        if entry.object_type == object::Type::AsymmetricKey {
+            assert_eq!(
+                entry.algorithm,
+                yubihsm::Algorithm::Asymmetric(asymmetric::Algorithm::Ed25519)
+            );
+        }
+    }
+
+    let json = serde_json::to_string(&manifest).unwrap();
+    assert!(json.contains(r#""object_type":"asymmetric-key""#));
+    assert!(json.contains(&format!(r#""label":"{label}""#)));
+    let manifest: wrap::BackupManifest = serde_json::from_str(&json).unwrap();
+
+    // Restore only keys 3 and 5 on the second MockHsm
+    for message in messages {
+        let entry = manifest.entry_for(&message).unwrap();
+
+        if entry.object_type != object::Type::AsymmetricKey || entry.object_id == 2 {
+            continue;
+        }
+
+        let handle = clients[1].import_wrapped(wrap_key_id, message).unwrap();
+        assert_eq!(handle, entry.handle());
+    }
+
+    let restored = |key_id| {
+        clients[1]
+            .get_object_info(key_id, object::Type::AsymmetricKey)
+            .is_ok()
+    };
+
+    assert!(!restored(2));
+    assert!(restored(3));
+    assert!(restored(5));
+}
+
+#[test]
+fn storage_info_test() {
+    let client = open_client(Builder::new());
+    let initial = client.get_storage_info().unwrap();
+
+    // The default authentication key occupies a record
+    assert_eq!(initial.total_records, 256);
+    assert_eq!(initial.free_records, 255);
+
+    for object_id in 1..=10 {
+        client
+            .put_opaque(
+                object_id,
+                "yubihsm.rs test object".into(),
+                Domain::DOM1,
+                Capability::default(),
+                opaque::Algorithm::Data,
+                b"opaque data".as_ref(),
+            )
+            .unwrap();
+    }
+
+    let info = client.get_storage_info().unwrap();
+    assert_eq!(info.free_records, initial.free_records - 10);
+
+    client.delete_object(1, object::Type::Opaque).unwrap();
+    assert_eq!(
+        client.get_storage_info().unwrap().free_records,
+        initial.free_records - 9
+    );
+}
+
+#[test]
+fn storage_info_nearly_full_test() {
+    let client = open_client(Builder::new().max_objects(8));
+    let mut free_records = client.get_storage_info().unwrap().free_records;
+    assert_eq!(free_records, 7);
+
+    // Fill all but one of the free records, one object at a time
+    for object_id in 1..=6 {
+        client
+            .generate_hmac_key(
+                object_id,
+                "yubihsm.rs test key".into(),
+                Domain::DOM1,
+                Capability::SIGN_HMAC,
+                hmac::Algorithm::Sha256,
+            )
+            .unwrap();
+
+        let info = client.get_storage_info().unwrap();
+        assert_eq!(info.total_records, 8);
+        assert_eq!(info.free_records, free_records - 1);
+        free_records = info.free_records;
+    }
+
+    // Room for exactly one more key
+    let info = client.get_storage_info().unwrap();
+    assert_eq!(info.free_records, 1);
+    assert!(info.has_room_for(info.footprint(32)));
+    assert!(!info.has_room_for(info.footprint(32) + info.footprint(32)));
+}
+
+#[test]
+fn wrap_key_delegated_capabilities_test() {
+    const WRAP_KEY_ID: object::Id = 1;
+
+    let client = open_client(Builder::new());
+    let label = "yubihsm.rs test object";
+    let delegated = Capability::SIGN_HMAC | Capability::EXPORTABLE_UNDER_WRAP;
+
+    client
+        .put_wrap_key(
+            WRAP_KEY_ID,
+            label.into(),
+            Domain::DOM1,
+            Capability::EXPORT_WRAPPED,
+            delegated,
+            wrap::Algorithm::Aes128Ccm,
+            [0x42; 16],
+        )
+        .unwrap();
+
+    let info = client
+        .get_object_info(WRAP_KEY_ID, object::Type::WrapKey)
+        .unwrap();
+    assert_eq!(info.capabilities, Capability::EXPORT_WRAPPED);
+    assert_eq!(info.delegated_capabilities, delegated);
+    assert!(info
+        .to_string()
+        .ends_with("capabilities=[export-wrapped] delegated=[exportable-under-wrap, sign-hmac]"));
+
+    for (object_id, capabilities) in [(2, delegated), (3, delegated | Capability::VERIFY_HMAC)] {
+        client
+            .put_hmac_key(
+                object_id,
+                label.into(),
+                Domain::DOM1,
+                capabilities,
+                hmac::Algorithm::Sha256,
+                vec![0x24; 32],
+            )
+            .unwrap();
+    }
+
+    assert!(client
+        .export_wrapped(WRAP_KEY_ID, object::Type::HmacKey, 2)
+        .is_ok());
+
+    // `verify-hmac` isn't among the wrap key's delegated capabilities
+    let err = client
+        .export_wrapped(WRAP_KEY_ID, object::Type::HmacKey, 3)
+        .unwrap_err();
+    assert_eq!(
+        err.device_error(),
+        Some(device::ErrorKind::InsufficientPermissions)
+    );
+    assert!(
+        format!("{err:#}").contains("delegated capabilities"),
+        "{err:#}"
+    );
+}
+
+#[cfg(feature = "software-wrap")]
+#[test]
+fn import_wrapped_invalid_payload_test() {
+    const WRAP_KEY_ID: object::Id = 1;
+    const KEY_ID: object::Id = 2;
+
+    let client = open_client(Builder::new());
+    let wrap_key_bytes = [0x42; 16];
+
+    client
+        .put_wrap_key(
+            WRAP_KEY_ID,
+            "wrap key".into(),
+            Domain::DOM1,
+            Capability::EXPORT_WRAPPED | Capability::IMPORT_WRAPPED,
+            Capability::all(),
+            wrap::Algorithm::Aes128Ccm,
+            wrap_key_bytes,
+        )
+        .unwrap();
+
+    client
+        .generate_asymmetric_key(
+            KEY_ID,
+            Default::default(),
+            Domain::DOM1,
+            Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP,
+            asymmetric::Algorithm::Ed25519,
+        )
+        .unwrap();
+
+    let message = client
+        .export_wrapped(WRAP_KEY_ID, object::Type::AsymmetricKey, KEY_ID)
+        .unwrap();
+
+    client
+        .delete_object(KEY_ID, object::Type::AsymmetricKey)
+        .unwrap();
+
+    // A consistently truncated key, which isn't a valid Ed25519 key
+    let wrap_key =
+        wrap::SoftwareKey::new(wrap::Key::from_bytes(WRAP_KEY_ID, &wrap_key_bytes).unwrap());
+    let mut object = wrap_key.decrypt(&message).unwrap();
+    object.data.pop();
+    object.object_info.length -= 1;
+    let truncated = wrap_key.encrypt(&object).unwrap();
+
+    let err = client.import_wrapped(WRAP_KEY_ID, truncated).unwrap_err();
+    assert_eq!(err.device_error(), Some(device::ErrorKind::InvalidData));
+    assert!(client
+        .get_object_info(KEY_ID, object::Type::AsymmetricKey)
+        .is_err());
+}
+
+/// Public keys of NIST curve keys generated by the `MockHsm` are valid points
+#[test]
+fn generated_nist_keys_test() {
+    use p256::elliptic_curve::sec1::FromEncodedPoint;
+    use yubihsm::ecdsa::{NistP256, NistP384};
+
+    let client = open_client(Builder::new());
+
+    for (key_id, algorithm) in [
+        (1, asymmetric::Algorithm::EcP256),
+        (2, asymmetric::Algorithm::EcP384),
+    ] {
+        client
+            .generate_asymmetric_key(
+                key_id,
+                "nist key".into(),
+                Domain::DOM1,
+                Capability::SIGN_ECDSA,
+                algorithm,
+            )
+            .unwrap();
+    }
+
+    let p256_key = client.get_public_key(1).unwrap();
+    let point = p256_key.ecdsa::<NistP256>().unwrap();
+    assert!(bool::from(
+        p256::PublicKey::from_encoded_point(&point).is_some()
+    ));
+
+    let p384_key = client.get_public_key(2).unwrap();
+    assert_eq!(p384_key.algorithm, asymmetric::Algorithm::EcP384);
+    let point = p384_key.ecdsa::<NistP384>().unwrap();
+    assert!(bool::from(
+        p384::PublicKey::from_encoded_point(&point).is_some()
+    ));
+}
+
+/// Brainpool keys can be stored and listed, even though the `MockHsm` can't
+/// compute with them
+#[test]
+fn brainpool_keys_test() {
+    let client = open_client(Builder::new());
+    let label = "brainpool key";
+
+    client
+        .put_asymmetric_key(
+            4,
+            label.into(),
+            Domain::DOM1,
+            Capability::SIGN_ECDSA,
+            asymmetric::Algorithm::EcBp256,
+            [0x42; 32],
+        )
+        .unwrap();
+
+    for (key_id, algorithm) in [
+        (5, asymmetric::Algorithm::EcBp384),
+        (6, asymmetric::Algorithm::EcBp512),
+    ] {
+        client
+            .generate_asymmetric_key(
+                key_id,
+                label.into(),
+                Domain::DOM1,
+                Capability::SIGN_ECDSA,
+                algorithm,
+            )
+            .unwrap();
+    }
+
+    let info = client
+        .get_object_info(4, object::Type::AsymmetricKey)
+        .unwrap();
+    assert_eq!(info.algorithm, asymmetric::Algorithm::EcBp256.into());
+    assert_eq!(info.length, 32);
+    assert_eq!(info.label, label.into());
+
+    let entries = client
+        .list_objects(&[object::Filter::Algorithm(
+            asymmetric::Algorithm::EcBp512.into(),
+        )])
+        .unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].object_id, 6);
+
+    let err = client.get_public_key(4).unwrap_err();
+    assert_eq!(err.device_error(), Some(device::ErrorKind::InvalidData));
+
+    let err = client.sign_ecdsa_prehash_raw(4, [0u8; 32]).unwrap_err();
+    assert!(err.device_error().is_some());
+}
+
+/// Labels listed with `list_objects_detailed` match the ones objects were
+/// created with, whether or not they fill the fixed-size label field
+#[test]
+fn list_objects_detailed_labels_test() {
+    let client = open_client(Builder::new());
+    let full_label = "x".repeat(object::LABEL_SIZE);
+    let labels = ["", "short", "ünïcödé", full_label.as_str()];
+
+    for (key_id, label) in (10..).zip(labels) {
+        client
+            .put_opaque(
+                key_id,
+                label.into(),
+                Domain::DOM1,
+                Capability::default(),
+                opaque::Algorithm::Data,
+                b"opaque data".as_ref(),
+            )
+            .unwrap();
+    }
+
+    let infos = client
+        .list_objects_detailed(&[object::Filter::Type(object::Type::Opaque)])
+        .unwrap();
+
+    assert_eq!(infos.len(), labels.len());
+
+    for (info, label) in infos.iter().zip(labels) {
+        assert_eq!(info.label, label.into());
+        assert_eq!(info.label.to_string(), label);
+    }
+}
+
+#[test]
+fn hsm_rng_error_test() {
+    use rand_core::RngCore;
+    use yubihsm::rng::HsmRng;
+
+    let hsm = Builder::new().build();
+    let mut rng = HsmRng::new(
+        Client::builder(Connector::from(hsm.clone()))
+            .open()
+            .unwrap(),
+    );
+
+    hsm.corrupt_next_command();
+    assert!(rng.try_fill_bytes(&mut [0u8; 16]).is_err());
+    assert_eq!(rng.buffered(), 0);
+
+    // Recovers once the HSM does
+    assert!(rng.try_fill_bytes(&mut [0u8; 16]).is_ok());
+}
+
+#[test]
+fn objects_snapshot_test() {
+    use yubihsm::mockhsm::ObjectsSnapshot;
+
+    let hsm = Builder::new().build();
+    let client = Client::open(Connector::from(hsm.clone()), Credentials::default(), false).unwrap();
+    let label = "yubihsm.rs test key";
+
+    for (key_id, algorithm) in [
+        (2, asymmetric::Algorithm::Ed25519),
+        (3, asymmetric::Algorithm::EcP256),
+    ] {
+        client
+            .generate_asymmetric_key(
+                key_id,
+                label.into(),
+                Domain::DOM1,
+                Capability::SIGN_EDDSA | Capability::SIGN_ECDSA,
+                algorithm,
+            )
+            .unwrap();
+    }
+
+    client
+        .generate_hmac_key(
+            4,
+            label.into(),
+            Domain::DOM2,
+            Capability::SIGN_HMAC,
+            hmac::Algorithm::Sha256,
+        )
+        .unwrap();
+
+    client
+        .put_opaque(
+            5,
+            label.into(),
+            Domain::DOM3,
+            Capability::empty(),
+            opaque::Algorithm::Data,
+            b"opaque data".to_vec(),
+        )
+        .unwrap();
+
+    let path = std::env::temp_dir().join(format!(
+        "yubihsm-mockhsm-snapshot-{}.json",
+        std::process::id()
+    ));
+
+    hsm.snapshot_objects().save(&path).unwrap();
+    let snapshot = ObjectsSnapshot::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(snapshot.len(), 5);
+
+    let restored_hsm = Builder::new().build();
+    restored_hsm.restore_objects(&snapshot).unwrap();
+    let restored =
+        Client::open(Connector::from(restored_hsm), Credentials::default(), false).unwrap();
+
+    // Every object is present with identical metadata...
+    assert_eq!(
+        format!("{:?}", restored.list_objects_detailed(&[]).unwrap()),
+        format!("{:?}", client.list_objects_detailed(&[]).unwrap())
+    );
+
+    // ...and the same keys
+    assert_eq!(
+        restored.sign_ed25519(2, b"test message").unwrap(),
+        client.sign_ed25519(2, b"test message").unwrap()
+    );
+    assert_eq!(
+        restored.get_public_key(3).unwrap(),
+        client.get_public_key(3).unwrap()
+    );
+    assert_eq!(restored.get_opaque(5).unwrap(), b"opaque data");
+
+    let tag = client.sign_hmac(4, b"test message").unwrap();
+    assert!(restored.verify_hmac(4, b"test message", tag).is_ok());
+}
+
+#[test]
+fn timing_jitter_test() {
+    const JITTER: std::ops::Range<Duration> = Duration::from_millis(50)..Duration::from_millis(60);
+
+    let connector = Connector::from(Builder::new().build());
+    let client = Client::open(connector.clone(), Credentials::default(), false).unwrap();
+
+    client
+        .generate_asymmetric_key(
+            1,
+            "yubihsm.rs test key".into(),
+            Domain::DOM1,
+            Capability::SIGN_EDDSA,
+            asymmetric::Algorithm::Ed25519,
+        )
+        .unwrap();
+
+    let jittered = Client::builder(connector)
+        .timing_jitter(JITTER)
+        .open()
+        .unwrap();
+
+    let started_at = Instant::now();
+    let signature = jittered.sign_ed25519(1, b"jittered").unwrap();
+    assert!(started_at.elapsed() >= JITTER.start);
+
+    // Ed25519 signatures are deterministic, so the jitter mustn't change them
+    assert_eq!(signature, client.sign_ed25519(1, b"jittered").unwrap());
+
+    let public_key = client.get_public_key(1).unwrap().ed25519().unwrap();
+    assert!(ed25519_dalek::VerifyingKey::try_from(public_key.as_ref())
+        .unwrap()
+        .verify_strict(b"jittered", &signature)
+        .is_ok());
+}
+
+#[test]
+fn max_message_size_test() {
+    let client = open_client(Builder::new().max_message_size(200));
+    assert_eq!(client.max_message_size(), 200);
+
+    // Each encrypted message can carry at most 172 bytes of data
+    let random = client.get_pseudo_random(1000).unwrap();
+    assert_eq!(random.len(), 1000);
+
+    for len in [0, 171, 172, 173, 345] {
+        let message = vec![0x42; len];
+        assert_eq!(client.echo(message.clone()).unwrap(), message);
+    }
+
+    // Messages can be too small to carry any data
+    let tiny_client = open_client(Builder::new().max_message_size(27));
+    assert_eq!(
+        *tiny_client.echo(vec![0x42]).unwrap_err().kind(),
+        yubihsm::client::ErrorKind::ProtocolError
+    );
+
+    // Put Opaque parameters take 53 bytes, which leaves 119 for the data
+    let put_opaque = |len| {
+        let _ = client.delete_object(1, object::Type::Opaque);
+
+        client.put_opaque(
+            1,
+            object::Label::default(),
+            Domain::DOM1,
+            Capability::default(),
+            opaque::Algorithm::Data,
+            vec![0; len],
+        )
+    };
+
+    assert!(put_opaque(119).is_ok());
+
+    let err = put_opaque(120).unwrap_err();
+    assert_eq!(*err.kind(), yubihsm::client::ErrorKind::ProtocolError);
+}
+
+#[test]
+fn wrap_oversized_object_test() {
+    let client = open_client(Builder::new());
+
+    client
+        .put_wrap_key(
+            1,
+            object::Label::default(),
+            Domain::DOM1,
+            Capability::EXPORT_WRAPPED,
+            Capability::all(),
+            wrap::Algorithm::Aes128Ccm,
+            [0x42; 16],
+        )
+        .unwrap();
+
+    // The largest opaque object which fits in a Put Opaque command is too
+    // large to export once its metadata, nonce, and MAC are added
+    for (object_id, len) in [(2, 256), (3, 1975)] {
+        client
+            .put_opaque(
+                object_id,
+                object::Label::default(),
+                Domain::DOM1,
+                Capability::EXPORTABLE_UNDER_WRAP,
+                opaque::Algorithm::Data,
+                vec![0; len],
+            )
+            .unwrap();
+    }
+
+    assert!(client.export_wrapped(1, object::Type::Opaque, 2).is_ok());
+
+    let err = client
+        .export_wrapped(1, object::Type::Opaque, 3)
+        .unwrap_err();
+
+    assert_eq!(err.device_error(), Some(device::ErrorKind::WrongLength));
+}
+
+#[test]
+fn client_is_send_and_sync_test() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Client>();
+}
+
+#[test]
+fn cloned_client_shares_session_test() {
+    let hsm = Builder::new().build();
+    let client = Client::open(Connector::from(hsm.clone()), Credentials::default(), true).unwrap();
+    let session_id = client.session().unwrap().id();
+
+    let clones: Vec<_> = (0..4).map(|_| client.clone()).collect();
+
+    let workers: Vec<_> = clones
+        .into_iter()
+        .map(|clone| thread::spawn(move || clone.session().unwrap().id()))
+        .collect();
+
+    for worker in workers {
+        assert_eq!(worker.join().unwrap(), session_id);
+    }
+
+    assert_eq!(hsm.session_ids(), [session_id]);
+
+    // Dropping a clone leaves the session open, but dropping the last closes it
+    let clone = client.clone();
+    drop(client);
+    assert_eq!(hsm.session_ids(), [session_id]);
+
+    drop(clone);
+    assert!(hsm.session_ids().is_empty());
+}
+
+/// A seeded `MockHsm` deterministically passes the RNG health check
+#[test]
+fn rng_healthcheck_test() {
+    let client = open_client(Builder::new().seed([0x42; 32]));
+    let health = client.rng_healthcheck().unwrap();
+
+    assert_eq!(health.bits, 2048 * 8);
+    assert!(health.passed(), "RNG health check failed: {health:?}");
+}
+
+#[test]
+fn shared_ecdsa_signers_test() {
+    use std::sync::Arc;
+    use yubihsm::{
+        audit::AuditOption,
+        command,
+        ecdsa::{
+            self,
+            signature::{Signer as _, Verifier as _},
+            NistP256,
+        },
+    };
+
+    const NUM_SIGNERS: u16 = 16;
+    const NUM_THREADS: usize = 4;
+    const ROUNDS: usize = 3;
+
+    let client = open_client(Builder::new());
+    let key_ids: Vec<object::Id> = (1..=NUM_SIGNERS).map(|i| 0x100 + i).collect();
+
+    for &key_id in &key_ids {
+        client
+            .generate_asymmetric_key(
+                key_id,
+                Default::default(),
+                Domain::DOM1,
+                Capability::SIGN_ECDSA,
+                asymmetric::Algorithm::EcP256,
+            )
+            .unwrap();
+    }
+
+    // Audit `DeviceInfo` too, so the log accounts for every command sent
+    client
+        .set_command_audit_option(command::Code::DeviceInfo, AuditOption::On)
+        .unwrap();
+
+    let last_item = |client: &Client| {
+        client
+            .get_log_entries()
+            .unwrap()
+            .entries
+            .last()
+            .unwrap()
+            .item
+    };
+    let first_item = last_item(&client);
+
+    let signers = Arc::new(ecdsa::Signer::<NistP256>::create_many(&client, &key_ids).unwrap());
+    assert_eq!(signers.len(), key_ids.len());
+
+    let workers: Vec<_> = (0..NUM_THREADS)
+        .map(|thread_index| {
+            let signers = Arc::clone(&signers);
+
+            thread::spawn(move || {
+                for round in 0..ROUNDS {
+                    for (i, signer) in signers.iter().enumerate() {
+                        let msg = format!("thread {thread_index} round {round} signer {i}");
+                        let signature: ecdsa::Signature<NistP256> = signer.sign(msg.as_bytes());
+                        assert!(signer.as_ref().verify(msg.as_bytes(), &signature).is_ok());
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    // Constructing the signers takes a `GetPublicKey` per key, and each
+    // signature a single `SignEcdsa`: no other commands
+    let signatures = NUM_THREADS * ROUNDS * usize::from(NUM_SIGNERS);
+    let commands = last_item(&client).wrapping_sub(first_item);
+    assert_eq!(usize::from(commands), usize::from(NUM_SIGNERS) + signatures);
+}
+
+#[test]
+fn signature_fault_test() {
+    use std::error::Error as _;
+    use yubihsm::{
+        client,
+        ecdsa::{self, signature::Signer as _, NistP256},
+        mockhsm::SignatureFault,
+    };
+
+    let hsm = Builder::new().build();
+    let client = Client::open(Connector::from(hsm.clone()), Credentials::default(), false).unwrap();
+
+    client
+        .generate_asymmetric_key(
+            1,
+            "yubihsm.rs test key".into(),
+            Domain::DOM1,
+            Capability::SIGN_ECDSA,
+            asymmetric::Algorithm::EcP256,
+        )
+        .unwrap();
+
+    let invalid_signature = |err: ecdsa::signature::Error| {
+        err.source()
+            .and_then(|source| source.downcast_ref::<client::Error>())
+            .map(|err| *err.kind())
+            == Some(client::ErrorKind::InvalidSignature)
+    };
+
+    // Out of range signatures are always rejected...
+    let signer = ecdsa::Signer::<NistP256>::create(client.clone(), 1).unwrap();
+    hsm.corrupt_next_signature(SignatureFault::OutOfRange);
+    let result: Result<ecdsa::Signature<NistP256>, _> = signer.try_sign(b"test message");
+    assert!(invalid_signature(result.unwrap_err()));
+
+    // ...but ones which don't verify are only rejected when verifying responses
+    hsm.corrupt_next_signature(SignatureFault::Invalid);
+    let result: Result<ecdsa::Signature<NistP256>, _> = signer.try_sign(b"test message");
+    assert!(result.is_ok());
+
+    let signer = ecdsa::Signer::<NistP256>::builder(client, 1)
+        .verify_responses(true)
+        .create()
+        .unwrap();
+
+    hsm.corrupt_next_signature(SignatureFault::Invalid);
+    let result: Result<ecdsa::Signature<NistP256>, _> = signer.try_sign(b"test message");
+    assert!(invalid_signature(result.unwrap_err()));
+
+    let _signature: ecdsa::Signature<NistP256> = signer.sign(b"test message");
+}
+
+#[test]
+fn ecdsa_signer_retry_test() {
+    use std::time::Duration;
+    use yubihsm::{
+        client::RetryPolicy,
+        ecdsa::{
+            self,
+            signature::{Signer as _, Verifier as _},
+            NistP256,
+        },
+    };
+
+    let hsm = Builder::new().build();
+    let client = Client::builder(Connector::from(hsm.clone()))
+        .retry_policy(RetryPolicy::new(1, Duration::from_millis(1)))
+        .open()
+        .unwrap();
+
+    client
+        .generate_asymmetric_key(
+            1,
+            "yubihsm.rs test key".into(),
+            Domain::DOM1,
+            Capability::SIGN_ECDSA,
+            asymmetric::Algorithm::EcP256,
+        )
+        .unwrap();
+
+    let signer = ecdsa::Signer::<NistP256>::create(client.clone(), 1).unwrap();
+
+    // The connection drops once, then the retry succeeds in a new session
+    hsm.fail_next_commands(1);
+    let signature: ecdsa::Signature<NistP256> = signer.try_sign(b"test message").unwrap();
+    signer.as_ref().verify(b"test message", &signature).unwrap();
+
+    // Retries are bounded by the policy
+    hsm.fail_next_commands(2);
+    let result: Result<ecdsa::Signature<NistP256>, _> = signer.try_sign(b"test message");
+    assert!(result.is_err());
+
+    // Errors which aren't transient (here, a missing key) aren't retried
+    let signer = ecdsa::Signer::<NistP256>::create(client.clone(), 1).unwrap();
+    client
+        .delete_object(1, object::Type::AsymmetricKey)
+        .unwrap();
+    client.reset_stats();
+    let result: Result<ecdsa::Signature<NistP256>, _> = signer.try_sign(b"test message");
+    assert!(result.is_err());
+    assert_eq!(client.stats().count(command::Code::SignEcdsa), 1);
+}
+
+#[test]
+fn dispatch_hook_test() {
+    use std::sync::{Arc, Barrier, Mutex};
+    use yubihsm::{
+        command,
+        ecdsa::{
+            self,
+            signature::{Signer as _, Verifier as _},
+            NistP256,
+        },
+    };
+
+    const ROUNDS: usize = 5;
+
+    let hsm = Builder::new().build();
+    let barrier = Arc::new(Barrier::new(2));
+    let dispatched = Arc::new(Mutex::new(vec![]));
+
+    // Each client's signing commands wait at the barrier until the other
+    // client's are about to be dispatched too, so they're always in flight
+    // at the same time
+    let clients: Vec<Client> = (0..2)
+        .map(|client_index| {
+            let barrier = Arc::clone(&barrier);
+            let dispatched = Arc::clone(&dispatched);
+
+            Client::builder(Connector::from(hsm.clone()))
+                .dispatch_hook(move |code| {
+                    if code == command::Code::SignEcdsa {
+                        dispatched.lock().unwrap().push(client_index);
+                        barrier.wait();
+                    }
+                })
+                .open()
+                .unwrap()
+        })
+        .collect();
+
+    assert_eq!(hsm.session_ids().len(), 2);
+
+    for key_id in [2, 3] {
+        clients[0]
+            .generate_asymmetric_key(
+                key_id,
+                Default::default(),
+                Domain::DOM1,
+                Capability::SIGN_ECDSA,
+                asymmetric::Algorithm::EcP256,
+            )
+            .unwrap();
+    }
+
+    let workers: Vec<_> = clients
+        .into_iter()
+        .zip([2, 3])
+        .map(|(client, key_id)| {
+            thread::spawn(move || {
+                let signer = ecdsa::Signer::<NistP256>::create(client, key_id).unwrap();
+
+                for round in 0..ROUNDS {
+                    let msg = format!("key {key_id} round {round}");
+                    let signature: ecdsa::Signature<NistP256> = signer.sign(msg.as_bytes());
+                    assert!(signer.as_ref().verify(msg.as_bytes(), &signature).is_ok());
+                }
+            })
+        })
+        .collect();
+
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    // Both clients dispatched a signing command in every round
+    let dispatched = dispatched.lock().unwrap();
+    assert_eq!(dispatched.len(), 2 * ROUNDS);
+
+    for pair in dispatched.chunks(2) {
+        assert_ne!(pair[0], pair[1]);
+    }
+}
@@ -0,0 +1,17 @@
+//! Attestation certificate chain generated with the Python `cryptography`
+//! package (backed by OpenSSL): an RSA-2048 root (`sha256WithRSAEncryption`)
+//! issuing a P-256 device attestation certificate, which in turn issues a
+//! certificate for key 0x0064 carrying the YubiHSM attestation extensions
+//! (origin: generated, domains: 1, capabilities: `SIGN_ECDSA`) but no label
+//! extension. The root and device certificates are CAs (critical
+//! `basicConstraints` and `keyUsage` with `keyCertSign`), and all of them are
+//! valid from 2024-01-01 until 2124-01-01.
+
+/// Self-signed RSA-2048 root certificate
+pub const ATTESTATION_ROOT: &[u8] = b"\x30\x82\x02\xE0\x30\x82\x01\xC8\xA0\x03\x02\x01\x02\x02\x01\x01\x30\x0D\x06\x09\x2A\x86\x48\x86\xF7\x0D\x01\x01\x0B\x05\x00\x30\x20\x31\x1E\x30\x1C\x06\x03\x55\x04\x03\x0C\x15\x54\x65\x73\x74\x20\x41\x74\x74\x65\x73\x74\x61\x74\x69\x6F\x6E\x20\x52\x6F\x6F\x74\x30\x20\x17\x0D\x32\x34\x30\x31\x30\x31\x30\x30\x30\x30\x30\x30\x5A\x18\x0F\x32\x31\x32\x34\x30\x31\x30\x31\x30\x30\x30\x30\x30\x30\x5A\x30\x20\x31\x1E\x30\x1C\x06\x03\x55\x04\x03\x0C\x15\x54\x65\x73\x74\x20\x41\x74\x74\x65\x73\x74\x61\x74\x69\x6F\x6E\x20\x52\x6F\x6F\x74\x30\x82\x01\x22\x30\x0D\x06\x09\x2A\x86\x48\x86\xF7\x0D\x01\x01\x01\x05\x00\x03\x82\x01\x0F\x00\x30\x82\x01\x0A\x02\x82\x01\x01\x00\xB9\x23\x6D\x03\xB4\xC0\xA7\x22\x38\x29\xD2\x64\xB9\x6D\x3D\x44\x76\x03\x86\xB0\x7D\x3C\x97\xD9\x63\xC8\x05\x8F\xA9\x97\x22\xC7\x8C\xE4\x94\x83\x33\x4F\x53\x0B\x0E\x78\x6C\xA2\xFC\x2D\xDB\xD8\xFC\x48\x68\xBD\xB9\xB9\xAB\x8D\x78\x89\x3F\x63\x5D\x02\x40\xFB\x84\xAD\x2D\x4B\xD9\x14\x8F\xA1\x26\x76\x3D\x7F\xBF\xB0\x33\x69\x31\xBC\x4D\x3C\xC3\xEE\xAE\x14\xF3\x4A\xD0\x01\xE5\x7C\x11\xB4\xA2\xB4\xF1\x52\xE2\x17\xD0\x8A\xB5\x62\x15\xA9\xCE\xC3\x77\x56\xBC\x65\x32\x0B\x3F\xD6\xFB\xC8\x9A\x28\x26\x19\x58\xF8\xD9\xA9\x24\x45\x27\x5A\x7D\xEB\xBE\x51\x5E\xC8\x6B\x27\x01\x58\x34\x31\xF1\xF1\x97\x71\x30\x6E\xFB\xEA\xFC\x18\x22\xE5\x86\x6E\x79\x75\xEB\xC2\x42\x27\xE7\xCC\xB9\xA3\x77\x09\x73\xEA\xAA\x0E\x44\xB7\xDC\xDC\x66\x2D\xD1\xF0\x00\x05\x5C\x7B\x6D\x8B\xBC\x23\x37\xA4\x78\x40\x29\x27\x61\x61\xD0\xC4\x02\xB4\x90\xD6\xEB\x18\x8E\x32\x0B\x6A\xA7\xED\x37\xFC\x4D\x18\xBE\x7D\xD8\xD9\x1C\x61\x78\xCB\x6D\xA2\x98\x54\xA6\xBD\x02\xC7\xB9\x0B\xB8\x25\x6F\x80\x14\x3C\x92\xFA\xC5\xCC\xBF\xF8\x2C\xA3\x61\xCF\xEE\x9C\x1A\xE9\x51\xF3\x02\x03\x01\x00\x01\xA3\x23\x30\x21\x30\x0F\x06\x03\x55\x1D\x13\x01\x01\xFF\x04\x05\x30\x03\x01\x01\xFF\x30\x0E\x06\x03\x55\x1D\x0F\x01\x01\xFF\x04\x04\x03\x02\x01\x06\x30\x0D\x06\x09\x2A\x86\x48\x86\xF7\x0D\x01\x01\x0B\x05\x00\x03\x82\x01\x01\x00\x9D\xC5\x1C\x24\xF0\xBD\xBB\x3D\xA5\xDD\x0F\x2C\x6C\x1E\xFC\x4E\xEE\xD0\x53\xAC\x17\x26\x0E\xFF\xD8\x05\xD2\x53\x69\xAA\x15\xE2\x80\x47\x77\x9B\x1B\x99\x88\xAD\x2B\x96\x7D\x19\xCC\x67\x99\xEF\xB4\x60\xB4\xC2\x91\xB6\x4D\x5C\xFE\x41\xE4\x50\x8E\x3C\xC6\xBE\x25\x04\x09\x8C\xA9\xD1\xD3\xE9\xFA\x56\x8F\x4D\xFE\xD8\xC4\xA3\x1D\x74\xB2\x39\x63\x74\x6E\x0D\x66\xF8\x08\xB0\x1E\xCE\xD7\x06\x35\x18\xBD\x41\x09\x07\x47\x63\x6E\x0C\x8A\xCB\x26\xF5\x57\x38\x4B\xCF\x47\x85\x76\x20\x04\xD6\xD5\x4C\x7D\x19\x28\x36\x88\xE6\x0A\x2A\xF1\x61\xCE\xF5\x55\xD8\x64\x70\xD0\xAD\x9D\x73\xBD\x8E\x8D\x4E\x60\x19\x2D\x21\x76\x78\x5F\x3A\x51\x10\xC2\x98\x0D\xE2\xF3\x4F\x7D\xA4\x3D\xB4\xC8\x5F\x47\xF8\x9A\x83\x6C\xAC\x82\x28\xBF\x18\x45\xFB\x3F\x02\x41\xD0\x10\x18\xF2\x4F\x7E\x68\x15\x36\xCD\x93\xDA\xA5\xB6\x09\xBE\x35\xD3\xE2\x27\xFC\x32\x19\xE4\x9F\x0A\x95\x52\x68\xBB\x1C\x10\x2B\xFF\x98\x8A\x3E\x1C\x8C\xD7\x06\x6C\x03\xE2\x95\xA7\xA7\xFB\x69\xD6\x38\xA2\xBD\xFD\xF0\x06\xD2\x7B\x0B\x52\x64\xB8\xE6\x62\x22\x80\x7F\x51\x22\x4B\x31\xAA\x37";
+
+/// P-256 device attestation certificate issued by the root
+pub const ATTESTATION_DEVICE: &[u8] = b"\x30\x82\x02\x16\x30\x81\xFF\xA0\x03\x02\x01\x02\x02\x01\x02\x30\x0D\x06\x09\x2A\x86\x48\x86\xF7\x0D\x01\x01\x0B\x05\x00\x30\x20\x31\x1E\x30\x1C\x06\x03\x55\x04\x03\x0C\x15\x54\x65\x73\x74\x20\x41\x74\x74\x65\x73\x74\x61\x74\x69\x6F\x6E\x20\x52\x6F\x6F\x74\x30\x20\x17\x0D\x32\x34\x30\x31\x30\x31\x30\x30\x30\x30\x30\x30\x5A\x18\x0F\x32\x31\x32\x34\x30\x31\x30\x31\x30\x30\x30\x30\x30\x30\x5A\x30\x22\x31\x20\x30\x1E\x06\x03\x55\x04\x03\x0C\x17\x54\x65\x73\x74\x20\x44\x65\x76\x69\x63\x65\x20\x41\x74\x74\x65\x73\x74\x61\x74\x69\x6F\x6E\x30\x59\x30\x13\x06\x07\x2A\x86\x48\xCE\x3D\x02\x01\x06\x08\x2A\x86\x48\xCE\x3D\x03\x01\x07\x03\x42\x00\x04\xEF\x98\xB7\x0D\x44\xDB\x49\x00\x66\x19\x35\x0A\x60\xE8\xA9\x14\x00\x16\x7F\xBB\xA5\xBE\x79\x75\xE3\xC9\xDA\xB2\xA4\x8D\xA2\x76\x88\x6F\xCE\x20\x9E\xE0\x80\xBD\x0A\x1C\x73\x40\x95\x79\x09\x8B\xD3\xD7\xD5\xF7\x03\xAF\xF1\xAD\xE0\x64\x15\xCB\x39\x78\x74\xA4\xA3\x23\x30\x21\x30\x0F\x06\x03\x55\x1D\x13\x01\x01\xFF\x04\x05\x30\x03\x01\x01\xFF\x30\x0E\x06\x03\x55\x1D\x0F\x01\x01\xFF\x04\x04\x03\x02\x01\x06\x30\x0D\x06\x09\x2A\x86\x48\x86\xF7\x0D\x01\x01\x0B\x05\x00\x03\x82\x01\x01\x00\x18\xD0\x08\xB7\xB4\xBE\x99\x80\xFA\x64\xD3\xE5\xFB\x17\x61\x58\x65\x7C\x3E\x94\x6E\xF4\x51\x1B\xE2\x9C\x87\xFB\x9D\xF5\xC1\x9D\xD0\xE0\x1E\x75\xEE\x8E\x75\xC0\x47\x7C\xDF\xEA\x04\xE0\x92\x40\x2E\x61\xA2\xBB\x34\x6B\xBF\xA2\xFC\x30\xAC\x26\xCC\x5C\x6B\xF5\xB5\xBE\x84\xC0\x52\x34\x34\x86\x9B\x80\x8F\x91\x96\x09\x2F\xA9\x23\xE5\x21\x12\xC6\xED\x37\xC3\xE7\x39\x79\xA2\xDC\x8F\xD5\x7A\xF5\xB7\x76\x7A\xAF\xB6\xF1\x0E\x11\xD7\x21\xD1\xF7\x6A\x65\xA9\x69\x58\x43\xDD\x37\xE3\x72\x7F\xD2\x62\xC9\xA6\x6F\x07\x25\x88\x85\x60\x0C\xE3\xDF\x4E\xAA\x8E\x99\xBF\xFD\x73\xAA\x60\x9E\xC2\x0D\xC3\xDA\x20\xB4\xF0\x3A\x3F\xEC\x21\x59\x2D\x82\x01\xDC\x29\xF3\xB8\x36\x8C\x99\x7B\x49\x0E\x42\x1D\x9B\x3D\xC1\x16\xBA\xEC\x2A\xC2\x7A\xED\x87\x01\x56\x79\x03\x27\xA8\x58\x8E\x5F\x0E\x0B\x9F\x7E\x25\x37\xA4\x42\x95\xF6\x78\x27\xCF\x3D\x83\x95\xD5\xF1\x75\x6E\x98\x8E\x1E\xAB\x86\xA5\xB4\xE7\x07\x3B\xA4\x5E\x00\x70\xED\x25\xBF\x8A\x5A\xB1\x97\x06\x14\x9F\x13\x5A\x44\x55\xB8\x53\xCF\x83\xF0\x6A\x30\x79\xE7\xDC\x24\x37\x58\xCF\x9E\x43\xAA\x27";
+
+/// Attestation certificate for key 0x0064 issued by the device certificate
+pub const ATTESTATION_LEAF: &[u8] = b"\x30\x82\x01\x89\x30\x82\x01\x2E\xA0\x03\x02\x01\x02\x02\x01\x03\x30\x0A\x06\x08\x2A\x86\x48\xCE\x3D\x04\x03\x02\x30\x22\x31\x20\x30\x1E\x06\x03\x55\x04\x03\x0C\x17\x54\x65\x73\x74\x20\x44\x65\x76\x69\x63\x65\x20\x41\x74\x74\x65\x73\x74\x61\x74\x69\x6F\x6E\x30\x20\x17\x0D\x32\x34\x30\x31\x30\x31\x30\x30\x30\x30\x30\x30\x5A\x18\x0F\x32\x31\x32\x34\x30\x31\x30\x31\x30\x30\x30\x30\x30\x30\x5A\x30\x1C\x31\x1A\x30\x18\x06\x03\x55\x04\x03\x0C\x11\x54\x65\x73\x74\x20\x41\x74\x74\x65\x73\x74\x65\x64\x20\x4B\x65\x79\x30\x59\x30\x13\x06\x07\x2A\x86\x48\xCE\x3D\x02\x01\x06\x08\x2A\x86\x48\xCE\x3D\x03\x01\x07\x03\x42\x00\x04\x5F\xCA\x01\x69\x0D\xA2\x50\x29\x23\x3F\xCD\x50\xC4\x3C\x7A\x7F\x03\x08\x2C\xF0\x09\xF9\x31\xAE\x2A\xA3\xF6\xC6\x6D\xA4\x27\x62\xB0\x23\x06\x7D\x68\x9A\xB8\xB4\xEA\xC8\x82\x1B\x22\x62\x3A\xA9\xC3\x68\xC1\xFA\x11\x9D\x55\x58\xBE\x06\x61\xF4\x67\x16\xDE\x28\xA3\x59\x30\x57\x30\x12\x06\x0A\x2B\x06\x01\x04\x01\x82\xC4\x0A\x04\x03\x04\x04\x03\x02\x00\x01\x30\x13\x06\x0A\x2B\x06\x01\x04\x01\x82\xC4\x0A\x04\x04\x04\x05\x03\x03\x00\x00\x01\x30\x19\x06\x0A\x2B\x06\x01\x04\x01\x82\xC4\x0A\x04\x05\x04\x0B\x03\x09\x00\x00\x00\x00\x00\x00\x00\x00\x80\x30\x11\x06\x0A\x2B\x06\x01\x04\x01\x82\xC4\x0A\x04\x06\x04\x03\x02\x01\x64\x30\x0A\x06\x08\x2A\x86\x48\xCE\x3D\x04\x03\x02\x03\x49\x00\x30\x46\x02\x21\x00\x89\xF8\x5A\x87\x4D\xE1\x52\x80\xDF\x62\xD7\xB4\x4B\x11\xC2\x9C\xEF\x9C\x7C\x63\x07\x54\xB9\x4D\x30\xE0\x1D\x4B\x33\xA3\x97\x56\x02\x21\x00\xC7\x42\x55\xB6\xBE\x9D\xA9\x25\xC8\xF1\x61\xF2\xE3\x36\x53\x7C\xF5\xF3\x25\x05\xBE\xBE\xEE\x31\x66\xC2\x44\xB3\x21\x50\x4D\x66";
@@ -3,17 +3,30 @@
 /// AES-CCM (Counter with CBC-MAC) test vectors
 mod aesccm;
 
+/// Attestation certificate chain with an RSA root
+#[cfg(all(feature = "rsa", feature = "sha2"))]
+mod attestation;
+
 /// Ed25519 digital signature test vectors
 mod ed25519;
 
 /// HMAC-SHA-256 test vectors
 mod hmac;
 
+/// Wrapped object encrypted independently of this crate
+#[cfg(feature = "software-wrap")]
+mod wrap;
+
 pub use self::aesccm::AESCCM_TEST_VECTORS;
+#[cfg(all(feature = "rsa", feature = "sha2"))]
+pub use self::attestation::{ATTESTATION_DEVICE, ATTESTATION_LEAF, ATTESTATION_ROOT};
 pub use self::ed25519::ED25519_TEST_VECTORS;
 pub use self::hmac::HMAC_SHA256_TEST_VECTORS;
+#[cfg(feature = "software-wrap")]
+pub use self::wrap::WRAPPED_ED25519_KEY;
 
 /// Authenticated encryption test vector (presently specialized for AES-CCM)
+#[allow(dead_code)]
 pub struct EncryptionTestVector {
     /// Encryption key
     pub key: &'static [u8],
@@ -0,0 +1,11 @@
+/// Wrap message (nonce followed by ciphertext) of the first RFC 8032 Ed25519
+/// test key, encrypted under the key of the first AES-CCM test vector.
+///
+/// Its plaintext follows the documented YubiHSM 2 layout: a `Get Object
+/// Info` response for key 0x0065 (label "yubihsm.rs exported test key",
+/// domain 1, `sign-eddsa` and `exportable-under-wrap`, sequence 0, generated
+/// origin) followed by the key. It was encrypted with Python's `cryptography`
+/// AES-CCM implementation (13-byte nonce, 8-byte tag), not by this crate.
+/// It wasn't captured from a device, but a real YubiHSM 2 importing it in
+/// the integration tests checks the layout against hardware.
+pub const WRAPPED_ED25519_KEY: &[u8] = b"\x00\x11\x22\x33\x44\x55\x66\x77\x88\x99\xAA\xBB\xCC\x9A\x58\x3A\x93\x6B\x3A\xEF\xAA\xF9\x96\x0D\xC0\xD0\xA2\xB8\x8C\x03\x9D\x76\x65\xDE\x1E\x70\xC9\xC7\x00\xDD\xCB\x76\x31\x77\x47\x2B\x82\x3D\x8F\xFF\x5C\x85\x1C\x67\xA7\x10\x08\xCC\x59\x1E\x95\x60\x3A\x87\xF2\x9B\x81\x1B\x1E\x11\xAE\x10\x4F\xF4\x12\x9A\x84\x39\x6D\xB3\x58\x01\x86\xAB\x3D\xC2\xC5\x7D\x2C\x78\x4C\xEA\x5C\x57\xD8\x8B\x1A\xBD\x45\xC3\x06\x02\xC3\x9C\x7C\x0D\xC2\xB1\xB3\x4A\x65\xE4\x9B\x88\x5F\x56\x11\xD7\xE3";